@@ -0,0 +1,520 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::datasource::cex::{derive_position_pnl, hmac_sha256, keep_since};
+use crate::datasource::credentials::{ApiCredentials, CredentialStore};
+use crate::datasource::{Capability, DataSource};
+use crate::error::{AppError, AppResult};
+
+/// Venue tag and coin prefix for Bybit events, e.g. `bybit:BTC`
+pub const BYBIT_VENUE: &str = "bybit";
+
+const RECV_WINDOW: &str = "5000";
+
+/// Funding rate history is returned at most 200 records at a time
+const MAX_FUNDING_RATES_PER_REQUEST: usize = 200;
+
+/// Executions and transaction logs may be queried at most 7 days at a time
+const EXECUTION_WINDOW_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Deposit and withdrawal records may be queried at most 30 days at a time
+const TRANSFER_WINDOW_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+
+/// How far back Bybit keeps execution and transaction history
+const HISTORY_RETENTION_MS: i64 = 730 * 24 * 60 * 60 * 1000;
+
+/// Windows ending this long ago are treated as final and not fetched again
+const SETTLED_AFTER_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Records of a wallet's settled windows for one endpoint
+struct SettledWindows {
+    /// Key the records were fetched with, so a replaced key fetches afresh
+    api_key: String,
+    /// Start of the first window not yet settled
+    until: i64,
+    items: Vec<Value>,
+}
+
+/// Reads USDT/USDC perpetual history from the Bybit v5 API with a read-only key.
+///
+/// Executions become fills, funding settlements become funding payments, and deposits and
+/// withdrawals become ledger updates, all in the Hyperliquid-shaped records the ledger
+/// parses. Wallets without Bybit credentials have no Bybit history.
+///
+/// History can only be queried a few days at a time, so records of settled windows are kept
+/// in memory and later syncs only fetch the windows since.
+pub struct BybitClient {
+    client: Client,
+    base_url: String,
+    credentials: Arc<dyn CredentialStore>,
+    /// Settled windows by wallet and endpoint path
+    settled: RwLock<HashMap<(String, String), SettledWindows>>,
+}
+
+impl BybitClient {
+    pub fn new(base_url: &str, credentials: Arc<dyn CredentialStore>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            credentials,
+            settled: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Sends a GET request, signed when credentials are given, and returns the `result` object
+    async fn get(
+        &self,
+        credentials: Option<&ApiCredentials>,
+        path: &str,
+        params: &[(&str, String)],
+    ) -> AppResult<Value> {
+        let query = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut request = self
+            .client
+            .get(format!("{}{}?{}", self.base_url, path, query));
+
+        if let Some(credentials) = credentials {
+            let timestamp = Utc::now().timestamp_millis().to_string();
+            let payload = format!(
+                "{}{}{}{}",
+                timestamp, credentials.api_key, RECV_WINDOW, query
+            );
+            let signature = hex::encode(hmac_sha256(
+                credentials.api_secret.as_bytes(),
+                payload.as_bytes(),
+            ));
+
+            request = request
+                .header("X-BAPI-API-KEY", &credentials.api_key)
+                .header("X-BAPI-TIMESTAMP", timestamp)
+                .header("X-BAPI-RECV-WINDOW", RECV_WINDOW)
+                .header("X-BAPI-SIGN", signature);
+        }
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalApiError(format!(
+                "Bybit request failed: {}",
+                error_text
+            )));
+        }
+
+        let mut body: Value = response.json().await?;
+        if body["retCode"].as_i64() != Some(0) {
+            return Err(AppError::ExternalApiError(format!(
+                "Bybit request failed: {}",
+                body["retMsg"].as_str().unwrap_or_default()
+            )));
+        }
+
+        Ok(body["result"].take())
+    }
+
+    /// Fetches every record of a cursor-paginated list, one time window at a time.
+    ///
+    /// Without a start time the whole retained history is returned, reusing the wallet's
+    /// settled windows from earlier calls.
+    async fn fetch_windowed(
+        &self,
+        wallet: &str,
+        credentials: &ApiCredentials,
+        path: &str,
+        params: &[(&str, &str)],
+        start_time: Option<i64>,
+        window_ms: i64,
+    ) -> AppResult<Vec<Value>> {
+        let now = Utc::now().timestamp_millis();
+        let key = (wallet.to_lowercase(), path.to_string());
+        let (mut window_start, mut settled_items) = match start_time {
+            Some(start_time) => (start_time, Vec::new()),
+            None => self
+                .settled
+                .read()
+                .await
+                .get(&key)
+                .filter(|settled| settled.api_key == credentials.api_key)
+                .map(|settled| (settled.until, settled.items.clone()))
+                .unwrap_or((now - HISTORY_RETENTION_MS, Vec::new())),
+        };
+        let mut settled_until = window_start;
+        let mut all_items = Vec::new();
+
+        while window_start < now {
+            let window_end = (window_start + window_ms).min(now);
+            let mut cursor = String::new();
+            let mut window_items = Vec::new();
+
+            loop {
+                let mut query: Vec<(&str, String)> =
+                    params.iter().map(|(k, v)| (*k, v.to_string())).collect();
+                query.push(("startTime", window_start.to_string()));
+                query.push(("endTime", window_end.to_string()));
+                query.push(("limit", "50".to_string()));
+                if !cursor.is_empty() {
+                    query.push(("cursor", cursor.clone()));
+                }
+
+                let result = self.get(Some(credentials), path, &query).await?;
+                window_items.extend(
+                    result["list"]
+                        .as_array()
+                        .or_else(|| result["rows"].as_array())
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+
+                match result["nextPageCursor"].as_str() {
+                    Some(next) if !next.is_empty() && next != cursor => cursor = next.to_string(),
+                    _ => break,
+                }
+            }
+
+            if window_end < now - SETTLED_AFTER_MS {
+                settled_items.extend(window_items);
+                settled_until = window_end + 1;
+            } else {
+                all_items.extend(window_items);
+            }
+            window_start = window_end + 1;
+        }
+
+        if start_time.is_none() {
+            self.settled.write().await.insert(
+                key,
+                SettledWindows {
+                    api_key: credentials.api_key.clone(),
+                    until: settled_until,
+                    items: settled_items.clone(),
+                },
+            );
+        }
+        settled_items.extend(all_items);
+        Ok(settled_items)
+    }
+
+    /// Loads the wallet's key, dropping its settled windows once the key is removed
+    async fn credentials(&self, wallet: &str) -> AppResult<Option<ApiCredentials>> {
+        let credentials = self.credentials.get(wallet, BYBIT_VENUE).await?;
+        if credentials.is_none() {
+            let wallet = wallet.to_lowercase();
+            self.settled
+                .write()
+                .await
+                .retain(|(cached, _), _| *cached != wallet);
+        }
+        Ok(credentials)
+    }
+}
+
+#[async_trait]
+impl DataSource for BybitClient {
+    fn capabilities(&self) -> BTreeSet<Capability> {
+        BTreeSet::from([
+            Capability::Fills,
+            Capability::Funding,
+            Capability::LedgerUpdates,
+            Capability::FundingRates,
+            Capability::Candles,
+        ])
+    }
+
+    fn venue_sources(&self) -> Vec<(String, Arc<dyn DataSource>)> {
+        Vec::new()
+    }
+
+    async fn get_fills(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        let Some(credentials) = self.credentials(wallet).await? else {
+            return Ok(Vec::new());
+        };
+
+        // Positions are derived from all retained executions, so fetch them regardless of
+        // start time
+        let executions = self
+            .fetch_windowed(
+                wallet,
+                &credentials,
+                "/v5/execution/list",
+                &[("category", "linear")],
+                None,
+                EXECUTION_WINDOW_MS,
+            )
+            .await?;
+
+        let mut fills: Vec<Value> = executions
+            .iter()
+            .filter(|e| matches!(e["execType"].as_str(), Some("Trade") | Some("BustTrade")))
+            .filter_map(|e| {
+                Some(json!({
+                    "venue": BYBIT_VENUE,
+                    "coin": coin(e["symbol"].as_str()?),
+                    "side": if e["side"].as_str()? == "Buy" { "B" } else { "A" },
+                    "sz": e["execQty"],
+                    "px": e["execPrice"],
+                    "fee": e["execFee"],
+                    "feeToken": settle_coin(e["symbol"].as_str()?),
+                    "crossed": !e["isMaker"].as_bool().unwrap_or(false),
+                    "time": e["execTime"].as_str()?.parse::<i64>().ok()?,
+                    "tid": e["execId"],
+                }))
+            })
+            .collect();
+
+        fills.sort_by_key(|fill| fill["time"].as_i64());
+        derive_position_pnl(&mut fills);
+        keep_since(&mut fills, start_time);
+        Ok(fills)
+    }
+
+    async fn get_fill_page(
+        &self,
+        _wallet: &str,
+        _start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        Err(AppError::ValidationError(
+            "Bybit fill pages are not supported".to_string(),
+        ))
+    }
+
+    async fn get_funding(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        let Some(credentials) = self.credentials(wallet).await? else {
+            return Ok(Vec::new());
+        };
+
+        let settlements = self
+            .fetch_windowed(
+                wallet,
+                &credentials,
+                "/v5/account/transaction-log",
+                &[
+                    ("accountType", "UNIFIED"),
+                    ("category", "linear"),
+                    ("type", "SETTLEMENT"),
+                ],
+                start_time,
+                EXECUTION_WINDOW_MS,
+            )
+            .await?;
+
+        Ok(settlements
+            .iter()
+            .filter_map(|s| {
+                // Bybit reports funding as a fee: positive is paid, negative is received
+                let funding = s["funding"].as_str()?;
+                let amount = funding
+                    .strip_prefix('-')
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("-{}", funding));
+
+                Some(json!({
+                    "venue": BYBIT_VENUE,
+                    "coin": coin(s["symbol"].as_str()?),
+                    "usdc": amount,
+                    "token": settle_coin(s["symbol"].as_str()?),
+                    "fundingRate": s["feeRate"].as_str().unwrap_or("0"),
+                    "szi": s["size"],
+                    "time": s["transactionTime"].as_str()?.parse::<i64>().ok()?,
+                }))
+            })
+            .collect())
+    }
+
+    async fn get_funding_page(&self, _wallet: &str, _start_time: i64) -> AppResult<Vec<Value>> {
+        Err(AppError::ValidationError(
+            "Bybit funding pages are not supported".to_string(),
+        ))
+    }
+
+    async fn get_ledger_updates(
+        &self,
+        wallet: &str,
+        start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        let Some(credentials) = self.credentials(wallet).await? else {
+            return Ok(Vec::new());
+        };
+
+        let deposits = self
+            .fetch_windowed(
+                wallet,
+                &credentials,
+                "/v5/asset/deposit/query-record",
+                &[],
+                start_time,
+                TRANSFER_WINDOW_MS,
+            )
+            .await?;
+        let withdrawals = self
+            .fetch_windowed(
+                wallet,
+                &credentials,
+                "/v5/asset/withdraw/query-record",
+                &[],
+                start_time,
+                TRANSFER_WINDOW_MS,
+            )
+            .await?;
+
+        let transfer = |record: &Value, kind: &str, time_key: &str| {
+            Some(json!({
+                "venue": BYBIT_VENUE,
+                "time": record[time_key].as_str()?.parse::<i64>().ok()?,
+                "hash": record["txID"],
+                "delta": {
+                    "type": kind,
+                    "usdc": record["amount"],
+                    "token": record["coin"],
+                },
+            }))
+        };
+
+        let mut updates: Vec<Value> = deposits
+            .iter()
+            .filter(|d| d["status"].as_i64() == Some(3))
+            .filter_map(|d| transfer(d, "deposit", "successAt"))
+            .chain(
+                withdrawals
+                    .iter()
+                    .filter(|w| w["status"].as_str() == Some("success"))
+                    .filter_map(|w| transfer(w, "withdraw", "updateTime")),
+            )
+            .collect();
+        updates.sort_by_key(|update| update["time"].as_i64());
+
+        Ok(updates)
+    }
+
+    async fn get_ledger_page(&self, _wallet: &str, _start_time: i64) -> AppResult<Vec<Value>> {
+        Err(AppError::ValidationError(
+            "Bybit ledger update pages are not supported".to_string(),
+        ))
+    }
+
+    async fn get_user_state(&self, _wallet: &str) -> AppResult<Value> {
+        Err(AppError::ValidationError(
+            "Bybit account state is not supported".to_string(),
+        ))
+    }
+
+    async fn get_all_mids(&self) -> AppResult<Value> {
+        Ok(json!({}))
+    }
+
+    async fn get_meta(&self) -> AppResult<Value> {
+        Ok(json!({ "universe": [] }))
+    }
+
+    async fn get_spot_meta(&self) -> AppResult<Value> {
+        Ok(json!({ "tokens": [], "universe": [] }))
+    }
+
+    async fn get_funding_rates(
+        &self,
+        coin: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        let symbol = format!("{}USDT", base_coin(coin));
+        let mut rates = Vec::new();
+        let mut window_end = end_time;
+
+        // Records come newest first; page backwards by moving the end time
+        loop {
+            let result = self
+                .get(
+                    None,
+                    "/v5/market/funding/history",
+                    &[
+                        ("category", "linear".to_string()),
+                        ("symbol", symbol.clone()),
+                        ("startTime", start_time.to_string()),
+                        ("endTime", window_end.to_string()),
+                        ("limit", MAX_FUNDING_RATES_PER_REQUEST.to_string()),
+                    ],
+                )
+                .await?;
+
+            let page: Vec<Value> = result["list"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|r| {
+                    Some(json!({
+                        "coin": coin,
+                        "fundingRate": r["fundingRate"],
+                        "time": r["fundingRateTimestamp"].as_str()?.parse::<i64>().ok()?,
+                    }))
+                })
+                .collect();
+            let page_count = page.len();
+            let oldest = page.iter().filter_map(|r| r["time"].as_i64()).min();
+
+            rates.extend(page);
+
+            match oldest {
+                Some(oldest)
+                    if page_count >= MAX_FUNDING_RATES_PER_REQUEST && oldest > start_time =>
+                {
+                    window_end = oldest - 1
+                }
+                _ => break,
+            }
+        }
+
+        rates.sort_by_key(|rate| rate["time"].as_i64());
+        Ok(rates)
+    }
+
+    async fn get_predicted_fundings(&self) -> AppResult<Value> {
+        Err(AppError::ValidationError(
+            "Bybit predicted funding is not supported".to_string(),
+        ))
+    }
+
+    async fn get_candles(
+        &self,
+        _coin: &str,
+        _interval: &str,
+        _start_time: i64,
+        _end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        // Market data checks are skipped for CEX coins
+        Ok(Vec::new())
+    }
+}
+
+/// Strips the venue prefix from a coin such as `bybit:BTC`
+fn base_coin(coin: &str) -> &str {
+    coin.strip_prefix(BYBIT_VENUE)
+        .and_then(|c| c.strip_prefix(':'))
+        .unwrap_or(coin)
+}
+
+/// Maps a linear contract symbol such as `BTCUSDT` or `ETHPERP` to `bybit:BTC`
+fn coin(symbol: &str) -> String {
+    let base = ["USDT", "USDC", "PERP"]
+        .iter()
+        .find_map(|quote| symbol.strip_suffix(quote))
+        .unwrap_or(symbol);
+    format!("{}:{}", BYBIT_VENUE, base)
+}
+
+/// Linear USDT contracts settle in USDT; USDC perpetuals (`PERP` and `USDC`) in USDC
+fn settle_coin(symbol: &str) -> &'static str {
+    if symbol.ends_with("USDT") {
+        "USDT"
+    } else {
+        "USDC"
+    }
+}
@@ -0,0 +1,186 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use crate::datasource::{Capability, DataSource};
+use crate::error::AppResult;
+
+/// Methods served by every venue rather than only the primary source
+const MERGED_CAPABILITIES: [Capability; 6] = [
+    Capability::Fills,
+    Capability::Funding,
+    Capability::LedgerUpdates,
+    Capability::Mids,
+    Capability::FundingRates,
+    Capability::Candles,
+];
+
+/// Combines a primary data source with additional venues into one history.
+///
+/// Fills and funding from every venue are merged in time order. A failing venue is left out
+/// with a warning rather than failing the whole request; its metered calls mark it unhealthy. Account state and asset
+/// metadata come from the primary source. Mids are merged, and candle and funding rate
+/// requests are routed to the venue whose coin prefix (e.g. `gmx:`) matches.
+pub struct CompositeDataSource {
+    primary: Arc<dyn DataSource>,
+    venues: Vec<(String, Arc<dyn DataSource>)>,
+}
+
+impl CompositeDataSource {
+    pub fn new(primary: Arc<dyn DataSource>) -> Self {
+        Self {
+            primary,
+            venues: Vec::new(),
+        }
+    }
+
+    /// Adds a venue whose coins are prefixed with `{venue}:`
+    pub fn with_venue(mut self, venue: &str, source: Arc<dyn DataSource>) -> Self {
+        self.venues.push((format!("{}:", venue), source));
+        self
+    }
+
+    /// The venue whose coin prefix matches, or the primary source for unprefixed coins
+    fn source_for(&self, coin: &str) -> &Arc<dyn DataSource> {
+        self.venues
+            .iter()
+            .find(|(prefix, _)| coin.starts_with(prefix.as_str()))
+            .map(|(_, source)| source)
+            .unwrap_or(&self.primary)
+    }
+}
+
+fn sort_by_time(items: &mut [Value]) {
+    items.sort_by_key(|item| item.get("time").and_then(|t| t.as_i64()));
+}
+
+#[async_trait]
+impl DataSource for CompositeDataSource {
+    fn capabilities(&self) -> BTreeSet<Capability> {
+        let mut capabilities = self.primary.capabilities();
+        for (_, source) in &self.venues {
+            capabilities.extend(
+                source
+                    .capabilities()
+                    .into_iter()
+                    .filter(|capability| MERGED_CAPABILITIES.contains(capability)),
+            );
+        }
+        capabilities
+    }
+
+    fn venue_sources(&self) -> Vec<(String, Arc<dyn DataSource>)> {
+        self.venues
+            .iter()
+            .map(|(prefix, source)| (prefix.trim_end_matches(':').to_string(), source.clone()))
+            .collect()
+    }
+
+    async fn get_fills(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        let mut fills = self.primary.get_fills(wallet, start_time).await?;
+        for (venue, source) in &self.venues {
+            match source.get_fills(wallet, start_time).await {
+                Ok(items) => fills.extend(items),
+                Err(e) => tracing::warn!("Skipping {} in get_fills: {}", venue, e),
+            }
+        }
+        sort_by_time(&mut fills);
+        Ok(fills)
+    }
+
+    async fn get_fill_page(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        self.primary.get_fill_page(wallet, start_time).await
+    }
+
+    async fn get_funding(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        let mut funding = self.primary.get_funding(wallet, start_time).await?;
+        for (venue, source) in &self.venues {
+            match source.get_funding(wallet, start_time).await {
+                Ok(items) => funding.extend(items),
+                Err(e) => tracing::warn!("Skipping {} in get_funding: {}", venue, e),
+            }
+        }
+        sort_by_time(&mut funding);
+        Ok(funding)
+    }
+
+    async fn get_funding_page(&self, wallet: &str, start_time: i64) -> AppResult<Vec<Value>> {
+        self.primary.get_funding_page(wallet, start_time).await
+    }
+
+    async fn get_ledger_updates(
+        &self,
+        wallet: &str,
+        start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        let mut updates = self.primary.get_ledger_updates(wallet, start_time).await?;
+        for (venue, source) in &self.venues {
+            match source.get_ledger_updates(wallet, start_time).await {
+                Ok(items) => updates.extend(items),
+                Err(e) => tracing::warn!("Skipping {} in get_ledger_updates: {}", venue, e),
+            }
+        }
+        sort_by_time(&mut updates);
+        Ok(updates)
+    }
+
+    async fn get_ledger_page(&self, wallet: &str, start_time: i64) -> AppResult<Vec<Value>> {
+        self.primary.get_ledger_page(wallet, start_time).await
+    }
+
+    async fn get_user_state(&self, wallet: &str) -> AppResult<Value> {
+        self.primary.get_user_state(wallet).await
+    }
+
+    async fn get_all_mids(&self) -> AppResult<Value> {
+        let mut mids = self.primary.get_all_mids().await?;
+        for (venue, source) in &self.venues {
+            match source.get_all_mids().await {
+                Ok(Value::Object(venue_mids)) => {
+                    if let Some(mids) = mids.as_object_mut() {
+                        mids.extend(venue_mids);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to fetch {} mids: {}", venue, e),
+            }
+        }
+        Ok(mids)
+    }
+
+    async fn get_meta(&self) -> AppResult<Value> {
+        self.primary.get_meta().await
+    }
+
+    async fn get_spot_meta(&self) -> AppResult<Value> {
+        self.primary.get_spot_meta().await
+    }
+
+    async fn get_funding_rates(
+        &self,
+        coin: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        self.source_for(coin)
+            .get_funding_rates(coin, start_time, end_time)
+            .await
+    }
+
+    async fn get_predicted_fundings(&self) -> AppResult<Value> {
+        self.primary.get_predicted_fundings().await
+    }
+
+    async fn get_candles(
+        &self,
+        coin: &str,
+        interval: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        self.source_for(coin)
+            .get_candles(coin, interval, start_time, end_time)
+            .await
+    }
+}
@@ -0,0 +1,306 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+use crate::error::{AppError, AppResult};
+
+/// Read-only API key for a centralized exchange
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ApiCredentials {
+    pub api_key: String,
+    pub api_secret: String,
+    /// Required by OKX
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+/// Source of exchange credentials, looked up per profile and venue.
+///
+/// A profile is the wallet identifier ledger requests are made with, so a wallet's CEX
+/// activity is merged into the same ledger as its on-chain activity.
+#[async_trait]
+pub trait CredentialStore: Send + Sync {
+    /// Credentials for a profile on a venue (`bybit`, `okx`), if configured
+    async fn get(&self, profile: &str, venue: &str) -> AppResult<Option<ApiCredentials>>;
+}
+
+/// Credentials loaded once from a JSON file of `{ "<profile>": { "<venue>": { ... } } }`
+pub struct FileCredentialStore {
+    profiles: HashMap<String, HashMap<String, ApiCredentials>>,
+}
+
+impl FileCredentialStore {
+    pub fn load(path: &str) -> AppResult<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            AppError::InternalError(format!("Failed to read credentials file {}: {}", path, e))
+        })?;
+        let profiles: HashMap<String, HashMap<String, ApiCredentials>> =
+            serde_json::from_str(&contents)?;
+
+        Ok(Self {
+            profiles: profiles
+                .into_iter()
+                .map(|(profile, venues)| (profile.to_lowercase(), venues))
+                .collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl CredentialStore for FileCredentialStore {
+    async fn get(&self, profile: &str, venue: &str) -> AppResult<Option<ApiCredentials>> {
+        Ok(self
+            .profiles
+            .get(&profile.to_lowercase())
+            .and_then(|venues| venues.get(venue))
+            .cloned())
+    }
+}
+
+/// An encrypted credential as persisted on disk
+#[derive(Clone, Serialize, Deserialize)]
+struct SealedCredentials {
+    nonce: String,
+    ciphertext: String,
+    updated_at: DateTime<Utc>,
+}
+
+/// Non-secret description of a stored credential
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialInfo {
+    pub profile: String,
+    pub venue: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Credentials encrypted at rest with AES-256-GCM under a master key.
+///
+/// Each entry is sealed separately with the profile and venue as associated data, so
+/// ciphertexts cannot be swapped between entries. Entries can be added, rotated and removed
+/// at runtime; every change rewrites the store file before it takes effect.
+pub struct EncryptedCredentialStore {
+    cipher: Aes256Gcm,
+    path: String,
+    profiles: RwLock<HashMap<String, HashMap<String, SealedCredentials>>>,
+}
+
+impl EncryptedCredentialStore {
+    /// Opens the store at `path`, starting empty if the file does not exist yet.
+    ///
+    /// `master_key` is a base64-encoded 32-byte key.
+    pub fn open(path: &str, master_key: &str) -> AppResult<Self> {
+        let key = STANDARD
+            .decode(master_key.trim())
+            .ok()
+            .filter(|key| key.len() == 32)
+            .ok_or_else(|| {
+                AppError::InternalError(
+                    "Credential master key must be 32 base64-encoded bytes".to_string(),
+                )
+            })?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+
+        let profiles: HashMap<String, HashMap<String, SealedCredentials>> =
+            match std::fs::read_to_string(path) {
+                Ok(contents) => serde_json::from_str(&contents)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+                Err(e) => {
+                    return Err(AppError::InternalError(format!(
+                        "Failed to read credential store {}: {}",
+                        path, e
+                    )))
+                }
+            };
+
+        let store = Self {
+            cipher,
+            path: path.to_string(),
+            profiles: RwLock::new(profiles),
+        };
+
+        // Fail at startup rather than on first use when the key does not match the file
+        for (profile, venues) in store
+            .profiles
+            .try_read()
+            .expect("store is not shared yet")
+            .iter()
+        {
+            for (venue, sealed) in venues {
+                store.open_entry(profile, venue, sealed)?;
+            }
+        }
+
+        Ok(store)
+    }
+
+    /// Adds credentials for a profile on a venue, replacing any existing ones
+    pub async fn put(
+        &self,
+        profile: &str,
+        venue: &str,
+        credentials: &ApiCredentials,
+    ) -> AppResult<CredentialInfo> {
+        let profile = profile.to_lowercase();
+        let sealed = self.seal(&profile, venue, credentials)?;
+        let updated_at = sealed.updated_at;
+
+        let mut profiles = self.profiles.write().await;
+        let mut next = profiles.clone();
+        next.entry(profile.clone())
+            .or_default()
+            .insert(venue.to_string(), sealed);
+        self.persist(&next).await?;
+        *profiles = next;
+
+        Ok(CredentialInfo {
+            profile,
+            venue: venue.to_string(),
+            updated_at,
+        })
+    }
+
+    /// Removes credentials for a profile on a venue, returning whether any existed
+    pub async fn remove(&self, profile: &str, venue: &str) -> AppResult<bool> {
+        let profile = profile.to_lowercase();
+
+        let mut profiles = self.profiles.write().await;
+        let mut next = profiles.clone();
+        let removed = next
+            .get_mut(&profile)
+            .and_then(|venues| venues.remove(venue))
+            .is_some();
+        if !removed {
+            return Ok(false);
+        }
+        next.retain(|_, venues| !venues.is_empty());
+        self.persist(&next).await?;
+        *profiles = next;
+
+        Ok(true)
+    }
+
+    /// Stored profiles and venues, without secrets
+    pub async fn list(&self) -> Vec<CredentialInfo> {
+        let mut entries: Vec<CredentialInfo> = self
+            .profiles
+            .read()
+            .await
+            .iter()
+            .flat_map(|(profile, venues)| {
+                venues.iter().map(|(venue, sealed)| CredentialInfo {
+                    profile: profile.clone(),
+                    venue: venue.clone(),
+                    updated_at: sealed.updated_at,
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| (&a.profile, &a.venue).cmp(&(&b.profile, &b.venue)));
+        entries
+    }
+
+    fn seal(
+        &self,
+        profile: &str,
+        venue: &str,
+        credentials: &ApiCredentials,
+    ) -> AppResult<SealedCredentials> {
+        let plaintext = serde_json::to_vec(credentials)?;
+        let aad = associated_data(profile, venue);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| AppError::InternalError("Failed to encrypt credentials".to_string()))?;
+
+        Ok(SealedCredentials {
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+            updated_at: Utc::now(),
+        })
+    }
+
+    fn open_entry(
+        &self,
+        profile: &str,
+        venue: &str,
+        sealed: &SealedCredentials,
+    ) -> AppResult<ApiCredentials> {
+        let invalid = || {
+            AppError::InternalError(format!(
+                "Failed to decrypt {} credentials for {}",
+                venue, profile
+            ))
+        };
+
+        let nonce = STANDARD
+            .decode(&sealed.nonce)
+            .ok()
+            .filter(|nonce| nonce.len() == 12)
+            .ok_or_else(invalid)?;
+        let ciphertext = STANDARD.decode(&sealed.ciphertext).map_err(|_| invalid())?;
+        let aad = associated_data(profile, venue);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| invalid())?;
+
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Writes the store to a temporary file and renames it over the old one
+    async fn persist(
+        &self,
+        profiles: &HashMap<String, HashMap<String, SealedCredentials>>,
+    ) -> AppResult<()> {
+        let contents = serde_json::to_vec_pretty(profiles)?;
+        let tmp_path = format!("{}.tmp", self.path);
+        let write_error = |e: std::io::Error| {
+            AppError::InternalError(format!(
+                "Failed to write credential store {}: {}",
+                self.path, e
+            ))
+        };
+
+        tokio::fs::write(&tmp_path, contents)
+            .await
+            .map_err(write_error)?;
+        tokio::fs::rename(&tmp_path, &self.path)
+            .await
+            .map_err(write_error)
+    }
+}
+
+#[async_trait]
+impl CredentialStore for EncryptedCredentialStore {
+    async fn get(&self, profile: &str, venue: &str) -> AppResult<Option<ApiCredentials>> {
+        let profile = profile.to_lowercase();
+        let profiles = self.profiles.read().await;
+
+        profiles
+            .get(&profile)
+            .and_then(|venues| venues.get(venue))
+            .map(|sealed| self.open_entry(&profile, venue, sealed))
+            .transpose()
+    }
+}
+
+fn associated_data(profile: &str, venue: &str) -> String {
+    format!("{}:{}", profile, venue)
+}
@@ -0,0 +1,271 @@
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::error::{AppError, AppResult};
+
+/// keccak256("Transfer(address,address,uint256)")
+const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+/// USDC uses 6 decimals
+const TOKEN_DECIMALS: i64 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferDirection {
+    /// Wallet to bridge
+    Deposit,
+    /// Bridge to wallet
+    Withdrawal,
+}
+
+/// A bridge token transfer observed on-chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnchainTransfer {
+    pub direction: TransferDirection,
+    pub amount: BigDecimal,
+    pub timestamp: DateTime<Utc>,
+    pub block_number: u64,
+    pub tx_hash: String,
+}
+
+/// Reads Hyperliquid bridge deposits and withdrawals from an EVM JSON-RPC endpoint.
+///
+/// Transfers are the bridge token's `Transfer` logs between the wallet and the bridge
+/// contract, scanned in fixed block ranges to stay within provider log limits.
+pub struct EvmTransferClient {
+    client: Client,
+    rpc_url: String,
+    bridge_address: String,
+    token_address: String,
+    block_range: u64,
+}
+
+impl EvmTransferClient {
+    pub fn new(rpc_url: &str, bridge_address: &str, token_address: &str, block_range: u64) -> Self {
+        Self {
+            client: Client::new(),
+            rpc_url: rpc_url.to_string(),
+            bridge_address: bridge_address.to_lowercase(),
+            token_address: token_address.to_lowercase(),
+            block_range: block_range.max(1),
+        }
+    }
+
+    async fn rpc(&self, method: &str, params: Value) -> AppResult<Value> {
+        let response = self
+            .client
+            .post(&self.rpc_url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalApiError(format!(
+                "RPC request failed: {}",
+                error_text
+            )));
+        }
+
+        let mut body: Value = response.json().await?;
+        if let Some(error) = body.get("error") {
+            return Err(AppError::ExternalApiError(format!(
+                "RPC {} failed: {}",
+                method, error
+            )));
+        }
+
+        Ok(body["result"].take())
+    }
+
+    async fn latest_block(&self) -> AppResult<u64> {
+        let result = self.rpc("eth_blockNumber", json!([])).await?;
+        parse_quantity(&result)
+            .ok_or_else(|| AppError::ExternalApiError("Invalid block number".to_string()))
+    }
+
+    async fn block_timestamp(&self, number: u64) -> AppResult<i64> {
+        let block = self
+            .rpc(
+                "eth_getBlockByNumber",
+                json!([format!("0x{:x}", number), false]),
+            )
+            .await?;
+        parse_quantity(&block["timestamp"])
+            .map(|ts| ts as i64)
+            .ok_or_else(|| AppError::ExternalApiError(format!("Block {} not found", number)))
+    }
+
+    /// Block timestamp, looked up once per block within a scan
+    async fn cached_block_timestamp(
+        &self,
+        number: u64,
+        block_timestamps: &mut HashMap<u64, i64>,
+    ) -> AppResult<i64> {
+        if let Some(ts) = block_timestamps.get(&number) {
+            return Ok(*ts);
+        }
+        let ts = self.block_timestamp(number).await?;
+        block_timestamps.insert(number, ts);
+        Ok(ts)
+    }
+
+    /// First block from `low` with a timestamp at or after `timestamp_s`, by binary search
+    async fn block_at(
+        &self,
+        timestamp_s: i64,
+        low: u64,
+        latest: u64,
+        block_timestamps: &mut HashMap<u64, i64>,
+    ) -> AppResult<u64> {
+        let (mut low, mut high) = (low, latest);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.cached_block_timestamp(mid, block_timestamps).await? < timestamp_s {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+
+    /// Bridge deposits and withdrawals for a wallet within time windows (epoch ms).
+    ///
+    /// Windows must be ordered and disjoint. Block lookups are shared between windows, so
+    /// nearby windows cost a few extra probes each rather than a full search.
+    pub async fn get_bridge_transfers(
+        &self,
+        wallet: &str,
+        windows: &[(i64, i64)],
+    ) -> AppResult<Vec<OnchainTransfer>> {
+        let latest = self.latest_block().await?;
+        let mut block_timestamps: HashMap<u64, i64> = HashMap::new();
+        let mut transfers = Vec::new();
+        let mut low = 0;
+
+        for (start_time, end_time) in windows {
+            let from_block = self
+                .block_at(start_time / 1000, low, latest, &mut block_timestamps)
+                .await?;
+            let to_block = self
+                .block_at(end_time / 1000, from_block, latest, &mut block_timestamps)
+                .await?;
+            transfers.extend(
+                self.scan_transfers(wallet, from_block, to_block, &mut block_timestamps)
+                    .await?,
+            );
+            low = to_block;
+        }
+
+        Ok(transfers)
+    }
+
+    /// Bridge transfer logs between two blocks, scanned in chunks of `block_range`
+    async fn scan_transfers(
+        &self,
+        wallet: &str,
+        from_block: u64,
+        to_block: u64,
+        block_timestamps: &mut HashMap<u64, i64>,
+    ) -> AppResult<Vec<OnchainTransfer>> {
+        let wallet_topic = address_topic(wallet);
+        let bridge_topic = address_topic(&self.bridge_address);
+
+        let mut transfers = Vec::new();
+        let mut chunk_start = from_block;
+
+        while chunk_start <= to_block {
+            let chunk_end = (chunk_start + self.block_range - 1).min(to_block);
+            let logs = self
+                .rpc(
+                    "eth_getLogs",
+                    json!([{
+                        "address": self.token_address,
+                        "fromBlock": format!("0x{:x}", chunk_start),
+                        "toBlock": format!("0x{:x}", chunk_end),
+                        "topics": [
+                            TRANSFER_TOPIC,
+                            [wallet_topic, bridge_topic],
+                            [wallet_topic, bridge_topic],
+                        ],
+                    }]),
+                )
+                .await?;
+
+            for log in logs.as_array().into_iter().flatten() {
+                let topics: Vec<&str> = log["topics"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|t| t.as_str())
+                    .collect();
+                let direction = match (topics.get(1), topics.get(2)) {
+                    (Some(from), Some(to)) if *from == wallet_topic && *to == bridge_topic => {
+                        TransferDirection::Deposit
+                    }
+                    (Some(from), Some(to)) if *from == bridge_topic && *to == wallet_topic => {
+                        TransferDirection::Withdrawal
+                    }
+                    _ => continue,
+                };
+
+                let Some(block_number) = parse_quantity(&log["blockNumber"]) else {
+                    continue;
+                };
+
+                // Some nodes include the block time in logs; otherwise look it up once per block
+                let timestamp_s = match parse_quantity(&log["blockTimestamp"]) {
+                    Some(ts) => ts as i64,
+                    None => {
+                        self.cached_block_timestamp(block_number, block_timestamps)
+                            .await?
+                    }
+                };
+
+                transfers.push(OnchainTransfer {
+                    direction,
+                    amount: parse_amount(log["data"].as_str().unwrap_or_default()),
+                    timestamp: DateTime::from_timestamp(timestamp_s, 0).unwrap_or_default(),
+                    block_number,
+                    tx_hash: log["transactionHash"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                });
+            }
+
+            chunk_start = chunk_end + 1;
+        }
+
+        Ok(transfers)
+    }
+}
+
+/// An address left-padded to a 32-byte log topic
+fn address_topic(address: &str) -> String {
+    format!("0x{:0>64}", address.trim_start_matches("0x").to_lowercase())
+}
+
+fn parse_quantity(value: &Value) -> Option<u64> {
+    value
+        .as_str()
+        .and_then(|v| u64::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+}
+
+/// Token amount from a `Transfer` log's data word
+fn parse_amount(data: &str) -> BigDecimal {
+    BigInt::parse_bytes(data.trim_start_matches("0x").as_bytes(), 16)
+        .map(|raw| BigDecimal::new(raw, TOKEN_DECIMALS))
+        .unwrap_or_default()
+}
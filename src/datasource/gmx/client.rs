@@ -0,0 +1,481 @@
+use async_trait::async_trait;
+use bigdecimal::{BigDecimal, Zero};
+use reqwest::Client;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::datasource::{Capability, DataSource};
+use crate::error::{AppError, AppResult};
+
+/// Venue tag and coin prefix for GMX events, e.g. `gmx:ETH`
+pub const GMX_VENUE: &str = "gmx";
+
+const MAX_ITEMS_PER_REQUEST: usize = 500;
+
+/// GMX stores USD amounts and prices with 30 decimals
+const USD_DECIMALS: i64 = 30;
+
+/// Order types that change a position (swaps are excluded)
+const INCREASE_ORDER_TYPES: [u64; 2] = [2, 3];
+const DECREASE_ORDER_TYPES: [u64; 4] = [4, 5, 6, 7];
+
+const TRADE_ACTIONS_QUERY: &str = r#"
+query TradeActions($account: String!, $since: Int!, $limit: Int!, $offset: Int!) {
+  tradeActions(
+    where: { account_eq: $account, eventName_eq: "OrderExecuted", timestamp_gte: $since }
+    orderBy: timestamp_ASC
+    limit: $limit
+    offset: $offset
+  ) {
+    id
+    orderType
+    marketAddress
+    isLong
+    sizeDeltaUsd
+    executionPrice
+    positionFeeAmount
+    borrowingFeeAmount
+    fundingFeeAmount
+    collateralTokenPriceMin
+    pnlUsd
+    timestamp
+    transaction { hash }
+  }
+}
+"#;
+
+/// Index token of a GMX market
+#[derive(Debug, Clone)]
+struct MarketToken {
+    symbol: String,
+    decimals: i64,
+}
+
+/// Reads GMX v2 trade history from the GMX subgraph and prices from the GMX API.
+///
+/// Trades and fees are mapped into the Hyperliquid-shaped records the rest of the ledger
+/// parses: executed increase/decrease orders become fills, and the borrowing and funding
+/// fees settled on them become funding payments. Coins are prefixed with `gmx:` so GMX
+/// positions never net against Hyperliquid positions in the same asset.
+pub struct GmxClient {
+    client: Client,
+    subgraph_url: String,
+    api_url: String,
+    /// Index tokens by market address; `None` marks markets the API does not list
+    markets: RwLock<HashMap<String, Option<MarketToken>>>,
+}
+
+impl GmxClient {
+    pub fn new(subgraph_url: &str, api_url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            subgraph_url: subgraph_url.to_string(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            markets: RwLock::new(HashMap::new()),
+        }
+    }
+
+    async fn get_json(&self, path: &str) -> AppResult<Value> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.api_url, path))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalApiError(format!(
+                "GMX API request failed: {}",
+                error_text
+            )));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    async fn query(&self, query: &str, variables: Value) -> AppResult<Value> {
+        let response = self
+            .client
+            .post(&self.subgraph_url)
+            .json(&json!({ "query": query, "variables": variables }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalApiError(format!(
+                "GMX subgraph request failed: {}",
+                error_text
+            )));
+        }
+
+        let mut result: Value = response.json().await?;
+        if let Some(errors) = result.get("errors") {
+            return Err(AppError::ExternalApiError(format!(
+                "GMX subgraph returned errors: {}",
+                errors
+            )));
+        }
+
+        Ok(result["data"].take())
+    }
+
+    /// Fetches all executed position orders for an account, paging by offset
+    async fn fetch_trade_actions(
+        &self,
+        wallet: &str,
+        start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        let mut all_items = Vec::new();
+        let since = start_time.map(|ms| ms / 1000).unwrap_or(0);
+
+        loop {
+            let variables = json!({
+                "account": wallet,
+                "since": since,
+                "limit": MAX_ITEMS_PER_REQUEST,
+                "offset": all_items.len(),
+            });
+            let mut data = self.query(TRADE_ACTIONS_QUERY, variables).await?;
+
+            let items = data["tradeActions"]
+                .take()
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            let items_count = items.len();
+            all_items.extend(items);
+
+            if items_count < MAX_ITEMS_PER_REQUEST {
+                break;
+            }
+        }
+
+        Ok(all_items
+            .into_iter()
+            .filter(|action| {
+                action["orderType"].as_u64().is_some_and(|t| {
+                    INCREASE_ORDER_TYPES.contains(&t) || DECREASE_ORDER_TYPES.contains(&t)
+                })
+            })
+            .collect())
+    }
+
+    /// Token addresses with their symbol and decimals
+    async fn fetch_tokens(&self) -> AppResult<HashMap<String, MarketToken>> {
+        let tokens = self.get_json("/tokens").await?;
+
+        Ok(tokens["tokens"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|token| {
+                let address = token["address"].as_str()?.to_lowercase();
+                let symbol = token["symbol"].as_str()?.to_string();
+                let decimals = token["decimals"].as_i64()?;
+                Some((address, MarketToken { symbol, decimals }))
+            })
+            .collect())
+    }
+
+    /// Resolves a market's index token, reloading the market list for unseen markets
+    async fn market_token(&self, market: &str) -> AppResult<Option<MarketToken>> {
+        let market = market.to_lowercase();
+        if let Some(token) = self.markets.read().await.get(&market) {
+            return Ok(token.clone());
+        }
+
+        let tokens = self.fetch_tokens().await?;
+        let markets = self.get_json("/markets").await?;
+
+        let mut cache = self.markets.write().await;
+        for entry in markets["markets"].as_array().into_iter().flatten() {
+            if let (Some(market_address), Some(index_token)) =
+                (entry["marketToken"].as_str(), entry["indexToken"].as_str())
+                && let Some(token) = tokens.get(&index_token.to_lowercase())
+            {
+                cache.insert(market_address.to_lowercase(), Some(token.clone()));
+            }
+        }
+
+        Ok(cache.entry(market).or_insert(None).clone())
+    }
+
+    /// Maps an executed order into a Hyperliquid-shaped fill
+    async fn to_fill(&self, action: &Value) -> AppResult<Option<Value>> {
+        let Some(token) = self
+            .market_token(action["marketAddress"].as_str().unwrap_or_default())
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let size_usd = scaled(&action["sizeDeltaUsd"], USD_DECIMALS);
+        let price = scaled(&action["executionPrice"], USD_DECIMALS - token.decimals);
+        if price.is_zero() {
+            return Ok(None);
+        }
+
+        let is_increase = action["orderType"]
+            .as_u64()
+            .is_some_and(|t| INCREASE_ORDER_TYPES.contains(&t));
+        let is_long = action["isLong"].as_bool().unwrap_or(false);
+        let side = if is_increase == is_long { "B" } else { "A" };
+
+        let position_fee = collateral_usd(action, "positionFeeAmount");
+        let pnl = scaled(&action["pnlUsd"], USD_DECIMALS);
+
+        Ok(Some(json!({
+            "venue": GMX_VENUE,
+            "coin": format!("{}:{}", GMX_VENUE, token.symbol),
+            "side": side,
+            "sz": (size_usd / &price).round(12).normalized().to_string(),
+            "px": price.round(12).normalized().to_string(),
+            "fee": position_fee.round(8).normalized().to_string(),
+            "closedPnl": pnl.round(8).normalized().to_string(),
+            "crossed": true,
+            "time": timestamp_ms(action),
+            "tid": action["id"],
+            "hash": action["transaction"]["hash"],
+        })))
+    }
+
+    /// Maps the borrowing and funding fees settled on an order into a funding payment
+    async fn to_funding(&self, action: &Value) -> AppResult<Option<Value>> {
+        let cost = collateral_usd(action, "borrowingFeeAmount")
+            + collateral_usd(action, "fundingFeeAmount");
+        if cost.is_zero() {
+            return Ok(None);
+        }
+
+        let Some(token) = self
+            .market_token(action["marketAddress"].as_str().unwrap_or_default())
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(json!({
+            "venue": GMX_VENUE,
+            "coin": format!("{}:{}", GMX_VENUE, token.symbol),
+            "usdc": (-cost).round(8).normalized().to_string(),
+            "fundingRate": "0",
+            "time": timestamp_ms(action),
+            "hash": action["transaction"]["hash"],
+        })))
+    }
+}
+
+#[async_trait]
+impl DataSource for GmxClient {
+    fn capabilities(&self) -> BTreeSet<Capability> {
+        BTreeSet::from([
+            Capability::Fills,
+            Capability::Funding,
+            Capability::Mids,
+            Capability::Candles,
+        ])
+    }
+
+    fn venue_sources(&self) -> Vec<(String, Arc<dyn DataSource>)> {
+        Vec::new()
+    }
+
+    async fn get_fills(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        let mut fills = Vec::new();
+        for action in self.fetch_trade_actions(wallet, start_time).await? {
+            if let Some(fill) = self.to_fill(&action).await? {
+                fills.push(fill);
+            }
+        }
+        Ok(fills)
+    }
+
+    async fn get_fill_page(
+        &self,
+        _wallet: &str,
+        _start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        Err(AppError::ValidationError(
+            "GMX fill pages are not supported".to_string(),
+        ))
+    }
+
+    async fn get_funding(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        let mut funding = Vec::new();
+        for action in self.fetch_trade_actions(wallet, start_time).await? {
+            if let Some(payment) = self.to_funding(&action).await? {
+                funding.push(payment);
+            }
+        }
+        Ok(funding)
+    }
+
+    async fn get_funding_page(&self, _wallet: &str, _start_time: i64) -> AppResult<Vec<Value>> {
+        Err(AppError::ValidationError(
+            "GMX funding pages are not supported".to_string(),
+        ))
+    }
+
+    async fn get_ledger_updates(
+        &self,
+        _wallet: &str,
+        _start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        // Collateral moves with each order on GMX; there are no separate transfers
+        Ok(Vec::new())
+    }
+
+    async fn get_ledger_page(&self, _wallet: &str, _start_time: i64) -> AppResult<Vec<Value>> {
+        Err(AppError::ValidationError(
+            "GMX ledger update pages are not supported".to_string(),
+        ))
+    }
+
+    async fn get_user_state(&self, _wallet: &str) -> AppResult<Value> {
+        Err(AppError::ValidationError(
+            "GMX account state is not supported".to_string(),
+        ))
+    }
+
+    async fn get_all_mids(&self) -> AppResult<Value> {
+        let tokens = self.fetch_tokens().await?;
+        let tickers = self.get_json("/prices/tickers").await?;
+
+        let mut mids = Map::new();
+        for ticker in tickers.as_array().into_iter().flatten() {
+            let Some(token) = ticker["tokenAddress"]
+                .as_str()
+                .and_then(|address| tokens.get(&address.to_lowercase()))
+            else {
+                continue;
+            };
+
+            let decimals = USD_DECIMALS - token.decimals;
+            let mid = (scaled(&ticker["minPrice"], decimals)
+                + scaled(&ticker["maxPrice"], decimals))
+                / BigDecimal::from(2);
+            mids.insert(
+                format!("{}:{}", GMX_VENUE, token.symbol),
+                Value::String(mid.round(12).normalized().to_string()),
+            );
+        }
+
+        Ok(Value::Object(mids))
+    }
+
+    async fn get_meta(&self) -> AppResult<Value> {
+        Ok(json!({ "universe": [] }))
+    }
+
+    async fn get_spot_meta(&self) -> AppResult<Value> {
+        Ok(json!({ "tokens": [], "universe": [] }))
+    }
+
+    async fn get_funding_rates(
+        &self,
+        _coin: &str,
+        _start_time: i64,
+        _end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        // GMX funding accrues continuously from open interest imbalance; no rate history is published
+        Ok(Vec::new())
+    }
+
+    async fn get_predicted_fundings(&self) -> AppResult<Value> {
+        Err(AppError::ValidationError(
+            "GMX predicted funding is not supported".to_string(),
+        ))
+    }
+
+    async fn get_candles(
+        &self,
+        coin: &str,
+        interval: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        let symbol = coin
+            .strip_prefix(GMX_VENUE)
+            .and_then(|c| c.strip_prefix(':'))
+            .unwrap_or(coin);
+        let period_ms = interval_millis(interval).ok_or_else(|| {
+            AppError::ValidationError(format!("Unsupported candle interval: {}", interval))
+        })?;
+        let limit = ((end_time - start_time) / period_ms + 1).clamp(1, 10_000);
+
+        let response = self
+            .get_json(&format!(
+                "/prices/candles?tokenSymbol={}&period={}&limit={}",
+                symbol, interval, limit
+            ))
+            .await?;
+
+        // Rows are `[timestamp_s, open, high, low, close]`, newest first
+        let mut candles: Vec<Value> = response["candles"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|row| {
+                let open_time = row.get(0)?.as_i64()? * 1000;
+                let price = |i: usize| {
+                    let p = row.get(i)?;
+                    p.as_str()
+                        .map(String::from)
+                        .or_else(|| p.as_f64().map(|f| f.to_string()))
+                };
+                Some(json!({
+                    "t": open_time,
+                    "T": open_time + period_ms - 1,
+                    "o": price(1)?,
+                    "h": price(2)?,
+                    "l": price(3)?,
+                    "c": price(4)?,
+                }))
+            })
+            .filter(|candle| {
+                candle["T"].as_i64().is_some_and(|t| t >= start_time)
+                    && candle["t"].as_i64().is_some_and(|t| t <= end_time)
+            })
+            .collect();
+        candles.sort_by_key(|candle| candle["t"].as_i64());
+
+        Ok(candles)
+    }
+}
+
+/// Parses an integer string with a fixed number of implied decimals
+fn scaled(value: &Value, decimals: i64) -> BigDecimal {
+    value
+        .as_str()
+        .and_then(|v| BigDecimal::from_str(&format!("{}e-{}", v, decimals)).ok())
+        .unwrap_or_default()
+}
+
+/// Converts a collateral token amount to USD using the order's collateral price.
+///
+/// GMX prices are quoted per smallest token unit with 30 decimals, so the product needs no
+/// token decimals.
+fn collateral_usd(action: &Value, amount_key: &str) -> BigDecimal {
+    scaled(&action[amount_key], 0) * scaled(&action["collateralTokenPriceMin"], USD_DECIMALS)
+}
+
+fn timestamp_ms(action: &Value) -> i64 {
+    action["timestamp"].as_i64().unwrap_or_default() * 1000
+}
+
+fn interval_millis(interval: &str) -> Option<i64> {
+    let minutes = match interval {
+        "1m" => 1,
+        "5m" => 5,
+        "15m" => 15,
+        "1h" => 60,
+        "4h" => 240,
+        "1d" => 1440,
+        _ => return None,
+    };
+    Some(minutes * 60 * 1000)
+}
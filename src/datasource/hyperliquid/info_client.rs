@@ -1,47 +1,235 @@
 use async_trait::async_trait;
 use reqwest::Client;
+use serde::Serialize;
 use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::{Layer, Service};
 
-use crate::datasource::DataSource;
+use crate::datasource::{Capability, DataSource};
 use crate::error::{AppError, AppResult};
 
 const MAX_ITEMS_PER_REQUEST: usize = 500;
 
+/// TCP keepalive probes stop idle pooled sockets from being dropped by middleboxes
+const TCP_KEEPALIVE_SECS: u64 = 30;
+
+/// How long an idle pooled connection is kept for reuse
+const POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+
+/// HTTP/2 PING interval, keeping connections warm between pagination bursts
+const HTTP2_KEEPALIVE_INTERVAL_SECS: u64 = 20;
+
+/// How long to wait for a PING acknowledgement before closing the connection
+const HTTP2_KEEPALIVE_TIMEOUT_SECS: u64 = 10;
+
+/// Attempts per request, counting the first, when upstream rate-limits or fails transiently
+const DEFAULT_MAX_ATTEMPTS: u32 = 4;
+
+/// Delay before the first retry; doubled for each one after
+const DEFAULT_RETRY_BASE_MS: u64 = 500;
+
+/// Requests that reached an upstream server, and the connections opened for them
+#[derive(Debug, Default)]
+pub struct ConnectionStats {
+    requests: AtomicU64,
+    connections: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionMetrics {
+    pub requests: u64,
+    pub new_connections: u64,
+    /// Share of requests sent over an already open connection
+    pub reuse_rate: Option<f64>,
+}
+
+impl ConnectionStats {
+    pub fn metrics(&self) -> ConnectionMetrics {
+        let requests = self.requests.load(Ordering::Relaxed);
+        let new_connections = self.connections.load(Ordering::Relaxed);
+
+        ConnectionMetrics {
+            requests,
+            new_connections,
+            reuse_rate: (requests > 0)
+                .then(|| 1.0 - (new_connections.min(requests) as f64 / requests as f64)),
+        }
+    }
+}
+
+/// Connector layer counting connections as they are established
+#[derive(Clone)]
+struct CountConnections(Arc<ConnectionStats>);
+
+impl<S> Layer<S> for CountConnections {
+    type Service = CountedConnector<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CountedConnector {
+            inner,
+            stats: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct CountedConnector<S> {
+    inner: S,
+    stats: Arc<ConnectionStats>,
+}
+
+impl<S, R> Service<R> for CountedConnector<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let connecting = self.inner.call(request);
+        let stats = self.stats.clone();
+
+        Box::pin(async move {
+            let connection = connecting.await?;
+            stats.connections.fetch_add(1, Ordering::Relaxed);
+            Ok(connection)
+        })
+    }
+}
+
+/// Client for the Hyperliquid info API.
+///
+/// Connections are pooled and negotiate HTTP/2 where the server offers it, so pagination
+/// loops multiplex over one warm connection instead of paying a handshake per page.
 #[derive(Clone)]
 pub struct HyperliquidInfoClient {
     client: Client,
     base_url: String,
+    stats: Arc<ConnectionStats>,
+    max_attempts: u32,
+    retry_base: Duration,
+}
+
+/// Outcome of one failed request attempt
+struct AttemptError {
+    error: AppError,
+    /// Network errors, 429s and 5xx responses may succeed on retry
+    retryable: bool,
 }
 
 impl HyperliquidInfoClient {
     pub fn new(base_url: &str) -> Self {
+        let stats = Arc::new(ConnectionStats::default());
+        let client = Client::builder()
+            .tcp_keepalive(Duration::from_secs(TCP_KEEPALIVE_SECS))
+            .tcp_nodelay(true)
+            .pool_idle_timeout(Duration::from_secs(POOL_IDLE_TIMEOUT_SECS))
+            .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+            .http2_adaptive_window(true)
+            .http2_keep_alive_interval(Duration::from_secs(HTTP2_KEEPALIVE_INTERVAL_SECS))
+            .http2_keep_alive_timeout(Duration::from_secs(HTTP2_KEEPALIVE_TIMEOUT_SECS))
+            .http2_keep_alive_while_idle(true)
+            .connector_layer(CountConnections(stats.clone()))
+            .build()
+            .expect("HTTP client configuration is valid");
+
         Self {
-            client: Client::new(),
+            client,
             base_url: base_url.to_string(),
+            stats,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            retry_base: Duration::from_millis(DEFAULT_RETRY_BASE_MS),
         }
     }
 
+    /// Overrides how often and how patiently transient failures are retried
+    pub fn with_retry(mut self, max_attempts: u32, retry_base: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_base = retry_base;
+        self
+    }
+
+    /// Counters shared with every clone of this client
+    pub fn connection_stats(&self) -> Arc<ConnectionStats> {
+        self.stats.clone()
+    }
+
+    /// Posts a request, retrying rate limits and transient failures with exponential backoff
     async fn post(&self, payload: Value) -> AppResult<Value> {
+        let mut delay = self.retry_base;
+        let mut attempt = 1;
+
+        loop {
+            match self.try_post(&payload).await {
+                Ok(result) => return Ok(result),
+                Err(failure) if !failure.retryable || attempt >= self.max_attempts => {
+                    return Err(failure.error);
+                }
+                Err(failure) => {
+                    tracing::debug!(
+                        "Hyperliquid attempt {} failed, retrying in {:?}: {}",
+                        attempt,
+                        delay,
+                        failure.error
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn try_post(&self, payload: &Value) -> Result<Value, AttemptError> {
         let response = self
             .client
             .post(&self.base_url)
-            .json(&payload)
+            .json(payload)
             .send()
-            .await?;
+            .await
+            .map_err(|e| AttemptError {
+                error: e.into(),
+                retryable: true,
+            })?;
+        self.stats.requests.fetch_add(1, Ordering::Relaxed);
 
-        if !response.status().is_success() {
+        let status = response.status();
+        if !status.is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            return Err(AppError::ExternalApiError(format!(
-                "Hyperliquid request failed: {}",
-                error_text
-            )));
+            return Err(AttemptError {
+                error: AppError::ExternalApiError(format!(
+                    "Hyperliquid request failed ({}): {}",
+                    status, error_text
+                )),
+                retryable: status.is_server_error()
+                    || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+            });
         }
 
-        let result: Value = response.json().await?;
-        Ok(result)
+        response.json().await.map_err(|e| AttemptError {
+            error: e.into(),
+            retryable: false,
+        })
     }
 
-    /// Fetches all items with pagination handling (500 item limit)
+    /// Fetches all items with pagination handling (500 item limit).
+    ///
+    /// Each page after the first starts at the previous page's last timestamp, so items
+    /// sharing that millisecond are not skipped; those already returned are dropped.
     async fn fetch_paginated(
         &self,
         request_type: &str,
@@ -50,6 +238,8 @@ impl HyperliquidInfoClient {
     ) -> AppResult<Vec<Value>> {
         let mut all_items = Vec::new();
         let mut current_start_time = start_time;
+        // Items of the previous page at the timestamp the current page starts from
+        let mut seen_at_start: Vec<Value> = Vec::new();
 
         loop {
             let mut payload = json!({
@@ -63,10 +253,7 @@ impl HyperliquidInfoClient {
 
             let response = self.post(payload).await?;
 
-            let items = response
-                .as_array()
-                .cloned()
-                .unwrap_or_default();
+            let items = response.as_array().cloned().unwrap_or_default();
 
             let items_count = items.len();
 
@@ -79,8 +266,21 @@ impl HyperliquidInfoClient {
                 .last()
                 .and_then(|item| item.get("time"))
                 .and_then(|t| t.as_i64());
+            let at_last_timestamp: Vec<Value> = items
+                .iter()
+                .filter(|item| {
+                    last_timestamp.is_some()
+                        && item.get("time").and_then(|t| t.as_i64()) == last_timestamp
+                })
+                .cloned()
+                .collect();
 
-            all_items.extend(items);
+            let new_items: Vec<Value> = items
+                .into_iter()
+                .filter(|item| !seen_at_start.contains(item))
+                .collect();
+            let made_progress = !new_items.is_empty();
+            all_items.extend(new_items);
 
             // If we got fewer than 500 items, we've reached the end
             if items_count < MAX_ITEMS_PER_REQUEST {
@@ -88,10 +288,17 @@ impl HyperliquidInfoClient {
             }
 
             // Update start time for next request
-            if let Some(ts) = last_timestamp {
-                current_start_time = Some(ts + 1);
-            } else {
-                break;
+            match last_timestamp {
+                // A full page within one millisecond: move past it rather than loop
+                Some(ts) if !made_progress => {
+                    current_start_time = Some(ts + 1);
+                    seen_at_start.clear();
+                }
+                Some(ts) => {
+                    current_start_time = Some(ts);
+                    seen_at_start = at_last_timestamp;
+                }
+                None => break,
             }
         }
 
@@ -101,12 +308,69 @@ impl HyperliquidInfoClient {
 
 #[async_trait]
 impl DataSource for HyperliquidInfoClient {
+    fn capabilities(&self) -> BTreeSet<Capability> {
+        Capability::ALL.into_iter().collect()
+    }
+
+    fn venue_sources(&self) -> Vec<(String, Arc<dyn DataSource>)> {
+        Vec::new()
+    }
+
     async fn get_fills(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
         self.fetch_paginated("userFills", wallet, start_time).await
     }
 
+    async fn get_fill_page(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        let payload = match start_time {
+            Some(start_time) => json!({
+                "type": "userFillsByTime",
+                "user": wallet,
+                "startTime": start_time
+            }),
+            None => json!({
+                "type": "userFills",
+                "user": wallet
+            }),
+        };
+
+        let response = self.post(payload).await?;
+        Ok(response.as_array().cloned().unwrap_or_default())
+    }
+
     async fn get_funding(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
-        self.fetch_paginated("userFunding", wallet, start_time).await
+        self.fetch_paginated("userFunding", wallet, start_time)
+            .await
+    }
+
+    async fn get_funding_page(&self, wallet: &str, start_time: i64) -> AppResult<Vec<Value>> {
+        let response = self
+            .post(json!({
+                "type": "userFunding",
+                "user": wallet,
+                "startTime": start_time
+            }))
+            .await?;
+        Ok(response.as_array().cloned().unwrap_or_default())
+    }
+
+    async fn get_ledger_updates(
+        &self,
+        wallet: &str,
+        start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        self.fetch_paginated("userNonFundingLedgerUpdates", wallet, start_time)
+            .await
+    }
+
+    async fn get_ledger_page(&self, wallet: &str, start_time: i64) -> AppResult<Vec<Value>> {
+        let response = self
+            .post(json!({
+                "type": "userNonFundingLedgerUpdates",
+                "user": wallet,
+                "startTime": start_time
+            }))
+            .await?;
+        Ok(response.as_array().cloned().unwrap_or_default())
     }
 
     async fn get_user_state(&self, wallet: &str) -> AppResult<Value> {
@@ -123,4 +387,86 @@ impl DataSource for HyperliquidInfoClient {
         });
         self.post(payload).await
     }
+
+    async fn get_meta(&self) -> AppResult<Value> {
+        let payload = json!({
+            "type": "meta"
+        });
+        self.post(payload).await
+    }
+
+    async fn get_spot_meta(&self) -> AppResult<Value> {
+        let payload = json!({
+            "type": "spotMeta"
+        });
+        self.post(payload).await
+    }
+
+    async fn get_funding_rates(
+        &self,
+        coin: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        let mut all_rates = Vec::new();
+        let mut current_start_time = start_time;
+
+        loop {
+            let payload = json!({
+                "type": "fundingHistory",
+                "coin": coin,
+                "startTime": current_start_time,
+                "endTime": end_time
+            });
+
+            let rates = self
+                .post(payload)
+                .await?
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            let rates_count = rates.len();
+            let last_timestamp = rates
+                .last()
+                .and_then(|rate| rate.get("time"))
+                .and_then(|t| t.as_i64());
+
+            all_rates.extend(rates);
+
+            match last_timestamp {
+                Some(ts) if rates_count >= MAX_ITEMS_PER_REQUEST => current_start_time = ts + 1,
+                _ => break,
+            }
+        }
+
+        Ok(all_rates)
+    }
+
+    async fn get_predicted_fundings(&self) -> AppResult<Value> {
+        let payload = json!({
+            "type": "predictedFundings"
+        });
+        self.post(payload).await
+    }
+
+    async fn get_candles(
+        &self,
+        coin: &str,
+        interval: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        let payload = json!({
+            "type": "candleSnapshot",
+            "req": {
+                "coin": coin,
+                "interval": interval,
+                "startTime": start_time,
+                "endTime": end_time
+            }
+        });
+
+        let response = self.post(payload).await?;
+        Ok(response.as_array().cloned().unwrap_or_default())
+    }
 }
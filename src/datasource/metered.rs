@@ -0,0 +1,195 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::datasource::{Capability, DataSource};
+use crate::error::AppResult;
+use crate::services::capture;
+use crate::services::slo::SloTracker;
+use crate::services::timing::{self, Phase};
+
+/// Records the outcome and latency of every call to a data source as an upstream SLO sample.
+///
+/// Calls made while serving a `?debug=capture` request are also added to the capture with
+/// their arguments and payloads.
+pub struct MeteredDataSource {
+    name: String,
+    inner: Arc<dyn DataSource>,
+    tracker: Arc<SloTracker>,
+}
+
+impl MeteredDataSource {
+    pub fn new(name: &str, inner: Arc<dyn DataSource>, tracker: Arc<SloTracker>) -> Self {
+        Self {
+            name: name.to_string(),
+            inner,
+            tracker,
+        }
+    }
+
+    async fn metered<T: Serialize>(
+        &self,
+        call: &str,
+        args: Value,
+        future: impl Future<Output = AppResult<T>>,
+    ) -> AppResult<T> {
+        let started = Instant::now();
+        let result = future.await;
+        timing::record(Phase::Upstream, started.elapsed());
+        self.tracker.record_upstream(
+            &self.name,
+            started.elapsed().as_millis() as u64,
+            result.is_ok(),
+        );
+
+        if capture::is_capturing() {
+            let payload = match &result {
+                Ok(value) => serde_json::to_value(value).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            capture::record_upstream(&self.name, call, args, payload);
+        }
+
+        result
+    }
+}
+
+#[async_trait]
+impl DataSource for MeteredDataSource {
+    fn capabilities(&self) -> BTreeSet<Capability> {
+        self.inner.capabilities()
+    }
+
+    fn venue_sources(&self) -> Vec<(String, Arc<dyn DataSource>)> {
+        self.inner.venue_sources()
+    }
+
+    async fn get_fills(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        self.metered(
+            "get_fills",
+            json!({ "wallet": wallet, "start_time": start_time }),
+            self.inner.get_fills(wallet, start_time),
+        )
+        .await
+    }
+
+    async fn get_fill_page(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        self.metered(
+            "get_fill_page",
+            json!({ "wallet": wallet, "start_time": start_time }),
+            self.inner.get_fill_page(wallet, start_time),
+        )
+        .await
+    }
+
+    async fn get_funding(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        self.metered(
+            "get_funding",
+            json!({ "wallet": wallet, "start_time": start_time }),
+            self.inner.get_funding(wallet, start_time),
+        )
+        .await
+    }
+
+    async fn get_funding_page(&self, wallet: &str, start_time: i64) -> AppResult<Vec<Value>> {
+        self.metered(
+            "get_funding_page",
+            json!({ "wallet": wallet, "start_time": start_time }),
+            self.inner.get_funding_page(wallet, start_time),
+        )
+        .await
+    }
+
+    async fn get_ledger_updates(
+        &self,
+        wallet: &str,
+        start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        self.metered(
+            "get_ledger_updates",
+            json!({ "wallet": wallet, "start_time": start_time }),
+            self.inner.get_ledger_updates(wallet, start_time),
+        )
+        .await
+    }
+
+    async fn get_ledger_page(&self, wallet: &str, start_time: i64) -> AppResult<Vec<Value>> {
+        self.metered(
+            "get_ledger_page",
+            json!({ "wallet": wallet, "start_time": start_time }),
+            self.inner.get_ledger_page(wallet, start_time),
+        )
+        .await
+    }
+
+    async fn get_user_state(&self, wallet: &str) -> AppResult<Value> {
+        self.metered(
+            "get_user_state",
+            json!({ "wallet": wallet }),
+            self.inner.get_user_state(wallet),
+        )
+        .await
+    }
+
+    async fn get_all_mids(&self) -> AppResult<Value> {
+        self.metered("get_all_mids", json!({}), self.inner.get_all_mids())
+            .await
+    }
+
+    async fn get_meta(&self) -> AppResult<Value> {
+        self.metered("get_meta", json!({}), self.inner.get_meta())
+            .await
+    }
+
+    async fn get_spot_meta(&self) -> AppResult<Value> {
+        self.metered("get_spot_meta", json!({}), self.inner.get_spot_meta())
+            .await
+    }
+
+    async fn get_funding_rates(
+        &self,
+        coin: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        self.metered(
+            "get_funding_rates",
+            json!({ "coin": coin, "start_time": start_time, "end_time": end_time }),
+            self.inner.get_funding_rates(coin, start_time, end_time),
+        )
+        .await
+    }
+
+    async fn get_predicted_fundings(&self) -> AppResult<Value> {
+        self.metered(
+            "get_predicted_fundings",
+            json!({}),
+            self.inner.get_predicted_fundings(),
+        )
+        .await
+    }
+
+    async fn get_candles(
+        &self,
+        coin: &str,
+        interval: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        self.metered(
+            "get_candles",
+            json!({
+                "coin": coin,
+                "interval": interval,
+                "start_time": start_time,
+                "end_time": end_time,
+            }),
+            self.inner.get_candles(coin, interval, start_time, end_time),
+        )
+        .await
+    }
+}
@@ -1,22 +1,119 @@
+pub mod bybit;
+pub mod cex;
+pub mod composite;
+pub mod credentials;
+pub mod evm;
+pub mod gmx;
 pub mod hyperliquid;
+pub mod metered;
+pub mod okx;
 
 use async_trait::async_trait;
+use serde::Serialize;
 use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::Arc;
 
 use crate::error::AppResult;
 
+/// A `DataSource` method a source actually serves, rather than answering empty or with an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Fills,
+    FillPages,
+    Funding,
+    FundingPages,
+    LedgerUpdates,
+    LedgerPages,
+    UserState,
+    Mids,
+    Meta,
+    SpotMeta,
+    FundingRates,
+    PredictedFundings,
+    Candles,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 13] = [
+        Capability::Fills,
+        Capability::FillPages,
+        Capability::Funding,
+        Capability::FundingPages,
+        Capability::LedgerUpdates,
+        Capability::LedgerPages,
+        Capability::UserState,
+        Capability::Mids,
+        Capability::Meta,
+        Capability::SpotMeta,
+        Capability::FundingRates,
+        Capability::PredictedFundings,
+        Capability::Candles,
+    ];
+}
+
 /// Trait for data sources that provide trading history
 #[async_trait]
 pub trait DataSource: Send + Sync {
+    /// Methods this source serves; the rest return nothing useful
+    fn capabilities(&self) -> BTreeSet<Capability>;
+
+    /// Additional venues merged into this source's history, by venue name
+    fn venue_sources(&self) -> Vec<(String, Arc<dyn DataSource>)>;
+
     /// Get user fills with pagination support
     async fn get_fills(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>>;
 
+    /// Get a single page of fills: the earliest at or after `start_time`, or the most recent
+    async fn get_fill_page(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>>;
+
     /// Get user funding payments with pagination support
     async fn get_funding(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>>;
 
+    /// Get a single page of funding payments, the earliest at or after `start_time`
+    async fn get_funding_page(&self, wallet: &str, start_time: i64) -> AppResult<Vec<Value>>;
+
+    /// Get deposits, withdrawals and other non-trading balance changes
+    async fn get_ledger_updates(
+        &self,
+        wallet: &str,
+        start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>>;
+
+    /// Get a single page of ledger updates, the earliest at or after `start_time`
+    async fn get_ledger_page(&self, wallet: &str, start_time: i64) -> AppResult<Vec<Value>>;
+
     /// Get user's current state (positions, balances)
     async fn get_user_state(&self, wallet: &str) -> AppResult<Value>;
 
     /// Get all available mid prices
     async fn get_all_mids(&self) -> AppResult<Value>;
+
+    /// Get perpetuals metadata (the asset universe)
+    async fn get_meta(&self) -> AppResult<Value>;
+
+    /// Get spot metadata (tokens and trading pairs)
+    async fn get_spot_meta(&self) -> AppResult<Value>;
+
+    /// Get historical funding rates for a coin between two timestamps (epoch milliseconds)
+    async fn get_funding_rates(
+        &self,
+        coin: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>>;
+
+    /// Get the next predicted funding rate per coin and venue, as
+    /// `[[coin, [[venue, { fundingRate, nextFundingTime, fundingIntervalHours }], ...]], ...]`
+    async fn get_predicted_fundings(&self) -> AppResult<Value>;
+
+    /// Get OHLC candles for a coin between two timestamps (epoch milliseconds)
+    async fn get_candles(
+        &self,
+        coin: &str,
+        interval: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>>;
 }
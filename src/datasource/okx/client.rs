@@ -0,0 +1,467 @@
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bigdecimal::BigDecimal;
+use chrono::{SecondsFormat, Utc};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::datasource::cex::{derive_position_pnl, hmac_sha256, keep_since};
+use crate::datasource::credentials::{ApiCredentials, CredentialStore};
+use crate::datasource::{Capability, DataSource};
+use crate::error::{AppError, AppResult};
+
+/// Venue tag and coin prefix for OKX events, e.g. `okx:BTC`
+pub const OKX_VENUE: &str = "okx";
+
+const MAX_ITEMS_PER_REQUEST: usize = 100;
+
+/// Funding rate history is returned at most 400 records at a time
+const MAX_FUNDING_RATES_PER_REQUEST: usize = 400;
+
+/// Funding fee bill type
+const FUNDING_BILL_TYPE: &str = "8";
+
+/// Successful deposit/withdrawal state
+const TRANSFER_SUCCESS_STATE: &str = "2";
+
+/// How far back OKX keeps fill and bill history
+const HISTORY_RETENTION_MS: i64 = 90 * 24 * 60 * 60 * 1000;
+
+/// Reads perpetual swap history from the OKX v5 API with a read-only key.
+///
+/// Fill sizes are converted from contracts to coins with each instrument's contract value.
+/// OKX keeps fill and bill history for three months, so older activity is not available.
+/// Wallets without OKX credentials have no OKX history.
+pub struct OkxClient {
+    client: Client,
+    base_url: String,
+    credentials: Arc<dyn CredentialStore>,
+    contract_values: RwLock<HashMap<String, BigDecimal>>,
+}
+
+impl OkxClient {
+    pub fn new(base_url: &str, credentials: Arc<dyn CredentialStore>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            credentials,
+            contract_values: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Sends a GET request, signed when credentials are given, and returns `data`
+    async fn get(&self, credentials: Option<&ApiCredentials>, path: &str) -> AppResult<Value> {
+        let mut request = self.client.get(format!("{}{}", self.base_url, path));
+
+        if let Some(credentials) = credentials {
+            let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+            let payload = format!("{}GET{}", timestamp, path);
+            let signature = STANDARD.encode(hmac_sha256(
+                credentials.api_secret.as_bytes(),
+                payload.as_bytes(),
+            ));
+
+            request = request
+                .header("OK-ACCESS-KEY", &credentials.api_key)
+                .header("OK-ACCESS-SIGN", signature)
+                .header("OK-ACCESS-TIMESTAMP", timestamp)
+                .header(
+                    "OK-ACCESS-PASSPHRASE",
+                    credentials.passphrase.as_deref().unwrap_or_default(),
+                );
+        }
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalApiError(format!(
+                "OKX request failed: {}",
+                error_text
+            )));
+        }
+
+        let mut body: Value = response.json().await?;
+        if body["code"].as_str() != Some("0") {
+            return Err(AppError::ExternalApiError(format!(
+                "OKX request failed: {}",
+                body["msg"].as_str().unwrap_or_default()
+            )));
+        }
+
+        Ok(body["data"].take())
+    }
+
+    /// Pages backwards from the newest record until `start_time`, or the start of OKX's
+    /// retention, using `cursor_key` as `after`
+    async fn fetch_paginated(
+        &self,
+        credentials: &ApiCredentials,
+        path: &str,
+        cursor_key: &str,
+        start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        let retained_from = Utc::now().timestamp_millis() - HISTORY_RETENTION_MS;
+        let begin = start_time.map_or(retained_from, |start| start.max(retained_from));
+        let mut all_items = Vec::new();
+        let mut after: Option<String> = None;
+
+        loop {
+            let mut url = format!("{}&limit={}", path, MAX_ITEMS_PER_REQUEST);
+            if let Some(cursor) = &after {
+                url.push_str(&format!("&after={}", cursor));
+            }
+
+            let items = self
+                .get(Some(credentials), &url)
+                .await?
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            let items_count = items.len();
+
+            let next = items
+                .last()
+                .and_then(|item| item[cursor_key].as_str())
+                .map(String::from);
+            let reached_start = items
+                .last()
+                .and_then(|item| item["ts"].as_str())
+                .and_then(|ts| ts.parse::<i64>().ok())
+                .is_some_and(|ts| ts < begin);
+
+            all_items.extend(items);
+
+            if items_count < MAX_ITEMS_PER_REQUEST || reached_start || next.is_none() {
+                break;
+            }
+            after = next;
+        }
+
+        Ok(all_items
+            .into_iter()
+            .filter(|item| {
+                item["ts"]
+                    .as_str()
+                    .and_then(|ts| ts.parse::<i64>().ok())
+                    .is_some_and(|ts| ts >= begin)
+            })
+            .collect())
+    }
+
+    /// Coins per contract for a swap instrument, loading the instrument list on a miss
+    async fn contract_value(&self, inst_id: &str) -> AppResult<Option<BigDecimal>> {
+        if let Some(value) = self.contract_values.read().await.get(inst_id) {
+            return Ok(Some(value.clone()));
+        }
+
+        let instruments = self
+            .get(None, "/api/v5/public/instruments?instType=SWAP")
+            .await?;
+
+        let mut cache = self.contract_values.write().await;
+        for instrument in instruments.as_array().into_iter().flatten() {
+            if let (Some(id), Some(value)) = (
+                instrument["instId"].as_str(),
+                instrument["ctVal"]
+                    .as_str()
+                    .and_then(|v| BigDecimal::from_str(v).ok()),
+            ) {
+                cache.insert(id.to_string(), value);
+            }
+        }
+
+        Ok(cache.get(inst_id).cloned())
+    }
+
+    async fn credentials(&self, wallet: &str) -> AppResult<Option<ApiCredentials>> {
+        self.credentials.get(wallet, OKX_VENUE).await
+    }
+}
+
+#[async_trait]
+impl DataSource for OkxClient {
+    fn capabilities(&self) -> BTreeSet<Capability> {
+        BTreeSet::from([
+            Capability::Fills,
+            Capability::Funding,
+            Capability::LedgerUpdates,
+            Capability::FundingRates,
+            Capability::Candles,
+        ])
+    }
+
+    fn venue_sources(&self) -> Vec<(String, Arc<dyn DataSource>)> {
+        Vec::new()
+    }
+
+    async fn get_fills(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        let Some(credentials) = self.credentials(wallet).await? else {
+            return Ok(Vec::new());
+        };
+
+        // Positions are derived from all retained fills, so fetch them regardless of start time
+        let raw_fills = self
+            .fetch_paginated(
+                &credentials,
+                "/api/v5/trade/fills-history?instType=SWAP",
+                "billId",
+                None,
+            )
+            .await?;
+
+        let mut fills = Vec::new();
+        for fill in &raw_fills {
+            let Some(inst_id) = fill["instId"].as_str() else {
+                continue;
+            };
+            let Some(contract_value) = self.contract_value(inst_id).await? else {
+                tracing::warn!("Skipping OKX fill for unknown instrument {}", inst_id);
+                continue;
+            };
+
+            let decimal = |key: &str| {
+                fill[key]
+                    .as_str()
+                    .and_then(|v| BigDecimal::from_str(v).ok())
+                    .unwrap_or_default()
+            };
+
+            // OKX reports fees as negative balance changes
+            fills.push(json!({
+                "venue": OKX_VENUE,
+                "coin": coin(inst_id),
+                "side": if fill["side"].as_str() == Some("buy") { "B" } else { "A" },
+                "sz": (decimal("fillSz") * &contract_value).normalized().to_string(),
+                "px": fill["fillPx"],
+                "fee": (-decimal("fee")).normalized().to_string(),
+                "feeToken": fill["feeCcy"],
+                "closedPnl": fill["fillPnl"],
+                "crossed": fill["execType"].as_str() == Some("T"),
+                "time": fill["ts"].as_str().and_then(|ts| ts.parse::<i64>().ok()),
+                "tid": fill["tradeId"],
+                "oid": fill["ordId"].as_str().and_then(|id| id.parse::<u64>().ok()),
+            }));
+        }
+
+        fills.sort_by_key(|fill| fill["time"].as_i64());
+        derive_position_pnl(&mut fills);
+        keep_since(&mut fills, start_time);
+        Ok(fills)
+    }
+
+    async fn get_fill_page(
+        &self,
+        _wallet: &str,
+        _start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        Err(AppError::ValidationError(
+            "OKX fill pages are not supported".to_string(),
+        ))
+    }
+
+    async fn get_funding(&self, wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        let Some(credentials) = self.credentials(wallet).await? else {
+            return Ok(Vec::new());
+        };
+
+        let bills = self
+            .fetch_paginated(
+                &credentials,
+                &format!(
+                    "/api/v5/account/bills-archive?instType=SWAP&type={}",
+                    FUNDING_BILL_TYPE
+                ),
+                "billId",
+                start_time,
+            )
+            .await?;
+
+        let mut funding: Vec<Value> = bills
+            .iter()
+            .filter_map(|bill| {
+                Some(json!({
+                    "venue": OKX_VENUE,
+                    "coin": coin(bill["instId"].as_str()?),
+                    "usdc": bill["balChg"],
+                    "token": bill["ccy"],
+                    "fundingRate": "0",
+                    "time": bill["ts"].as_str()?.parse::<i64>().ok()?,
+                }))
+            })
+            .collect();
+        funding.sort_by_key(|payment| payment["time"].as_i64());
+
+        Ok(funding)
+    }
+
+    async fn get_funding_page(&self, _wallet: &str, _start_time: i64) -> AppResult<Vec<Value>> {
+        Err(AppError::ValidationError(
+            "OKX funding pages are not supported".to_string(),
+        ))
+    }
+
+    async fn get_ledger_updates(
+        &self,
+        wallet: &str,
+        start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        let Some(credentials) = self.credentials(wallet).await? else {
+            return Ok(Vec::new());
+        };
+
+        let deposits = self
+            .fetch_paginated(
+                &credentials,
+                &format!(
+                    "/api/v5/asset/deposit-history?state={}",
+                    TRANSFER_SUCCESS_STATE
+                ),
+                "ts",
+                start_time,
+            )
+            .await?;
+        let withdrawals = self
+            .fetch_paginated(
+                &credentials,
+                &format!(
+                    "/api/v5/asset/withdrawal-history?state={}",
+                    TRANSFER_SUCCESS_STATE
+                ),
+                "ts",
+                start_time,
+            )
+            .await?;
+
+        let transfer = |record: &Value, kind: &str| {
+            Some(json!({
+                "venue": OKX_VENUE,
+                "time": record["ts"].as_str()?.parse::<i64>().ok()?,
+                "hash": record["txId"],
+                "delta": {
+                    "type": kind,
+                    "usdc": record["amt"],
+                    "token": record["ccy"],
+                },
+            }))
+        };
+
+        let mut updates: Vec<Value> = deposits
+            .iter()
+            .filter_map(|d| transfer(d, "deposit"))
+            .chain(withdrawals.iter().filter_map(|w| transfer(w, "withdraw")))
+            .collect();
+        updates.sort_by_key(|update| update["time"].as_i64());
+
+        Ok(updates)
+    }
+
+    async fn get_ledger_page(&self, _wallet: &str, _start_time: i64) -> AppResult<Vec<Value>> {
+        Err(AppError::ValidationError(
+            "OKX ledger update pages are not supported".to_string(),
+        ))
+    }
+
+    async fn get_user_state(&self, _wallet: &str) -> AppResult<Value> {
+        Err(AppError::ValidationError(
+            "OKX account state is not supported".to_string(),
+        ))
+    }
+
+    async fn get_all_mids(&self) -> AppResult<Value> {
+        Ok(json!({}))
+    }
+
+    async fn get_meta(&self) -> AppResult<Value> {
+        Ok(json!({ "universe": [] }))
+    }
+
+    async fn get_spot_meta(&self) -> AppResult<Value> {
+        Ok(json!({ "tokens": [], "universe": [] }))
+    }
+
+    async fn get_funding_rates(
+        &self,
+        coin: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        let base = coin
+            .strip_prefix(OKX_VENUE)
+            .and_then(|c| c.strip_prefix(':'))
+            .unwrap_or(coin);
+        let inst_id = format!("{}-USDT-SWAP", base);
+        let mut rates = Vec::new();
+        let mut after = end_time + 1;
+
+        // Records come newest first; `after` returns those older than the given time
+        loop {
+            let page: Vec<Value> = self
+                .get(
+                    None,
+                    &format!(
+                        "/api/v5/public/funding-rate-history?instId={}&after={}&limit={}",
+                        inst_id, after, MAX_FUNDING_RATES_PER_REQUEST
+                    ),
+                )
+                .await?
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|r| {
+                    Some(json!({
+                        "coin": coin,
+                        "fundingRate": r["realizedRate"].as_str().or(r["fundingRate"].as_str())?,
+                        "time": r["fundingTime"].as_str()?.parse::<i64>().ok()?,
+                    }))
+                })
+                .collect();
+            let page_count = page.len();
+            let oldest = page.iter().filter_map(|r| r["time"].as_i64()).min();
+
+            rates.extend(
+                page.into_iter()
+                    .filter(|r| r["time"].as_i64().is_some_and(|t| t >= start_time)),
+            );
+
+            match oldest {
+                Some(oldest)
+                    if page_count >= MAX_FUNDING_RATES_PER_REQUEST && oldest > start_time =>
+                {
+                    after = oldest
+                }
+                _ => break,
+            }
+        }
+
+        rates.sort_by_key(|rate| rate["time"].as_i64());
+        Ok(rates)
+    }
+
+    async fn get_predicted_fundings(&self) -> AppResult<Value> {
+        Err(AppError::ValidationError(
+            "OKX predicted funding is not supported".to_string(),
+        ))
+    }
+
+    async fn get_candles(
+        &self,
+        _coin: &str,
+        _interval: &str,
+        _start_time: i64,
+        _end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        // Market data checks are skipped for CEX coins
+        Ok(Vec::new())
+    }
+}
+
+/// Maps a swap instrument such as `BTC-USDT-SWAP` to `okx:BTC`
+fn coin(inst_id: &str) -> String {
+    let base = inst_id.split('-').next().unwrap_or(inst_id);
+    format!("{}:{}", OKX_VENUE, base)
+}
@@ -0,0 +1,3 @@
+pub mod client;
+
+pub use client::{OkxClient, OKX_VENUE};
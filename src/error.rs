@@ -10,6 +10,9 @@ pub enum AppError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
     #[error("Validation error: {0}")]
     ValidationError(String),
 
@@ -30,6 +33,7 @@ impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_message) = match &self {
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
             AppError::ValidationError(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
             AppError::ExternalApiError(msg) => (StatusCode::BAD_GATEWAY, msg.clone()),
             AppError::RequestError(e) => {
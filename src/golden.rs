@@ -0,0 +1,395 @@
+//! Golden-file regression tests for the PnL pipeline.
+//!
+//! Each case in `tests/golden/<case>/` feeds `input.json` through a fixture data source,
+//! ingestion, the timeline and every calculator, and compares the result with
+//! `expected.json`. After an intended change in numbers, regenerate the goldens with
+//! `cargo regen-goldens` and review the diff.
+
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::datasource::{Capability, DataSource};
+use crate::error::AppResult;
+use crate::services::aggregates::DailyAggregator;
+use crate::services::anomalies::{AnomalyConfig, AnomalyDetector};
+use crate::services::assets::AssetRegistry;
+use crate::services::capital::CapitalCalculator;
+use crate::services::ingestion::{Freshness, IngestionService};
+use crate::services::pnl_calculator::{FundingAttribution, PnlCalculator};
+use crate::services::positions::CostBasisEngine;
+use crate::services::statements::StatementCalculator;
+use crate::services::stats::StatsCalculator;
+use crate::services::timeline::{
+    group_funding, Granularity, TimelineEntry, TimelineEvent, TimelineService,
+};
+use crate::services::trades::TradeService;
+use crate::storage::memory::MemoryStorage;
+use crate::storage::Storage;
+
+const GOLDEN_DIR: &str = "tests/golden";
+
+/// Settings matching the API defaults
+const HISTOGRAM_BINS: usize = 20;
+const HORIZON_MINUTES: i64 = 5;
+const MAX_EQUITY_FRACTION: &str = "0.25";
+
+/// Upstream payloads for one wallet, in the shape the Hyperliquid API returns them
+#[derive(Debug, Clone, Deserialize)]
+struct Fixture {
+    wallet: String,
+    #[serde(default)]
+    fills: Vec<Value>,
+    #[serde(default)]
+    funding: Vec<Value>,
+    #[serde(default)]
+    ledger: Vec<Value>,
+    #[serde(default)]
+    user_state: Value,
+    #[serde(default = "empty_meta")]
+    meta: Value,
+    #[serde(default = "empty_spot_meta")]
+    spot_meta: Value,
+    /// Candles by coin
+    #[serde(default)]
+    candles: BTreeMap<String, Vec<Value>>,
+}
+
+fn empty_meta() -> Value {
+    json!({ "universe": [] })
+}
+
+fn empty_spot_meta() -> Value {
+    json!({ "tokens": [], "universe": [] })
+}
+
+/// Serves a fixture in place of an upstream API
+struct FixtureDataSource {
+    fixture: Fixture,
+}
+
+#[async_trait]
+impl DataSource for FixtureDataSource {
+    fn capabilities(&self) -> BTreeSet<Capability> {
+        Capability::ALL.into_iter().collect()
+    }
+
+    fn venue_sources(&self) -> Vec<(String, Arc<dyn DataSource>)> {
+        Vec::new()
+    }
+
+    async fn get_fills(&self, _wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        Ok(since(&self.fixture.fills, start_time))
+    }
+
+    async fn get_fill_page(&self, _wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        Ok(since(&self.fixture.fills, start_time))
+    }
+
+    async fn get_funding(&self, _wallet: &str, start_time: Option<i64>) -> AppResult<Vec<Value>> {
+        Ok(since(&self.fixture.funding, start_time))
+    }
+
+    async fn get_funding_page(&self, _wallet: &str, start_time: i64) -> AppResult<Vec<Value>> {
+        Ok(since(&self.fixture.funding, Some(start_time)))
+    }
+
+    async fn get_ledger_updates(
+        &self,
+        _wallet: &str,
+        start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        Ok(since(&self.fixture.ledger, start_time))
+    }
+
+    async fn get_ledger_page(&self, _wallet: &str, start_time: i64) -> AppResult<Vec<Value>> {
+        Ok(since(&self.fixture.ledger, Some(start_time)))
+    }
+
+    async fn get_user_state(&self, _wallet: &str) -> AppResult<Value> {
+        Ok(self.fixture.user_state.clone())
+    }
+
+    async fn get_all_mids(&self) -> AppResult<Value> {
+        Ok(json!({}))
+    }
+
+    async fn get_meta(&self) -> AppResult<Value> {
+        Ok(self.fixture.meta.clone())
+    }
+
+    async fn get_spot_meta(&self) -> AppResult<Value> {
+        Ok(self.fixture.spot_meta.clone())
+    }
+
+    async fn get_funding_rates(
+        &self,
+        _coin: &str,
+        _start_time: i64,
+        _end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_predicted_fundings(&self) -> AppResult<Value> {
+        Ok(json!([]))
+    }
+
+    async fn get_candles(
+        &self,
+        coin: &str,
+        _interval: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        Ok(self
+            .fixture
+            .candles
+            .get(coin)
+            .into_iter()
+            .flatten()
+            .filter(|c| {
+                c["t"]
+                    .as_i64()
+                    .is_some_and(|t| t >= start_time && t <= end_time)
+            })
+            .cloned()
+            .collect())
+    }
+}
+
+fn since(items: &[Value], start_time: Option<i64>) -> Vec<Value> {
+    items
+        .iter()
+        .filter(|item| start_time.is_none_or(|start| item["time"].as_i64() >= Some(start)))
+        .cloned()
+        .collect()
+}
+
+/// Runs a fixture through ingestion, the timeline and every calculator
+async fn run_pipeline(fixture: Fixture) -> Value {
+    let wallet = fixture.wallet.clone();
+    let datasource: Arc<dyn DataSource> = Arc::new(FixtureDataSource { fixture });
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+
+    let asset_registry = Arc::new(AssetRegistry::new(datasource.clone(), storage.clone()));
+    asset_registry.refresh().await.expect("fixture metadata");
+
+    let ingestion = Arc::new(IngestionService::new(
+        datasource,
+        storage.clone(),
+        AnomalyDetector::new(AnomalyConfig::default()),
+        true,
+    ));
+    let timeline_service = Arc::new(TimelineService::new(asset_registry));
+    let pnl_calculator = Arc::new(PnlCalculator::new(FundingAttribution::default()));
+    let daily_aggregator = DailyAggregator::new(
+        ingestion.clone(),
+        storage,
+        timeline_service.clone(),
+        pnl_calculator.clone(),
+    );
+    let stats_calculator = StatsCalculator::new();
+    let trade_service = TradeService::new();
+
+    let history = ingestion
+        .fetch_history(&wallet, None, Freshness::Blocking)
+        .await
+        .expect("fixture history");
+    let user_state = ingestion
+        .fetch_user_state(&wallet)
+        .await
+        .expect("fixture state");
+    let (materialized, _) = daily_aggregator
+        .daily_pnl(&wallet, &history)
+        .await
+        .expect("daily aggregates");
+
+    let mut timeline = timeline_service
+        .build_timeline(&wallet, history.fills, history.funding)
+        .expect("timeline");
+    timeline_service.add_ledger_updates(&mut timeline, history.ledger);
+
+    let unrealized_pnl = pnl_calculator.calculate_unrealized_from_state(&user_state);
+    let summary = pnl_calculator.calculate_summary(&wallet, &timeline, unrealized_pnl);
+    let daily = pnl_calculator.calculate_daily(&timeline, FundingAttribution::default());
+    assert_eq!(
+        serde_json::to_value(&materialized).expect("serialize"),
+        serde_json::to_value(&daily).expect("serialize"),
+        "materialized daily PnL diverged from the timeline"
+    );
+    let statement = StatementCalculator::new().calculate(&timeline, FundingAttribution::default());
+
+    let equity = stats_calculator.equity_from_state(&user_state);
+    let sizing = stats_calculator.calculate_sizing(
+        &wallet,
+        &timeline,
+        equity,
+        MAX_EQUITY_FRACTION.parse::<BigDecimal>().expect("decimal"),
+    );
+
+    let mut trips = trade_service.build_round_trips(&timeline);
+    let as_of = timeline
+        .events
+        .last()
+        .map(|e| e.timestamp())
+        .expect("fixture events");
+    for trip in &mut trips {
+        let Some(entry_time) = trip.entry_time else {
+            continue;
+        };
+        let exit_time = trip.exit_time.unwrap_or(as_of);
+        let candles = ingestion
+            .fetch_candles(
+                &trip.coin,
+                entry_time.timestamp_millis(),
+                exit_time.timestamp_millis(),
+            )
+            .await
+            .expect("fixture candles");
+        trip.excursion = stats_calculator.calculate_excursion(trip, &candles, as_of);
+    }
+    let excursions = stats_calculator.calculate_excursion_stats(&wallet, &trips);
+    let distributions =
+        stats_calculator.calculate_distributions(&wallet, &timeline, &trips, HISTOGRAM_BINS);
+
+    let coins: BTreeSet<String> = timeline
+        .events
+        .iter()
+        .filter_map(|e| match e {
+            TimelineEvent::Fill { coin, .. } => Some(coin.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut market_making = BTreeMap::new();
+    let mut decompositions = BTreeMap::new();
+    for coin in coins {
+        decompositions.insert(
+            coin.clone(),
+            pnl_calculator.decompose(
+                &timeline,
+                &coin,
+                Granularity::Daily,
+                FundingAttribution::default(),
+            ),
+        );
+
+        let times: Vec<i64> = timeline
+            .events
+            .iter()
+            .filter(|e| matches!(e, TimelineEvent::Fill { coin: c, .. } if *c == coin))
+            .map(|e| e.timestamp().timestamp_millis())
+            .collect();
+        let (first, last) = (times[0], times[times.len() - 1]);
+        let candles = ingestion
+            .fetch_candles(&coin, first, last + HORIZON_MINUTES * 60 * 1000)
+            .await
+            .expect("fixture candles");
+
+        market_making.insert(
+            coin.clone(),
+            stats_calculator.calculate_market_making(
+                &wallet,
+                &coin,
+                &timeline,
+                &candles,
+                HORIZON_MINUTES,
+            ),
+        );
+    }
+
+    let engine = CostBasisEngine::replay(&timeline.events);
+    let positions = engine.snapshot();
+    let open_lots = engine.open_lots(&json!({}), as_of);
+    let funding_rollups: Vec<TimelineEntry> = group_funding(timeline.events.clone())
+        .into_iter()
+        .filter(|entry| matches!(entry, TimelineEntry::FundingRollup(_)))
+        .collect();
+    let capital_calculator = CapitalCalculator::new();
+    let capital_efficiency = capital_calculator.calculate(
+        &timeline,
+        &capital_calculator.leverage_from_state(&user_state),
+        as_of,
+    );
+
+    json!({
+        "timeline": timeline,
+        "funding_rollups": funding_rollups,
+        "summary": summary,
+        "daily": daily,
+        "statement": statement,
+        "positions": positions,
+        "open_lots": open_lots,
+        "round_trips": trips,
+        "sizing": sizing,
+        "distributions": distributions,
+        "excursions": excursions,
+        "market_making": market_making,
+        "decompositions": decompositions,
+        "capital_efficiency": capital_efficiency,
+    })
+}
+
+fn cases() -> Vec<PathBuf> {
+    let mut cases: Vec<PathBuf> =
+        std::fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(GOLDEN_DIR))
+            .expect("golden directory")
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.join("input.json").is_file())
+            .collect();
+    cases.sort();
+    cases
+}
+
+async fn render(case: &Path) -> String {
+    let input = std::fs::read_to_string(case.join("input.json")).expect("fixture input");
+    let fixture: Fixture = serde_json::from_str(&input).expect("valid fixture");
+    let output = run_pipeline(fixture).await;
+    serde_json::to_string_pretty(&output).expect("serializable output") + "\n"
+}
+
+#[tokio::test]
+async fn pipeline_matches_goldens() {
+    let cases = cases();
+    assert!(!cases.is_empty(), "no golden cases in {}", GOLDEN_DIR);
+
+    let mut mismatched = Vec::new();
+    for case in &cases {
+        let actual = render(case).await;
+        let expected = std::fs::read_to_string(case.join("expected.json")).unwrap_or_default();
+        if actual != expected {
+            mismatched.push(case.display().to_string());
+        }
+    }
+
+    assert!(
+        mismatched.is_empty(),
+        "output differs from goldens for {:?}; run `cargo regen-goldens` if the change is intended",
+        mismatched
+    );
+}
+
+#[tokio::test]
+async fn pipeline_is_deterministic() {
+    for case in cases() {
+        assert_eq!(
+            render(&case).await,
+            render(&case).await,
+            "{}",
+            case.display()
+        );
+    }
+}
+
+/// Rewrites every `expected.json`; run through `cargo regen-goldens`
+#[tokio::test]
+#[ignore]
+async fn regenerate_goldens() {
+    for case in cases() {
+        std::fs::write(case.join("expected.json"), render(&case).await).expect("write golden");
+    }
+}
@@ -0,0 +1,22 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::error::AppResult;
+use crate::services::activity::ActivitySummary;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    pub wallet: String,
+}
+
+/// Screening profile built from a couple of upstream requests instead of a full sync
+pub async fn get_activity(
+    State(state): State<AppState>,
+    Query(query): Query<ActivityQuery>,
+) -> AppResult<Json<ActivitySummary>> {
+    Ok(Json(state.activity_service.summarize(&query.wallet).await?))
+}
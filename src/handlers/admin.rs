@@ -0,0 +1,291 @@
+use axum::{
+    extract::{FromRequestParts, Path, Query, State},
+    http::{header, request::Parts, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+use crate::datasource::bybit::BYBIT_VENUE;
+use crate::datasource::credentials::{ApiCredentials, CredentialInfo, EncryptedCredentialStore};
+use crate::datasource::okx::OKX_VENUE;
+use crate::error::{AppError, AppResult};
+use crate::services::alerts::{DeadLetter, FiredAlert};
+use crate::services::backfill::BACKFILL_JOB_KIND;
+use crate::services::capture::{Capture, CaptureSummary};
+use crate::services::heat::HeatReport;
+use crate::services::ingestion::{Freshness, NORMALIZATION_VERSION};
+use crate::services::invariants::SelfTestReport;
+use crate::services::jobs::Job;
+use crate::services::slo::SloReport;
+use crate::AppState;
+
+/// Extractor guarding admin endpoints with a bearer token from `ADMIN_API_KEY`
+pub struct AdminAuth;
+
+impl FromRequestParts<AppState> for AdminAuth {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> AppResult<Self> {
+        let expected = state
+            .admin_api_key
+            .as_deref()
+            .ok_or_else(|| AppError::Unauthorized("Admin API is disabled".to_string()))?;
+
+        let provided = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        // Constant-time comparison, so response timing reveals nothing about the key
+        let matches = provided
+            .is_some_and(|provided| bool::from(provided.as_bytes().ct_eq(expected.as_bytes())));
+        if !matches {
+            return Err(AppError::Unauthorized(
+                "Invalid admin credentials".to_string(),
+            ));
+        }
+
+        Ok(AdminAuth)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportJobQuery {
+    pub wallet: String,
+}
+
+pub async fn start_s3_export(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(query): Query<ExportJobQuery>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    let job = state.archive_service.start_export(&query.wallet)?;
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BackfillQuery {
+    pub wallet: String,
+}
+
+/// Fetches a wallet's full history page by page; checkpoints are reported on the returned job
+pub async fn start_backfill(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(query): Query<BackfillQuery>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    let job = state.backfill_service.start(&query.wallet)?;
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+pub async fn list_jobs(_admin: AdminAuth, State(state): State<AppState>) -> Json<Vec<Job>> {
+    Json(state.job_registry.list())
+}
+
+pub async fn get_job(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Job>> {
+    state
+        .job_registry
+        .get(id)
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Job {} not found", id)))
+}
+
+pub async fn resume_job(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    let job = match state.job_registry.get(id) {
+        Some(job) if job.kind == BACKFILL_JOB_KIND => state.backfill_service.resume(id)?,
+        _ => state.archive_service.resume_export(id)?,
+    };
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReprocessStatus {
+    pub normalization_version: u32,
+    /// Stored wallets derived with an older version
+    pub outdated_wallets: Vec<String>,
+}
+
+pub async fn get_reprocess_status(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> AppResult<Json<ReprocessStatus>> {
+    Ok(Json(ReprocessStatus {
+        normalization_version: NORMALIZATION_VERSION,
+        outdated_wallets: state.ingestion_service.outdated_wallets().await?,
+    }))
+}
+
+/// Re-derives outdated wallets from raw payloads; progress is reported on the returned job
+pub async fn start_reprocess(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    let job = state.reprocess_service.start()?;
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// Activity scores of stored wallets, for capacity planning
+pub async fn get_wallet_heat(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> AppResult<Json<HeatReport>> {
+    Ok(Json(state.heat_tracker.report(Utc::now()).await?))
+}
+
+fn credential_store(state: &AppState) -> AppResult<&EncryptedCredentialStore> {
+    state.credential_store.as_deref().ok_or_else(|| {
+        AppError::ValidationError("Encrypted credential store is not configured".to_string())
+    })
+}
+
+pub async fn list_credentials(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> AppResult<Json<Vec<CredentialInfo>>> {
+    Ok(Json(credential_store(&state)?.list().await))
+}
+
+/// Adds or rotates a profile's API key for a venue; datasources use it on their next request
+pub async fn put_credentials(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path((profile, venue)): Path<(String, String)>,
+    Json(credentials): Json<ApiCredentials>,
+) -> AppResult<Json<CredentialInfo>> {
+    if ![BYBIT_VENUE, OKX_VENUE].contains(&venue.as_str()) {
+        return Err(AppError::ValidationError(format!(
+            "Unknown credential venue: {}",
+            venue
+        )));
+    }
+    if credentials.api_key.is_empty() || credentials.api_secret.is_empty() {
+        return Err(AppError::ValidationError(
+            "api_key and api_secret are required".to_string(),
+        ));
+    }
+
+    let info = credential_store(&state)?
+        .put(&profile, &venue, &credentials)
+        .await?;
+
+    Ok(Json(info))
+}
+
+pub async fn delete_credentials(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path((profile, venue)): Path<(String, String)>,
+) -> AppResult<StatusCode> {
+    if !credential_store(&state)?.remove(&profile, &venue).await? {
+        return Err(AppError::NotFound(format!(
+            "No {} credentials for {}",
+            venue, profile
+        )));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_slo(_admin: AdminAuth, State(state): State<AppState>) -> Json<SloReport> {
+    Json(state.slo_tracker.report())
+}
+
+pub async fn list_captures(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> Json<Vec<CaptureSummary>> {
+    Json(state.capture_store.list())
+}
+
+pub async fn get_capture(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Capture>> {
+    state
+        .capture_store
+        .get(id)
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Capture {} not found", id)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SelfTestQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+/// Recomputes a wallet's PnL views and checks that they agree with each other
+pub async fn run_selftest(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(query): Query<SelfTestQuery>,
+) -> AppResult<Json<SelfTestReport>> {
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+
+    let user_state = state
+        .ingestion_service
+        .fetch_user_state(&query.wallet)
+        .await?;
+
+    let timeline =
+        state
+            .timeline_service
+            .build_timeline(&query.wallet, history.fills, history.funding)?;
+
+    let unrealized_pnl = state
+        .pnl_calculator
+        .calculate_unrealized_from_state(&user_state);
+    let summary = state
+        .pnl_calculator
+        .calculate_summary(&query.wallet, &timeline, unrealized_pnl);
+    let daily = state
+        .pnl_calculator
+        .calculate_daily(&timeline, state.pnl_calculator.funding_attribution());
+
+    let report = state
+        .invariant_checker
+        .check(&query.wallet, &timeline, &summary, &daily);
+    if !report.passed {
+        tracing::warn!("Self-test found invariant violations for {}", query.wallet);
+    }
+
+    Ok(Json(report))
+}
+
+pub async fn list_dead_letters(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> AppResult<Json<Vec<DeadLetter>>> {
+    Ok(Json(state.alert_service.dead_letters().await?))
+}
+
+/// Redelivers a failed webhook; it leaves the dead-letter list once delivered
+pub async fn replay_dead_letter(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<FiredAlert>> {
+    Ok(Json(state.alert_service.replay_dead_letter(id).await?))
+}
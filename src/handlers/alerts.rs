@@ -0,0 +1,92 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::handlers::admin::AdminAuth;
+use crate::handlers::Pagination;
+use crate::services::alerts::{AlertRule, AlertRuleInput, FiredAlert};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct RulesQuery {
+    pub wallet: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub wallet: Option<String>,
+    pub rule_id: Option<Uuid>,
+}
+
+pub async fn list_rules(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(query): Query<RulesQuery>,
+) -> AppResult<Json<Vec<AlertRule>>> {
+    let rules = state
+        .alert_service
+        .list_rules(query.wallet.as_deref())
+        .await?;
+
+    Ok(Json(rules))
+}
+
+pub async fn create_rule(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Json(input): Json<AlertRuleInput>,
+) -> AppResult<(StatusCode, Json<AlertRule>)> {
+    let rule = state.alert_service.create_rule(input).await?;
+
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+pub async fn get_rule(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<AlertRule>> {
+    let rule = state.alert_service.get_rule(id).await?;
+
+    Ok(Json(rule))
+}
+
+pub async fn update_rule(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(input): Json<AlertRuleInput>,
+) -> AppResult<Json<AlertRule>> {
+    let rule = state.alert_service.update_rule(id, input).await?;
+
+    Ok(Json(rule))
+}
+
+pub async fn delete_rule(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    state.alert_service.delete_rule(id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_history(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+    Query(pagination): Query<Pagination>,
+) -> AppResult<Json<Vec<FiredAlert>>> {
+    let alerts = state
+        .alert_service
+        .history(query.wallet.as_deref(), query.rule_id)
+        .await?;
+
+    Ok(Json(pagination.apply(alerts)))
+}
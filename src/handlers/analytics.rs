@@ -0,0 +1,53 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::error::AppResult;
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::capital::CapitalEfficiency;
+use crate::services::ingestion::Freshness;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+/// Margin utilization, idle time and returns on average equity and deployed capital
+pub async fn get_analytics(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> AppResult<(FreshnessHeaders, Json<CapitalEfficiency>)> {
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let user_state = state
+        .ingestion_service
+        .fetch_user_state(&query.wallet)
+        .await?;
+
+    // Deposits and withdrawals are needed to track equity over time
+    let mut timeline =
+        state
+            .timeline_service
+            .build_timeline(&query.wallet, history.fills, history.funding)?;
+    state
+        .timeline_service
+        .add_ledger_updates(&mut timeline, history.ledger);
+
+    let leverage = state.capital_calculator.leverage_from_state(&user_state);
+    let efficiency = state
+        .capital_calculator
+        .calculate(&timeline, &leverage, Utc::now());
+
+    Ok((headers, Json(efficiency)))
+}
@@ -0,0 +1,90 @@
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::AppResult;
+use crate::handlers::share::report_data;
+use crate::services::anomalies::without_flags;
+use crate::services::attestation::{Attestation, AttestationCheck};
+use crate::services::corrections::EventCategory;
+use crate::services::ingestion::Freshness;
+use crate::services::sharing::SharedReportKind;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct AttestationQuery {
+    pub wallet: String,
+    pub report: SharedReportKind,
+    pub since: Option<i64>,
+}
+
+/// Downloads a signed attestation of a report and the venue events behind it
+pub async fn get_attestation(
+    State(state): State<AppState>,
+    Query(query): Query<AttestationQuery>,
+) -> AppResult<impl IntoResponse> {
+    // Always fetch from upstream so the attestation reflects venue data at generation time
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, Freshness::Blocking)
+        .await?;
+
+    let service = &state.attestation_service;
+    let mut events = Vec::new();
+    // Attest the venue's payloads, not the flags ingestion added to them
+    for fill in &without_flags(history.fills.clone()) {
+        if let Some(event) = state.timeline_service.parse_fill(fill) {
+            events.push(service.attested_event(
+                event.id().to_string(),
+                EventCategory::Fill,
+                fill,
+            )?);
+        }
+    }
+    for payment in &without_flags(history.funding.clone()) {
+        if let Some(event) = state.timeline_service.parse_funding(payment) {
+            events.push(service.attested_event(
+                event.id().to_string(),
+                EventCategory::Funding,
+                payment,
+            )?);
+        }
+    }
+
+    let data = report_data(&state, query.report, &query.wallet, query.since, history).await?;
+    let attestation = service.attest(query.report, &query.wallet, data, events)?;
+
+    let disposition = format!(
+        "attachment; filename=\"attestation-{}-{}.json\"",
+        query.wallet,
+        attestation.claims.generated_at.format("%Y%m%dT%H%M%SZ")
+    );
+
+    Ok((
+        [(header::CONTENT_DISPOSITION, disposition)],
+        Json(attestation),
+    ))
+}
+
+/// Key that attestations from this server are signed with
+pub async fn get_public_key(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({
+        "algorithm": "ed25519",
+        "public_key": state.attestation_service.public_key(),
+    }))
+}
+
+/// Checks an attestation's signature and hashes against this server's key
+pub async fn verify_attestation(
+    State(state): State<AppState>,
+    Json(attestation): Json<Attestation>,
+) -> AppResult<Json<AttestationCheck>> {
+    let check = state.attestation_service.verify(&attestation)?;
+
+    Ok(Json(check))
+}
@@ -0,0 +1,54 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppResult;
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::corrections::Restatement;
+use crate::services::ingestion::Freshness;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct RestatementsQuery {
+    pub wallet: String,
+    /// Only restatements detected at or after this time (ms)
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestatementReport {
+    pub wallet: String,
+    pub synced_at: DateTime<Utc>,
+    pub restatements: Vec<Restatement>,
+}
+
+pub async fn get_restatements(
+    State(state): State<AppState>,
+    Query(query): Query<RestatementsQuery>,
+) -> AppResult<(FreshnessHeaders, Json<RestatementReport>)> {
+    // Sync first so restatements since the last sync are detected
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, None, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let mut restatements = state.ingestion_service.restatements(&query.wallet).await?;
+    if let Some(since) = query.since {
+        restatements.retain(|r| r.detected_at.timestamp_millis() >= since);
+    }
+
+    Ok((
+        headers,
+        Json(RestatementReport {
+            wallet: query.wallet,
+            synced_at: history.synced_at,
+            restatements,
+        }),
+    ))
+}
@@ -0,0 +1,44 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::error::AppResult;
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::basis::BasisReport;
+use crate::services::ingestion::Freshness;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct BasisQuery {
+    pub wallet: String,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+pub async fn get_basis(
+    State(state): State<AppState>,
+    Query(query): Query<BasisQuery>,
+) -> AppResult<(FreshnessHeaders, Json<BasisReport>)> {
+    // Fetch full history so both legs are sized from the start
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, None, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let mids = state.ingestion_service.fetch_all_mids().await?;
+
+    let timeline =
+        state
+            .timeline_service
+            .build_timeline(&query.wallet, history.fills, history.funding)?;
+
+    let report = state
+        .basis_tracker
+        .track(&query.wallet, &timeline, &mids, Utc::now());
+
+    Ok((headers, Json(report)))
+}
@@ -0,0 +1,107 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::datasource::Capability;
+use crate::error::{AppError, AppResult};
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::benchmark::{BenchmarkBasket, BenchmarkComparison, Rebalance};
+use crate::services::ingestion::Freshness;
+use crate::AppState;
+
+/// Days compared when `since` is omitted
+const DEFAULT_BENCHMARK_DAYS: i64 = 90;
+
+#[derive(Debug, Deserialize)]
+pub struct CustomBenchmarkQuery {
+    pub wallet: String,
+    /// Basket weights, e.g. `BTC:0.6,ETH:0.4`
+    pub weights: String,
+    #[serde(default)]
+    pub rebalance: Rebalance,
+    /// Start of the comparison in milliseconds
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+/// Compares a wallet's daily returns with a user-defined static-weight basket
+pub async fn get_custom_benchmark(
+    State(state): State<AppState>,
+    Query(query): Query<CustomBenchmarkQuery>,
+) -> AppResult<(FreshnessHeaders, Json<BenchmarkComparison>)> {
+    let basket = BenchmarkBasket::parse(&query.weights, query.rebalance)?;
+    for weight in &basket.weights {
+        if !state
+            .source_registry
+            .supports(&weight.coin, Capability::Candles)
+        {
+            return Err(AppError::ValidationError(format!(
+                "{}'s venue publishes no candles",
+                weight.coin
+            )));
+        }
+    }
+
+    let now = Utc::now();
+    let since = query
+        .since
+        .unwrap_or_else(|| (now - Duration::days(DEFAULT_BENCHMARK_DAYS)).timestamp_millis());
+    let start: NaiveDate = chrono::DateTime::from_timestamp_millis(since)
+        .filter(|start| *start < now)
+        .ok_or_else(|| AppError::ValidationError("since must be in the past".to_string()))?
+        .date_naive();
+
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, Some(since), query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let user_state = state
+        .ingestion_service
+        .fetch_user_state(&query.wallet)
+        .await?;
+    let equity = state.stats_calculator.equity_from_state(&user_state);
+
+    let mut timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, history.funding)?;
+    state
+        .timeline_service
+        .add_ledger_updates(&mut timeline, history.ledger);
+
+    let daily = state
+        .pnl_calculator
+        .calculate_daily(&timeline, state.pnl_calculator.funding_attribution());
+
+    // Candles from the start of the first day, so its close is known
+    let range_start = start.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let mut candles = BTreeMap::new();
+    for weight in &basket.weights {
+        let coin_candles = state
+            .ingestion_service
+            .fetch_candles(
+                &weight.coin,
+                range_start.timestamp_millis(),
+                now.timestamp_millis(),
+            )
+            .await?;
+        candles.insert(weight.coin.clone(), coin_candles);
+    }
+
+    let comparison = state.benchmark_calculator.compare(
+        &timeline,
+        &daily,
+        &equity,
+        basket,
+        &candles,
+        start..=now.date_naive(),
+    )?;
+
+    Ok((headers, Json(comparison)))
+}
@@ -0,0 +1,57 @@
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+
+use crate::error::AppResult;
+use crate::handlers::freshness_headers;
+use crate::services::export::ExportFormat;
+use crate::services::ingestion::Freshness;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    /// BCP 47 locale controlling decimal separators, e.g. `de-DE`
+    pub locale: Option<String>,
+    /// `iso`, `us`, `eu` or a strftime pattern
+    pub date_format: Option<String>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+pub async fn get_journal_csv(
+    State(state): State<AppState>,
+    Query(query): Query<ExportQuery>,
+) -> AppResult<impl IntoResponse> {
+    let format = ExportFormat::new(query.locale.as_deref(), query.date_format.as_deref())?;
+
+    // Fetch fills and funding
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    // Build timeline and round trips
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, history.funding)?;
+
+    let trips = state.trade_service.build_round_trips(&timeline);
+    let csv = state.export_service.journal_csv(&trips, &format)?;
+
+    let disposition = format!("attachment; filename=\"journal-{}.csv\"", query.wallet);
+
+    Ok((
+        headers,
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        csv,
+    ))
+}
@@ -1,27 +1,103 @@
 use axum::{
     extract::{Query, State},
+    http::{HeaderMap, HeaderName},
     Json,
 };
 use serde::Deserialize;
 use serde_json::Value;
 
 use crate::error::AppResult;
+use crate::handlers::{
+    freshness_headers, summary_headers, FreshnessHeaders, Pagination, VENUE_SPECIFIC_SCHEMA,
+};
+use crate::services::anomalies::without_flags;
+use crate::services::ingestion::Freshness;
+use crate::services::normalized::{NormalizedEvents, NormalizedFill};
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
 pub struct FillsQuery {
     pub wallet: String,
     pub since: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EnrichmentQuery {
+    /// Adds the mid price at fill time, and slippage against it, to recent fills
+    #[serde(default)]
+    pub with_mids: bool,
 }
 
+/// Fills as the upstream venue returned them; the shape depends on the datasource
 pub async fn get_fills(
     State(state): State<AppState>,
     Query(query): Query<FillsQuery>,
-) -> AppResult<Json<Vec<Value>>> {
-    let fills = state
+    Query(pagination): Query<Pagination>,
+) -> AppResult<(
+    FreshnessHeaders,
+    HeaderMap,
+    [(HeaderName, &'static str); 1],
+    Json<Vec<Value>>,
+)> {
+    let history = state
         .ingestion_service
-        .fetch_all_fills(&query.wallet, query.since)
+        .fetch_history(&query.wallet, query.since, query.freshness)
         .await?;
 
-    Ok(Json(fills))
+    // Summarize the full selection, then page through it
+    let mut summary = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills.clone(), Vec::new())?
+        .summary();
+    summary.count = history.fills.len();
+
+    Ok((
+        freshness_headers(&history),
+        summary_headers(&summary),
+        VENUE_SPECIFIC_SCHEMA,
+        Json(pagination.apply(without_flags(history.fills))),
+    ))
+}
+
+/// Fills in the venue-independent model, oldest first
+pub async fn get_normalized_fills(
+    State(state): State<AppState>,
+    Query(query): Query<FillsQuery>,
+    Query(pagination): Query<Pagination>,
+    Query(enrichment): Query<EnrichmentQuery>,
+) -> AppResult<(
+    FreshnessHeaders,
+    HeaderMap,
+    Json<NormalizedEvents<NormalizedFill>>,
+)> {
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, Vec::new())?;
+    let summary = timeline.summary();
+    let fills = timeline
+        .events
+        .into_iter()
+        .filter_map(NormalizedFill::from_event)
+        .collect();
+
+    let mut fills = pagination.apply(fills);
+    if enrichment.with_mids {
+        for fill in &mut fills {
+            state.mids_poller.enrich(fill);
+        }
+    }
+
+    Ok((
+        headers,
+        summary_headers(&summary),
+        Json(NormalizedEvents::new(fills)),
+    ))
 }
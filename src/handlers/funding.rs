@@ -1,27 +1,247 @@
 use axum::{
     extract::{Query, State},
+    http::{HeaderMap, HeaderName},
     Json,
 };
+use chrono::{Duration, Utc};
 use serde::Deserialize;
 use serde_json::Value;
+use std::collections::HashMap;
 
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
+use crate::handlers::{
+    freshness_headers, summary_headers, FreshnessHeaders, Pagination, VENUE_SPECIFIC_SCHEMA,
+};
+use crate::services::forecasts::ForecastAccuracyReport;
+use crate::services::funding_comparison::FundingRateComparison;
+use crate::services::funding_scanner::{FundingOpportunity, ScannerSort};
+use crate::services::anomalies::without_flags;
+use crate::services::ingestion::Freshness;
+use crate::services::normalized::{NormalizedEvents, NormalizedFunding};
 use crate::AppState;
 
+/// Default number of coins returned by the scanner
+const DEFAULT_SCANNER_LIMIT: usize = 20;
+
+/// Most trailing days of realized funding the scanner looks up per coin
+const MAX_SCANNER_HISTORY_DAYS: i64 = 30;
+
+/// Default number of bins in funding rate comparison histograms
+const DEFAULT_COMPARISON_BINS: usize = 20;
+
+/// Largest number of comparison histogram bins a client may request
+const MAX_COMPARISON_BINS: usize = 200;
+
 #[derive(Debug, Deserialize)]
 pub struct FundingQuery {
     pub wallet: String,
     pub since: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
 }
 
+/// Funding payments as the upstream venue returned them; the shape depends on the datasource
 pub async fn get_funding(
     State(state): State<AppState>,
     Query(query): Query<FundingQuery>,
-) -> AppResult<Json<Vec<Value>>> {
-    let funding = state
+    Query(pagination): Query<Pagination>,
+) -> AppResult<(
+    FreshnessHeaders,
+    HeaderMap,
+    [(HeaderName, &'static str); 1],
+    Json<Vec<Value>>,
+)> {
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+
+    // Summarize the full selection, then page through it
+    let mut summary = state
+        .timeline_service
+        .build_timeline(&query.wallet, Vec::new(), history.funding.clone())?
+        .summary();
+    summary.count = history.funding.len();
+
+    Ok((
+        freshness_headers(&history),
+        summary_headers(&summary),
+        VENUE_SPECIFIC_SCHEMA,
+        Json(pagination.apply(without_flags(history.funding))),
+    ))
+}
+
+/// Funding payments in the venue-independent model, oldest first
+pub async fn get_normalized_funding(
+    State(state): State<AppState>,
+    Query(query): Query<FundingQuery>,
+    Query(pagination): Query<Pagination>,
+) -> AppResult<(
+    FreshnessHeaders,
+    HeaderMap,
+    Json<NormalizedEvents<NormalizedFunding>>,
+)> {
+    let history = state
         .ingestion_service
-        .fetch_all_funding(&query.wallet, query.since)
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, Vec::new(), history.funding)?;
+    let summary = timeline.summary();
+    let funding = timeline
+        .events
+        .into_iter()
+        .filter_map(NormalizedFunding::from_event)
+        .collect();
+
+    Ok((
+        headers,
+        summary_headers(&summary),
+        Json(NormalizedEvents::new(pagination.apply(funding))),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FundingComparisonQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    pub bins: Option<usize>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+/// Funding rates the wallet settled at, weighted by position size, against the market
+/// average on the same days
+pub async fn get_funding_comparison(
+    State(state): State<AppState>,
+    Query(query): Query<FundingComparisonQuery>,
+) -> AppResult<(FreshnessHeaders, Json<FundingRateComparison>)> {
+    let bins = query.bins.unwrap_or(DEFAULT_COMPARISON_BINS);
+    if bins == 0 || bins > MAX_COMPARISON_BINS {
+        return Err(AppError::ValidationError(format!(
+            "bins must be between 1 and {}",
+            MAX_COMPARISON_BINS
+        )));
+    }
+
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, Vec::new(), history.funding)?;
+
+    // Coins whose market history cannot be fetched are compared without a market rate
+    let mut market = HashMap::new();
+    for (coin, (start, end)) in state.funding_comparator.market_windows(&timeline) {
+        match state
+            .ingestion_service
+            .fetch_funding_rates(&coin, start, end)
+            .await
+        {
+            Ok(rates) => {
+                market.insert(coin, rates);
+            }
+            Err(e) => tracing::warn!("Failed to fetch {} funding rates: {}", coin, e),
+        }
+    }
+
+    let comparison = state.funding_comparator.compare(&timeline, &market, bins);
+
+    Ok((headers, Json(comparison)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FundingScannerQuery {
+    /// Highlights coins this wallet already holds a position in
+    pub wallet: Option<String>,
+    #[serde(default)]
+    pub sort: ScannerSort,
+    /// Adds each returned coin's mean realized funding over this many trailing days
+    pub history_days: Option<i64>,
+}
+
+/// Ranks coins by annualized funding yield for delta-neutral carry
+pub async fn get_scanner(
+    State(state): State<AppState>,
+    Query(query): Query<FundingScannerQuery>,
+    Query(mut pagination): Query<Pagination>,
+) -> AppResult<Json<Vec<FundingOpportunity>>> {
+    pagination.limit.get_or_insert(DEFAULT_SCANNER_LIMIT);
+    if let Some(days) = query.history_days
+        && !(1..=MAX_SCANNER_HISTORY_DAYS).contains(&days)
+    {
+        return Err(AppError::ValidationError(format!(
+            "history_days must be between 1 and {}",
+            MAX_SCANNER_HISTORY_DAYS
+        )));
+    }
+
+    let predicted = state.ingestion_service.fetch_predicted_fundings().await?;
+    let user_state = match &query.wallet {
+        Some(wallet) => Some(state.ingestion_service.fetch_user_state(wallet).await?),
+        None => None,
+    };
+
+    let opportunities = state
+        .funding_scanner
+        .scan(&predicted, user_state.as_ref(), query.sort);
+    if let Err(e) = state
+        .forecast_tracker
+        .record(&opportunities, Utc::now())
+        .await
+    {
+        tracing::warn!("Failed to record funding predictions: {}", e);
+    }
+    let mut opportunities = pagination.apply(opportunities);
+
+    if let Some(days) = query.history_days {
+        let end = Utc::now();
+        let start = end - Duration::days(days);
+        for opportunity in &mut opportunities {
+            match state
+                .ingestion_service
+                .fetch_funding_rates(
+                    &opportunity.coin,
+                    start.timestamp_millis(),
+                    end.timestamp_millis(),
+                )
+                .await
+            {
+                Ok(rates) => {
+                    opportunity.trailing_annualized_rate =
+                        state.carry_simulator.annualized_rate(&rates);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch {} funding rates: {}", opportunity.coin, e);
+                }
+            }
+        }
+    }
+
+    Ok(Json(opportunities))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastAccuracyQuery {
+    pub coin: Option<String>,
+}
+
+/// How well the scanner's predicted funding matched what settled, over recorded predictions
+pub async fn get_forecast_accuracy(
+    State(state): State<AppState>,
+    Query(query): Query<ForecastAccuracyQuery>,
+) -> AppResult<Json<ForecastAccuracyReport>> {
+    let report = state
+        .forecast_tracker
+        .report(query.coin.as_deref(), Utc::now())
         .await?;
 
-    Ok(Json(funding))
+    Ok(Json(report))
 }
@@ -0,0 +1,15 @@
+use axum::{extract::State, Json};
+
+use crate::services::assets::CoinMetadata;
+use crate::services::sources::SourceStatus;
+use crate::AppState;
+
+/// Per-perp trading parameters, refreshed with the asset registry
+pub async fn get_coins(State(state): State<AppState>) -> Json<CoinMetadata> {
+    Json(state.asset_registry.coin_metadata())
+}
+
+/// Configured upstreams with the methods they serve and their recent health
+pub async fn get_sources(State(state): State<AppState>) -> Json<Vec<SourceStatus>> {
+    Json(state.source_registry.statuses())
+}
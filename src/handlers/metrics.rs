@@ -0,0 +1,41 @@
+use axum::{extract::State, http::header, response::IntoResponse};
+use std::fmt::Write;
+
+use crate::AppState;
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Process metrics in the Prometheus text format
+pub async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let connections = state.upstream_connections.metrics();
+
+    let mut body = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+        let _ = writeln!(body, "# HELP {} {}", name, help);
+        let _ = writeln!(body, "# TYPE {} {}", name, kind);
+        let _ = writeln!(body, "{}{{source=\"hyperliquid\"}} {}", name, value);
+    };
+    metric(
+        "goker_upstream_requests_total",
+        "counter",
+        "Requests sent to the upstream API",
+        connections.requests.to_string(),
+    );
+    metric(
+        "goker_upstream_connections_total",
+        "counter",
+        "Connections opened to the upstream API",
+        connections.new_connections.to_string(),
+    );
+    if let Some(reuse_rate) = connections.reuse_rate {
+        metric(
+            "goker_upstream_connection_reuse_ratio",
+            "gauge",
+            "Share of upstream requests sent over an already open connection",
+            reuse_rate.to_string(),
+        );
+    }
+
+    ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body)
+}
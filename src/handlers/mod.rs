@@ -1,4 +1,162 @@
+pub mod activity;
+pub mod admin;
+pub mod alerts;
+pub mod analytics;
+pub mod attestations;
+pub mod audit;
+pub mod basis;
+pub mod benchmark;
+pub mod export;
 pub mod fills;
 pub mod funding;
+pub mod meta;
+pub mod metrics;
 pub mod pnl;
+pub mod portfolio;
+pub mod positions;
+pub mod reconcile;
+pub mod reports;
+pub mod risk;
+pub mod share;
+pub mod simulate;
+pub mod state;
+pub mod stats;
 pub mod timeline;
+pub mod trades;
+pub mod volume;
+pub mod wallets;
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use serde::Deserialize;
+
+use crate::output::SignConvention;
+use crate::services::ingestion::WalletHistory;
+use crate::services::pnl_calculator::CalculationMetadata;
+use crate::services::timeline::EventSummary;
+
+/// Headers telling clients when the data behind a response was synced
+pub type FreshnessHeaders = [(HeaderName, String); 2];
+
+pub fn freshness_headers(history: &WalletHistory) -> FreshnessHeaders {
+    [
+        (
+            HeaderName::from_static("x-data-synced-at"),
+            history.synced_at.to_rfc3339(),
+        ),
+        (
+            HeaderName::from_static("x-data-stale"),
+            history.stale.to_string(),
+        ),
+    ]
+}
+
+/// Optional `limit`/`offset` paging for list endpoints
+#[derive(Debug, Default, Deserialize)]
+pub struct Pagination {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl Pagination {
+    pub fn apply<T>(&self, items: Vec<T>) -> Vec<T> {
+        items
+            .into_iter()
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+/// Headers describing the full filtered selection behind a paginated response
+pub fn summary_headers(summary: &EventSummary) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let mut insert = |name: &'static str, value: String| {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    };
+
+    insert("x-total-count", summary.count.to_string());
+    if let Some(from) = summary.from_timestamp {
+        insert("x-range-start", from.to_rfc3339());
+    }
+    if let Some(to) = summary.to_timestamp {
+        insert("x-range-end", to.to_rfc3339());
+    }
+    insert(
+        "x-total-fees",
+        SignConvention::current()
+            .cost(&summary.total_fees)
+            .to_string(),
+    );
+    insert("x-total-volume", summary.total_volume.to_string());
+    insert("x-total-funding", summary.total_funding.to_string());
+
+    headers
+}
+
+/// Headers carrying the calculation metadata of a response whose body is a bare series
+pub fn calculation_headers(calculation: &CalculationMetadata) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let mut insert = |name: &'static str, value: String| {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    };
+    let label = |value: serde_json::Result<serde_json::Value>| {
+        value
+            .ok()
+            .and_then(|value| value.as_str().map(String::from))
+            .unwrap_or_default()
+    };
+
+    insert(
+        "x-calculation-method",
+        label(serde_json::to_value(calculation.method)),
+    );
+    insert(
+        "x-price-source",
+        label(serde_json::to_value(calculation.price_source)),
+    );
+    if let Some(from) = calculation.requested_from {
+        insert("x-requested-from", from.to_rfc3339());
+    }
+    if let Some(from) = calculation.covered_from {
+        insert("x-covered-from", from.to_rfc3339());
+    }
+    if let Some(to) = calculation.covered_to {
+        insert("x-covered-to", to.to_rfc3339());
+    }
+    insert(
+        "x-events-considered",
+        calculation.events_considered.to_string(),
+    );
+    insert("x-events-skipped", calculation.events_skipped.to_string());
+    insert(
+        "x-normalization-version",
+        calculation.normalization_version.to_string(),
+    );
+    insert("x-from-aggregates", calculation.from_aggregates.to_string());
+
+    headers
+}
+
+/// Marks a response as upstream JSON whose shape depends on the datasource serving it
+pub const VENUE_SPECIFIC_SCHEMA: [(HeaderName, &str); 1] =
+    [(HeaderName::from_static("x-schema"), "venue-specific")];
+
+/// Custom response headers browsers may read across origins
+pub const EXPOSED_HEADERS: [HeaderName; 12] = [
+    HeaderName::from_static("x-data-synced-at"),
+    HeaderName::from_static("x-data-stale"),
+    HeaderName::from_static("x-total-count"),
+    HeaderName::from_static("x-range-start"),
+    HeaderName::from_static("x-range-end"),
+    HeaderName::from_static("x-total-fees"),
+    HeaderName::from_static("x-total-volume"),
+    HeaderName::from_static("x-total-funding"),
+    HeaderName::from_static("x-capture-id"),
+    HeaderName::from_static("x-funding-attribution"),
+    HeaderName::from_static("x-schema"),
+    HeaderName::from_static("x-timing"),
+];
@@ -1,33 +1,73 @@
 use axum::{
     extract::{Query, State},
+    http::{HeaderMap, HeaderName},
     Json,
 };
+use bigdecimal::{BigDecimal, Zero};
 use serde::Deserialize;
 
-use crate::error::AppResult;
-use crate::services::pnl_calculator::{DailyPnl, PnlSummary};
+use crate::error::{AppError, AppResult};
+use crate::handlers::{calculation_headers, freshness_headers, FreshnessHeaders};
+use crate::services::collateral::CollateralReport;
+use crate::services::ingestion::Freshness;
+use crate::services::pnl_calculator::{
+    CalculationMetadata, DailyPnl, FundingAttribution, PnlDecomposition, PnlMethod, PnlSummary,
+    PriceSource,
+};
+use crate::services::positions::{diff_positions, CostBasisEngine, HypotheticalFill, PnlPreview};
+use crate::services::statements::Statement;
+use crate::services::timeline::{Granularity, TimelineEvent};
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
 pub struct PnlQuery {
     pub wallet: String,
     pub since: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DailyPnlQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+    /// Overrides the configured rule for funding settled at 00:00 UTC
+    pub funding_attribution: Option<FundingAttribution>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DecompositionQuery {
+    pub wallet: String,
+    pub coin: String,
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub granularity: Granularity,
+    #[serde(default)]
+    pub freshness: Freshness,
+    /// Overrides the configured rule for funding settled at 00:00 UTC
+    pub funding_attribution: Option<FundingAttribution>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PnlPreviewRequest {
+    pub wallet: String,
+    pub fills: Vec<HypotheticalFill>,
+    #[serde(default)]
+    pub freshness: Freshness,
 }
 
 pub async fn get_pnl_summary(
     State(state): State<AppState>,
     Query(query): Query<PnlQuery>,
-) -> AppResult<Json<PnlSummary>> {
+) -> AppResult<(FreshnessHeaders, Json<PnlSummary>)> {
     // Fetch data
-    let fills = state
+    let history = state
         .ingestion_service
-        .fetch_all_fills(&query.wallet, query.since)
-        .await?;
-
-    let funding = state
-        .ingestion_service
-        .fetch_all_funding(&query.wallet, query.since)
+        .fetch_history(&query.wallet, query.since, query.freshness)
         .await?;
+    let headers = freshness_headers(&history);
 
     let user_state = state
         .ingestion_service
@@ -37,41 +77,267 @@ pub async fn get_pnl_summary(
     // Build timeline
     let timeline = state
         .timeline_service
-        .build_timeline(&query.wallet, fills, funding)?;
+        .build_timeline(&query.wallet, history.fills, history.funding)?;
 
     // Calculate unrealized PnL
     let unrealized_pnl = state.pnl_calculator.calculate_unrealized_from_state(&user_state);
 
     // Calculate PnL summary
-    let summary = state
+    let mut summary = state
         .pnl_calculator
         .calculate_summary(&query.wallet, &timeline, unrealized_pnl);
+    summary.calculation = Some(CalculationMetadata::new(
+        PnlMethod::ExchangeReported,
+        PriceSource::MarkPrice,
+        &timeline,
+        query.since,
+        history.normalization_version,
+    ));
 
-    Ok(Json(summary))
+    Ok((headers, Json(summary)))
 }
 
+/// Daily PnL series; its calculation metadata is sent in headers, as the body is a bare list
 pub async fn get_daily_pnl(
     State(state): State<AppState>,
-    Query(query): Query<PnlQuery>,
-) -> AppResult<Json<Vec<DailyPnl>>> {
+    Query(query): Query<DailyPnlQuery>,
+) -> AppResult<(
+    FreshnessHeaders,
+    [(HeaderName, &'static str); 1],
+    HeaderMap,
+    Json<Vec<DailyPnl>>,
+)> {
     // Fetch data
-    let fills = state
+    let history = state
         .ingestion_service
-        .fetch_all_fills(&query.wallet, query.since)
+        .fetch_history(&query.wallet, query.since, query.freshness)
         .await?;
+    let headers = freshness_headers(&history);
+
+    let funding_attribution = query
+        .funding_attribution
+        .unwrap_or_else(|| state.pnl_calculator.funding_attribution());
+
+    // Full history in the configured attribution is served from materialized aggregates
+    let (daily, calculation) = if query.since.is_none()
+        && funding_attribution == state.pnl_calculator.funding_attribution()
+    {
+        state
+            .daily_aggregator
+            .daily_pnl(&query.wallet, &history)
+            .await?
+    } else {
+        let normalization_version = history.normalization_version;
+        let timeline = state
+            .timeline_service
+            .build_timeline(&query.wallet, history.fills, history.funding)?;
+        let calculation = CalculationMetadata::new(
+            PnlMethod::ExchangeReported,
+            PriceSource::FillPrices,
+            &timeline,
+            query.since,
+            normalization_version,
+        );
+        (
+            state
+                .pnl_calculator
+                .calculate_daily(&timeline, funding_attribution),
+            calculation,
+        )
+    };
+
+    Ok((
+        headers,
+        [(
+            HeaderName::from_static("x-funding-attribution"),
+            funding_attribution.as_str(),
+        )],
+        calculation_headers(&calculation),
+        Json(daily),
+    ))
+}
 
-    let funding = state
+/// Price PnL, funding and fees per period for one coin, as parallel series for charting
+pub async fn get_pnl_decomposition(
+    State(state): State<AppState>,
+    Query(query): Query<DecompositionQuery>,
+) -> AppResult<(FreshnessHeaders, Json<PnlDecomposition>)> {
+    let history = state
         .ingestion_service
-        .fetch_all_funding(&query.wallet, query.since)
+        .fetch_history(&query.wallet, query.since, query.freshness)
         .await?;
+    let headers = freshness_headers(&history);
 
-    // Build timeline
     let timeline = state
         .timeline_service
-        .build_timeline(&query.wallet, fills, funding)?;
+        .build_timeline(&query.wallet, history.fills, history.funding)?;
+
+    let funding_attribution = query
+        .funding_attribution
+        .unwrap_or_else(|| state.pnl_calculator.funding_attribution());
+    let mut decomposition = state.pnl_calculator.decompose(
+        &timeline,
+        &query.coin,
+        query.granularity,
+        funding_attribution,
+    );
+    decomposition.calculation = Some(CalculationMetadata::new(
+        PnlMethod::ExchangeReported,
+        PriceSource::FillPrices,
+        &timeline,
+        query.since,
+        history.normalization_version,
+    ));
+
+    Ok((headers, Json(decomposition)))
+}
+
+/// Monthly and per-coin waterfalls from gross trading PnL to net PnL
+pub async fn get_statement(
+    State(state): State<AppState>,
+    Query(query): Query<DailyPnlQuery>,
+) -> AppResult<(FreshnessHeaders, Json<Statement>)> {
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, history.funding)?;
+
+    let funding_attribution = query
+        .funding_attribution
+        .unwrap_or_else(|| state.pnl_calculator.funding_attribution());
+    let mut statement = state
+        .statement_calculator
+        .calculate(&timeline, funding_attribution);
+    statement.calculation = Some(CalculationMetadata::new(
+        PnlMethod::ExchangeReported,
+        PriceSource::FillPrices,
+        &timeline,
+        query.since,
+        history.normalization_version,
+    ));
+
+    Ok((headers, Json(statement)))
+}
+
+pub async fn get_collateral_pnl(
+    State(state): State<AppState>,
+    Query(query): Query<PnlQuery>,
+) -> AppResult<(FreshnessHeaders, Json<CollateralReport>)> {
+    // Fetch data
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    // Build timeline including deposits and withdrawals
+    let mut timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, history.funding)?;
+    state
+        .timeline_service
+        .add_ledger_updates(&mut timeline, history.ledger);
+
+    let mut report = state
+        .collateral_service
+        .report(&query.wallet, &timeline)
+        .await;
+    report.calculation = Some(CalculationMetadata::new(
+        PnlMethod::ExchangeReported,
+        PriceSource::FillPrices,
+        &timeline,
+        query.since,
+        history.normalization_version,
+    ));
+
+    Ok((headers, Json(report)))
+}
+
+pub async fn preview_pnl(
+    State(state): State<AppState>,
+    Json(request): Json<PnlPreviewRequest>,
+) -> AppResult<Json<PnlPreview>> {
+    if let Some(fill) = request
+        .fills
+        .iter()
+        .find(|f| (f.side != "B" && f.side != "A") || f.size <= BigDecimal::zero())
+    {
+        return Err(AppError::ValidationError(format!(
+            "Invalid hypothetical fill for {}: side must be B or A and size positive",
+            fill.coin
+        )));
+    }
+
+    // Fetch full history so cost bases are complete
+    let history = state
+        .ingestion_service
+        .fetch_history(&request.wallet, None, request.freshness)
+        .await?;
+
+    // Build the real timeline, then a copy merged with the hypothetical fills
+    let timeline =
+        state
+            .timeline_service
+            .build_timeline(&request.wallet, history.fills, history.funding)?;
+
+    let previews: Vec<TimelineEvent> = request
+        .fills
+        .iter()
+        .enumerate()
+        .map(|(i, f)| f.to_event(i))
+        .collect();
+    for (fill, preview) in request.fills.iter().zip(&previews) {
+        let last_real_fill = timeline
+            .events
+            .iter()
+            .filter(|e| matches!(e, TimelineEvent::Fill { coin, .. } if *coin == fill.coin))
+            .map(|e| e.timestamp())
+            .max();
+        if let Some(last) = last_real_fill
+            && preview.timestamp() < last
+        {
+            return Err(AppError::ValidationError(format!(
+                "Hypothetical fill for {} is dated before its last real fill at {}",
+                fill.coin,
+                last.to_rfc3339()
+            )));
+        }
+    }
+
+    let mut merged = timeline.events.clone();
+    merged.extend(previews);
+    merged.sort_by_key(|e| e.timestamp());
+
+    let before = CostBasisEngine::replay(&timeline.events);
+    let after = CostBasisEngine::replay(&merged);
+    let changes = diff_positions(&before, &after);
 
-    // Calculate daily PnL
-    let daily = state.pnl_calculator.calculate_daily(&timeline);
+    let realized_pnl_change = changes
+        .iter()
+        .fold(BigDecimal::zero(), |acc, c| acc + &c.realized_pnl_change);
+    let fees = request
+        .fills
+        .iter()
+        .fold(BigDecimal::zero(), |acc, f| acc + &f.fee);
 
-    Ok(Json(daily))
+    Ok(Json(PnlPreview {
+        wallet: request.wallet,
+        hypothetical_fills: request.fills.len(),
+        net_pnl_change: &realized_pnl_change - &fees,
+        calculation: CalculationMetadata::new(
+            PnlMethod::Fifo,
+            PriceSource::FillPrices,
+            &timeline,
+            None,
+            history.normalization_version,
+        ),
+        changes,
+        realized_pnl_change,
+        fees,
+    }))
 }
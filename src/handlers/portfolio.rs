@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::error::{AppError, AppResult};
+use crate::services::ingestion::Freshness;
+use crate::services::portfolio::PortfolioReport;
+use crate::AppState;
+
+/// Most wallets grouped in one portfolio request
+const MAX_PORTFOLIO_WALLETS: usize = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct PortfolioQuery {
+    /// Comma-separated addresses of the owned wallets
+    pub wallets: String,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+/// Deposits, withdrawals and returns across owned wallets, net of transfers between them
+pub async fn get_portfolio(
+    State(state): State<AppState>,
+    Query(query): Query<PortfolioQuery>,
+) -> AppResult<Json<PortfolioReport>> {
+    let mut wallets: Vec<String> = query
+        .wallets
+        .split(',')
+        .map(|wallet| wallet.trim().to_lowercase())
+        .filter(|wallet| !wallet.is_empty())
+        .collect();
+    wallets.sort();
+    wallets.dedup();
+    if wallets.is_empty() || wallets.len() > MAX_PORTFOLIO_WALLETS {
+        return Err(AppError::ValidationError(format!(
+            "wallets must list between 1 and {} addresses",
+            MAX_PORTFOLIO_WALLETS
+        )));
+    }
+
+    let mut timelines = Vec::with_capacity(wallets.len());
+    for wallet in &wallets {
+        let history = state
+            .ingestion_service
+            .fetch_history(wallet, None, query.freshness)
+            .await?;
+
+        let mut timeline = state
+            .timeline_service
+            .build_timeline(wallet, history.fills, history.funding)?;
+        state
+            .timeline_service
+            .add_ledger_updates(&mut timeline, history.ledger);
+        timelines.push(timeline);
+    }
+
+    Ok(Json(state.portfolio_service.calculate(&timelines)))
+}
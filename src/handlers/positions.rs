@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::error::AppResult;
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::ingestion::Freshness;
+use crate::services::positions::{CostBasisEngine, OpenLotsReport};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct LotsQuery {
+    pub wallet: String,
+    pub coin: Option<String>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+/// Open lots of each current position, so partial closes can be planned lot by lot
+pub async fn get_lots(
+    State(state): State<AppState>,
+    Query(query): Query<LotsQuery>,
+) -> AppResult<(FreshnessHeaders, Json<OpenLotsReport>)> {
+    // Fetch full history so lots are reconstructed from the start
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, None, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let mids = state.ingestion_service.fetch_all_mids().await?;
+
+    let timeline =
+        state
+            .timeline_service
+            .build_timeline(&query.wallet, history.fills, history.funding)?;
+
+    let as_of = Utc::now();
+    let mut positions = CostBasisEngine::replay(&timeline.events).open_lots(&mids, as_of);
+    if let Some(coin) = &query.coin {
+        positions.retain(|position| position.coin == *coin);
+    }
+
+    Ok((
+        headers,
+        Json(OpenLotsReport {
+            wallet: query.wallet,
+            as_of,
+            positions,
+        }),
+    ))
+}
@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::error::AppResult;
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::ingestion::Freshness;
+use crate::services::reconciliation::GapReport;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ReconcileQuery {
+    pub wallet: String,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+pub async fn get_gaps(
+    State(state): State<AppState>,
+    Query(query): Query<ReconcileQuery>,
+) -> AppResult<(FreshnessHeaders, Json<GapReport>)> {
+    // Fetch full history; a partial window would report positions opened before it as gaps
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, None, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    // Build timeline
+    let mut timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, history.funding)?;
+    state
+        .timeline_service
+        .add_ledger_updates(&mut timeline, history.ledger);
+
+    let mut report = state.reconciliation_service.detect_gaps(&timeline);
+
+    // Cross-check deposits and withdrawals against the bridge when an RPC is configured
+    if let Some(evm_client) = &state.evm_client {
+        if let Some(scan) = state.reconciliation_service.bridge_scan(&timeline) {
+            match evm_client
+                .get_bridge_transfers(&query.wallet, &scan.windows)
+                .await
+            {
+                Ok(onchain) => {
+                    report
+                        .gaps
+                        .extend(state.reconciliation_service.check_transfers(
+                            &timeline,
+                            &onchain,
+                            scan.checked_from,
+                        ));
+                    report.gaps.sort_by_key(|gap| gap.from);
+                    report.onchain_checked = true;
+                    report.onchain_checked_from = Some(scan.checked_from);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Skipping on-chain transfer check for {}: {}",
+                        query.wallet,
+                        e
+                    );
+                }
+            }
+        } else {
+            report.onchain_checked = true;
+        }
+    }
+
+    Ok((headers, Json(report)))
+}
@@ -0,0 +1,85 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderName},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppResult;
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::ingestion::Freshness;
+use crate::services::pnl_calculator::FundingAttribution;
+use crate::services::reports::{content_type, STATEMENT_TEMPLATE};
+use crate::services::statements::Statement;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct StatementReportQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+    pub funding_attribution: Option<FundingAttribution>,
+    /// Template to render; defaults to the statement template
+    pub template: Option<String>,
+    /// Comma-separated sections for the template to include, e.g. `summary,months`
+    pub sections: Option<String>,
+}
+
+/// Variables available to statement templates
+#[derive(Debug, Serialize)]
+struct StatementContext<'a> {
+    statement: &'a Statement,
+    /// Requested sections; empty means all
+    sections: Vec<String>,
+    generated_at: DateTime<Utc>,
+}
+
+pub async fn list_templates(State(state): State<AppState>) -> Json<Vec<String>> {
+    Json(state.report_renderer.templates())
+}
+
+/// Renders a wallet's statement through a deployment-provided or built-in template
+pub async fn get_statement_report(
+    State(state): State<AppState>,
+    Query(query): Query<StatementReportQuery>,
+) -> AppResult<(FreshnessHeaders, [(HeaderName, &'static str); 1], String)> {
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let timeline =
+        state
+            .timeline_service
+            .build_timeline(&query.wallet, history.fills, history.funding)?;
+
+    let funding_attribution = query
+        .funding_attribution
+        .unwrap_or_else(|| state.pnl_calculator.funding_attribution());
+    let statement = state
+        .statement_calculator
+        .calculate(&timeline, funding_attribution);
+
+    let template = query.template.as_deref().unwrap_or(STATEMENT_TEMPLATE);
+    let context = StatementContext {
+        statement: &statement,
+        sections: query
+            .sections
+            .iter()
+            .flat_map(|sections| sections.split(','))
+            .map(|section| section.trim().to_string())
+            .filter(|section| !section.is_empty())
+            .collect(),
+        generated_at: Utc::now(),
+    };
+    let body = state.report_renderer.render(template, &context)?;
+
+    Ok((
+        headers,
+        [(header::CONTENT_TYPE, content_type(template))],
+        body,
+    ))
+}
@@ -0,0 +1,38 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::error::AppResult;
+use crate::services::scenarios::{Scenario, ScenarioReport};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ScenarioQuery {
+    pub wallet: String,
+    /// `;`-separated scenarios of `<all|alts|coin>:<percent>` moves, e.g. `all:-10;BTC:+5`
+    pub scenarios: Option<String>,
+}
+
+/// Applies price shocks to a wallet's current positions
+pub async fn get_scenarios(
+    State(state): State<AppState>,
+    Query(query): Query<ScenarioQuery>,
+) -> AppResult<Json<ScenarioReport>> {
+    let scenarios = match &query.scenarios {
+        Some(specs) => Scenario::parse_list(specs)?,
+        None => state.scenario_analyzer.defaults().to_vec(),
+    };
+
+    let user_state = state
+        .ingestion_service
+        .fetch_user_state(&query.wallet)
+        .await?;
+
+    Ok(Json(state.scenario_analyzer.analyze(
+        &query.wallet,
+        &user_state,
+        &scenarios,
+    )))
+}
@@ -0,0 +1,133 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Duration;
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::ingestion::{Freshness, WalletHistory};
+use crate::services::pnl_calculator::{CalculationMetadata, PnlMethod, PriceSource};
+use crate::services::sharing::{ShareLink, SharedReport, SharedReportKind};
+use crate::AppState;
+
+/// Link lifetime when the request names none
+const DEFAULT_SHARE_TTL_HOURS: i64 = 24;
+
+/// Longest a link may stay valid
+const MAX_SHARE_TTL_HOURS: i64 = 30 * 24;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateShareRequest {
+    pub report: SharedReportKind,
+    pub wallet: String,
+    pub since: Option<i64>,
+    pub ttl_hours: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShareTokenQuery {
+    pub token: String,
+}
+
+/// Snapshots a report and returns an expiring link that serves it without authentication
+pub async fn create_share(
+    State(state): State<AppState>,
+    Json(request): Json<CreateShareRequest>,
+) -> AppResult<(StatusCode, Json<ShareLink>)> {
+    let ttl_hours = request.ttl_hours.unwrap_or(DEFAULT_SHARE_TTL_HOURS);
+    if !(1..=MAX_SHARE_TTL_HOURS).contains(&ttl_hours) {
+        return Err(AppError::ValidationError(format!(
+            "ttl_hours must be between 1 and {}",
+            MAX_SHARE_TTL_HOURS
+        )));
+    }
+
+    let history = state
+        .ingestion_service
+        .fetch_history(&request.wallet, request.since, Freshness::default())
+        .await?;
+    let data = report_data(
+        &state,
+        request.report,
+        &request.wallet,
+        request.since,
+        history,
+    )
+    .await?;
+
+    let link = state
+        .share_service
+        .create(
+            request.report,
+            &request.wallet,
+            data,
+            Duration::hours(ttl_hours),
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(link)))
+}
+
+pub async fn get_share(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ShareTokenQuery>,
+) -> AppResult<Json<SharedReport>> {
+    let shared = state.share_service.open(id, &query.token).await?;
+
+    Ok(Json(shared))
+}
+
+/// Computes a shareable report from a wallet's history, as its endpoint would
+pub async fn report_data(
+    state: &AppState,
+    report: SharedReportKind,
+    wallet: &str,
+    since: Option<i64>,
+    history: WalletHistory,
+) -> AppResult<Value> {
+    let normalization_version = history.normalization_version;
+    let timeline = state
+        .timeline_service
+        .build_timeline(wallet, history.fills, history.funding)?;
+
+    let data = match report {
+        SharedReportKind::PnlSummary => {
+            let user_state = state.ingestion_service.fetch_user_state(wallet).await?;
+            let unrealized_pnl = state
+                .pnl_calculator
+                .calculate_unrealized_from_state(&user_state);
+            let mut summary =
+                state
+                    .pnl_calculator
+                    .calculate_summary(wallet, &timeline, unrealized_pnl);
+            summary.calculation = Some(CalculationMetadata::new(
+                PnlMethod::ExchangeReported,
+                PriceSource::MarkPrice,
+                &timeline,
+                since,
+                normalization_version,
+            ));
+            serde_json::to_value(summary)?
+        }
+        SharedReportKind::Statement => {
+            let mut statement = state
+                .statement_calculator
+                .calculate(&timeline, state.pnl_calculator.funding_attribution());
+            statement.calculation = Some(CalculationMetadata::new(
+                PnlMethod::ExchangeReported,
+                PriceSource::FillPrices,
+                &timeline,
+                since,
+                normalization_version,
+            ));
+            serde_json::to_value(statement)?
+        }
+    };
+
+    Ok(data)
+}
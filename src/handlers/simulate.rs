@@ -0,0 +1,155 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::str::FromStr;
+
+use crate::datasource::Capability;
+use crate::error::{AppError, AppResult};
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::carry::{CarryPosition, CarryProjection, PositionSide};
+use crate::services::fees::FeeSimulation;
+use crate::services::ingestion::Freshness;
+use crate::AppState;
+
+const DEFAULT_LOOKBACK_DAYS: i64 = 7;
+const MAX_LOOKBACK_DAYS: i64 = 90;
+
+/// Extra history fetched before `since` so the first fills get their volume tier
+const FEE_TIER_WARMUP_DAYS: i64 = 14;
+
+#[derive(Debug, Deserialize)]
+pub struct CarryQuery {
+    /// Coin as used in the ledger, e.g. `BTC` or `bybit:BTC`
+    pub coin: String,
+    pub side: PositionSide,
+    pub size: BigDecimal,
+    pub duration_hours: u32,
+    /// Entry price; defaults to the current mid
+    pub price: Option<BigDecimal>,
+    /// Days of funding history to project from
+    pub lookback_days: Option<i64>,
+}
+
+pub async fn simulate_carry(
+    State(state): State<AppState>,
+    Query(query): Query<CarryQuery>,
+) -> AppResult<Json<CarryProjection>> {
+    if query.size <= BigDecimal::zero() || query.duration_hours == 0 {
+        return Err(AppError::ValidationError(
+            "size and duration_hours must be positive".to_string(),
+        ));
+    }
+
+    let lookback_days = query.lookback_days.unwrap_or(DEFAULT_LOOKBACK_DAYS);
+    if !(1..=MAX_LOOKBACK_DAYS).contains(&lookback_days) {
+        return Err(AppError::ValidationError(format!(
+            "lookback_days must be between 1 and {}",
+            MAX_LOOKBACK_DAYS
+        )));
+    }
+
+    if !state
+        .source_registry
+        .supports(&query.coin, Capability::FundingRates)
+    {
+        return Err(AppError::ValidationError(format!(
+            "{}'s venue publishes no funding rate history",
+            query.coin
+        )));
+    }
+
+    let price = match query.price {
+        Some(price) => price,
+        None => {
+            let mids = state.ingestion_service.fetch_all_mids().await?;
+            mids.get(&query.coin)
+                .and_then(|mid| mid.as_str())
+                .and_then(|mid| BigDecimal::from_str(mid).ok())
+                .ok_or_else(|| {
+                    AppError::ValidationError(format!(
+                        "No mid price for {}; pass price explicitly",
+                        query.coin
+                    ))
+                })?
+        }
+    };
+
+    let end = Utc::now();
+    let start = end - Duration::days(lookback_days);
+    let rates = state
+        .ingestion_service
+        .fetch_funding_rates(
+            &query.coin,
+            start.timestamp_millis(),
+            end.timestamp_millis(),
+        )
+        .await?;
+
+    let position = CarryPosition {
+        coin: query.coin,
+        side: query.side,
+        size: query.size,
+        price,
+        duration_hours: query.duration_hours,
+    };
+
+    Ok(Json(state.carry_simulator.project(&position, &rates)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeeSimulationQuery {
+    pub wallet: String,
+    /// Start of the simulated range in milliseconds
+    pub since: Option<i64>,
+    /// Simulate every fill at this tier instead of the one its volume reached
+    pub tier: Option<usize>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+pub async fn simulate_fees(
+    State(state): State<AppState>,
+    Query(query): Query<FeeSimulationQuery>,
+) -> AppResult<(FreshnessHeaders, Json<FeeSimulation>)> {
+    let max_tier = state
+        .fee_simulator
+        .table()
+        .schedules()
+        .iter()
+        .map(|schedule| schedule.tiers.len() - 1)
+        .min()
+        .unwrap_or(0);
+    if query.tier.is_some_and(|tier| tier > max_tier) {
+        return Err(AppError::ValidationError(format!(
+            "tier must be between 0 and {}",
+            max_tier
+        )));
+    }
+
+    let fetch_since = query
+        .since
+        .map(|since| since - Duration::days(FEE_TIER_WARMUP_DAYS).num_milliseconds());
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, fetch_since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, Vec::new())?;
+
+    let from = query.since.and_then(DateTime::from_timestamp_millis);
+    Ok((
+        headers,
+        Json(
+            state
+                .fee_simulator
+                .simulate(&query.wallet, &timeline, from, query.tier, Utc::now()),
+        ),
+    ))
+}
@@ -0,0 +1,63 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use bigdecimal::BigDecimal;
+use chrono::DateTime;
+use serde::Deserialize;
+
+use crate::error::{AppError, AppResult};
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::ingestion::Freshness;
+use crate::services::positions::{CostBasisEngine, StateAt};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct StateAtQuery {
+    pub wallet: String,
+    /// Timestamp in epoch milliseconds
+    pub ts: i64,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+pub async fn get_state_at(
+    State(state): State<AppState>,
+    Query(query): Query<StateAtQuery>,
+) -> AppResult<(FreshnessHeaders, Json<StateAt>)> {
+    let as_of = DateTime::from_timestamp_millis(query.ts)
+        .ok_or_else(|| AppError::ValidationError(format!("Invalid timestamp: {}", query.ts)))?;
+
+    // Fetch full history so positions are reconstructed from the start
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, None, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    // Replay events up to the requested instant
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, history.funding)?
+        .until(as_of);
+
+    let engine = CostBasisEngine::replay(&timeline.events);
+    let summary =
+        state
+            .pnl_calculator
+            .calculate_summary(&query.wallet, &timeline, BigDecimal::from(0));
+
+    Ok((
+        headers,
+        Json(StateAt {
+            wallet: query.wallet,
+            as_of,
+            events_replayed: timeline.events.len(),
+            positions: engine.snapshot(),
+            realized_pnl: summary.realized_pnl,
+            funding_pnl: summary.funding_pnl,
+            trading_fees: summary.trading_fees,
+            net_pnl: summary.net_pnl,
+        }),
+    ))
+}
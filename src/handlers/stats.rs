@@ -0,0 +1,305 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+use serde::Deserialize;
+
+use crate::error::{AppError, AppResult};
+use crate::handlers::trades::{round_trips, with_excursions, MAX_TRADE_LIMIT};
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::ingestion::Freshness;
+use crate::services::market_data::CandleInterval;
+use crate::services::stats::{
+    Distributions, ExcursionStats, ExecutionQuality, MarketMakingStats, SizingStats,
+};
+use crate::services::timeline::TimelineEvent;
+use crate::AppState;
+
+/// Default delay after a fill at which the mid is compared for adverse selection
+const DEFAULT_HORIZON_MINUTES: i64 = 5;
+
+/// Default number of histogram bins
+const DEFAULT_BINS: usize = 20;
+
+/// Largest number of histogram bins a client may request
+const MAX_BINS: usize = 200;
+
+/// Default order notional boundaries between execution buckets
+const DEFAULT_BUCKET_EDGES: [u32; 5] = [100, 1_000, 10_000, 100_000, 1_000_000];
+
+/// Largest number of bucket boundaries a client may request
+const MAX_BUCKET_EDGES: usize = 20;
+
+/// Default number of most recent trades aggregated for excursions
+const DEFAULT_EXCURSION_TRADES: usize = 100;
+
+/// Default share of equity a single position may reach before it is flagged
+const DEFAULT_MAX_EQUITY_FRACTION: i64 = 25;
+
+#[derive(Debug, Deserialize)]
+pub struct SizingQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    pub max_equity_fraction: Option<BigDecimal>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarketMakingQuery {
+    pub wallet: String,
+    pub coin: String,
+    pub since: Option<i64>,
+    pub horizon_minutes: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DistributionsQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    pub bins: Option<usize>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExcursionsQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    pub coin: Option<String>,
+    /// Number of most recent trades to aggregate
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExecutionQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    pub coin: Option<String>,
+    /// Comma-separated ascending order notional boundaries, e.g. `1000,10000,100000`
+    pub buckets: Option<String>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+pub async fn get_sizing_stats(
+    State(state): State<AppState>,
+    Query(query): Query<SizingQuery>,
+) -> AppResult<(FreshnessHeaders, Json<SizingStats>)> {
+    // Fetch data
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let user_state = state
+        .ingestion_service
+        .fetch_user_state(&query.wallet)
+        .await?;
+
+    // Build timeline
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, Vec::new())?;
+
+    let equity = state.stats_calculator.equity_from_state(&user_state);
+    let max_equity_fraction = query
+        .max_equity_fraction
+        .unwrap_or_else(|| BigDecimal::new(DEFAULT_MAX_EQUITY_FRACTION.into(), 2));
+
+    let stats = state.stats_calculator.calculate_sizing(
+        &query.wallet,
+        &timeline,
+        equity,
+        max_equity_fraction,
+    );
+
+    Ok((headers, Json(stats)))
+}
+
+pub async fn get_market_making_stats(
+    State(state): State<AppState>,
+    Query(query): Query<MarketMakingQuery>,
+) -> AppResult<(FreshnessHeaders, Json<MarketMakingStats>)> {
+    let horizon_minutes = query.horizon_minutes.unwrap_or(DEFAULT_HORIZON_MINUTES);
+    if horizon_minutes <= 0 {
+        return Err(AppError::ValidationError(
+            "horizon_minutes must be positive".to_string(),
+        ));
+    }
+
+    // Fetch data
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    // Build timeline
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, Vec::new())?;
+
+    // Fetch candles no longer than the horizon, as far back from the last fill as one
+    // request reaches; earlier fills are left out of adverse selection
+    let fill_times: Vec<i64> = timeline
+        .events
+        .iter()
+        .filter(|e| matches!(e, TimelineEvent::Fill { coin, .. } if *coin == query.coin))
+        .map(|e| e.timestamp().timestamp_millis())
+        .collect();
+    let horizon_ms = horizon_minutes * 60 * 1000;
+
+    let candles = match (
+        fill_times.first(),
+        fill_times.last(),
+        CandleInterval::within(horizon_ms),
+    ) {
+        (Some(first), Some(last), Some(interval)) => {
+            let end = last + horizon_ms;
+            let start = (*first).max(end - interval.request_span());
+            state
+                .ingestion_service
+                .fetch_candles_at(&query.coin, interval, start, end)
+                .await?
+        }
+        _ => Vec::new(),
+    };
+
+    let stats = state.stats_calculator.calculate_market_making(
+        &query.wallet,
+        &query.coin,
+        &timeline,
+        &candles,
+        horizon_minutes,
+    );
+
+    Ok((headers, Json(stats)))
+}
+
+pub async fn get_distributions(
+    State(state): State<AppState>,
+    Query(query): Query<DistributionsQuery>,
+) -> AppResult<(FreshnessHeaders, Json<Distributions>)> {
+    let bins = query.bins.unwrap_or(DEFAULT_BINS);
+    if bins == 0 || bins > MAX_BINS {
+        return Err(AppError::ValidationError(format!(
+            "bins must be between 1 and {}",
+            MAX_BINS
+        )));
+    }
+
+    // Fetch fills and funding
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    // Build timeline and round trips
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, history.funding)?;
+
+    let trips = state.trade_service.build_round_trips(&timeline);
+    let distributions =
+        state
+            .stats_calculator
+            .calculate_distributions(&query.wallet, &timeline, &trips, bins);
+
+    Ok((headers, Json(distributions)))
+}
+
+pub async fn get_excursion_stats(
+    State(state): State<AppState>,
+    Query(query): Query<ExcursionsQuery>,
+) -> AppResult<(FreshnessHeaders, Json<ExcursionStats>)> {
+    let limit = query.limit.unwrap_or(DEFAULT_EXCURSION_TRADES);
+    if limit == 0 || limit > MAX_TRADE_LIMIT {
+        return Err(AppError::ValidationError(format!(
+            "limit must be between 1 and {}",
+            MAX_TRADE_LIMIT
+        )));
+    }
+
+    let (headers, mut trips) =
+        round_trips(&state, &query.wallet, query.since, query.freshness).await?;
+    trips.retain(|trip| {
+        trip.exit_time.is_some() && query.coin.as_ref().is_none_or(|coin| trip.coin == *coin)
+    });
+
+    // Keep the most recently closed trades
+    trips.sort_by_key(|trip| trip.exit_time);
+    let trips = trips.split_off(trips.len().saturating_sub(limit));
+    let trips = with_excursions(&state, trips).await;
+
+    Ok((
+        headers,
+        Json(
+            state
+                .stats_calculator
+                .calculate_excursion_stats(&query.wallet, &trips),
+        ),
+    ))
+}
+
+pub async fn get_execution_stats(
+    State(state): State<AppState>,
+    Query(query): Query<ExecutionQuery>,
+) -> AppResult<(FreshnessHeaders, Json<ExecutionQuality>)> {
+    let edges = match &query.buckets {
+        Some(buckets) => parse_bucket_edges(buckets)?,
+        None => DEFAULT_BUCKET_EDGES.map(BigDecimal::from).to_vec(),
+    };
+
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, Vec::new())?;
+
+    Ok((
+        headers,
+        Json(state.stats_calculator.calculate_execution_quality(
+            &query.wallet,
+            &timeline,
+            query.coin.as_deref(),
+            &edges,
+        )),
+    ))
+}
+
+fn parse_bucket_edges(buckets: &str) -> AppResult<Vec<BigDecimal>> {
+    let edges = buckets
+        .split(',')
+        .map(|edge| {
+            edge.trim()
+                .parse::<BigDecimal>()
+                .map_err(|_| AppError::ValidationError(format!("Invalid bucket edge: {}", edge)))
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+
+    if edges.is_empty() || edges.len() > MAX_BUCKET_EDGES {
+        return Err(AppError::ValidationError(format!(
+            "buckets must have between 1 and {} edges",
+            MAX_BUCKET_EDGES
+        )));
+    }
+    if edges[0] <= BigDecimal::zero() || edges.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(AppError::ValidationError(
+            "bucket edges must be positive and ascending".to_string(),
+        ));
+    }
+
+    Ok(edges)
+}
@@ -1,38 +1,184 @@
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     Json,
 };
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
-use crate::error::AppResult;
-use crate::services::timeline::Timeline;
+use crate::error::{AppError, AppResult};
+use crate::handlers::{freshness_headers, summary_headers, FreshnessHeaders, Pagination};
+use crate::services::ingestion::Freshness;
+use crate::services::timeline::{group_funding, TimelineEntry, TimelineEvent};
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
 pub struct TimelineQuery {
     pub wallet: String,
     pub since: Option<i64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+    /// Keep only flagged (`true`) or unflagged (`false`) events
+    pub flagged: Option<bool>,
+    /// Collapse funding payments into daily rollups per coin
+    #[serde(default)]
+    pub group_funding: bool,
+}
+
+/// A timeline page; events are plain timeline events unless funding is grouped
+#[derive(Debug, Serialize)]
+pub struct TimelinePage {
+    pub wallet: String,
+    pub events: Vec<TimelineEntry>,
+    pub from_timestamp: Option<DateTime<Utc>>,
+    pub to_timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimelineDiffQuery {
+    pub wallet: String,
+    /// ID of the last event the client holds; omitted on the first refresh
+    pub since_event: Option<String>,
+    /// `next_cursor` of the client's previous refresh, in place of `since_event`
+    pub cursor: Option<u64>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimelineDiff {
+    pub wallet: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since_event: Option<String>,
+    pub cursor: Option<u64>,
+    /// Events first served after `since_event`, or at or after `cursor`, in timeline order
+    pub events: Vec<TimelineEvent>,
+    /// Cursor to pass on the next refresh
+    pub next_cursor: u64,
 }
 
 pub async fn get_timeline(
     State(state): State<AppState>,
     Query(query): Query<TimelineQuery>,
-) -> AppResult<Json<Timeline>> {
+    Query(pagination): Query<Pagination>,
+) -> AppResult<(FreshnessHeaders, HeaderMap, Json<TimelinePage>)> {
     // Fetch fills and funding
-    let fills = state
+    let history = state
         .ingestion_service
-        .fetch_all_fills(&query.wallet, query.since)
+        .fetch_history(&query.wallet, query.since, query.freshness)
         .await?;
+    let headers = freshness_headers(&history);
 
-    let funding = state
+    // Build timeline
+    let mut timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, history.funding)?;
+    state
+        .timeline_service
+        .add_ledger_updates(&mut timeline, history.ledger);
+
+    if let Some(flagged) = query.flagged {
+        timeline
+            .events
+            .retain(|event| event.flags().is_empty() != flagged);
+        timeline.from_timestamp = timeline.events.first().map(|e| e.timestamp());
+        timeline.to_timestamp = timeline.events.last().map(|e| e.timestamp());
+    }
+
+    // Summarize the full selection, then page through its entries
+    let mut summary = timeline.summary();
+    let entries: Vec<TimelineEntry> = if query.group_funding {
+        group_funding(timeline.events)
+    } else {
+        timeline
+            .events
+            .into_iter()
+            .map(|event| TimelineEntry::Event(Box::new(event)))
+            .collect()
+    };
+    summary.count = entries.len();
+
+    Ok((
+        headers,
+        summary_headers(&summary),
+        Json(TimelinePage {
+            wallet: timeline.wallet,
+            events: pagination.apply(entries),
+            from_timestamp: timeline.from_timestamp,
+            to_timestamp: timeline.to_timestamp,
+        }),
+    ))
+}
+
+pub async fn get_timeline_diff(
+    State(state): State<AppState>,
+    Query(query): Query<TimelineDiffQuery>,
+) -> AppResult<(FreshnessHeaders, Json<TimelineDiff>)> {
+    if query.since_event.is_some() && query.cursor.is_some() {
+        return Err(AppError::ValidationError(
+            "Pass since_event or cursor, not both".to_string(),
+        ));
+    }
+
+    // Late and backfilled events can precede served ones, so diff on the full history
+    let history = state
         .ingestion_service
-        .fetch_all_funding(&query.wallet, query.since)
+        .fetch_history(&query.wallet, None, query.freshness)
         .await?;
+    let headers = freshness_headers(&history);
 
-    // Build timeline
-    let timeline = state
+    let mut timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, history.funding)?;
+    state
         .timeline_service
-        .build_timeline(&query.wallet, fills, funding)?;
+        .add_ledger_updates(&mut timeline, history.ledger);
+
+    let (sequence, next_cursor) = state
+        .event_sequencer
+        .sequence(&query.wallet, &timeline.events)
+        .await?;
+    let cursor = match &query.since_event {
+        Some(since_event) => {
+            let number = timeline
+                .events
+                .iter()
+                .zip(&sequence)
+                .find(|(event, _)| event.id() == since_event)
+                .map(|(_, number)| *number)
+                .ok_or_else(|| {
+                    AppError::NotFound(format!(
+                        "Event {} not found in the timeline of {}",
+                        since_event, query.wallet
+                    ))
+                })?;
+            number + 1
+        }
+        None => query.cursor.unwrap_or(0),
+    };
+    if cursor > next_cursor {
+        return Err(AppError::NotFound(format!(
+            "Cursor {} is ahead of the event sequence for {}; fetch /timeline/diff in full",
+            cursor, query.wallet
+        )));
+    }
+
+    let events = timeline
+        .events
+        .into_iter()
+        .zip(sequence)
+        .filter(|(_, number)| *number >= cursor)
+        .map(|(event, _)| event)
+        .collect();
 
-    Ok(Json(timeline))
+    Ok((
+        headers,
+        Json(TimelineDiff {
+            wallet: query.wallet,
+            since_event: query.since_event,
+            cursor: query.cursor,
+            events,
+            next_cursor,
+        }),
+    ))
 }
@@ -0,0 +1,125 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::datasource::Capability;
+use crate::error::{AppError, AppResult};
+use crate::handlers::{freshness_headers, FreshnessHeaders, Pagination};
+use crate::services::ingestion::Freshness;
+use crate::services::market_data::Candle;
+use crate::services::trades::RoundTrip;
+use crate::AppState;
+
+/// Default number of trades returned, each needing a candle lookup
+const DEFAULT_TRADE_LIMIT: usize = 100;
+
+/// Largest number of trades a client may request at once
+pub const MAX_TRADE_LIMIT: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct TradesQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    pub coin: Option<String>,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+pub async fn get_trades(
+    State(state): State<AppState>,
+    Query(query): Query<TradesQuery>,
+    Query(mut pagination): Query<Pagination>,
+) -> AppResult<(FreshnessHeaders, Json<Vec<RoundTrip>>)> {
+    let limit = *pagination.limit.get_or_insert(DEFAULT_TRADE_LIMIT);
+    if limit == 0 || limit > MAX_TRADE_LIMIT {
+        return Err(AppError::ValidationError(format!(
+            "limit must be between 1 and {}",
+            MAX_TRADE_LIMIT
+        )));
+    }
+
+    let (headers, mut trips) =
+        round_trips(&state, &query.wallet, query.since, query.freshness).await?;
+    if let Some(coin) = &query.coin {
+        trips.retain(|trip| trip.coin == *coin);
+    }
+
+    let trips = pagination.apply(trips);
+    Ok((headers, Json(with_excursions(&state, trips).await)))
+}
+
+/// Builds round trips from fills and funding since `since`, oldest entry first, with the
+/// freshness of the history they were built from
+pub async fn round_trips(
+    state: &AppState,
+    wallet: &str,
+    since: Option<i64>,
+    freshness: Freshness,
+) -> AppResult<(FreshnessHeaders, Vec<RoundTrip>)> {
+    let history = state
+        .ingestion_service
+        .fetch_history(wallet, since, freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    let timeline = state
+        .timeline_service
+        .build_timeline(wallet, history.fills, history.funding)?;
+
+    Ok((headers, state.trade_service.build_round_trips(&timeline)))
+}
+
+/// Looks up candles over the trips' lifetimes and attaches each trip's MAE/MFE.
+///
+/// Candles are fetched once per coin, at the finest interval covering all of its trips. A
+/// coin whose candles cannot be fetched has its trips returned without an excursion.
+pub async fn with_excursions(state: &AppState, mut trips: Vec<RoundTrip>) -> Vec<RoundTrip> {
+    let now = Utc::now();
+
+    // Union of the trips' lifetimes per coin
+    let mut spans: HashMap<String, (DateTime<Utc>, DateTime<Utc>)> = HashMap::new();
+    for trip in &trips {
+        let (Some(entry_time), Some(_)) = (trip.entry_time, &trip.entry_price) else {
+            continue;
+        };
+        let exit_time = trip.exit_time.unwrap_or(now);
+        let span = spans
+            .entry(trip.coin.clone())
+            .or_insert((entry_time, exit_time));
+        span.0 = span.0.min(entry_time);
+        span.1 = span.1.max(exit_time);
+    }
+
+    let mut candles: HashMap<String, Vec<Candle>> = HashMap::new();
+    for (coin, (start, end)) in spans {
+        if !state.source_registry.supports(&coin, Capability::Candles) {
+            continue;
+        }
+        match state
+            .ingestion_service
+            .fetch_candles(&coin, start.timestamp_millis(), end.timestamp_millis())
+            .await
+        {
+            Ok(coin_candles) => {
+                candles.insert(coin, coin_candles);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch candles for {} trades: {}", coin, e);
+            }
+        }
+    }
+
+    for trip in &mut trips {
+        if let Some(coin_candles) = candles.get(&trip.coin) {
+            trip.excursion = state
+                .stats_calculator
+                .calculate_excursion(trip, coin_candles, now);
+        }
+    }
+
+    trips
+}
@@ -0,0 +1,44 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::error::AppResult;
+use crate::handlers::{freshness_headers, FreshnessHeaders};
+use crate::services::ingestion::Freshness;
+use crate::services::timeline::Granularity;
+use crate::services::volume::VolumeReport;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct VolumeQuery {
+    pub wallet: String,
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub granularity: Granularity,
+    #[serde(default)]
+    pub freshness: Freshness,
+}
+
+pub async fn get_volume(
+    State(state): State<AppState>,
+    Query(query): Query<VolumeQuery>,
+) -> AppResult<(FreshnessHeaders, Json<VolumeReport>)> {
+    let history = state
+        .ingestion_service
+        .fetch_history(&query.wallet, query.since, query.freshness)
+        .await?;
+    let headers = freshness_headers(&history);
+
+    // Build timeline from fills only
+    let timeline = state
+        .timeline_service
+        .build_timeline(&query.wallet, history.fills, Vec::new())?;
+
+    let report = state
+        .volume_calculator
+        .calculate(&timeline, query.granularity);
+
+    Ok((headers, Json(report)))
+}
@@ -0,0 +1,115 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::error::AppResult;
+use crate::handlers::admin::AdminAuth;
+use crate::services::deletion::DeletionReceipt;
+use crate::services::ingestion::NORMALIZATION_VERSION;
+use crate::services::jobs::{Job, JobStatus};
+use crate::services::reconciliation::Gap;
+use crate::AppState;
+
+/// How complete and current a wallet's stored data is, read without syncing it
+#[derive(Debug, Serialize)]
+pub struct WalletStatus {
+    pub wallet: String,
+    /// Whether the wallet has stored history; counts stay empty until its first sync
+    pub synced: bool,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// Stored events predate the current normalization logic and await reprocessing
+    pub outdated: bool,
+    pub fills: usize,
+    pub funding_payments: usize,
+    pub ledger_updates: usize,
+    pub earliest_event_at: Option<DateTime<Utc>>,
+    pub latest_event_at: Option<DateTime<Utc>>,
+    /// Events tagged by anomaly detection
+    pub flagged_events: usize,
+    /// Corrections upstream made to events across syncs
+    pub restatements: usize,
+    /// Queued or running jobs for the wallet
+    pub pending_jobs: Vec<Job>,
+    /// Inconsistencies between stored fills and funding payments
+    pub reconciliation_gaps: Vec<Gap>,
+}
+
+/// Reports sync time, stored event counts and data quality issues for an address
+pub async fn get_wallet_status(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> AppResult<Json<WalletStatus>> {
+    let pending_jobs: Vec<Job> = state
+        .job_registry
+        .list()
+        .into_iter()
+        .filter(|job| job.wallet.eq_ignore_ascii_case(&address))
+        .filter(|job| matches!(job.status, JobStatus::Queued | JobStatus::Running))
+        .collect();
+
+    let Some(stored) = state.ingestion_service.stored_history(&address).await? else {
+        return Ok(Json(WalletStatus {
+            wallet: address,
+            synced: false,
+            last_synced_at: None,
+            outdated: false,
+            fills: 0,
+            funding_payments: 0,
+            ledger_updates: 0,
+            earliest_event_at: None,
+            latest_event_at: None,
+            flagged_events: 0,
+            restatements: 0,
+            pending_jobs,
+            reconciliation_gaps: Vec::new(),
+        }));
+    };
+
+    let restatements = state.ingestion_service.restatements(&address).await?.len();
+    let (fills, funding_payments, ledger_updates) = (
+        stored.fills.len(),
+        stored.funding.len(),
+        stored.ledger.len(),
+    );
+    let mut timeline = state
+        .timeline_service
+        .build_timeline(&address, stored.fills, stored.funding)?;
+    state
+        .timeline_service
+        .add_ledger_updates(&mut timeline, stored.ledger);
+    let gaps = state.reconciliation_service.detect_gaps(&timeline);
+
+    Ok(Json(WalletStatus {
+        synced: true,
+        last_synced_at: Some(stored.synced_at),
+        outdated: stored.normalization_version < NORMALIZATION_VERSION,
+        fills,
+        funding_payments,
+        ledger_updates,
+        earliest_event_at: timeline.events.first().map(|e| e.timestamp()),
+        latest_event_at: timeline.events.last().map(|e| e.timestamp()),
+        flagged_events: timeline
+            .events
+            .iter()
+            .filter(|e| !e.flags().is_empty())
+            .count(),
+        restatements,
+        pending_jobs,
+        reconciliation_gaps: gaps.gaps,
+        wallet: address,
+    }))
+}
+
+/// Removes all stored data for an address and returns a receipt of what was deleted
+pub async fn delete_wallet_data(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> AppResult<Json<DeletionReceipt>> {
+    let receipt = state.deletion_service.forget_wallet(&address).await?;
+
+    Ok(Json(receipt))
+}
@@ -1,8 +1,10 @@
 use axum::{
     http::{header, Method},
-    routing::get,
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
+use std::collections::BTreeSet;
 use std::env;
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
@@ -10,20 +12,117 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod datasource;
 mod error;
+#[cfg(test)]
+mod golden;
 mod handlers;
+#[cfg(test)]
+mod mock_hyperliquid;
+mod output;
 mod services;
+mod sink;
+mod storage;
 
+use datasource::bybit::{BybitClient, BYBIT_VENUE};
+use datasource::composite::CompositeDataSource;
+use datasource::credentials::{CredentialStore, EncryptedCredentialStore, FileCredentialStore};
+use datasource::evm::EvmTransferClient;
+use datasource::gmx::{GmxClient, GMX_VENUE};
+use datasource::hyperliquid::info_client::ConnectionStats;
 use datasource::hyperliquid::HyperliquidInfoClient;
+use datasource::metered::MeteredDataSource;
+use datasource::okx::{OkxClient, OKX_VENUE};
 use datasource::DataSource;
+use services::activity::ActivityService;
+use services::aggregates::DailyAggregator;
+use services::alerts::{AlertService, WebhookConfig};
+use services::anomalies::{AnomalyConfig, AnomalyDetector};
+use services::archive::ArchiveService;
+use services::backfill::BackfillService;
+use services::assets::AssetRegistry;
+use services::attestation::AttestationService;
+use services::basis::BasisTracker;
+use services::benchmark::BenchmarkCalculator;
+use services::capital::CapitalCalculator;
+use services::capture::CaptureStore;
+use services::carry::CarrySimulator;
+use services::collateral::CollateralService;
+use services::deletion::DeletionService;
+use services::export::ExportService;
+use services::fees::{FeeScheduleTable, FeeSimulator};
+use services::forecasts::ForecastTracker;
+use services::funding_comparison::FundingComparator;
+use services::funding_scanner::FundingScanner;
+use services::heat::HeatTracker;
 use services::ingestion::IngestionService;
-use services::pnl_calculator::PnlCalculator;
+use services::invariants::InvariantChecker;
+use services::jobs::JobRegistry;
+use services::mids::MidsPoller;
+use services::pnl_calculator::{FundingAttribution, PnlCalculator};
+use services::portfolio::PortfolioService;
+use services::reconciliation::ReconciliationService;
+use services::reports::ReportRenderer;
+use services::reprocess::ReprocessService;
+use services::scenarios::{Scenario, ScenarioAnalyzer, DEFAULT_SCENARIOS};
+use services::sequence::EventSequencer;
+use services::sharing::ShareService;
+use services::slo::{SloTargets, SloTracker};
+use services::sources::SourceRegistry;
+use services::statements::StatementCalculator;
+use services::stats::StatsCalculator;
 use services::timeline::TimelineService;
+use services::trades::TradeService;
+use services::volume::VolumeCalculator;
+use sink::s3::S3Sink;
+use sink::ArchiveSink;
+use storage::encrypted::EncryptedStorage;
+use storage::memory::MemoryStorage;
+use storage::Storage;
 
 #[derive(Clone)]
 pub struct AppState {
     pub ingestion_service: Arc<IngestionService>,
     pub timeline_service: Arc<TimelineService>,
+    pub event_sequencer: Arc<EventSequencer>,
+    pub asset_registry: Arc<AssetRegistry>,
+    pub mids_poller: Arc<MidsPoller>,
     pub pnl_calculator: Arc<PnlCalculator>,
+    pub daily_aggregator: Arc<DailyAggregator>,
+    pub stats_calculator: Arc<StatsCalculator>,
+    pub capital_calculator: Arc<CapitalCalculator>,
+    pub statement_calculator: Arc<StatementCalculator>,
+    pub report_renderer: Arc<ReportRenderer>,
+    pub trade_service: Arc<TradeService>,
+    pub export_service: Arc<ExportService>,
+    pub reconciliation_service: Arc<ReconciliationService>,
+    pub invariant_checker: Arc<InvariantChecker>,
+    pub carry_simulator: Arc<CarrySimulator>,
+    pub funding_scanner: Arc<FundingScanner>,
+    pub funding_comparator: Arc<FundingComparator>,
+    pub forecast_tracker: Arc<ForecastTracker>,
+    pub basis_tracker: Arc<BasisTracker>,
+    pub benchmark_calculator: Arc<BenchmarkCalculator>,
+    pub collateral_service: Arc<CollateralService>,
+    pub scenario_analyzer: Arc<ScenarioAnalyzer>,
+    pub fee_simulator: Arc<FeeSimulator>,
+    pub activity_service: Arc<ActivityService>,
+    pub job_registry: Arc<JobRegistry>,
+    pub archive_service: Arc<ArchiveService>,
+    pub reprocess_service: Arc<ReprocessService>,
+    pub backfill_service: Arc<BackfillService>,
+    pub heat_tracker: Arc<HeatTracker>,
+    pub volume_calculator: Arc<VolumeCalculator>,
+    pub portfolio_service: Arc<PortfolioService>,
+    pub alert_service: Arc<AlertService>,
+    pub deletion_service: Arc<DeletionService>,
+    pub share_service: Arc<ShareService>,
+    pub attestation_service: Arc<AttestationService>,
+    pub evm_client: Option<Arc<EvmTransferClient>>,
+    pub credential_store: Option<Arc<EncryptedCredentialStore>>,
+    pub slo_tracker: Arc<SloTracker>,
+    pub upstream_connections: Arc<ConnectionStats>,
+    pub source_registry: Arc<SourceRegistry>,
+    pub capture_store: Arc<CaptureStore>,
+    pub admin_api_key: Option<Arc<str>>,
 }
 
 #[tokio::main]
@@ -42,49 +141,648 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let hyperliquid_info_url = env::var("HYPERLIQUID_INFO_URL")
         .unwrap_or_else(|_| "https://api.hyperliquid.xyz/info".to_string());
+    let hyperliquid_max_attempts: u32 = env::var("HYPERLIQUID_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(4);
+    let hyperliquid_retry_base_ms: u64 = env::var("HYPERLIQUID_RETRY_BASE_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(500);
 
     let server_host = env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
     let server_port = env::var("SERVER_PORT").unwrap_or_else(|_| "8081".to_string());
 
+    let hyperliquid_client = HyperliquidInfoClient::new(&hyperliquid_info_url).with_retry(
+        hyperliquid_max_attempts,
+        std::time::Duration::from_millis(hyperliquid_retry_base_ms),
+    );
+    let app = build_app(hyperliquid_client).await?;
+
+    // Start server
+    let addr = format!("{}:{}", server_host, server_port);
+    tracing::info!("Starting Ledger API server on {}", addr);
+
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+/// Builds the API from environment settings around an upstream Hyperliquid client, and
+/// starts its background tasks
+async fn build_app(
+    hyperliquid_client: HyperliquidInfoClient,
+) -> Result<Router, Box<dyn std::error::Error>> {
+    let admin_api_key = env::var("ADMIN_API_KEY")
+        .ok()
+        .filter(|key| !key.is_empty())
+        .map(Arc::from);
+
+    // Keys pseudonymous wallet IDs for `addresses=pseudonym`; random per process when unset
+    let pseudonymizer = Arc::new(match env::var("ADDRESS_PSEUDONYM_KEY") {
+        Ok(key) if !key.is_empty() => output::Pseudonymizer::new(&key),
+        _ => output::Pseudonymizer::ephemeral(),
+    });
+
+    // Signs share links; without a secret, links stop working on restart
+    let share_signing_secret = match env::var("SHARE_SIGNING_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret,
+        _ => uuid::Uuid::new_v4().to_string(),
+    };
+
+    // Keep upstream responses of full syncs so timelines can be rebuilt without refetching
+    let store_raw_payloads: bool = env::var("STORE_RAW_PAYLOADS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true);
+
+    // Wallets synced at startup and kept warm in storage
+    let hot_wallets: Vec<String> = env::var("HOT_WALLETS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|wallet| wallet.trim().to_string())
+        .filter(|wallet| !wallet.is_empty())
+        .collect();
+    let hot_wallet_refresh_secs: u64 = env::var("HOT_WALLET_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(300);
+
+    // Wallets without trades for DORMANT_AFTER_DAYS are synced every DORMANT_SYNC_SECS instead
+    let heat_window_days: i64 = env::var("HEAT_WINDOW_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    let dormant_after_days: i64 = env::var("DORMANT_AFTER_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(14);
+    let dormant_sync_secs: i64 = env::var("DORMANT_SYNC_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(86400);
+
+    let alert_interval_secs: u64 = env::var("ALERT_EVAL_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+
+    let mut webhook_config = WebhookConfig {
+        signing_secret: env::var("WEBHOOK_SIGNING_SECRET")
+            .ok()
+            .filter(|secret| !secret.is_empty()),
+        ..WebhookConfig::default()
+    };
+    if let Some(max_attempts) = env::var("WEBHOOK_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+    {
+        webhook_config.max_attempts = max_attempts;
+    }
+    if let Some(retry_base_ms) = env::var("WEBHOOK_RETRY_BASE_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+    {
+        webhook_config.retry_base = std::time::Duration::from_millis(retry_base_ms);
+    }
+    if let Some(allow_private_hosts) = env::var("WEBHOOK_ALLOW_PRIVATE_HOSTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+    {
+        webhook_config.allow_private_hosts = allow_private_hosts;
+    }
+
+    // Deployment templates override the built-in report and alert templates by name
+    let report_template_dir = env::var("REPORT_TEMPLATE_DIR")
+        .ok()
+        .filter(|dir| !dir.is_empty());
+
+    let asset_refresh_secs: u64 = env::var("ASSET_META_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600);
+
+    // Live mid snapshots for slippage on fills too recent for candles; 0 disables polling
+    let mids_poll_secs: u64 = env::var("MIDS_POLL_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5);
+    let mids_retention_secs: u64 = env::var("MIDS_RETENTION_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(900);
+
+    let mut anomaly_config = AnomalyConfig::default();
+    if let Some(multiple) = env_decimal("ANOMALY_FEE_SPIKE_MULTIPLE") {
+        anomaly_config.fee_spike_multiple = multiple;
+    }
+    if let Some(tolerance) = env_decimal("ANOMALY_PRICE_TOLERANCE_BPS") {
+        anomaly_config.price_tolerance_bps = tolerance;
+    }
+    if let Some(max_rate) = env_decimal("ANOMALY_MAX_FUNDING_RATE") {
+        anomaly_config.max_funding_rate = max_rate;
+    }
+
+    let mut slo_targets = SloTargets::default();
+    if let Some(success_rate) = env::var("SLO_SUCCESS_TARGET")
+        .ok()
+        .and_then(|v| v.parse().ok())
+    {
+        slo_targets.success_rate = success_rate;
+    }
+    if let Some(p95_latency_ms) = env::var("SLO_P95_LATENCY_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+    {
+        slo_targets.p95_latency_ms = p95_latency_ms;
+    }
+    let slo_tracker = Arc::new(SloTracker::new(slo_targets));
+
+    let funding_attribution = match env::var("FUNDING_DAY_ATTRIBUTION") {
+        Ok(value) if !value.is_empty() => value.parse().unwrap_or_else(|e| {
+            tracing::warn!("{}; using the default", e);
+            FundingAttribution::default()
+        }),
+        _ => FundingAttribution::default(),
+    };
+
+    // Signs of fees and losses when a request does not pass `signs=`
+    let sign_convention = match env::var("SIGN_CONVENTION") {
+        Ok(value) if !value.is_empty() => value.parse().unwrap_or_else(|e| {
+            tracing::warn!("{}; using the default", e);
+            output::SignConvention::default()
+        }),
+        _ => output::SignConvention::default(),
+    };
+
+    let collateral_par_tokens: BTreeSet<String> = env::var("COLLATERAL_PAR_TOKENS")
+        .unwrap_or_else(|_| "USD,USDT,USDT0".to_string())
+        .split(',')
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+        .collect();
+
+    // Historical fee schedules; without a file today's schedule applies to all fills
+    let fee_schedule = match env::var("FEE_SCHEDULE_FILE") {
+        Ok(path) if !path.is_empty() => FeeScheduleTable::load(&path)?,
+        _ => FeeScheduleTable::builtin(),
+    };
+
+    // Price shocks applied by /risk/scenarios when a request names none
+    let risk_scenarios = Scenario::parse_list(
+        &env::var("RISK_SCENARIOS").unwrap_or_else(|_| DEFAULT_SCENARIOS.to_string()),
+    )?;
+
+    let capture_max_entries: usize = env::var("CAPTURE_MAX_ENTRIES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(50);
+    let capture_max_bytes: usize = env::var("CAPTURE_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(4 * 1024 * 1024);
+    let capture_store = Arc::new(CaptureStore::new(capture_max_entries, capture_max_bytes));
+
+    // Every upstream is metered for the SLO report
+    let metered = |name: &str, source: Arc<dyn DataSource>| -> Arc<dyn DataSource> {
+        Arc::new(MeteredDataSource::new(name, source, slo_tracker.clone()))
+    };
+
     // Initialize data source
-    let datasource: Arc<dyn DataSource> =
-        Arc::new(HyperliquidInfoClient::new(&hyperliquid_info_url));
+    let hyperliquid_client = Arc::new(hyperliquid_client);
+    let upstream_connections = hyperliquid_client.connection_stats();
+    let hyperliquid = metered("hyperliquid", hyperliquid_client);
+
+    let mut source_registry = SourceRegistry::new(slo_tracker.clone());
+    source_registry.register("hyperliquid", hyperliquid.clone());
+    let mut composite = CompositeDataSource::new(hyperliquid);
+
+    // Consolidate GMX activity when a subgraph is configured
+    if let Ok(subgraph_url) = env::var("GMX_SUBGRAPH_URL")
+        && !subgraph_url.is_empty()
+    {
+        let api_url = env::var("GMX_API_URL")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| "https://arbitrum-api.gmxinfra.io".to_string());
+        let gmx = metered(GMX_VENUE, Arc::new(GmxClient::new(&subgraph_url, &api_url)));
+        source_registry.register(GMX_VENUE, gmx.clone());
+        composite = composite.with_venue(GMX_VENUE, gmx);
+    }
+
+    // Prefer the encrypted credential store; a plaintext file is still accepted
+    let credential_store = match env::var("CREDENTIALS_MASTER_KEY") {
+        Ok(master_key) if !master_key.is_empty() => {
+            let store_path = env::var("CREDENTIALS_STORE_FILE")
+                .unwrap_or_else(|_| "credentials.enc.json".to_string());
+            Some(Arc::new(EncryptedCredentialStore::open(
+                &store_path,
+                &master_key,
+            )?))
+        }
+        _ => None,
+    };
+
+    let credentials_file = env::var("CEX_CREDENTIALS_FILE")
+        .ok()
+        .filter(|path| !path.is_empty());
+    let credentials: Option<Arc<dyn CredentialStore>> = match (&credential_store, credentials_file)
+    {
+        (Some(store), _) => Some(store.clone()),
+        (None, Some(credentials_file)) => {
+            tracing::warn!(
+                "Loading plaintext CEX credentials; set CREDENTIALS_MASTER_KEY to encrypt them"
+            );
+            Some(Arc::new(FileCredentialStore::load(&credentials_file)?))
+        }
+        (None, None) => None,
+    };
+
+    // Consolidate CEX activity for profiles with read-only API keys
+    if let Some(credentials) = credentials {
+        let bybit_url = env::var("BYBIT_API_URL")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| "https://api.bybit.com".to_string());
+        let okx_url = env::var("OKX_API_URL")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| "https://www.okx.com".to_string());
+
+        let bybit = metered(
+            BYBIT_VENUE,
+            Arc::new(BybitClient::new(&bybit_url, credentials.clone())),
+        );
+        let okx = metered(OKX_VENUE, Arc::new(OkxClient::new(&okx_url, credentials)));
+        source_registry.register(BYBIT_VENUE, bybit.clone());
+        source_registry.register(OKX_VENUE, okx.clone());
+
+        composite = composite
+            .with_venue(BYBIT_VENUE, bybit)
+            .with_venue(OKX_VENUE, okx);
+    }
+
+    let datasource: Arc<dyn DataSource> = Arc::new(composite);
+
+    // Initialize on-chain bridge transfer checks (optional)
+    let evm_client = env::var("RPC_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .map(|rpc_url| {
+            let bridge = env::var("HYPERLIQUID_BRIDGE_ADDRESS")
+                .unwrap_or_else(|_| "0x2Df1c51E09aECF9cacB7bc98cB1742757f163dF7".to_string());
+            let token = env::var("BRIDGE_TOKEN_ADDRESS")
+                .unwrap_or_else(|_| "0xaf88d065e77c8cC2239327C5EDb3A432268e5831".to_string());
+            let block_range = env::var("RPC_LOG_BLOCK_RANGE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000);
+
+            Arc::new(EvmTransferClient::new(&rpc_url, &bridge, &token, block_range))
+        });
+
+    // Initialize storage
+    let mut storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+
+    // Encrypt stored event payloads with per-wallet keys when a master key is set
+    if let Ok(master_key) = env::var("STORAGE_MASTER_KEY")
+        && !master_key.is_empty()
+    {
+        storage = Arc::new(EncryptedStorage::new(storage, &master_key)?);
+    }
+
+    // Initialize archive sink (optional)
+    let archive_sink: Option<Arc<dyn ArchiveSink>> = env::var("S3_BUCKET")
+        .ok()
+        .filter(|bucket| !bucket.is_empty())
+        .map(|bucket| {
+            let endpoint = env::var("S3_ENDPOINT")
+                .unwrap_or_else(|_| "https://s3.amazonaws.com".to_string());
+            let region = env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+            let access_key_id = env::var("S3_ACCESS_KEY_ID").unwrap_or_default();
+            let secret_access_key = env::var("S3_SECRET_ACCESS_KEY").unwrap_or_default();
+
+            Arc::new(S3Sink::new(
+                &endpoint,
+                &bucket,
+                &region,
+                &access_key_id,
+                &secret_access_key,
+            )) as Arc<dyn ArchiveSink>
+        });
+    let archive_prefix = env::var("S3_PREFIX").unwrap_or_else(|_| "goker-ledger".to_string());
+
+    // Initialize asset registry and refresh it in the background
+    let asset_registry = Arc::new(AssetRegistry::new(datasource.clone(), storage.clone()));
+    asset_registry.load().await?;
+    asset_registry.spawn_refresher(std::time::Duration::from_secs(asset_refresh_secs));
 
     // Initialize services
-    let ingestion_service = Arc::new(IngestionService::new(datasource));
-    let timeline_service = Arc::new(TimelineService::new());
-    let pnl_calculator = Arc::new(PnlCalculator::new());
+    let ingestion_service = Arc::new(IngestionService::new(
+        datasource,
+        storage.clone(),
+        AnomalyDetector::new(anomaly_config),
+        store_raw_payloads,
+    ));
+    let heat_tracker = Arc::new(HeatTracker::new(
+        storage.clone(),
+        heat_window_days,
+        dormant_after_days,
+        dormant_sync_secs,
+    ));
+    ingestion_service.spawn_warmer(
+        hot_wallets,
+        std::time::Duration::from_secs(hot_wallet_refresh_secs),
+        heat_tracker.clone(),
+    );
+    let timeline_service = Arc::new(TimelineService::new(asset_registry.clone()));
+    let event_sequencer = Arc::new(EventSequencer::new(storage.clone()));
+    let mids_poller = Arc::new(MidsPoller::new(
+        ingestion_service.clone(),
+        asset_registry.clone(),
+        std::time::Duration::from_secs(mids_poll_secs),
+        std::time::Duration::from_secs(mids_retention_secs),
+    ));
+    mids_poller.spawn_poller();
+    let pnl_calculator = Arc::new(PnlCalculator::new(funding_attribution));
+    let daily_aggregator = Arc::new(DailyAggregator::new(
+        ingestion_service.clone(),
+        storage.clone(),
+        timeline_service.clone(),
+        pnl_calculator.clone(),
+    ));
+    let stats_calculator = Arc::new(StatsCalculator::new());
+    let capital_calculator = Arc::new(CapitalCalculator::new());
+    let statement_calculator = Arc::new(StatementCalculator::new());
+    let report_renderer = Arc::new(ReportRenderer::new(report_template_dir.as_deref())?);
+    let trade_service = Arc::new(TradeService::new());
+    let export_service = Arc::new(ExportService::new());
+    let reconciliation_service = Arc::new(ReconciliationService::new());
+    let invariant_checker = Arc::new(InvariantChecker::new());
+    let carry_simulator = Arc::new(CarrySimulator::new());
+    let funding_scanner = Arc::new(FundingScanner::new());
+    let funding_comparator = Arc::new(FundingComparator::new());
+    let forecast_tracker = Arc::new(ForecastTracker::new(
+        ingestion_service.clone(),
+        storage.clone(),
+    ));
+    let basis_tracker = Arc::new(BasisTracker::new());
+    let benchmark_calculator = Arc::new(BenchmarkCalculator::new());
+    let collateral_service = Arc::new(CollateralService::new(
+        ingestion_service.clone(),
+        asset_registry.clone(),
+        collateral_par_tokens,
+    ));
+    let scenario_analyzer = Arc::new(ScenarioAnalyzer::new(risk_scenarios));
+    let fee_simulator = Arc::new(FeeSimulator::new(fee_schedule));
+    let activity_service = Arc::new(ActivityService::new(
+        ingestion_service.clone(),
+        asset_registry.clone(),
+    ));
+    let volume_calculator = Arc::new(VolumeCalculator::new());
+    let portfolio_service = Arc::new(PortfolioService::new());
+    let job_registry = Arc::new(JobRegistry::new());
+    let archive_service = Arc::new(ArchiveService::new(
+        ingestion_service.clone(),
+        timeline_service.clone(),
+        job_registry.clone(),
+        archive_sink,
+        &archive_prefix,
+    ));
+
+    // Re-derive wallets stored by an older normalization version in the background
+    let reprocess_service = Arc::new(ReprocessService::new(
+        ingestion_service.clone(),
+        job_registry.clone(),
+    ));
+    reprocess_service.start_if_outdated().await?;
+    let backfill_service = Arc::new(BackfillService::new(
+        ingestion_service.clone(),
+        storage.clone(),
+        job_registry.clone(),
+    ));
+    let alert_service = Arc::new(AlertService::new(
+        ingestion_service.clone(),
+        timeline_service.clone(),
+        storage.clone(),
+        webhook_config,
+        report_renderer.clone(),
+    ));
+
+    // Start background alert evaluation
+    alert_service.spawn_evaluator(std::time::Duration::from_secs(alert_interval_secs));
+
+    let share_service = Arc::new(ShareService::new(storage.clone(), &share_signing_secret));
+
+    // Signs attestations; without a key, earlier attestations stop verifying on restart
+    let attestation_signing_key = env::var("ATTESTATION_SIGNING_KEY")
+        .ok()
+        .filter(|key| !key.is_empty());
+    let attestation_service = Arc::new(AttestationService::new(
+        attestation_signing_key.as_deref(),
+    )?);
+    let deletion_service = Arc::new(DeletionService::new(
+        ingestion_service.clone(),
+        alert_service.clone(),
+        capture_store.clone(),
+        share_service.clone(),
+    ));
 
     // Create app state
     let state = AppState {
         ingestion_service,
         timeline_service,
+        event_sequencer,
+        asset_registry,
+        mids_poller,
         pnl_calculator,
+        daily_aggregator,
+        stats_calculator,
+        capital_calculator,
+        statement_calculator,
+        report_renderer,
+        trade_service,
+        export_service,
+        reconciliation_service,
+        invariant_checker,
+        carry_simulator,
+        funding_scanner,
+        funding_comparator,
+        forecast_tracker,
+        basis_tracker,
+        benchmark_calculator,
+        collateral_service,
+        scenario_analyzer,
+        fee_simulator,
+        activity_service,
+        job_registry,
+        archive_service,
+        reprocess_service,
+        backfill_service,
+        heat_tracker,
+        volume_calculator,
+        portfolio_service,
+        alert_service,
+        deletion_service,
+        share_service,
+        attestation_service,
+        evm_client,
+        credential_store,
+        slo_tracker: slo_tracker.clone(),
+        upstream_connections,
+        source_registry: Arc::new(source_registry),
+        capture_store,
+        admin_api_key,
     };
 
     // Build CORS layer
     let cors = CorsLayer::new()
         .allow_origin(Any)
-        .allow_methods([Method::GET])
-        .allow_headers([header::CONTENT_TYPE]);
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([header::CONTENT_TYPE])
+        .expose_headers(handlers::EXPOSED_HEADERS);
 
     // Build router
     let app = Router::new()
         .route("/health", get(|| async { "OK" }))
+        .route("/metrics", get(handlers::metrics::get_metrics))
+        .route("/activity", get(handlers::activity::get_activity))
         .route("/timeline", get(handlers::timeline::get_timeline))
+        .route("/timeline/diff", get(handlers::timeline::get_timeline_diff))
         .route("/pnl", get(handlers::pnl::get_pnl_summary))
         .route("/pnl/daily", get(handlers::pnl::get_daily_pnl))
+        .route("/pnl/decomposition", get(handlers::pnl::get_pnl_decomposition))
+        .route("/pnl/statement", get(handlers::pnl::get_statement))
+        .route("/pnl/collateral", get(handlers::pnl::get_collateral_pnl))
+        .route("/pnl/preview", post(handlers::pnl::preview_pnl))
         .route("/fills", get(handlers::fills::get_fills))
+        .route("/fills/normalized", get(handlers::fills::get_normalized_fills))
+        .route("/trades", get(handlers::trades::get_trades))
         .route("/funding", get(handlers::funding::get_funding))
+        .route(
+            "/funding/normalized",
+            get(handlers::funding::get_normalized_funding),
+        )
+        .route("/funding/scanner", get(handlers::funding::get_scanner))
+        .route(
+            "/funding/comparison",
+            get(handlers::funding::get_funding_comparison),
+        )
+        .route(
+            "/funding/forecast-accuracy",
+            get(handlers::funding::get_forecast_accuracy),
+        )
+        .route("/volume", get(handlers::volume::get_volume))
+        .route("/portfolio", get(handlers::portfolio::get_portfolio))
+        .route("/basis", get(handlers::basis::get_basis))
+        .route(
+            "/benchmark/custom",
+            get(handlers::benchmark::get_custom_benchmark),
+        )
+        .route("/meta/coins", get(handlers::meta::get_coins))
+        .route("/meta/sources", get(handlers::meta::get_sources))
+        .route("/state/at", get(handlers::state::get_state_at))
+        .route("/positions/lots", get(handlers::positions::get_lots))
+        .route("/analytics", get(handlers::analytics::get_analytics))
+        .route("/stats/sizing", get(handlers::stats::get_sizing_stats))
+        .route("/stats/mm", get(handlers::stats::get_market_making_stats))
+        .route("/stats/distributions", get(handlers::stats::get_distributions))
+        .route("/stats/excursions", get(handlers::stats::get_excursion_stats))
+        .route("/stats/execution", get(handlers::stats::get_execution_stats))
+        .route("/export/journal-csv", get(handlers::export::get_journal_csv))
+        .route("/reports/templates", get(handlers::reports::list_templates))
+        .route(
+            "/reports/statement",
+            get(handlers::reports::get_statement_report),
+        )
+        .route("/reconcile/gaps", get(handlers::reconcile::get_gaps))
+        .route("/audit/restatements", get(handlers::audit::get_restatements))
+        .route("/simulate/carry", get(handlers::simulate::simulate_carry))
+        .route("/simulate/fees", get(handlers::simulate::simulate_fees))
+        .route("/risk/scenarios", get(handlers::risk::get_scenarios))
+        .route(
+            "/alerts/rules",
+            get(handlers::alerts::list_rules).post(handlers::alerts::create_rule),
+        )
+        .route(
+            "/alerts/rules/{id}",
+            get(handlers::alerts::get_rule)
+                .put(handlers::alerts::update_rule)
+                .delete(handlers::alerts::delete_rule),
+        )
+        .route("/alerts/history", get(handlers::alerts::get_history))
+        .route("/share", post(handlers::share::create_share))
+        .route("/share/{id}", get(handlers::share::get_share))
+        .route(
+            "/export/attestation",
+            get(handlers::attestations::get_attestation),
+        )
+        .route(
+            "/attestations/public-key",
+            get(handlers::attestations::get_public_key),
+        )
+        .route(
+            "/attestations/verify",
+            post(handlers::attestations::verify_attestation),
+        )
+        .route(
+            "/wallets/{address}/status",
+            get(handlers::wallets::get_wallet_status),
+        )
+        .route(
+            "/wallets/{address}/data",
+            delete(handlers::wallets::delete_wallet_data),
+        )
+        .route("/admin/exports/s3", post(handlers::admin::start_s3_export))
+        .route("/admin/backfills", post(handlers::admin::start_backfill))
+        .route("/admin/jobs", get(handlers::admin::list_jobs))
+        .route("/admin/jobs/{id}", get(handlers::admin::get_job))
+        .route("/admin/jobs/{id}/resume", post(handlers::admin::resume_job))
+        .route(
+            "/admin/reprocess",
+            get(handlers::admin::get_reprocess_status).post(handlers::admin::start_reprocess),
+        )
+        .route("/admin/wallets/heat", get(handlers::admin::get_wallet_heat))
+        .route("/admin/credentials", get(handlers::admin::list_credentials))
+        .route(
+            "/admin/credentials/{profile}/{venue}",
+            put(handlers::admin::put_credentials).delete(handlers::admin::delete_credentials),
+        )
+        .route("/admin/slo", get(handlers::admin::get_slo))
+        .route("/admin/selftest", get(handlers::admin::run_selftest))
+        .route("/admin/captures", get(handlers::admin::list_captures))
+        .route("/admin/captures/{id}", get(handlers::admin::get_capture))
+        .route(
+            "/admin/webhooks/dead-letters",
+            get(handlers::admin::list_dead_letters),
+        )
+        .route(
+            "/admin/webhooks/dead-letters/{id}/replay",
+            post(handlers::admin::replay_dead_letter),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            slo_tracker,
+            services::slo::track_requests,
+        ))
+        .route_layer(middleware::from_fn(services::timing::time_handler))
+        .layer(middleware::from_fn_with_state(
+            sign_convention,
+            output::apply_sign_convention,
+        ))
+        .layer(middleware::from_fn_with_state(pseudonymizer, output::format_response))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            services::capture::capture_requests,
+        ))
+        .layer(middleware::from_fn(services::timing::time_requests))
         .layer(cors)
         .with_state(state);
 
-    // Start server
-    let addr = format!("{}:{}", server_host, server_port);
-    tracing::info!("Starting Ledger API server on {}", addr);
-
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    Ok(app)
+}
 
-    Ok(())
+/// Reads an optional decimal setting, ignoring unparseable values
+fn env_decimal(key: &str) -> Option<bigdecimal::BigDecimal> {
+    env::var(key).ok().and_then(|v| v.parse().ok())
 }
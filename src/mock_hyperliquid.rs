@@ -0,0 +1,959 @@
+//! Integration tests of the full API against an in-process Hyperliquid stand-in.
+//!
+//! `MockHyperliquid` serves the info endpoint on a local port: per-wallet fills, funding and
+//! ledger updates are paginated like upstream, in time order from `startTime` with at most
+//! 500 items per page. Tests queue rate limits, server errors and malformed bodies per
+//! request type, then drive the app over HTTP and inspect the requests upstream received.
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::datasource::composite::CompositeDataSource;
+use crate::datasource::hyperliquid::HyperliquidInfoClient;
+use crate::datasource::DataSource;
+use crate::services::alerts::{
+    AlertMetric, AlertService, Comparator, DeadLetter, FiredAlert, WebhookConfig,
+};
+use crate::services::anomalies::{AnomalyConfig, AnomalyDetector};
+use crate::services::assets::AssetRegistry;
+use crate::services::backfill::BackfillService;
+use crate::services::corrections;
+use crate::services::ingestion::{IngestionService, NORMALIZATION_VERSION};
+use crate::services::jobs::{Job, JobRegistry, JobStatus};
+use crate::services::reports::ReportRenderer;
+use crate::services::timeline::TimelineService;
+use crate::storage::memory::MemoryStorage;
+use crate::storage::Storage;
+
+/// Items per page, as upstream
+const PAGE_SIZE: usize = 500;
+
+/// Retry settings for the app under test, short enough to keep tests fast
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BASE: Duration = Duration::from_millis(5);
+
+/// A scripted failure answered in place of the next request of a type
+#[derive(Debug, Clone, Copy)]
+pub enum Fault {
+    RateLimited,
+    ServerError,
+    /// A 200 response whose body is not valid JSON
+    Malformed,
+}
+
+#[derive(Debug, Default)]
+struct WalletData {
+    fills: Vec<Value>,
+    funding: Vec<Value>,
+    ledger: Vec<Value>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    wallets: HashMap<String, WalletData>,
+    /// Scripted answers per request type; None answers normally
+    faults: HashMap<String, VecDeque<Option<Fault>>>,
+    requests: Vec<Value>,
+}
+
+/// An info endpoint serving scripted wallets, listening until dropped with the runtime
+#[derive(Clone)]
+pub struct MockHyperliquid {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockHyperliquid {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let router = Router::new()
+            .route("/info", post(info))
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock upstream");
+        let addr = listener.local_addr().expect("mock upstream address");
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.expect("mock upstream");
+        });
+
+        Self {
+            url: format!("http://{}/info", addr),
+            state,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn set_fills(&self, wallet: &str, fills: Vec<Value>) {
+        self.wallet(wallet, |data| data.fills = fills);
+    }
+
+    pub fn set_funding(&self, wallet: &str, funding: Vec<Value>) {
+        self.wallet(wallet, |data| data.funding = funding);
+    }
+
+    pub fn set_ledger(&self, wallet: &str, ledger: Vec<Value>) {
+        self.wallet(wallet, |data| data.ledger = ledger);
+    }
+
+    /// Answers the next `times` requests of `request_type` with `fault`
+    pub fn fail_next(&self, request_type: &str, fault: Fault, times: usize) {
+        let mut state = self.state.lock().expect("mock state lock poisoned");
+        let queue = state.faults.entry(request_type.to_string()).or_default();
+        queue.extend(std::iter::repeat_n(Some(fault), times));
+    }
+
+    /// Answers the next `times` requests of `request_type` normally, ahead of queued faults
+    pub fn pass_next(&self, request_type: &str, times: usize) {
+        let mut state = self.state.lock().expect("mock state lock poisoned");
+        let queue = state.faults.entry(request_type.to_string()).or_default();
+        queue.extend(std::iter::repeat_n(None, times));
+    }
+
+    /// Payloads received for `request_type`, in arrival order
+    pub fn requests(&self, request_type: &str) -> Vec<Value> {
+        let state = self.state.lock().expect("mock state lock poisoned");
+        state
+            .requests
+            .iter()
+            .filter(|request| request["type"] == request_type)
+            .cloned()
+            .collect()
+    }
+
+    fn wallet(&self, wallet: &str, update: impl FnOnce(&mut WalletData)) {
+        let mut state = self.state.lock().expect("mock state lock poisoned");
+        update(state.wallets.entry(wallet.to_lowercase()).or_default());
+    }
+}
+
+/// A perp fill in upstream's shape
+pub fn fill(tid: u64, time: i64, coin: &str, side: &str, px: &str, sz: &str) -> Value {
+    json!({
+        "coin": coin,
+        "px": px,
+        "sz": sz,
+        "side": side,
+        "time": time,
+        "startPosition": "0.0",
+        "dir": "",
+        "closedPnl": "0.0",
+        "hash": format!("0x{:064x}", tid),
+        "oid": tid,
+        "crossed": true,
+        "fee": "0.1",
+        "tid": tid,
+        "feeToken": "USDC"
+    })
+}
+
+/// A funding payment in the shape the data source passes on
+pub fn funding(time: i64, coin: &str, usdc: &str, szi: &str, rate: &str) -> Value {
+    json!({ "time": time, "coin": coin, "usdc": usdc, "szi": szi, "fundingRate": rate })
+}
+
+/// A USDC deposit ledger update
+pub fn deposit(time: i64, usdc: &str) -> Value {
+    json!({
+        "time": time,
+        "hash": format!("0x{:064x}", time),
+        "delta": { "type": "deposit", "usdc": usdc }
+    })
+}
+
+/// A USDC withdrawal ledger update
+pub fn withdrawal(time: i64, usdc: &str) -> Value {
+    json!({
+        "time": time,
+        "hash": format!("0x{:064x}", time + 1),
+        "delta": { "type": "withdraw", "usdc": usdc, "fee": "1.0" }
+    })
+}
+
+/// A USDC transfer between Hyperliquid accounts, as it appears in both wallets' ledgers
+pub fn internal_transfer(time: i64, user: &str, destination: &str, usdc: &str) -> Value {
+    json!({
+        "time": time,
+        "hash": format!("0x{:064x}", time + 2),
+        "delta": {
+            "type": "internalTransfer",
+            "usdc": usdc,
+            "user": user,
+            "destination": destination,
+            "fee": "0.0"
+        }
+    })
+}
+
+async fn info(State(state): State<Arc<Mutex<MockState>>>, Json(request): Json<Value>) -> Response {
+    let mut state = state.lock().expect("mock state lock poisoned");
+    state.requests.push(request.clone());
+
+    let request_type = request["type"].as_str().unwrap_or_default().to_string();
+    let fault = state
+        .faults
+        .get_mut(&request_type)
+        .and_then(|queue| queue.pop_front())
+        .flatten();
+    match fault {
+        Some(Fault::RateLimited) => return StatusCode::TOO_MANY_REQUESTS.into_response(),
+        Some(Fault::ServerError) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "upstream unavailable").into_response();
+        }
+        Some(Fault::Malformed) => {
+            return ([(header::CONTENT_TYPE, "application/json")], "{\"time\": [").into_response();
+        }
+        None => {}
+    }
+
+    let wallet = request["user"]
+        .as_str()
+        .map(str::to_lowercase)
+        .and_then(|user| state.wallets.get(&user));
+    let start_time = request["startTime"].as_i64();
+    let items = |select: fn(&WalletData) -> &Vec<Value>| {
+        Json(page(
+            wallet.map(select).map(Vec::as_slice).unwrap_or_default(),
+            start_time,
+        ))
+        .into_response()
+    };
+
+    match request_type.as_str() {
+        "userFills" | "userFillsByTime" => items(|data| &data.fills),
+        "userFunding" => items(|data| &data.funding),
+        "userNonFundingLedgerUpdates" => items(|data| &data.ledger),
+        "meta" => Json(json!({ "universe": [] })).into_response(),
+        "spotMeta" => Json(json!({ "tokens": [], "universe": [] })).into_response(),
+        "allMids" => Json(json!({})).into_response(),
+        "clearinghouseState" => Json(json!({
+            "assetPositions": [],
+            "marginSummary": { "accountValue": "0.0" }
+        }))
+        .into_response(),
+        _ => (StatusCode::UNPROCESSABLE_ENTITY, "unknown request type").into_response(),
+    }
+}
+
+/// Up to a page of items at or after `start_time`, oldest first
+fn page(items: &[Value], start_time: Option<i64>) -> Vec<Value> {
+    let mut selected: Vec<&Value> = items
+        .iter()
+        .filter(|item| start_time.is_none_or(|start| item["time"].as_i64() >= Some(start)))
+        .collect();
+    selected.sort_by_key(|item| item["time"].as_i64());
+    selected.into_iter().take(PAGE_SIZE).cloned().collect()
+}
+
+/// Serves the full app against `mock`, returning its base URL
+async fn serve_app(mock: &MockHyperliquid) -> String {
+    let client = HyperliquidInfoClient::new(mock.url()).with_retry(MAX_ATTEMPTS, RETRY_BASE);
+    let app = crate::build_app(client).await.expect("app builds");
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind app");
+    let addr = listener.local_addr().expect("app address");
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.expect("app server");
+    });
+
+    format!("http://{}", addr)
+}
+
+async fn get_fills(app: &str, wallet: &str) -> reqwest::Response {
+    reqwest::get(format!("{}/fills?wallet={}", app, wallet))
+        .await
+        .expect("request app")
+}
+
+/// Fills spread over several pages, with three sharing the first page's last millisecond
+fn paged_fills() -> Vec<Value> {
+    let start = 1_709_251_200_000;
+    (0..1_200u64)
+        .map(|i| {
+            let time = if (498..=500).contains(&i) {
+                start + 498
+            } else {
+                start + i as i64
+            };
+            fill(
+                i + 1,
+                time,
+                "BTC",
+                if i % 2 == 0 { "B" } else { "A" },
+                "60000.0",
+                "0.01",
+            )
+        })
+        .collect()
+}
+
+fn tids(fills: &[Value]) -> Vec<u64> {
+    let mut tids: Vec<u64> = fills.iter().filter_map(|f| f["tid"].as_u64()).collect();
+    tids.sort();
+    tids
+}
+
+#[tokio::test]
+async fn paginates_without_dropping_or_duplicating_boundary_fills() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000aa";
+    let fills = paged_fills();
+    mock.set_fills(wallet, fills.clone());
+    let app = serve_app(&mock).await;
+
+    let response = get_fills(&app, wallet).await;
+    assert_eq!(response.status(), 200);
+    let served: Vec<Value> = response.json().await.expect("fills body");
+
+    assert_eq!(tids(&served), tids(&fills));
+    let pages = mock.requests("userFills");
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages[1]["startTime"], fills[499]["time"]);
+}
+
+#[tokio::test]
+async fn retries_rate_limited_and_failed_requests() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000bb";
+    mock.set_fills(
+        wallet,
+        vec![fill(1, 1_709_251_200_000, "ETH", "B", "3000.0", "1.0")],
+    );
+    mock.fail_next("userFills", Fault::RateLimited, 1);
+    mock.fail_next("userFills", Fault::ServerError, 1);
+    let app = serve_app(&mock).await;
+
+    let response = get_fills(&app, wallet).await;
+    assert_eq!(response.status(), 200);
+    let served: Vec<Value> = response.json().await.expect("fills body");
+
+    assert_eq!(tids(&served), vec![1]);
+    assert_eq!(mock.requests("userFills").len(), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000cc";
+    mock.fail_next("userFills", Fault::RateLimited, MAX_ATTEMPTS as usize);
+    let app = serve_app(&mock).await;
+
+    let response = get_fills(&app, wallet).await;
+
+    assert_eq!(response.status(), 502);
+    assert_eq!(mock.requests("userFills").len(), MAX_ATTEMPTS as usize);
+}
+
+#[tokio::test]
+async fn malformed_payloads_fail_without_retrying() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000dd";
+    mock.fail_next("userFills", Fault::Malformed, 1);
+    let app = serve_app(&mock).await;
+
+    let response = get_fills(&app, wallet).await;
+
+    assert_eq!(response.status(), 502);
+    assert_eq!(mock.requests("userFills").len(), 1);
+}
+
+#[tokio::test]
+async fn syncs_paged_funding_and_retried_ledger_updates() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000ee";
+    let start = 1_709_251_200_000;
+    let payments: Vec<Value> = (0..600)
+        .map(|i| funding(start + i * 3_600_000, "BTC", "-1.5", "0.5", "0.00005"))
+        .collect();
+    mock.set_funding(wallet, payments);
+    mock.set_ledger(wallet, vec![deposit(start - 1, "50000.0")]);
+    mock.fail_next("userNonFundingLedgerUpdates", Fault::ServerError, 1);
+    let app = serve_app(&mock).await;
+
+    let response = reqwest::get(format!("{}/funding?wallet={}", app, wallet))
+        .await
+        .expect("request app");
+    assert_eq!(response.status(), 200);
+    let served: Vec<Value> = response.json().await.expect("funding body");
+
+    assert_eq!(served.len(), 600);
+    assert_eq!(mock.requests("userFunding").len(), 2);
+    assert_eq!(mock.requests("userNonFundingLedgerUpdates").len(), 2);
+}
+
+/// Waits for a job to leave the queued and running states
+async fn settled(registry: &JobRegistry, id: uuid::Uuid) -> Job {
+    for _ in 0..500 {
+        let job = registry.get(id).expect("job exists");
+        if !matches!(job.status, JobStatus::Queued | JobStatus::Running) {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("job {} did not settle", id);
+}
+
+#[tokio::test]
+async fn resumes_interrupted_backfill_from_checkpoints() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000ff";
+    let start = 1_709_251_200_000;
+    mock.set_fills(wallet, paged_fills());
+    let payments: Vec<Value> = (0..600)
+        .map(|i| funding(start + i * 3_600_000, "BTC", "-1.5", "0.5", "0.00005"))
+        .collect();
+    mock.set_funding(wallet, payments);
+    mock.set_ledger(wallet, vec![deposit(start - 1, "50000.0")]);
+    // The second funding page fails on every attempt
+    mock.pass_next("userFunding", 1);
+    mock.fail_next("userFunding", Fault::ServerError, MAX_ATTEMPTS as usize);
+
+    let client = HyperliquidInfoClient::new(mock.url()).with_retry(MAX_ATTEMPTS, RETRY_BASE);
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let ingestion = Arc::new(IngestionService::new(
+        Arc::new(client),
+        storage.clone(),
+        AnomalyDetector::new(AnomalyConfig::default()),
+        false,
+    ));
+    let registry = Arc::new(JobRegistry::new());
+    let backfill = Arc::new(BackfillService::new(
+        ingestion,
+        storage.clone(),
+        registry.clone(),
+    ));
+
+    let job = backfill.start(wallet).expect("backfill starts");
+    let interrupted = settled(&registry, job.id).await;
+    assert_eq!(interrupted.status, JobStatus::Failed);
+    assert_eq!(interrupted.completed_steps, vec!["fills"]);
+    let funding_checkpoint = interrupted
+        .checkpoints
+        .iter()
+        .find(|c| c.step == "funding")
+        .expect("funding checkpoint");
+    assert_eq!(
+        (funding_checkpoint.pages, funding_checkpoint.items),
+        (1, 500)
+    );
+    assert!(!funding_checkpoint.complete);
+    assert!(storage.load_history(wallet).await.expect("load").is_none());
+
+    backfill.resume(job.id).expect("backfill resumes");
+    let resumed = settled(&registry, job.id).await;
+    assert_eq!(resumed.status, JobStatus::Completed);
+
+    // Fills were not fetched again, and funding continued from its second page
+    assert_eq!(mock.requests("userFillsByTime").len(), 3);
+    let funding_pages = mock.requests("userFunding");
+    assert_eq!(funding_pages.len(), 2 + MAX_ATTEMPTS as usize);
+    assert_eq!(
+        funding_pages.last().expect("page")["startTime"],
+        start + 499 * 3_600_000
+    );
+
+    let stored = storage
+        .load_history(wallet)
+        .await
+        .expect("load")
+        .expect("history");
+    assert_eq!(
+        (
+            stored.fills.len(),
+            stored.funding.len(),
+            stored.ledger.len()
+        ),
+        (1_200, 600, 1)
+    );
+    assert!(storage
+        .load_backfill_cursor(wallet, "funding")
+        .await
+        .expect("load")
+        .is_none());
+}
+
+#[tokio::test]
+async fn backfill_keeps_other_venues_history() {
+    let primary = MockHyperliquid::start().await;
+    let venue = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000fe";
+    let start = 1_709_251_200_000;
+    primary.set_fills(
+        wallet,
+        vec![fill(1, start + 1_000, "BTC", "B", "60000.0", "0.01")],
+    );
+    let mut venue_fill = fill(2, start, "gmx:ETH", "B", "3000.0", "0.1");
+    venue_fill["venue"] = json!("gmx");
+    venue.set_fills(wallet, vec![venue_fill]);
+
+    let client = |mock: &MockHyperliquid| -> Arc<dyn DataSource> {
+        Arc::new(HyperliquidInfoClient::new(mock.url()).with_retry(MAX_ATTEMPTS, RETRY_BASE))
+    };
+    let composite = CompositeDataSource::new(client(&primary)).with_venue("gmx", client(&venue));
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let ingestion = Arc::new(IngestionService::new(
+        Arc::new(composite),
+        storage.clone(),
+        AnomalyDetector::new(AnomalyConfig::default()),
+        false,
+    ));
+    // A regular sync first, so the backfill replaces a history holding both venues
+    ingestion.sync_wallet(wallet).await.expect("sync");
+
+    let registry = Arc::new(JobRegistry::new());
+    let backfill = Arc::new(BackfillService::new(
+        ingestion,
+        storage.clone(),
+        registry.clone(),
+    ));
+    let job = backfill.start(wallet).expect("backfill starts");
+    let done = settled(&registry, job.id).await;
+    assert_eq!(done.status, JobStatus::Completed);
+    assert_eq!(done.total_steps, 6);
+    assert!(done.completed_steps.iter().any(|s| s == "gmx:fills"));
+
+    let stored = storage
+        .load_history(wallet)
+        .await
+        .expect("load")
+        .expect("history");
+    let active = corrections::active_history(stored);
+    let coins: Vec<&str> = active
+        .fills
+        .iter()
+        .filter_map(|f| f["coin"].as_str())
+        .collect();
+    assert_eq!(coins, vec!["gmx:ETH", "BTC"]);
+}
+
+#[tokio::test]
+async fn sync_survives_a_failing_venue() {
+    let primary = MockHyperliquid::start().await;
+    let venue = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000fd";
+    let start = 1_709_251_200_000;
+    primary.set_fills(
+        wallet,
+        vec![fill(1, start + 1_000, "BTC", "B", "60000.0", "0.01")],
+    );
+    let mut venue_fill = fill(2, start + 2_000, "gmx:ETH", "B", "3000.0", "0.1");
+    venue_fill["venue"] = json!("gmx");
+    venue.set_fills(wallet, vec![venue_fill]);
+
+    let client = |mock: &MockHyperliquid| -> Arc<dyn DataSource> {
+        Arc::new(HyperliquidInfoClient::new(mock.url()).with_retry(MAX_ATTEMPTS, RETRY_BASE))
+    };
+    let composite = CompositeDataSource::new(client(&primary)).with_venue("gmx", client(&venue));
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let ingestion = IngestionService::new(
+        Arc::new(composite),
+        storage.clone(),
+        AnomalyDetector::new(AnomalyConfig::default()),
+        false,
+    );
+    ingestion.sync_wallet(wallet).await.expect("first sync");
+
+    venue.fail_next("userFills", Fault::ServerError, MAX_ATTEMPTS as usize);
+    let synced = ingestion
+        .sync_wallet(wallet)
+        .await
+        .expect("sync without the venue");
+
+    // The venue's fill is neither lost nor reported as removed upstream
+    let coins: Vec<&str> = synced
+        .fills
+        .iter()
+        .filter_map(|f| f["coin"].as_str())
+        .collect();
+    assert_eq!(coins, vec!["BTC", "gmx:ETH"]);
+}
+
+#[tokio::test]
+async fn concurrent_syncs_of_a_wallet_fetch_once() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000fc";
+    let start = 1_709_251_200_000;
+    mock.set_funding(
+        wallet,
+        vec![funding(start, "BTC", "-1.5", "0.5", "0.00005")],
+    );
+
+    let client = HyperliquidInfoClient::new(mock.url()).with_retry(MAX_ATTEMPTS, RETRY_BASE);
+    let ingestion = IngestionService::new(
+        Arc::new(client),
+        Arc::new(MemoryStorage::new()),
+        AnomalyDetector::new(AnomalyConfig::default()),
+        false,
+    );
+
+    let (first, second) =
+        tokio::join!(ingestion.sync_wallet(wallet), ingestion.sync_wallet(wallet));
+    assert_eq!(first.expect("first sync").funding.len(), 1);
+    assert_eq!(second.expect("second sync").funding.len(), 1);
+    // The second caller waited and took the first sync's result
+    assert_eq!(mock.requests("userFunding").len(), 1);
+}
+
+#[tokio::test]
+async fn timeline_diff_returns_late_events_ordered_before_served_ones() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000c1";
+    let start = 1_709_251_200_000;
+    let mut fills = vec![fill(999, start + 1_000, "ETH", "B", "3000.0", "0.1")];
+    mock.set_fills(wallet, fills.clone());
+    let app = serve_app(&mock).await;
+    let request = |params: &str| {
+        let url = format!("{}/timeline/diff?wallet={}{}", app, wallet, params);
+        async move { reqwest::get(url).await.expect("request app") }
+    };
+    let diff = |params: &'static str| {
+        let response = request(params);
+        async move {
+            let response = response.await;
+            assert_eq!(response.status(), 200);
+            response.json::<Value>().await.expect("diff body")
+        }
+    };
+
+    let first = diff("").await;
+    assert_eq!(first["events"].as_array().expect("events").len(), 1);
+    assert_eq!(first["next_cursor"], 1);
+
+    // Same millisecond with a smaller ID string, and a late fill with an earlier timestamp
+    fills.push(fill(1000, start + 1_000, "ETH", "B", "3000.0", "0.1"));
+    fills.push(fill(5, start, "ETH", "B", "3000.0", "0.1"));
+    mock.set_fills(wallet, fills);
+
+    let second = diff("&since_event=hyperliquid:fill:999").await;
+    let ids: Vec<&str> = second["events"]
+        .as_array()
+        .expect("events")
+        .iter()
+        .filter_map(|event| event["id"].as_str())
+        .collect();
+    assert_eq!(ids, vec!["hyperliquid:fill:5", "hyperliquid:fill:1000"]);
+    assert_eq!(second["next_cursor"], 3);
+    assert_eq!(diff("&cursor=3").await["events"], json!([]));
+    assert_eq!(
+        request("&since_event=hyperliquid:fill:42").await.status(),
+        404
+    );
+}
+
+#[tokio::test]
+async fn nets_transfers_between_portfolio_wallets() {
+    let mock = MockHyperliquid::start().await;
+    let (first, second) = (
+        "0x0000000000000000000000000000000000000a01",
+        "0x0000000000000000000000000000000000000a02",
+    );
+    let start = 1_709_251_200_000;
+    let outsider = "0x0000000000000000000000000000000000000a03";
+    let to_second = internal_transfer(start + 120_000, first, second, "500.0");
+    mock.set_ledger(
+        first,
+        vec![
+            deposit(start, "5000.0"),
+            withdrawal(start + 60_000, "1000.0"),
+            to_second.clone(),
+            internal_transfer(start + 180_000, first, outsider, "200.0"),
+        ],
+    );
+    // The bridge deposit of 200 resembles the transfer out of the portfolio but is unrelated
+    mock.set_ledger(
+        second,
+        vec![
+            to_second,
+            deposit(start + 240_000, "200.0"),
+            deposit(start + 600_000, "999.0"),
+        ],
+    );
+    mock.set_fills(
+        second,
+        vec![fill(1, start + 700_000, "ETH", "B", "3000.0", "0.1")],
+    );
+    let app = serve_app(&mock).await;
+
+    let response = reqwest::get(format!("{}/portfolio?wallets={},{}", app, first, second))
+        .await
+        .expect("request app");
+    assert_eq!(response.status(), 200);
+    let report: Value = response.json().await.expect("portfolio body");
+
+    let transfers = report["internal_transfers"].as_array().expect("transfers");
+    assert_eq!(transfers.len(), 2);
+    assert_eq!(transfers[0]["from_wallet"], first);
+    assert_eq!(transfers[0]["to_wallet"], second);
+    assert_eq!(transfers[0]["received"], "999.0");
+    assert_eq!(transfers[0]["match_kind"], "inferred");
+    assert_eq!(transfers[1]["received"], "500.0");
+    assert_eq!(transfers[1]["match_kind"], "exact");
+    assert_eq!(report["external_deposits"], "5200.0");
+    assert_eq!(report["external_withdrawals"], "200.0");
+    assert_eq!(report["internal_transfer_fees"], "1.0");
+    // The fill's fee plus the bridge fee
+    assert_eq!(report["net_pnl"], "-1.1");
+}
+
+#[tokio::test]
+async fn reports_how_pnl_was_calculated() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000b1";
+    let start = 1_709_251_200_000;
+    let mut unparseable = fill(3, start + 2_000, "ETH", "A", "3100.0", "0.1");
+    unparseable["px"] = json!(null);
+    mock.set_fills(
+        wallet,
+        vec![
+            fill(1, start, "ETH", "B", "3000.0", "0.1"),
+            fill(2, start + 1_000, "ETH", "A", "3100.0", "0.1"),
+            unparseable,
+        ],
+    );
+    let app = serve_app(&mock).await;
+
+    let response = reqwest::get(format!("{}/pnl?wallet={}", app, wallet))
+        .await
+        .expect("request app");
+    assert_eq!(response.status(), 200);
+    let summary: Value = response.json().await.expect("pnl body");
+
+    let calculation = &summary["calculation"];
+    assert_eq!(calculation["method"], "exchange_reported");
+    assert_eq!(calculation["price_source"], "mark_price");
+    assert_eq!(calculation["requested_from"], Value::Null);
+    assert_eq!(calculation["covered_from"], "2024-03-01T00:00:00Z");
+    assert_eq!(calculation["covered_to"], "2024-03-01T00:00:01Z");
+    assert_eq!(calculation["events_considered"], 2);
+    assert_eq!(calculation["events_skipped"], 1);
+    assert_eq!(calculation["normalization_version"], NORMALIZATION_VERSION);
+}
+
+#[tokio::test]
+async fn captures_require_admin_credentials() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000b2";
+    let app = serve_app(&mock).await;
+
+    let response = reqwest::get(format!("{}/fills?wallet={}&debug=capture", app, wallet))
+        .await
+        .expect("request app");
+
+    assert_eq!(response.status(), 401);
+    assert!(response.headers().get("x-capture-id").is_none());
+    assert!(mock.requests("userFills").is_empty());
+}
+
+#[tokio::test]
+async fn replayed_dead_letters_show_as_delivered_in_history() {
+    let mock = MockHyperliquid::start().await;
+    let client =
+        Arc::new(HyperliquidInfoClient::new(mock.url()).with_retry(MAX_ATTEMPTS, RETRY_BASE));
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let ingestion = Arc::new(IngestionService::new(
+        client.clone(),
+        storage.clone(),
+        AnomalyDetector::new(AnomalyConfig::default()),
+        false,
+    ));
+    let timeline = Arc::new(TimelineService::new(Arc::new(AssetRegistry::new(
+        client,
+        storage.clone(),
+    ))));
+    let alerts = AlertService::new(
+        ingestion,
+        timeline,
+        storage.clone(),
+        WebhookConfig {
+            allow_private_hosts: true,
+            ..WebhookConfig::default()
+        },
+        Arc::new(ReportRenderer::new(None).expect("renderer")),
+    );
+
+    let receiver = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind webhook receiver");
+    let url = format!(
+        "http://{}/hook",
+        receiver.local_addr().expect("receiver address")
+    );
+    tokio::spawn(async move {
+        let app = Router::new().route("/hook", post(|| async { StatusCode::OK }));
+        axum::serve(receiver, app).await.expect("webhook receiver");
+    });
+
+    let alert = FiredAlert {
+        id: uuid::Uuid::new_v4(),
+        rule_id: uuid::Uuid::new_v4(),
+        wallet: "0x00000000000000000000000000000000000000c1".to_string(),
+        metric: AlertMetric::NetPnl,
+        comparator: Comparator::Lt,
+        threshold: 0.into(),
+        value: (-5).into(),
+        fired_at: chrono::Utc::now(),
+        delivered: false,
+        error: Some("Webhook returned 503 Service Unavailable".to_string()),
+        message: None,
+    };
+    storage
+        .append_fired_alert(alert.clone())
+        .await
+        .expect("store alert");
+    let letter = DeadLetter {
+        id: uuid::Uuid::new_v4(),
+        url,
+        alert: alert.clone(),
+        attempts: 5,
+        last_error: "Webhook returned 503 Service Unavailable".to_string(),
+        failed_at: chrono::Utc::now(),
+    };
+    storage
+        .save_dead_letter(letter.clone())
+        .await
+        .expect("store dead letter");
+
+    alerts
+        .replay_dead_letter(letter.id)
+        .await
+        .expect("replay succeeds");
+
+    let history = alerts.history(None, None).await.expect("history");
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].id, alert.id);
+    assert!(history[0].delivered);
+    assert_eq!(history[0].error, None);
+    assert!(alerts
+        .dead_letters()
+        .await
+        .expect("dead letters")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn reports_how_daily_pnl_was_calculated() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000b3";
+    let start = 1_709_251_200_000;
+    let mut unparseable = fill(3, start + 2_000, "ETH", "A", "3100.0", "0.1");
+    unparseable["px"] = json!(null);
+    mock.set_fills(
+        wallet,
+        vec![
+            fill(1, start, "ETH", "B", "3000.0", "0.1"),
+            fill(2, start + 1_000, "ETH", "A", "3100.0", "0.1"),
+            unparseable,
+        ],
+    );
+    let app = serve_app(&mock).await;
+    let header = |response: &reqwest::Response, name: &str| {
+        response.headers()[name]
+            .to_str()
+            .expect("header value")
+            .to_string()
+    };
+
+    let aggregated = reqwest::get(format!("{}/pnl/daily?wallet={}", app, wallet))
+        .await
+        .expect("request app");
+    assert_eq!(aggregated.status(), 200);
+    assert_eq!(header(&aggregated, "x-from-aggregates"), "true");
+    assert_eq!(header(&aggregated, "x-events-considered"), "2");
+    assert_eq!(header(&aggregated, "x-events-skipped"), "1");
+    assert_eq!(
+        header(&aggregated, "x-covered-from"),
+        "2024-03-01T00:00:00+00:00"
+    );
+
+    let computed = reqwest::get(format!(
+        "{}/pnl/daily?wallet={}&since={}",
+        app, wallet, start
+    ))
+    .await
+    .expect("request app");
+    assert_eq!(computed.status(), 200);
+    assert_eq!(header(&computed, "x-from-aggregates"), "false");
+    assert_eq!(header(&computed, "x-events-considered"), "2");
+    assert_eq!(header(&computed, "x-events-skipped"), "1");
+}
+
+#[tokio::test]
+async fn trade_stats_serve_stored_history_when_stale_is_ok() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000b4";
+    let start = 1_709_251_200_000;
+    mock.set_fills(
+        wallet,
+        vec![
+            fill(1, start, "ETH", "B", "3000.0", "0.1"),
+            fill(2, start + 1_000, "ETH", "A", "3100.0", "0.1"),
+        ],
+    );
+    let app = serve_app(&mock).await;
+
+    let synced = reqwest::get(format!("{}/stats/execution?wallet={}", app, wallet))
+        .await
+        .expect("request app");
+    assert_eq!(synced.status(), 200);
+    assert_eq!(synced.headers()["x-data-stale"], "false");
+
+    let stale = reqwest::get(format!(
+        "{}/stats/execution?wallet={}&freshness=stale_ok",
+        app, wallet
+    ))
+    .await
+    .expect("request app");
+    assert_eq!(stale.status(), 200);
+    assert_eq!(stale.headers()["x-data-stale"], "true");
+    assert_eq!(
+        stale.headers()["x-data-synced-at"],
+        synced.headers()["x-data-synced-at"]
+    );
+    let stats: Value = stale.json().await.expect("execution body");
+    assert_eq!(stats["order_count"], 2);
+}
+
+#[tokio::test]
+async fn deleted_wallets_are_not_warmed_until_requested_again() {
+    let mock = MockHyperliquid::start().await;
+    let wallet = "0x00000000000000000000000000000000000000d1";
+    mock.set_fills(
+        wallet,
+        vec![fill(1, 1_709_251_200_000, "ETH", "B", "3000.0", "0.1")],
+    );
+    let client = HyperliquidInfoClient::new(mock.url()).with_retry(MAX_ATTEMPTS, RETRY_BASE);
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let ingestion = IngestionService::new(
+        Arc::new(client),
+        storage.clone(),
+        AnomalyDetector::new(AnomalyConfig::default()),
+        false,
+    );
+
+    ingestion.sync_wallet(wallet).await.expect("sync");
+    ingestion.forget_wallet(wallet).await.expect("forget");
+    let fill_requests = mock.requests("userFills").len();
+
+    assert!(ingestion.warm_wallet(wallet).await.expect("warm").is_none());
+    assert_eq!(mock.requests("userFills").len(), fill_requests);
+    assert!(storage.load_history(wallet).await.expect("load").is_none());
+
+    // A request for the wallet tracks it again
+    ingestion.sync_wallet(wallet).await.expect("sync");
+    assert!(ingestion.warm_wallet(wallet).await.expect("warm").is_some());
+}
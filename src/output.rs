@@ -0,0 +1,474 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{rejection::QueryRejection, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use bigdecimal::BigDecimal;
+use chrono::DateTime;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Number, Value};
+use std::sync::Arc;
+
+use crate::datasource::cex::hmac_sha256;
+use crate::error::AppError;
+
+/// How decimal amounts are written in JSON responses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberFormat {
+    /// Exact decimal strings, e.g. `"0.1"`
+    #[default]
+    String,
+    /// Plain JSON numbers; may lose precision
+    Float,
+}
+
+/// How timestamps are written in JSON responses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    /// RFC 3339 strings, e.g. `"2024-03-01T00:00:00Z"`
+    #[default]
+    Iso,
+    /// Epoch milliseconds in place of each RFC 3339 string
+    Epoch,
+    /// RFC 3339 strings, plus an epoch-millisecond `<field>_ms` sibling for each object field
+    Both,
+}
+
+/// How wallet addresses are written in responses and exports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressFormat {
+    /// Addresses as stored
+    #[default]
+    Full,
+    /// First and last four hex digits, e.g. `0x1234...abcd`
+    Masked,
+    /// A stable pseudonymous ID per address, e.g. `wallet-3f9a0c51d2e7`
+    Pseudonym,
+}
+
+/// Encoding of structured response bodies, negotiated from the `Accept` header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyEncoding {
+    #[default]
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+impl BodyEncoding {
+    /// The first encoding the client accepts, in the order it listed them
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter(|range| !range.split(';').skip(1).any(|p| p.trim() == "q=0"))
+            .find_map(|range| match range.split(';').next().unwrap_or_default().trim() {
+                "application/json" => Some(BodyEncoding::Json),
+                "application/msgpack" | "application/x-msgpack" => {
+                    Some(BodyEncoding::MessagePack)
+                }
+                "application/cbor" => Some(BodyEncoding::Cbor),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            BodyEncoding::Json => "application/json",
+            BodyEncoding::MessagePack => "application/msgpack",
+            BodyEncoding::Cbor => "application/cbor",
+        }
+    }
+
+    pub fn encode(&self, value: &Value) -> Result<Vec<u8>, AppError> {
+        match self {
+            BodyEncoding::Json => Ok(serde_json::to_vec(value)?),
+            BodyEncoding::MessagePack => rmp_serde::to_vec_named(value)
+                .map_err(|e| AppError::InternalError(format!("MessagePack encoding: {}", e))),
+            BodyEncoding::Cbor => {
+                let mut body = Vec::new();
+                ciborium::into_writer(value, &mut body)
+                    .map_err(|e| AppError::InternalError(format!("CBOR encoding: {}", e)))?;
+                Ok(body)
+            }
+        }
+    }
+}
+
+/// Signs of money amounts in responses and exports.
+///
+/// Amounts are kept internally with costs as positive magnitudes: fees (negative for maker
+/// rebates) and liquidation losses. Funding is normalized by each data source so that
+/// payments received are positive, and is the same under both conventions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignConvention {
+    /// Income positive, costs negative: fees and liquidation losses are negative, rebates
+    /// positive
+    #[default]
+    Signed,
+    /// Fees and liquidation losses as positive costs, as served before sign normalization
+    Legacy,
+}
+
+impl SignConvention {
+    /// The convention in force for the current request, or the default outside one
+    pub fn current() -> Self {
+        SIGN_CONVENTION.try_with(|c| *c).unwrap_or_default()
+    }
+
+    /// Writes a cost kept as a positive magnitude under this convention
+    pub fn cost(&self, amount: &BigDecimal) -> BigDecimal {
+        match self {
+            SignConvention::Signed => -amount,
+            SignConvention::Legacy => amount.clone(),
+        }
+    }
+}
+
+impl std::str::FromStr for SignConvention {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "signed" => Ok(SignConvention::Signed),
+            "legacy" => Ok(SignConvention::Legacy),
+            other => Err(format!("Unknown sign convention: {}", other)),
+        }
+    }
+}
+
+tokio::task_local! {
+    /// Sign convention selected for the current request
+    static SIGN_CONVENTION: SignConvention;
+}
+
+/// Serde adapter for costs kept as positive magnitudes, written under the current
+/// `SignConvention`
+pub mod cost {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(amount: &BigDecimal, serializer: S) -> Result<S::Ok, S::Error> {
+        SignConvention::current().cost(amount).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigDecimal, D::Error> {
+        // Negation is its own inverse, so reading back applies the same conversion
+        Ok(SignConvention::current().cost(&BigDecimal::deserialize(deserializer)?))
+    }
+}
+
+/// Serde adapter like `cost` for a series of costs
+pub mod costs {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        amounts: &[BigDecimal],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let convention = SignConvention::current();
+        serializer.collect_seq(amounts.iter().map(|amount| convention.cost(amount)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<BigDecimal>, D::Error> {
+        let convention = SignConvention::current();
+        Ok(Vec::<BigDecimal>::deserialize(deserializer)?
+            .iter()
+            .map(|amount| convention.cost(amount))
+            .collect())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SignOptions {
+    pub signs: Option<SignConvention>,
+}
+
+/// Middleware selecting the sign convention for a request from `?signs=`, falling back to
+/// the deployment default
+pub async fn apply_sign_convention(
+    State(default): State<SignConvention>,
+    options: Result<Query<SignOptions>, QueryRejection>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let convention = match options {
+        Ok(Query(options)) => options.signs.unwrap_or(default),
+        Err(e) => return AppError::ValidationError(e.body_text()).into_response(),
+    };
+
+    SIGN_CONVENTION.scope(convention, next.run(request)).await
+}
+
+/// Keyed pseudonyms for wallet addresses.
+///
+/// IDs are an HMAC of the lowercased address, so they stay stable across requests and
+/// restarts while the key is kept, and cannot be reversed by hashing known addresses.
+pub struct Pseudonymizer {
+    key: Vec<u8>,
+}
+
+impl Pseudonymizer {
+    pub fn new(key: &str) -> Self {
+        Self {
+            key: key.as_bytes().to_vec(),
+        }
+    }
+
+    /// Pseudonymizer with a random key, so IDs only hold until restart
+    pub fn ephemeral() -> Self {
+        Self::new(&uuid::Uuid::new_v4().to_string())
+    }
+
+    fn pseudonym(&self, address: &str) -> String {
+        let digest = hmac_sha256(&self.key, address.to_lowercase().as_bytes());
+        format!("wallet-{}", &hex::encode(digest)[..12])
+    }
+}
+
+/// Per-request output options, read from the query string of any endpoint
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OutputOptions {
+    #[serde(default)]
+    pub numbers: NumberFormat,
+    #[serde(default)]
+    pub timestamps: TimestampFormat,
+    #[serde(default)]
+    pub addresses: AddressFormat,
+}
+
+impl OutputOptions {
+    fn is_default(&self) -> bool {
+        self.numbers == NumberFormat::String
+            && self.timestamps == TimestampFormat::Iso
+            && self.addresses == AddressFormat::Full
+    }
+
+    /// Replaces every wallet address in `text` according to `addresses`
+    fn hide_addresses(&self, text: &str, pseudonyms: &Pseudonymizer) -> String {
+        match self.addresses {
+            AddressFormat::Full => text.to_string(),
+            AddressFormat::Masked => replace_addresses(text, |address| {
+                format!("{}...{}", &address[..6], &address[address.len() - 4..])
+            }),
+            AddressFormat::Pseudonym => {
+                replace_addresses(text, |address| pseudonyms.pseudonym(address))
+            }
+        }
+    }
+
+    /// Rewrites a JSON document according to the options.
+    ///
+    /// `key` is the object field holding `value`, if any; identifier fields are never
+    /// converted to numbers.
+    fn apply(&self, value: &mut Value, key: Option<&str>, pseudonyms: &Pseudonymizer) {
+        match value {
+            Value::String(s) => {
+                if self.addresses != AddressFormat::Full {
+                    *s = self.hide_addresses(s, pseudonyms);
+                }
+                if self.numbers == NumberFormat::Float
+                    && !key.is_some_and(is_identifier_key)
+                    && let Some(number) = decimal_to_number(s)
+                {
+                    *value = Value::Number(number);
+                } else if self.timestamps == TimestampFormat::Epoch
+                    && let Some(millis) = timestamp_millis(s)
+                {
+                    *value = Value::from(millis);
+                }
+            }
+            Value::Array(items) => items
+                .iter_mut()
+                .for_each(|item| self.apply(item, key, pseudonyms)),
+            Value::Object(map) => {
+                if self.addresses != AddressFormat::Full {
+                    *map = std::mem::take(map)
+                        .into_iter()
+                        .map(|(key, field)| (self.hide_addresses(&key, pseudonyms), field))
+                        .collect::<Map<String, Value>>();
+                }
+                if self.timestamps == TimestampFormat::Both {
+                    let epochs: Vec<(String, Value)> = map
+                        .iter()
+                        .filter_map(|(key, field)| {
+                            let millis = field.as_str().and_then(timestamp_millis)?;
+                            Some((format!("{}_ms", key), Value::from(millis)))
+                        })
+                        .filter(|(key, _)| !map.contains_key(key))
+                        .collect();
+                    map.extend(epochs);
+                }
+                map.iter_mut()
+                    .for_each(|(key, item)| self.apply(item, Some(key), pseudonyms));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Middleware applying `OutputOptions` to every JSON response, and re-encoding it as
+/// MessagePack or CBOR when the client asks for one in `Accept`.
+///
+/// Address hiding also applies to text responses such as CSV exports and rendered reports,
+/// and to their download file names.
+pub async fn format_response(
+    State(pseudonyms): State<Arc<Pseudonymizer>>,
+    options: Result<Query<OutputOptions>, QueryRejection>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let options = match options {
+        Ok(Query(options)) => options,
+        Err(e) => return AppError::ValidationError(e.body_text()).into_response(),
+    };
+    let encoding = BodyEncoding::negotiate(request.headers());
+
+    let mut response = next.run(request).await;
+
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let is_json = content_type.starts_with("application/json");
+    let is_text = content_type.starts_with("text/") && options.addresses != AddressFormat::Full;
+
+    if is_json {
+        response
+            .headers_mut()
+            .insert(header::VARY, HeaderValue::from_static("accept"));
+    }
+    let reencode = is_json && encoding != BodyEncoding::Json;
+    if (options.is_default() && !reencode) || !(is_json || is_text) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return AppError::InternalError(e.to_string()).into_response(),
+    };
+
+    if let Some(disposition) = parts
+        .headers
+        .get(header::CONTENT_DISPOSITION)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| options.hide_addresses(v, &pseudonyms))
+        && let Ok(disposition) = HeaderValue::from_str(&disposition)
+    {
+        parts
+            .headers
+            .insert(header::CONTENT_DISPOSITION, disposition);
+    }
+
+    let body = if is_json {
+        let mut value: Value = match serde_json::from_slice(&bytes) {
+            Ok(value) => value,
+            Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+        };
+        if !options.is_default() {
+            options.apply(&mut value, None, &pseudonyms);
+        }
+        parts.headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(encoding.content_type()),
+        );
+        encoding.encode(&value)
+    } else {
+        match std::str::from_utf8(&bytes) {
+            Ok(text) => Ok(options.hide_addresses(text, &pseudonyms).into_bytes()),
+            Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+        }
+    };
+
+    match body {
+        Ok(body) => {
+            parts
+                .headers
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+            Response::from_parts(parts, Body::from(body))
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Replaces each standalone 20-byte hex address (`0x` plus 40 hex digits) in `text`.
+///
+/// Longer hex strings such as transaction hashes are left alone.
+fn replace_addresses(text: &str, replace: impl Fn(&str) -> String) -> String {
+    const ADDRESS_LEN: usize = 42;
+    let bytes = text.as_bytes();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i + ADDRESS_LEN <= bytes.len() {
+        let is_address = bytes[i] == b'0'
+            && matches!(bytes[i + 1], b'x' | b'X')
+            && bytes[i + 2..i + ADDRESS_LEN]
+                .iter()
+                .all(u8::is_ascii_hexdigit)
+            && (i == 0 || !is_word(bytes[i - 1]))
+            && bytes.get(i + ADDRESS_LEN).is_none_or(|&b| !is_word(b));
+
+        if is_address {
+            out.push_str(&text[copied..i]);
+            out.push_str(&replace(&text[i..i + ADDRESS_LEN]));
+            i += ADDRESS_LEN;
+            copied = i;
+        } else {
+            i += 1;
+        }
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Epoch milliseconds of a string holding an RFC 3339 date and time, as `DateTime` serializes
+fn timestamp_millis(s: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|timestamp| timestamp.timestamp_millis())
+}
+
+/// Whether a field holds an identifier (trade, order or transaction IDs, nonces) whose digits
+/// must survive exactly, even when they look like a decimal
+fn is_identifier_key(key: &str) -> bool {
+    matches!(
+        key,
+        "id" | "tid" | "oid" | "cloid" | "hash" | "nonce" | "tids" | "oids" | "ids"
+    ) || key.ends_with("_id")
+        || key.ends_with("_ids")
+        || key.ends_with("Id")
+        || key.ends_with("Ids")
+}
+
+/// Converts strings that hold a plain decimal (as `BigDecimal` serializes) to JSON numbers
+fn decimal_to_number(s: &str) -> Option<Number> {
+    let mantissa = s.split(['e', 'E']).next()?;
+    let digits = mantissa.strip_prefix('-').unwrap_or(mantissa);
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, "0"));
+
+    let is_decimal = !integer.is_empty()
+        && integer.bytes().all(|b| b.is_ascii_digit())
+        && !fraction.is_empty()
+        && fraction.bytes().all(|b| b.is_ascii_digit());
+
+    if !is_decimal {
+        return None;
+    }
+
+    s.parse::<f64>().ok().and_then(Number::from_f64)
+}
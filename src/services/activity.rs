@@ -0,0 +1,151 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::error::AppResult;
+use crate::services::assets::AssetRegistry;
+use crate::services::ingestion::IngestionService;
+
+/// Cheap profile of a wallet built from at most two fill pages and its current state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivitySummary {
+    pub wallet: String,
+    pub first_trade_at: Option<DateTime<Utc>>,
+    pub last_trade_at: Option<DateTime<Utc>>,
+    /// Exact when the earliest and most recent fill pages overlap, extrapolated otherwise
+    pub trade_count: u64,
+    pub trade_count_exact: bool,
+    /// Coins traded since `active_since` or held now
+    pub active_coins: Vec<String>,
+    /// Time of the oldest fill in the most recent page
+    pub active_since: Option<DateTime<Utc>>,
+    pub open_positions: Vec<String>,
+    pub account_value: BigDecimal,
+    /// Whether the account holds positions or collateral
+    pub is_open: bool,
+}
+
+/// Profiles wallets for screening without syncing their full history
+pub struct ActivityService {
+    ingestion_service: Arc<IngestionService>,
+    asset_registry: Arc<AssetRegistry>,
+}
+
+impl ActivityService {
+    pub fn new(
+        ingestion_service: Arc<IngestionService>,
+        asset_registry: Arc<AssetRegistry>,
+    ) -> Self {
+        Self {
+            ingestion_service,
+            asset_registry,
+        }
+    }
+
+    pub async fn summarize(&self, wallet: &str) -> AppResult<ActivitySummary> {
+        let (latest, user_state) = tokio::try_join!(
+            self.ingestion_service.fetch_fill_page(wallet, None),
+            self.ingestion_service.fetch_user_state(wallet),
+        )?;
+        let earliest = if latest.is_empty() {
+            Vec::new()
+        } else {
+            self.ingestion_service
+                .fetch_fill_page(wallet, Some(0))
+                .await?
+        };
+
+        let (trade_count, trade_count_exact) = estimate_trade_count(&earliest, &latest);
+        let active_since = latest.iter().filter_map(fill_time).min();
+
+        let open_positions: Vec<String> = user_state
+            .get("assetPositions")
+            .and_then(|positions| positions.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.get("position"))
+            .filter(|position| {
+                position
+                    .get("szi")
+                    .and_then(|szi| szi.as_str())
+                    .and_then(|szi| BigDecimal::from_str(szi).ok())
+                    .is_some_and(|szi| !szi.is_zero())
+            })
+            .filter_map(|position| position.get("coin").and_then(|c| c.as_str()))
+            .map(str::to_string)
+            .collect();
+
+        let account_value = user_state
+            .get("marginSummary")
+            .and_then(|summary| summary.get("accountValue"))
+            .and_then(|value| value.as_str())
+            .and_then(|s| BigDecimal::from_str(s).ok())
+            .unwrap_or_default();
+
+        let active_coins: BTreeSet<String> = latest
+            .iter()
+            .filter_map(|fill| {
+                let coin = fill.get("coin").and_then(|c| c.as_str())?;
+                Some(self.asset_registry.normalize_coin(coin, fill_time(fill)?))
+            })
+            .chain(open_positions.iter().cloned())
+            .collect();
+
+        Ok(ActivitySummary {
+            wallet: wallet.to_string(),
+            first_trade_at: earliest.iter().filter_map(fill_time).min(),
+            last_trade_at: latest.iter().filter_map(fill_time).max(),
+            trade_count,
+            trade_count_exact,
+            active_coins: active_coins.into_iter().collect(),
+            active_since,
+            is_open: !open_positions.is_empty() || account_value > BigDecimal::zero(),
+            open_positions,
+            account_value,
+        })
+    }
+}
+
+/// Counts fills across both pages, extrapolating the gap between them from their fill rate
+fn estimate_trade_count(earliest: &[Value], latest: &[Value]) -> (u64, bool) {
+    let span = |fills: &[Value]| {
+        let times: Vec<DateTime<Utc>> = fills.iter().filter_map(fill_time).collect();
+        Some((*times.iter().min()?, *times.iter().max()?))
+    };
+    let (Some((first, earliest_end)), Some((latest_start, last))) = (span(earliest), span(latest))
+    else {
+        return (latest.len() as u64, true);
+    };
+
+    if earliest_end >= latest_start {
+        let ids: HashSet<String> = earliest.iter().chain(latest).map(fill_id).collect();
+        return (ids.len() as u64, true);
+    }
+
+    let sampled = (earliest.len() + latest.len()) as u64;
+    let sampled_ms = (earliest_end - first + (last - latest_start)).num_milliseconds();
+    if sampled_ms <= 0 {
+        return (sampled, false);
+    }
+
+    let total_ms = (last - first).num_milliseconds();
+    let extrapolated = (sampled as f64 * total_ms as f64 / sampled_ms as f64) as u64;
+    (extrapolated.max(sampled), false)
+}
+
+fn fill_time(fill: &Value) -> Option<DateTime<Utc>> {
+    fill.get("time")
+        .and_then(|t| t.as_i64())
+        .and_then(DateTime::from_timestamp_millis)
+}
+
+/// Trade ID, or the whole fill when upstream omits one
+fn fill_id(fill: &Value) -> String {
+    fill.get("tid")
+        .map(|tid| tid.to_string())
+        .unwrap_or_else(|| fill.to_string())
+}
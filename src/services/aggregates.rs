@@ -0,0 +1,253 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use crate::error::AppResult;
+use crate::services::ingestion::{IngestionService, WalletHistory};
+use crate::services::pnl_calculator::{
+    self, CalculationMetadata, DailyPnl, PnlCalculator, PnlMethod, PriceSource,
+};
+use crate::services::timeline::{TimelineEvent, TimelineService};
+use crate::storage::{Storage, StoredAggregates};
+
+/// One UTC date of a wallet's materialized PnL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DailyBucket {
+    date: NaiveDate,
+    /// Fills and funding payments that occurred on the date
+    events: usize,
+    /// Those of `events` that could not be parsed
+    #[serde(default)]
+    skipped: usize,
+    /// PnL attributed to the date, which may include funding settled just after midnight
+    pnl: Option<BigDecimal>,
+}
+
+/// Maintains per-wallet daily PnL in storage, so `/pnl/daily` costs O(days) once synced.
+///
+/// After each sync only the dates whose event count changed, or that hold an event restated
+/// since the last update, are re-aggregated, together with the preceding date because
+/// funding at the day boundary may be attributed to it. Aggregates follow the configured
+/// funding attribution; other attributions are computed from the timeline.
+pub struct DailyAggregator {
+    ingestion: Arc<IngestionService>,
+    storage: Arc<dyn Storage>,
+    timeline_service: Arc<TimelineService>,
+    pnl_calculator: Arc<PnlCalculator>,
+}
+
+impl DailyAggregator {
+    pub fn new(
+        ingestion: Arc<IngestionService>,
+        storage: Arc<dyn Storage>,
+        timeline_service: Arc<TimelineService>,
+        pnl_calculator: Arc<PnlCalculator>,
+    ) -> Self {
+        Self {
+            ingestion,
+            storage,
+            timeline_service,
+            pnl_calculator,
+        }
+    }
+
+    /// Daily PnL for a wallet's full synced history, updating the aggregates if it changed,
+    /// with what the aggregates were calculated from
+    pub async fn daily_pnl(
+        &self,
+        wallet: &str,
+        history: &WalletHistory,
+    ) -> AppResult<(Vec<DailyPnl>, CalculationMetadata)> {
+        let key = wallet.to_lowercase();
+        let funding_attribution = self.pnl_calculator.funding_attribution();
+
+        let stored = self
+            .storage
+            .load_aggregates(&key)
+            .await?
+            .filter(|stored| stored.funding_attribution == funding_attribution);
+        let previous = match &stored {
+            Some(stored) => Some((stored.synced_at, buckets(&stored.days)?)),
+            None => None,
+        };
+
+        if let Some((synced_at, buckets)) = &previous
+            && *synced_at == history.synced_at
+        {
+            return Ok((daily_from_buckets(buckets), calculation(history, buckets)));
+        }
+
+        let counts = event_counts(history);
+        let dirty: BTreeSet<NaiveDate> = match &previous {
+            None => counts.keys().copied().collect(),
+            Some((synced_at, buckets)) => {
+                let mut dirty: BTreeSet<NaiveDate> = buckets
+                    .keys()
+                    .chain(counts.keys())
+                    .filter(|date| {
+                        let stored = buckets.get(date).map_or(0, |bucket| bucket.events);
+                        stored != counts.get(date).copied().unwrap_or(0)
+                    })
+                    .copied()
+                    .collect();
+                dirty.extend(
+                    self.ingestion
+                        .restatements(wallet)
+                        .await?
+                        .into_iter()
+                        .filter(|restatement| restatement.detected_at > *synced_at)
+                        .filter_map(|restatement| restatement.event_time)
+                        .map(|time| time.date_naive()),
+                );
+                dirty
+            }
+        };
+        tracing::debug!("Re-aggregating {} days for wallet {}", dirty.len(), wallet);
+
+        // Funding settled on a dirty date may be attributed to the day before
+        let affected: BTreeSet<NaiveDate> = dirty
+            .iter()
+            .flat_map(|date| [date.pred_opt(), Some(*date)])
+            .flatten()
+            .collect();
+        let mut pnl: BTreeMap<NaiveDate, BigDecimal> = BTreeMap::new();
+        let mut skipped: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        for (date, event) in self.events_near(history, &affected) {
+            let Some(event) = event else {
+                if affected.contains(&date) {
+                    *skipped.entry(date).or_default() += 1;
+                }
+                continue;
+            };
+            for (date, amount) in self.pnl_calculator.daily_parts(&event, funding_attribution) {
+                if affected.contains(&date) {
+                    let entry = pnl.entry(date).or_default();
+                    *entry = &*entry + amount;
+                }
+            }
+        }
+
+        let mut buckets = previous.map(|(_, buckets)| buckets).unwrap_or_default();
+        buckets.retain(|date, _| !affected.contains(date));
+        for date in affected {
+            let events = counts.get(&date).copied().unwrap_or(0);
+            let skipped = skipped.get(&date).copied().unwrap_or(0);
+            let pnl = pnl.remove(&date);
+            if events > 0 || pnl.is_some() {
+                buckets.insert(
+                    date,
+                    DailyBucket {
+                        date,
+                        events,
+                        skipped,
+                        pnl,
+                    },
+                );
+            }
+        }
+
+        let aggregates = StoredAggregates {
+            funding_attribution,
+            synced_at: history.synced_at,
+            days: buckets
+                .values()
+                .map(serde_json::to_value)
+                .collect::<Result<_, _>>()?,
+        };
+        self.storage.save_aggregates(&key, aggregates).await?;
+
+        Ok((daily_from_buckets(&buckets), calculation(history, &buckets)))
+    }
+
+    /// Parses the fills and funding payments on or the day after any of `dates`, by the date
+    /// they occurred on; items that cannot be parsed yield None
+    fn events_near<'a>(
+        &'a self,
+        history: &'a WalletHistory,
+        dates: &'a BTreeSet<NaiveDate>,
+    ) -> impl Iterator<Item = (NaiveDate, Option<TimelineEvent>)> + 'a {
+        let near = move |raw: &Value| {
+            event_date(raw).filter(|date| {
+                dates.contains(date) || date.pred_opt().is_some_and(|prev| dates.contains(&prev))
+            })
+        };
+
+        let fills = history.fills.iter().filter_map(move |fill| {
+            near(fill).map(|date| (date, self.timeline_service.parse_fill(fill)))
+        });
+        let funding = history.funding.iter().filter_map(move |payment| {
+            near(payment).map(|date| (date, self.timeline_service.parse_funding(payment)))
+        });
+        fills.chain(funding)
+    }
+}
+
+fn buckets(days: &[Value]) -> AppResult<BTreeMap<NaiveDate, DailyBucket>> {
+    days.iter()
+        .map(|day| {
+            let bucket = DailyBucket::deserialize(day)?;
+            Ok((bucket.date, bucket))
+        })
+        .collect()
+}
+
+fn daily_from_buckets(buckets: &BTreeMap<NaiveDate, DailyBucket>) -> Vec<DailyPnl> {
+    pnl_calculator::cumulative_daily(
+        buckets
+            .values()
+            .filter_map(|bucket| Some((bucket.date, bucket.pnl.clone()?)))
+            .collect(),
+    )
+}
+
+/// What aggregates over a full history were calculated from; coverage spans the raw items
+fn calculation(
+    history: &WalletHistory,
+    buckets: &BTreeMap<NaiveDate, DailyBucket>,
+) -> CalculationMetadata {
+    let times = history
+        .fills
+        .iter()
+        .chain(&history.funding)
+        .filter_map(|raw| raw.get("time").and_then(|t| t.as_i64()));
+    let (first, last) = (times.clone().min(), times.max());
+    let (events, skipped) = buckets.values().fold((0, 0), |(events, skipped), bucket| {
+        (events + bucket.events, skipped + bucket.skipped)
+    });
+
+    CalculationMetadata {
+        method: PnlMethod::ExchangeReported,
+        price_source: PriceSource::FillPrices,
+        requested_from: None,
+        covered_from: first.and_then(DateTime::from_timestamp_millis),
+        covered_to: last.and_then(DateTime::from_timestamp_millis),
+        events_considered: events - skipped,
+        events_skipped: skipped,
+        normalization_version: history.normalization_version,
+        from_aggregates: true,
+    }
+}
+
+/// Fills and funding payments per UTC date they occurred on
+fn event_counts(history: &WalletHistory) -> BTreeMap<NaiveDate, usize> {
+    let mut counts = BTreeMap::new();
+    for date in history
+        .fills
+        .iter()
+        .chain(&history.funding)
+        .filter_map(event_date)
+    {
+        *counts.entry(date).or_default() += 1;
+    }
+    counts
+}
+
+fn event_date(raw: &Value) -> Option<NaiveDate> {
+    raw.get("time")
+        .and_then(|t| t.as_i64())
+        .and_then(DateTime::from_timestamp_millis)
+        .map(|time| time.date_naive())
+}
@@ -0,0 +1,757 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::datasource::cex::hmac_sha256;
+use crate::error::{AppError, AppResult};
+use crate::services::ingestion::IngestionService;
+use crate::services::reports::{ReportRenderer, ALERT_TEMPLATE};
+use crate::services::timeline::{Timeline, TimelineEvent, TimelineService};
+use crate::storage::Storage;
+
+/// Longest a single webhook request may take, so an unresponsive endpoint cannot hold a
+/// delivery forever
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// Quantity an alert rule watches, computed over the rule's window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    RealizedPnl,
+    FundingPnl,
+    Fees,
+    /// Realized PnL plus funding, minus fees
+    NetPnl,
+    Volume,
+    FillCount,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparator {
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl Comparator {
+    fn matches(&self, value: &BigDecimal, threshold: &BigDecimal) -> bool {
+        match self {
+            Comparator::Gt => value > threshold,
+            Comparator::Gte => value >= threshold,
+            Comparator::Lt => value < threshold,
+            Comparator::Lte => value <= threshold,
+        }
+    }
+}
+
+/// Where a fired alert is delivered
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertChannel {
+    /// Written to the service log only
+    #[default]
+    Log,
+    /// POSTed as JSON to a URL
+    Webhook { url: String },
+}
+
+/// Fields a client supplies when creating or replacing a rule
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertRuleInput {
+    pub wallet: String,
+    pub metric: AlertMetric,
+    pub comparator: Comparator,
+    pub threshold: BigDecimal,
+    /// Trailing window in hours; the whole history when omitted
+    pub window_hours: Option<u32>,
+    #[serde(default)]
+    pub channel: AlertChannel,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: Uuid,
+    pub wallet: String,
+    pub metric: AlertMetric,
+    pub comparator: Comparator,
+    pub threshold: BigDecimal,
+    pub window_hours: Option<u32>,
+    pub channel: AlertChannel,
+    pub enabled: bool,
+    /// Whether the condition held at the last evaluation; alerts fire on the transition
+    pub triggered: bool,
+    pub last_evaluated_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A record of a rule firing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FiredAlert {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub wallet: String,
+    pub metric: AlertMetric,
+    pub comparator: Comparator,
+    pub threshold: BigDecimal,
+    pub value: BigDecimal,
+    pub fired_at: DateTime<Utc>,
+    pub delivered: bool,
+    pub error: Option<String>,
+    /// Body rendered from the alert template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// A webhook delivery that failed every attempt, kept for replay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub url: String,
+    pub alert: FiredAlert,
+    /// Deliveries attempted so far, including replays
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// Signing and retry behaviour for webhook deliveries
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// Key for the `X-Webhook-Signature` HMAC; payloads are unsigned without one
+    pub signing_secret: Option<String>,
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each one after
+    pub retry_base: std::time::Duration,
+    /// Lets webhooks reach private, loopback and link-local addresses, for receivers on the
+    /// internal network
+    pub allow_private_hosts: bool,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            signing_secret: None,
+            max_attempts: 5,
+            retry_base: std::time::Duration::from_secs(1),
+            allow_private_hosts: false,
+        }
+    }
+}
+
+/// Outcome of one failed delivery attempt
+struct DeliveryError {
+    message: String,
+    /// Network errors, 429s and 5xx responses may succeed on retry
+    retryable: bool,
+}
+
+pub struct AlertService {
+    ingestion_service: Arc<IngestionService>,
+    timeline_service: Arc<TimelineService>,
+    storage: Arc<dyn Storage>,
+    client: Client,
+    webhook_config: WebhookConfig,
+    renderer: Arc<ReportRenderer>,
+}
+
+impl AlertService {
+    pub fn new(
+        ingestion_service: Arc<IngestionService>,
+        timeline_service: Arc<TimelineService>,
+        storage: Arc<dyn Storage>,
+        webhook_config: WebhookConfig,
+        renderer: Arc<ReportRenderer>,
+    ) -> Self {
+        // Redirects and hostnames are checked again on delivery: a host that passed when the
+        // rule was saved may since redirect or resolve to an internal address
+        let mut client = Client::builder()
+            .timeout(std::time::Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .redirect(reqwest::redirect::Policy::none());
+        if !webhook_config.allow_private_hosts {
+            client = client.dns_resolver(PublicResolver);
+        }
+
+        Self {
+            ingestion_service,
+            timeline_service,
+            storage,
+            client: client.build().expect("HTTP client configuration is valid"),
+            webhook_config,
+            renderer,
+        }
+    }
+
+    pub async fn create_rule(&self, input: AlertRuleInput) -> AppResult<AlertRule> {
+        validate(&input)?;
+        check_webhook_host(&input.channel, &self.webhook_config).await?;
+
+        let now = Utc::now();
+        let rule = AlertRule {
+            id: Uuid::new_v4(),
+            wallet: input.wallet,
+            metric: input.metric,
+            comparator: input.comparator,
+            threshold: input.threshold,
+            window_hours: input.window_hours,
+            channel: input.channel,
+            enabled: input.enabled,
+            triggered: false,
+            last_evaluated_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.storage.save_alert_rule(rule.clone()).await?;
+        Ok(rule)
+    }
+
+    /// Lists rules, optionally for one wallet, oldest first
+    pub async fn list_rules(&self, wallet: Option<&str>) -> AppResult<Vec<AlertRule>> {
+        let mut rules: Vec<AlertRule> = self
+            .storage
+            .list_alert_rules()
+            .await?
+            .into_iter()
+            .filter(|rule| wallet.is_none_or(|w| rule.wallet.eq_ignore_ascii_case(w)))
+            .collect();
+        rules.sort_by_key(|rule| rule.created_at);
+        Ok(rules)
+    }
+
+    pub async fn get_rule(&self, id: Uuid) -> AppResult<AlertRule> {
+        self.storage
+            .list_alert_rules()
+            .await?
+            .into_iter()
+            .find(|rule| rule.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("Alert rule {} not found", id)))
+    }
+
+    /// Replaces a rule's definition; its trigger state is reset
+    pub async fn update_rule(&self, id: Uuid, input: AlertRuleInput) -> AppResult<AlertRule> {
+        validate(&input)?;
+        check_webhook_host(&input.channel, &self.webhook_config).await?;
+
+        let existing = self.get_rule(id).await?;
+        let rule = AlertRule {
+            wallet: input.wallet,
+            metric: input.metric,
+            comparator: input.comparator,
+            threshold: input.threshold,
+            window_hours: input.window_hours,
+            channel: input.channel,
+            enabled: input.enabled,
+            triggered: false,
+            last_evaluated_at: None,
+            updated_at: Utc::now(),
+            ..existing
+        };
+
+        self.storage.save_alert_rule(rule.clone()).await?;
+        Ok(rule)
+    }
+
+    pub async fn delete_rule(&self, id: Uuid) -> AppResult<()> {
+        if !self.storage.delete_alert_rule(id).await? {
+            return Err(AppError::NotFound(format!("Alert rule {} not found", id)));
+        }
+        Ok(())
+    }
+
+    /// Deletes a wallet's rules, fired alerts and dead letters.
+    ///
+    /// Returns how many of each were removed.
+    pub async fn forget_wallet(&self, wallet: &str) -> AppResult<(usize, usize, usize)> {
+        let rules = self.list_rules(Some(wallet)).await?;
+        for rule in &rules {
+            self.storage.delete_alert_rule(rule.id).await?;
+        }
+
+        let fired = self.storage.delete_fired_alerts(wallet).await?;
+        let dead_letters = self.storage.delete_dead_letters(wallet).await?;
+
+        Ok((rules.len(), fired, dead_letters))
+    }
+
+    /// Lists fired alerts, most recent first
+    pub async fn history(
+        &self,
+        wallet: Option<&str>,
+        rule_id: Option<Uuid>,
+    ) -> AppResult<Vec<FiredAlert>> {
+        let mut alerts: Vec<FiredAlert> = self
+            .storage
+            .list_fired_alerts()
+            .await?
+            .into_iter()
+            .filter(|alert| wallet.is_none_or(|w| alert.wallet.eq_ignore_ascii_case(w)))
+            .filter(|alert| rule_id.is_none_or(|id| alert.rule_id == id))
+            .collect();
+        alerts.sort_by_key(|alert| std::cmp::Reverse(alert.fired_at));
+        Ok(alerts)
+    }
+
+    /// Syncs and evaluates every wallet with an enabled rule, forever
+    pub fn spawn_evaluator(self: &Arc<Self>, interval: std::time::Duration) {
+        let service = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = service.evaluate_all().await {
+                    tracing::warn!("Alert evaluation failed: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn evaluate_all(self: &Arc<Self>) -> AppResult<()> {
+        let rules = self.storage.list_alert_rules().await?;
+        let wallets: BTreeSet<String> = rules
+            .iter()
+            .filter(|rule| rule.enabled)
+            .map(|rule| rule.wallet.to_lowercase())
+            .collect();
+
+        for wallet in wallets {
+            if let Err(e) = self.evaluate_wallet(&wallet).await {
+                tracing::warn!("Alert evaluation failed for wallet {}: {}", wallet, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Refreshes a wallet's history and evaluates its enabled rules against it
+    async fn evaluate_wallet(self: &Arc<Self>, wallet: &str) -> AppResult<()> {
+        let history = self.ingestion_service.sync_wallet(wallet).await?;
+        let timeline =
+            self.timeline_service
+                .build_timeline(wallet, history.fills, history.funding)?;
+
+        let now = Utc::now();
+        let rules = self.list_rules(Some(wallet)).await?;
+
+        for mut rule in rules.into_iter().filter(|rule| rule.enabled) {
+            let since = rule
+                .window_hours
+                .map(|hours| now - Duration::hours(i64::from(hours)));
+            let value = metric_value(&timeline, rule.metric, since);
+            let matched = rule.comparator.matches(&value, &rule.threshold);
+
+            if matched && !rule.triggered {
+                let alert = self.fire(&rule, value);
+                self.storage.append_fired_alert(alert.clone()).await?;
+                if let AlertChannel::Webhook { url } = &rule.channel {
+                    self.spawn_delivery(url.clone(), alert);
+                }
+            }
+
+            // Skip the write if the rule was edited or deleted while evaluating
+            let current = self.get_rule(rule.id).await.ok();
+            if current.is_none_or(|current| current.updated_at != rule.updated_at) {
+                continue;
+            }
+
+            rule.triggered = matched;
+            rule.last_evaluated_at = Some(now);
+            self.storage.save_alert_rule(rule).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds the record of a rule firing, logging it for the log channel. Webhook alerts are
+    /// delivered afterwards by `spawn_delivery`.
+    fn fire(&self, rule: &AlertRule, value: BigDecimal) -> FiredAlert {
+        let mut alert = FiredAlert {
+            id: Uuid::new_v4(),
+            rule_id: rule.id,
+            wallet: rule.wallet.clone(),
+            metric: rule.metric,
+            comparator: rule.comparator,
+            threshold: rule.threshold.clone(),
+            value,
+            fired_at: Utc::now(),
+            delivered: false,
+            error: None,
+            message: None,
+        };
+        alert.message = match self
+            .renderer
+            .render(ALERT_TEMPLATE, &serde_json::json!({ "alert": &alert }))
+        {
+            Ok(message) => Some(message),
+            Err(e) => {
+                tracing::warn!("Failed to render alert {}: {}", alert.id, e);
+                None
+            }
+        };
+
+        match &rule.channel {
+            AlertChannel::Log => {
+                match &alert.message {
+                    Some(message) => tracing::info!("Alert {} fired: {}", rule.id, message),
+                    None => tracing::info!(
+                        "Alert {} fired for wallet {}: {:?} {:?} {} (value {})",
+                        rule.id,
+                        rule.wallet,
+                        rule.metric,
+                        rule.comparator,
+                        rule.threshold,
+                        alert.value
+                    ),
+                }
+                alert.delivered = true;
+            }
+            AlertChannel::Webhook { .. } => {}
+        }
+
+        alert
+    }
+
+    /// Delivers a stored webhook alert in the background, so retries never hold up the
+    /// evaluation of other rules, then records the outcome
+    fn spawn_delivery(self: &Arc<Self>, url: String, mut alert: FiredAlert) {
+        let service = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let (attempts, result) = service.deliver_with_retries(&url, &alert).await;
+            match result {
+                Ok(()) => alert.delivered = true,
+                Err(error) => {
+                    tracing::warn!(
+                        "Failed to deliver alert {} after {} attempts: {}",
+                        alert.id,
+                        attempts,
+                        error
+                    );
+                    alert.error = Some(error.clone());
+                    service.dead_letter(&url, &alert, attempts, error).await;
+                }
+            }
+
+            if let Err(e) = service.storage.update_fired_alert(alert.clone()).await {
+                tracing::warn!("Failed to record delivery of alert {}: {}", alert.id, e);
+            }
+        });
+    }
+
+    /// Lists webhook deliveries that exhausted their retries, most recent first
+    pub async fn dead_letters(&self) -> AppResult<Vec<DeadLetter>> {
+        let mut letters = self.storage.list_dead_letters().await?;
+        letters.sort_by_key(|letter| std::cmp::Reverse(letter.failed_at));
+        Ok(letters)
+    }
+
+    /// Redelivers a dead-lettered alert, recording the delivery in the alert history and
+    /// removing the letter once the webhook accepts it
+    pub async fn replay_dead_letter(&self, id: Uuid) -> AppResult<FiredAlert> {
+        let mut letter = self
+            .storage
+            .list_dead_letters()
+            .await?
+            .into_iter()
+            .find(|letter| letter.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("Dead letter {} not found", id)))?;
+
+        let (attempts, result) = self.deliver_with_retries(&letter.url, &letter.alert).await;
+        letter.attempts += attempts;
+
+        match result {
+            Ok(()) => {
+                let mut alert = letter.alert;
+                alert.delivered = true;
+                alert.error = None;
+                self.storage.update_fired_alert(alert.clone()).await?;
+                self.storage.delete_dead_letter(id).await?;
+                Ok(alert)
+            }
+            Err(error) => {
+                letter.last_error = error.clone();
+                letter.failed_at = Utc::now();
+                self.storage.save_dead_letter(letter).await?;
+                Err(AppError::ExternalApiError(format!(
+                    "Replay of {} failed: {}",
+                    id, error
+                )))
+            }
+        }
+    }
+
+    async fn dead_letter(&self, url: &str, alert: &FiredAlert, attempts: u32, error: String) {
+        let letter = DeadLetter {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            alert: alert.clone(),
+            attempts,
+            last_error: error,
+            failed_at: Utc::now(),
+        };
+
+        if let Err(e) = self.storage.save_dead_letter(letter).await {
+            tracing::warn!("Failed to store dead letter for alert {}: {}", alert.id, e);
+        }
+    }
+
+    /// Posts an alert, retrying transient failures with exponential backoff.
+    ///
+    /// Returns the number of attempts made alongside the final outcome.
+    async fn deliver_with_retries(
+        &self,
+        url: &str,
+        alert: &FiredAlert,
+    ) -> (u32, Result<(), String>) {
+        let max_attempts = self.webhook_config.max_attempts.max(1);
+        let mut delay = self.webhook_config.retry_base;
+        let mut attempt = 1;
+
+        loop {
+            match self.post_webhook(url, alert).await {
+                Ok(()) => return (attempt, Ok(())),
+                Err(error) if !error.retryable || attempt >= max_attempts => {
+                    return (attempt, Err(error.message));
+                }
+                Err(error) => {
+                    tracing::debug!(
+                        "Webhook attempt {} for alert {} failed, retrying in {:?}: {}",
+                        attempt,
+                        alert.id,
+                        delay,
+                        error.message
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn post_webhook(&self, url: &str, alert: &FiredAlert) -> Result<(), DeliveryError> {
+        // Addresses in the url bypass the resolver, so they are checked here
+        let literal = reqwest::Url::parse(url).ok().and_then(|parsed| {
+            let host = parsed.host_str()?.trim_start_matches('[').trim_end_matches(']');
+            host.parse::<IpAddr>().ok()
+        });
+        if !self.webhook_config.allow_private_hosts && literal.is_some_and(|ip| !is_public(ip)) {
+            return Err(DeliveryError {
+                message: format!("Webhook address {} is not public", url),
+                retryable: false,
+            });
+        }
+
+        let body = serde_json::to_vec(alert).map_err(|e| DeliveryError {
+            message: e.to_string(),
+            retryable: false,
+        })?;
+
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Webhook-Id", alert.id.to_string());
+        if let Some(secret) = &self.webhook_config.signing_secret {
+            let timestamp = Utc::now().timestamp();
+            request = request
+                .header("X-Webhook-Timestamp", timestamp.to_string())
+                .header(
+                    "X-Webhook-Signature",
+                    sign_payload(secret, timestamp, &body),
+                );
+        }
+
+        let response = request.body(body).send().await.map_err(|e| DeliveryError {
+            message: e.to_string(),
+            retryable: true,
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(DeliveryError {
+                message: format!("Webhook returned {}", status),
+                retryable: status.is_server_error()
+                    || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Signature over `{timestamp}.{body}`, so receivers can reject replayed payloads
+fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut message = format!("{}.", timestamp).into_bytes();
+    message.extend_from_slice(body);
+    format!(
+        "sha256={}",
+        hex::encode(hmac_sha256(secret.as_bytes(), &message))
+    )
+}
+
+fn validate(input: &AlertRuleInput) -> AppResult<()> {
+    if input.wallet.trim().is_empty() {
+        return Err(AppError::ValidationError("wallet is required".to_string()));
+    }
+
+    if input.window_hours == Some(0) {
+        return Err(AppError::ValidationError(
+            "window_hours must be positive".to_string(),
+        ));
+    }
+
+    if let AlertChannel::Webhook { url } = &input.channel {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| AppError::ValidationError(format!("Invalid webhook url: {}", e)))?;
+        if !matches!(parsed.scheme(), "https" | "http") {
+            return Err(AppError::ValidationError(
+                "Webhook url must be http(s)".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Rejects webhooks whose host is, or resolves to, a private, loopback or link-local address,
+/// so rules cannot make the server call internal services, unless the config allows them
+async fn check_webhook_host(channel: &AlertChannel, config: &WebhookConfig) -> AppResult<()> {
+    let AlertChannel::Webhook { url } = channel else {
+        return Ok(());
+    };
+    if config.allow_private_hosts {
+        return Ok(());
+    }
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| AppError::ValidationError(format!("Invalid webhook url: {}", e)))?;
+    let (Some(host), Some(port)) = (parsed.host_str(), parsed.port_or_known_default()) else {
+        return Err(AppError::ValidationError(
+            "Webhook url must have a host".to_string(),
+        ));
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let addresses: Vec<IpAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| {
+            AppError::ValidationError(format!("Webhook host {} does not resolve: {}", host, e))
+        })?
+        .map(|address| address.ip())
+        .collect();
+    if addresses.is_empty() || !addresses.into_iter().all(is_public) {
+        return Err(AppError::ValidationError(format!(
+            "Webhook host {} is not a public address",
+            host
+        )));
+    }
+
+    Ok(())
+}
+
+/// Resolves webhook hosts only when every address is public, so a DNS record changed after a
+/// rule was saved cannot point deliveries at internal services
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .collect();
+            if addresses.is_empty() || !addresses.iter().all(|address| is_public(address.ip())) {
+                return Err(format!("Webhook host {} is not a public address", host).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            // 100.64.0.0/10 is carrier-grade NAT space
+            let shared = ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64;
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Computes a metric over the events at or after `since`
+fn metric_value(
+    timeline: &Timeline,
+    metric: AlertMetric,
+    since: Option<DateTime<Utc>>,
+) -> BigDecimal {
+    let mut realized = BigDecimal::zero();
+    let mut funding = BigDecimal::zero();
+    let mut fees = BigDecimal::zero();
+    let mut volume = BigDecimal::zero();
+    let mut fill_count = 0u64;
+
+    let events = timeline
+        .events
+        .iter()
+        .filter(|e| since.is_none_or(|since| e.timestamp() >= since));
+
+    for event in events {
+        match event {
+            TimelineEvent::Fill {
+                size,
+                price,
+                fee,
+                realized_pnl,
+                ..
+            } => {
+                if let Some(pnl) = realized_pnl {
+                    realized += pnl;
+                }
+                fees += fee;
+                volume += size * price;
+                fill_count += 1;
+            }
+            TimelineEvent::Funding { amount, .. } => funding += amount,
+            _ => {}
+        }
+    }
+
+    match metric {
+        AlertMetric::RealizedPnl => realized,
+        AlertMetric::FundingPnl => funding,
+        AlertMetric::Fees => fees,
+        AlertMetric::NetPnl => realized + funding - fees,
+        AlertMetric::Volume => volume,
+        AlertMetric::FillCount => BigDecimal::from(fill_count),
+    }
+}
@@ -0,0 +1,159 @@
+use bigdecimal::{BigDecimal, Zero};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::services::market_data::{candle_at, Candle};
+
+/// Reason an event was flagged as unusual
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyFlag {
+    /// Fee rate far above the wallet's typical fee rate
+    FeeSpike,
+    /// Fill price outside the traded range of the surrounding candle
+    OffMarketPrice,
+    /// Funding rate beyond the configured bound
+    FundingRateOutOfBounds,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnomalyConfig {
+    /// A fill's fee rate is a spike above this multiple of the wallet's median fee rate
+    pub fee_spike_multiple: BigDecimal,
+    /// Allowed distance outside a candle's low/high before a fill is off-market, in bps
+    pub price_tolerance_bps: BigDecimal,
+    /// Largest absolute funding rate considered normal
+    pub max_funding_rate: BigDecimal,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            fee_spike_multiple: BigDecimal::from(5),
+            price_tolerance_bps: BigDecimal::from(50),
+            max_funding_rate: BigDecimal::from_str("0.0005").unwrap(),
+        }
+    }
+}
+
+/// Heuristics that tag upstream events with `flags` before they are stored.
+///
+/// Flags are read into timeline events; endpoints serving the venue's own payloads remove
+/// them with `without_flags`.
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self { config }
+    }
+
+    /// Flags fee spikes and, where candles are available for the coin, off-market prices
+    pub fn flag_fills(&self, fills: &mut [Value], candles: &HashMap<String, Vec<Candle>>) {
+        let fee_rates: Vec<Option<BigDecimal>> = fills.iter().map(fee_rate).collect();
+
+        let mut sorted: Vec<&BigDecimal> = fee_rates.iter().flatten().collect();
+        sorted.sort();
+        let median = sorted.get(sorted.len() / 2).copied();
+        let spike_above = median
+            .filter(|m| !m.is_zero())
+            .map(|m| m * &self.config.fee_spike_multiple);
+
+        let tolerance = &self.config.price_tolerance_bps / BigDecimal::from(10_000);
+
+        for (fill, rate) in fills.iter_mut().zip(fee_rates) {
+            let mut flags = Vec::new();
+
+            if let (Some(rate), Some(limit)) = (&rate, &spike_above)
+                && rate > limit
+            {
+                flags.push(AnomalyFlag::FeeSpike);
+            }
+
+            let coin_candles = fill
+                .get("coin")
+                .and_then(|c| c.as_str())
+                .and_then(|coin| candles.get(coin));
+            let timestamp = fill
+                .get("time")
+                .and_then(|t| t.as_i64())
+                .and_then(chrono::DateTime::from_timestamp_millis);
+
+            if let (Some(coin_candles), Some(timestamp), Some(price)) =
+                (coin_candles, timestamp, decimal(fill, "px"))
+                && let Some(candle) = candle_at(coin_candles, timestamp)
+            {
+                let low = &candle.low * (BigDecimal::from(1) - &tolerance);
+                let high = &candle.high * (BigDecimal::from(1) + &tolerance);
+                if price < low || price > high {
+                    flags.push(AnomalyFlag::OffMarketPrice);
+                }
+            }
+
+            set_flags(fill, flags);
+        }
+    }
+
+    /// Flags funding payments whose rate exceeds the configured bound
+    pub fn flag_funding(&self, funding: &mut [Value]) {
+        for payment in funding.iter_mut() {
+            let mut flags = Vec::new();
+
+            if decimal(payment, "fundingRate")
+                .is_some_and(|r| r.abs() > self.config.max_funding_rate)
+            {
+                flags.push(AnomalyFlag::FundingRateOutOfBounds);
+            }
+
+            set_flags(payment, flags);
+        }
+    }
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::new(AnomalyConfig::default())
+    }
+}
+
+fn decimal(value: &Value, key: &str) -> Option<BigDecimal> {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .and_then(|v| BigDecimal::from_str(v).ok())
+}
+
+/// Absolute fee as a fraction of notional
+fn fee_rate(fill: &Value) -> Option<BigDecimal> {
+    let notional = decimal(fill, "sz")? * decimal(fill, "px")?;
+    if notional.is_zero() {
+        return None;
+    }
+    Some((decimal(fill, "fee")? / notional).abs())
+}
+
+/// Returns events as the venue reported them, without the flags added during ingestion
+pub fn without_flags(mut values: Vec<Value>) -> Vec<Value> {
+    for value in &mut values {
+        if let Some(object) = value.as_object_mut() {
+            object.remove("flags");
+        }
+    }
+    values
+}
+
+/// Replaces any previous flags so re-ingesting an event reflects the current heuristics
+fn set_flags(value: &mut Value, flags: Vec<AnomalyFlag>) {
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+
+    if flags.is_empty() {
+        object.remove("flags");
+    } else if let Ok(flags) = serde_json::to_value(flags) {
+        object.insert("flags".to_string(), flags);
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::ingestion::IngestionService;
+use crate::services::jobs::{Job, JobRegistry, JobStatus};
+use crate::services::timeline::{TimelineEvent, TimelineService};
+use crate::sink::ArchiveSink;
+
+pub const EXPORT_JOB_KIND: &str = "s3_export";
+
+/// Multipart part size; S3 requires at least 5 MiB for all but the last part
+const PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Archives a wallet's normalized history as monthly JSONL objects
+pub struct ArchiveService {
+    ingestion_service: Arc<IngestionService>,
+    timeline_service: Arc<TimelineService>,
+    job_registry: Arc<JobRegistry>,
+    sink: Option<Arc<dyn ArchiveSink>>,
+    prefix: String,
+}
+
+impl ArchiveService {
+    pub fn new(
+        ingestion_service: Arc<IngestionService>,
+        timeline_service: Arc<TimelineService>,
+        job_registry: Arc<JobRegistry>,
+        sink: Option<Arc<dyn ArchiveSink>>,
+        prefix: &str,
+    ) -> Self {
+        Self {
+            ingestion_service,
+            timeline_service,
+            job_registry,
+            sink,
+            prefix: prefix.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Queues an export job for a wallet and runs it in the background
+    pub fn start_export(self: &Arc<Self>, wallet: &str) -> AppResult<Job> {
+        self.require_sink()?;
+
+        let job = self.job_registry.create(EXPORT_JOB_KIND, wallet);
+        self.spawn(job.id, wallet.to_string());
+        Ok(job)
+    }
+
+    /// Restarts a failed export, skipping months that were already uploaded
+    pub fn resume_export(self: &Arc<Self>, id: Uuid) -> AppResult<Job> {
+        self.require_sink()?;
+
+        let job = self
+            .job_registry
+            .get(id)
+            .filter(|job| job.kind == EXPORT_JOB_KIND)
+            .ok_or_else(|| AppError::NotFound(format!("Export job {} not found", id)))?;
+
+        if job.status != JobStatus::Failed {
+            return Err(AppError::ValidationError(format!(
+                "Only failed jobs can be resumed, job {} is {:?}",
+                id, job.status
+            )));
+        }
+
+        self.job_registry
+            .update(id, |job| job.status = JobStatus::Queued);
+        self.spawn(id, job.wallet);
+
+        self.job_registry
+            .get(id)
+            .ok_or_else(|| AppError::NotFound(format!("Export job {} not found", id)))
+    }
+
+    fn require_sink(&self) -> AppResult<Arc<dyn ArchiveSink>> {
+        self.sink
+            .clone()
+            .ok_or_else(|| AppError::ValidationError("S3 export is not configured".to_string()))
+    }
+
+    fn spawn(self: &Arc<Self>, id: Uuid, wallet: String) {
+        let service = Arc::clone(self);
+
+        tokio::spawn(async move {
+            service.job_registry.update(id, |job| {
+                job.status = JobStatus::Running;
+                job.error = None;
+            });
+
+            match service.export_wallet(id, &wallet).await {
+                Ok(()) => {
+                    tracing::info!("Export job {} completed for wallet {}", id, wallet);
+                    service
+                        .job_registry
+                        .update(id, |job| job.status = JobStatus::Completed);
+                }
+                Err(e) => {
+                    tracing::error!("Export job {} failed: {}", id, e);
+                    service.job_registry.update(id, |job| {
+                        job.status = JobStatus::Failed;
+                        job.error = Some(e.to_string());
+                    });
+                }
+            }
+        });
+    }
+
+    async fn export_wallet(&self, id: Uuid, wallet: &str) -> AppResult<()> {
+        let sink = self.require_sink()?;
+
+        let fills = self.ingestion_service.fetch_all_fills(wallet, None).await?;
+        let funding = self
+            .ingestion_service
+            .fetch_all_funding(wallet, None)
+            .await?;
+        let timeline = self
+            .timeline_service
+            .build_timeline(wallet, fills, funding)?;
+
+        // Partition by calendar month
+        let mut partitions: BTreeMap<String, Vec<&TimelineEvent>> = BTreeMap::new();
+        for event in &timeline.events {
+            partitions
+                .entry(event.timestamp().format("%Y-%m").to_string())
+                .or_default()
+                .push(event);
+        }
+
+        let completed = self
+            .job_registry
+            .get(id)
+            .map(|job| job.completed_steps)
+            .unwrap_or_default();
+        self.job_registry
+            .update(id, |job| job.total_steps = partitions.len());
+
+        for (month, events) in partitions {
+            if completed.contains(&month) {
+                continue;
+            }
+
+            let key = format!("{}/{}/{}.jsonl", self.prefix, wallet, month);
+            self.upload_partition(sink.as_ref(), &key, &events).await?;
+
+            tracing::info!("Export job {} uploaded {}", id, key);
+            self.job_registry
+                .update(id, |job| job.completed_steps.push(month));
+        }
+
+        Ok(())
+    }
+
+    /// Uploads one partition, aborting the multipart upload if any part fails
+    async fn upload_partition(
+        &self,
+        sink: &dyn ArchiveSink,
+        key: &str,
+        events: &[&TimelineEvent],
+    ) -> AppResult<()> {
+        let upload_id = sink.create_upload(key).await?;
+
+        match self.upload_parts(sink, key, &upload_id, events).await {
+            Ok(parts) => sink.complete_upload(key, &upload_id, &parts).await,
+            Err(e) => {
+                if let Err(abort_error) = sink.abort_upload(key, &upload_id).await {
+                    tracing::warn!("Failed to abort upload {}: {}", upload_id, abort_error);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Serializes events into parts, waiting for each part to upload before buffering more
+    async fn upload_parts(
+        &self,
+        sink: &dyn ArchiveSink,
+        key: &str,
+        upload_id: &str,
+        events: &[&TimelineEvent],
+    ) -> AppResult<Vec<(u32, String)>> {
+        let mut parts = Vec::new();
+        let mut buffer = Vec::with_capacity(PART_SIZE);
+
+        for event in events {
+            serde_json::to_writer(&mut buffer, event)?;
+            buffer.push(b'\n');
+
+            if buffer.len() >= PART_SIZE {
+                let part_number = parts.len() as u32 + 1;
+                let body = std::mem::replace(&mut buffer, Vec::with_capacity(PART_SIZE));
+                let etag = sink.upload_part(key, upload_id, part_number, body).await?;
+                parts.push((part_number, etag));
+            }
+        }
+
+        if !buffer.is_empty() || parts.is_empty() {
+            let part_number = parts.len() as u32 + 1;
+            let etag = sink
+                .upload_part(key, upload_id, part_number, buffer)
+                .await?;
+            parts.push((part_number, etag));
+        }
+
+        Ok(parts)
+    }
+}
@@ -0,0 +1,291 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use crate::datasource::DataSource;
+use crate::error::{AppError, AppResult};
+use crate::storage::Storage;
+
+/// Asset names by index, as listed by upstream metadata over a span of time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetMapping {
+    pub effective_from: DateTime<Utc>,
+    /// Set once a newer mapping replaces this one
+    pub effective_to: Option<DateTime<Utc>>,
+    /// Perp names by asset index
+    pub perps: BTreeMap<u32, String>,
+    /// Spot pair names (`BASE/QUOTE`) by spot universe index, as referenced by `@<index>` coins
+    pub spot: BTreeMap<u32, String>,
+}
+
+impl AssetMapping {
+    /// True when both mappings list the same assets under the same indices
+    fn same_assets(&self, other: &AssetMapping) -> bool {
+        self.perps == other.perps && self.spot == other.spot
+    }
+}
+
+/// Hyperliquid prices allow this many decimals minus the asset's size decimals
+const MAX_PERP_PRICE_DECIMALS: u32 = 6;
+
+/// Coins per contract of perps listed with a `k` prefix (`kPEPE` trades in 1000 PEPE)
+const KILO_CONTRACT_SIZE: u32 = 1000;
+
+/// Trading parameters of a listed perp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoinMeta {
+    pub name: String,
+    /// Asset index used by the exchange API
+    pub index: u32,
+    /// Decimals allowed in order sizes
+    pub sz_decimals: u32,
+    /// Decimals allowed in prices, before the five significant figure limit
+    pub price_decimals: u32,
+    /// Coins per unit of size; sizes and prices are quoted per contract when above 1
+    pub contract_size: u32,
+    pub max_leverage: u32,
+    /// Whether the asset can only be traded with isolated margin
+    pub only_isolated: bool,
+    pub is_delisted: bool,
+}
+
+/// Perp metadata as of the last successful refresh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoinMetadata {
+    pub refreshed_at: Option<DateTime<Utc>>,
+    pub coins: Vec<CoinMeta>,
+}
+
+/// Versioned asset mappings used to normalize coin identifiers at each event's time
+pub struct AssetRegistry {
+    datasource: Arc<dyn DataSource>,
+    storage: Arc<dyn Storage>,
+    /// Versions ordered by `effective_from`
+    mappings: RwLock<Vec<AssetMapping>>,
+    coins: RwLock<CoinMetadata>,
+}
+
+impl AssetRegistry {
+    pub fn new(datasource: Arc<dyn DataSource>, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            datasource,
+            storage,
+            mappings: RwLock::new(Vec::new()),
+            coins: RwLock::new(CoinMetadata {
+                refreshed_at: None,
+                coins: Vec::new(),
+            }),
+        }
+    }
+
+    /// Loads previously stored mappings
+    pub async fn load(&self) -> AppResult<()> {
+        let mut mappings = self.storage.load_asset_mappings().await?;
+        mappings.sort_by_key(|m| m.effective_from);
+        *self.mappings.write().expect("asset registry lock poisoned") = mappings;
+        Ok(())
+    }
+
+    /// Fetches current metadata and starts a new mapping version if the listing changed
+    pub async fn refresh(&self) -> AppResult<()> {
+        let meta = self.datasource.get_meta().await?;
+        let spot_meta = self.datasource.get_spot_meta().await?;
+
+        let now = Utc::now();
+        *self.coins.write().expect("asset registry lock poisoned") = CoinMetadata {
+            refreshed_at: Some(now),
+            coins: parse_coin_meta(&meta)?,
+        };
+
+        let latest = AssetMapping {
+            effective_from: now,
+            effective_to: None,
+            perps: parse_perps(&meta)?,
+            spot: parse_spot(&spot_meta)?,
+        };
+
+        let snapshot = {
+            let mut mappings = self.mappings.write().expect("asset registry lock poisoned");
+            if mappings.last().is_some_and(|m| m.same_assets(&latest)) {
+                return Ok(());
+            }
+
+            if let Some(previous) = mappings.last_mut() {
+                previous.effective_to = Some(now);
+            }
+            tracing::info!(
+                "Asset listing changed: {} perps, {} spot pairs",
+                latest.perps.len(),
+                latest.spot.len()
+            );
+            mappings.push(latest);
+            mappings.clone()
+        };
+
+        self.storage.save_asset_mappings(snapshot).await
+    }
+
+    /// Perp trading parameters from the latest refresh
+    pub fn coin_metadata(&self) -> CoinMetadata {
+        self.coins
+            .read()
+            .expect("asset registry lock poisoned")
+            .clone()
+    }
+
+    /// Trading parameters of a currently listed perp
+    pub fn coin_meta(&self, coin: &str) -> Option<CoinMeta> {
+        self.coins
+            .read()
+            .expect("asset registry lock poisoned")
+            .coins
+            .iter()
+            .find(|meta| meta.name == coin)
+            .cloned()
+    }
+
+    /// Refreshes metadata on a fixed interval, forever
+    pub fn spawn_refresher(self: &Arc<Self>, interval: std::time::Duration) {
+        let registry = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = registry.refresh().await {
+                    tracing::warn!("Asset metadata refresh failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Resolves an upstream coin identifier to a stable asset name.
+    ///
+    /// Spot coins referenced by index (`@107`) are resolved with the mapping effective at
+    /// `timestamp`, falling back to the earliest known mapping for older events. Named coins
+    /// and indices missing from every mapping are returned unchanged.
+    pub fn normalize_coin(&self, coin: &str, timestamp: DateTime<Utc>) -> String {
+        let Some(index) = coin.strip_prefix('@').and_then(|i| i.parse::<u32>().ok()) else {
+            return coin.to_string();
+        };
+
+        let mappings = self.mappings.read().expect("asset registry lock poisoned");
+        let effective = mappings
+            .iter()
+            .rev()
+            .find(|m| m.effective_from <= timestamp)
+            .or_else(|| mappings.first());
+
+        effective
+            .and_then(|m| m.spot.get(&index))
+            .cloned()
+            .unwrap_or_else(|| coin.to_string())
+    }
+
+    /// Returns the upstream coin identifier of a currently listed spot pair (`BASE/QUOTE`).
+    ///
+    /// Pairs are referenced as `@<index>`, except the first which keeps its name.
+    pub fn spot_coin(&self, pair: &str) -> Option<String> {
+        let mappings = self.mappings.read().expect("asset registry lock poisoned");
+        let (index, _) = mappings
+            .last()?
+            .spot
+            .iter()
+            .find(|(_, name)| *name == pair)?;
+
+        Some(if *index == 0 {
+            pair.to_string()
+        } else {
+            format!("@{}", index)
+        })
+    }
+}
+
+/// Perp universe entries are indexed by position
+fn parse_perps(meta: &Value) -> AppResult<BTreeMap<u32, String>> {
+    let universe = meta
+        .get("universe")
+        .and_then(|u| u.as_array())
+        .ok_or_else(|| AppError::ExternalApiError("meta response has no universe".to_string()))?;
+
+    Ok(universe
+        .iter()
+        .enumerate()
+        .filter_map(|(index, asset)| {
+            let name = asset.get("name").and_then(|n| n.as_str())?;
+            Some((index as u32, name.to_string()))
+        })
+        .collect())
+}
+
+fn parse_coin_meta(meta: &Value) -> AppResult<Vec<CoinMeta>> {
+    let universe = meta
+        .get("universe")
+        .and_then(|u| u.as_array())
+        .ok_or_else(|| AppError::ExternalApiError("meta response has no universe".to_string()))?;
+
+    Ok(universe
+        .iter()
+        .enumerate()
+        .filter_map(|(index, asset)| {
+            let flag = |field: &str| asset.get(field).and_then(|f| f.as_bool()) == Some(true);
+            let sz_decimals = asset.get("szDecimals").and_then(|d| d.as_u64())? as u32;
+            let name = asset.get("name").and_then(|n| n.as_str())?;
+
+            Some(CoinMeta {
+                name: name.to_string(),
+                index: index as u32,
+                sz_decimals,
+                price_decimals: MAX_PERP_PRICE_DECIMALS.saturating_sub(sz_decimals),
+                contract_size: contract_size(name),
+                max_leverage: asset.get("maxLeverage").and_then(|l| l.as_u64())? as u32,
+                only_isolated: flag("onlyIsolated"),
+                is_delisted: flag("isDelisted"),
+            })
+        })
+        .collect())
+}
+
+/// Perps named `k` plus an uppercase ticker are quoted in thousand-coin contracts
+fn contract_size(name: &str) -> u32 {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some('k'), Some(c)) if c.is_ascii_uppercase() => KILO_CONTRACT_SIZE,
+        _ => 1,
+    }
+}
+
+/// Spot pairs are named from their token names, since most are listed as `@<index>`
+fn parse_spot(spot_meta: &Value) -> AppResult<BTreeMap<u32, String>> {
+    let tokens: BTreeMap<u64, &str> = spot_meta
+        .get("tokens")
+        .and_then(|t| t.as_array())
+        .ok_or_else(|| AppError::ExternalApiError("spotMeta response has no tokens".to_string()))?
+        .iter()
+        .filter_map(|token| {
+            let index = token.get("index").and_then(|i| i.as_u64())?;
+            let name = token.get("name").and_then(|n| n.as_str())?;
+            Some((index, name))
+        })
+        .collect();
+
+    let universe = spot_meta
+        .get("universe")
+        .and_then(|u| u.as_array())
+        .ok_or_else(|| {
+            AppError::ExternalApiError("spotMeta response has no universe".to_string())
+        })?;
+
+    Ok(universe
+        .iter()
+        .filter_map(|pair| {
+            let index = pair.get("index").and_then(|i| i.as_u64())?;
+            let pair_tokens = pair.get("tokens").and_then(|t| t.as_array())?;
+            let base = tokens.get(&pair_tokens.first()?.as_u64()?)?;
+            let quote = tokens.get(&pair_tokens.get(1)?.as_u64()?)?;
+            Some((index as u32, format!("{}/{}", base, quote)))
+        })
+        .collect())
+}
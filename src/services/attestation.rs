@@ -0,0 +1,203 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, AppResult};
+use crate::services::corrections::EventCategory;
+use crate::services::sharing::SharedReportKind;
+
+/// Format version of the attestation document
+pub const ATTESTATION_VERSION: u32 = 1;
+
+/// Fields ingestion adds to raw events, removed before hashing so hashes match the venue's data
+const ANNOTATION_FIELDS: [&str; 1] = ["flags"];
+
+/// A venue event a report was computed from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestedEvent {
+    pub id: String,
+    pub category: EventCategory,
+    /// SHA-256 of the event as the venue returned it
+    pub sha256: String,
+}
+
+/// The signed statement of an attestation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationClaims {
+    pub version: u32,
+    pub wallet: String,
+    pub report: SharedReportKind,
+    pub generated_at: DateTime<Utc>,
+    /// SHA-256 of the report data
+    pub report_sha256: String,
+    /// SHA-256 of the event list
+    pub events_sha256: String,
+    pub event_count: usize,
+    /// Hex-encoded Ed25519 key the claims are signed with
+    pub public_key: String,
+}
+
+/// A report bundled with the events behind it and a server signature.
+///
+/// Hashes are taken over compact JSON with object keys sorted, and the signature covers
+/// `claims` encoded the same way, so anyone holding the server's public key can check that
+/// the report and events are unchanged and re-hash each event against the venue's API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attestation {
+    pub claims: AttestationClaims,
+    /// Hex-encoded Ed25519 signature over `claims`
+    pub signature: String,
+    pub data: Value,
+    pub events: Vec<AttestedEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationCheck {
+    pub signature_valid: bool,
+    pub report_hash_valid: bool,
+    pub events_hash_valid: bool,
+    /// Whether the claims name this server's current key
+    pub signed_by_this_server: bool,
+    pub valid: bool,
+}
+
+/// Signs performance reports so third parties can verify them.
+///
+/// Without a configured key a random one is generated, and attestations issued before a
+/// restart can no longer be checked against the server's published key.
+pub struct AttestationService {
+    signing_key: SigningKey,
+}
+
+impl AttestationService {
+    /// `seed` is a base64-encoded 32-byte Ed25519 secret key.
+    pub fn new(seed: Option<&str>) -> AppResult<Self> {
+        let seed: [u8; 32] = match seed {
+            Some(seed) => STANDARD
+                .decode(seed.trim())
+                .ok()
+                .and_then(|seed| seed.try_into().ok())
+                .ok_or_else(|| {
+                    AppError::InternalError(
+                        "Attestation signing key must be 32 base64-encoded bytes".to_string(),
+                    )
+                })?,
+            None => {
+                let mut seed = [0u8; 32];
+                OsRng.fill_bytes(&mut seed);
+                seed
+            }
+        };
+
+        Ok(Self {
+            signing_key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Hex-encoded public key attestations are signed with
+    pub fn public_key(&self) -> String {
+        hex::encode(self.signing_key.verifying_key().as_bytes())
+    }
+
+    /// Hashes a raw venue event for inclusion in an attestation
+    pub fn attested_event(
+        &self,
+        id: String,
+        category: EventCategory,
+        raw: &Value,
+    ) -> AppResult<AttestedEvent> {
+        let mut raw = raw.clone();
+        if let Some(object) = raw.as_object_mut() {
+            for field in ANNOTATION_FIELDS {
+                object.remove(field);
+            }
+        }
+
+        Ok(AttestedEvent {
+            id,
+            category,
+            sha256: sha256_json(&raw)?,
+        })
+    }
+
+    /// Signs a report together with the events it was computed from
+    pub fn attest(
+        &self,
+        report: SharedReportKind,
+        wallet: &str,
+        data: Value,
+        mut events: Vec<AttestedEvent>,
+    ) -> AppResult<Attestation> {
+        events.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let claims = AttestationClaims {
+            version: ATTESTATION_VERSION,
+            wallet: wallet.to_string(),
+            report,
+            generated_at: Utc::now(),
+            report_sha256: sha256_json(&data)?,
+            events_sha256: sha256_json(&events)?,
+            event_count: events.len(),
+            public_key: self.public_key(),
+        };
+        let signature = self.signing_key.sign(&canonical_json(&claims)?);
+
+        Ok(Attestation {
+            claims,
+            signature: hex::encode(signature.to_bytes()),
+            data,
+            events,
+        })
+    }
+
+    /// Checks an attestation's signature and hashes
+    pub fn verify(&self, attestation: &Attestation) -> AppResult<AttestationCheck> {
+        let claims = &attestation.claims;
+        let signature_valid = verify_signature(claims, &attestation.signature)?;
+        let report_hash_valid = sha256_json(&attestation.data)? == claims.report_sha256;
+        let events_hash_valid = sha256_json(&attestation.events)? == claims.events_sha256
+            && attestation.events.len() == claims.event_count;
+        let signed_by_this_server = claims.public_key == self.public_key();
+
+        Ok(AttestationCheck {
+            valid: signature_valid
+                && report_hash_valid
+                && events_hash_valid
+                && signed_by_this_server,
+            signature_valid,
+            report_hash_valid,
+            events_hash_valid,
+            signed_by_this_server,
+        })
+    }
+}
+
+fn verify_signature(claims: &AttestationClaims, signature: &str) -> AppResult<bool> {
+    let key = hex::decode(&claims.public_key)
+        .ok()
+        .and_then(|key| <[u8; 32]>::try_from(key).ok())
+        .and_then(|key| VerifyingKey::from_bytes(&key).ok());
+    let signature = hex::decode(signature)
+        .ok()
+        .and_then(|signature| <[u8; 64]>::try_from(signature).ok())
+        .map(|signature| Signature::from_bytes(&signature));
+
+    Ok(match (key, signature) {
+        (Some(key), Some(signature)) => key.verify(&canonical_json(claims)?, &signature).is_ok(),
+        _ => false,
+    })
+}
+
+/// Compact JSON with object keys sorted
+fn canonical_json<T: Serialize>(value: &T) -> AppResult<Vec<u8>> {
+    Ok(serde_json::to_vec(&serde_json::to_value(value)?)?)
+}
+
+fn sha256_json<T: Serialize>(value: &T) -> AppResult<String> {
+    Ok(hex::encode(Sha256::digest(canonical_json(value)?)))
+}
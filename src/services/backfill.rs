@@ -0,0 +1,338 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::ingestion::{deleted_during_backfill, IngestionService};
+use crate::services::jobs::{Checkpoint, Job, JobRegistry, JobStatus};
+use crate::storage::{Storage, StoredBackfillCursor};
+
+pub const BACKFILL_JOB_KIND: &str = "backfill";
+
+/// Items per page upstream; a shorter page is the last one
+const FULL_PAGE: usize = 500;
+
+/// History fetched page by page, in the order backfills walk them
+#[derive(Debug, Clone, Copy)]
+enum DataType {
+    Fills,
+    Funding,
+    Ledger,
+}
+
+impl DataType {
+    const ALL: [DataType; 3] = [DataType::Fills, DataType::Funding, DataType::Ledger];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            DataType::Fills => "fills",
+            DataType::Funding => "funding",
+            DataType::Ledger => "ledger",
+        }
+    }
+}
+
+/// Fetches a wallet's full history one page at a time, persisting a cursor per data type
+/// after every page.
+///
+/// Venues merged alongside the primary source cannot be paged; each of their data types is
+/// fetched whole as one step.
+///
+/// A failed backfill continues from the last completed page when resumed or started again for
+/// the same wallet. Cursors live in the configured storage and jobs in memory, so with the
+/// in-memory backend a restart starts over. The history is stored once every data type is
+/// complete, and the cursors are then dropped.
+pub struct BackfillService {
+    ingestion_service: Arc<IngestionService>,
+    storage: Arc<dyn Storage>,
+    job_registry: Arc<JobRegistry>,
+}
+
+impl BackfillService {
+    pub fn new(
+        ingestion_service: Arc<IngestionService>,
+        storage: Arc<dyn Storage>,
+        job_registry: Arc<JobRegistry>,
+    ) -> Self {
+        Self {
+            ingestion_service,
+            storage,
+            job_registry,
+        }
+    }
+
+    /// Queues a backfill for a wallet, resuming from stored cursors if an earlier one was cut off
+    pub fn start(self: &Arc<Self>, wallet: &str) -> AppResult<Job> {
+        let wallet = wallet.to_lowercase();
+        let active = self.job_registry.list().into_iter().find(|job| {
+            job.kind == BACKFILL_JOB_KIND
+                && job.wallet == wallet
+                && matches!(job.status, JobStatus::Queued | JobStatus::Running)
+        });
+        if let Some(job) = active {
+            return Err(AppError::ValidationError(format!(
+                "Backfill job {} for {} is already {:?}",
+                job.id, wallet, job.status
+            )));
+        }
+
+        let job = self.job_registry.create(BACKFILL_JOB_KIND, &wallet);
+        let sources = 1 + self.ingestion_service.venue_sources().len();
+        self.job_registry
+            .update(job.id, |job| job.total_steps = DataType::ALL.len() * sources);
+        self.spawn(job.id, wallet);
+
+        self.job_registry
+            .get(job.id)
+            .ok_or_else(|| AppError::NotFound(format!("Backfill job {} not found", job.id)))
+    }
+
+    /// Restarts a failed backfill from its last checkpoints
+    pub fn resume(self: &Arc<Self>, id: Uuid) -> AppResult<Job> {
+        let job = self
+            .job_registry
+            .get(id)
+            .filter(|job| job.kind == BACKFILL_JOB_KIND)
+            .ok_or_else(|| AppError::NotFound(format!("Backfill job {} not found", id)))?;
+
+        if job.status != JobStatus::Failed {
+            return Err(AppError::ValidationError(format!(
+                "Only failed jobs can be resumed, job {} is {:?}",
+                id, job.status
+            )));
+        }
+
+        self.job_registry
+            .update(id, |job| job.status = JobStatus::Queued);
+        self.spawn(id, job.wallet);
+
+        self.job_registry
+            .get(id)
+            .ok_or_else(|| AppError::NotFound(format!("Backfill job {} not found", id)))
+    }
+
+    fn spawn(self: &Arc<Self>, id: Uuid, wallet: String) {
+        let service = Arc::clone(self);
+
+        tokio::spawn(async move {
+            service.job_registry.update(id, |job| {
+                job.status = JobStatus::Running;
+                job.error = None;
+            });
+
+            match service.backfill(id, &wallet).await {
+                Ok(()) => {
+                    tracing::info!("Backfill job {} completed for wallet {}", id, wallet);
+                    service
+                        .job_registry
+                        .update(id, |job| job.status = JobStatus::Completed);
+                }
+                Err(e) => {
+                    tracing::error!("Backfill job {} failed: {}", id, e);
+                    service.job_registry.update(id, |job| {
+                        job.status = JobStatus::Failed;
+                        job.error = Some(e.to_string());
+                    });
+                }
+            }
+        });
+    }
+
+    async fn backfill(&self, id: Uuid, wallet: &str) -> AppResult<()> {
+        let started_at = self
+            .job_registry
+            .get(id)
+            .map_or_else(Utc::now, |job| job.created_at);
+
+        let mut histories: [Vec<Value>; 3] = Default::default();
+        for (index, data_type) in DataType::ALL.into_iter().enumerate() {
+            let step = data_type.as_str();
+            let mut cursor = match self.storage.load_backfill_cursor(wallet, step).await? {
+                Some(cursor) => cursor,
+                None => StoredBackfillCursor {
+                    next_start: 0,
+                    pages: 0,
+                    complete: false,
+                    items: Vec::new(),
+                    updated_at: Utc::now(),
+                },
+            };
+            self.record_checkpoint(id, step, &cursor);
+
+            while !cursor.complete {
+                let page = self
+                    .fetch_page(data_type, wallet, cursor.next_start)
+                    .await?;
+                advance(&mut cursor, page);
+                self.storage
+                    .save_backfill_cursor(wallet, step, cursor.clone())
+                    .await?;
+                self.stop_if_deleted(wallet, started_at).await?;
+                self.record_checkpoint(id, step, &cursor);
+            }
+
+            self.complete_step(id, step);
+            histories[index] = cursor.items;
+        }
+
+        // Other venues cannot be paged, so each data type is fetched whole in one step
+        for (venue, source) in self.ingestion_service.venue_sources() {
+            for (index, data_type) in DataType::ALL.into_iter().enumerate() {
+                let step = format!("{}:{}", venue, data_type.as_str());
+                let cursor = match self.storage.load_backfill_cursor(wallet, &step).await? {
+                    Some(cursor) if cursor.complete => cursor,
+                    _ => {
+                        let items = match data_type {
+                            DataType::Fills => source.get_fills(wallet, None).await?,
+                            DataType::Funding => source.get_funding(wallet, None).await?,
+                            DataType::Ledger => source.get_ledger_updates(wallet, None).await?,
+                        };
+                        let cursor = StoredBackfillCursor {
+                            next_start: items.last().and_then(time).unwrap_or_default(),
+                            pages: 1,
+                            complete: true,
+                            items,
+                            updated_at: Utc::now(),
+                        };
+                        self.storage
+                            .save_backfill_cursor(wallet, &step, cursor.clone())
+                            .await?;
+                        self.stop_if_deleted(wallet, started_at).await?;
+                        cursor
+                    }
+                };
+                self.record_checkpoint(id, &step, &cursor);
+                self.complete_step(id, &step);
+                histories[index].extend(cursor.items);
+            }
+        }
+        for history in &mut histories {
+            history.sort_by_key(time);
+        }
+
+        let [fills, funding, ledger] = histories;
+        let stored = match self
+            .ingestion_service
+            .store_backfill(wallet, started_at, fills, funding, ledger)
+            .await
+        {
+            Ok(stored) => stored,
+            Err(e) => {
+                self.stop_if_deleted(wallet, started_at).await?;
+                return Err(e);
+            }
+        };
+        tracing::info!(
+            "Backfill job {} stored {} fills, {} funding payments and {} ledger updates",
+            id,
+            stored.fills.len(),
+            stored.funding.len(),
+            stored.ledger.len()
+        );
+
+        self.storage.delete_backfill_cursors(wallet).await?;
+        Ok(())
+    }
+
+    /// Fails the backfill, dropping the pages fetched so far, if the wallet was deleted on
+    /// request after it started
+    async fn stop_if_deleted(&self, wallet: &str, started_at: DateTime<Utc>) -> AppResult<()> {
+        if self
+            .ingestion_service
+            .deleted_since(wallet, started_at)
+            .await?
+        {
+            self.storage.delete_backfill_cursors(wallet).await?;
+            return Err(deleted_during_backfill(wallet));
+        }
+        Ok(())
+    }
+
+    async fn fetch_page(
+        &self,
+        data_type: DataType,
+        wallet: &str,
+        start_time: i64,
+    ) -> AppResult<Vec<Value>> {
+        match data_type {
+            DataType::Fills => {
+                self.ingestion_service
+                    .fetch_fill_page(wallet, Some(start_time))
+                    .await
+            }
+            DataType::Funding => {
+                self.ingestion_service
+                    .fetch_funding_page(wallet, start_time)
+                    .await
+            }
+            DataType::Ledger => {
+                self.ingestion_service
+                    .fetch_ledger_page(wallet, start_time)
+                    .await
+            }
+        }
+    }
+
+    fn complete_step(&self, id: Uuid, step: &str) {
+        self.job_registry.update(id, |job| {
+            if !job.completed_steps.iter().any(|s| s == step) {
+                job.completed_steps.push(step.to_string());
+            }
+        });
+    }
+
+    fn record_checkpoint(&self, id: Uuid, step: &str, cursor: &StoredBackfillCursor) {
+        let checkpoint = Checkpoint {
+            step: step.to_string(),
+            next_start: cursor.next_start,
+            pages: cursor.pages,
+            items: cursor.items.len(),
+            complete: cursor.complete,
+            updated_at: cursor.updated_at,
+        };
+        self.job_registry.update(id, |job| {
+            match job.checkpoints.iter_mut().find(|c| c.step == step) {
+                Some(existing) => *existing = checkpoint,
+                None => job.checkpoints.push(checkpoint),
+            }
+        });
+    }
+}
+
+/// Appends a fetched page to the cursor and moves it to the next page.
+///
+/// Pages after the first start at the previous page's last timestamp, so items sharing that
+/// millisecond are not skipped; those already fetched are dropped.
+fn advance(cursor: &mut StoredBackfillCursor, page: Vec<Value>) {
+    let full = page.len() >= FULL_PAGE;
+    let last_timestamp = page.last().and_then(time);
+    let seen: Vec<Value> = cursor
+        .items
+        .iter()
+        .rev()
+        .take_while(|item| time(item) == Some(cursor.next_start))
+        .cloned()
+        .collect();
+    let new_items: Vec<Value> = page
+        .into_iter()
+        .filter(|item| !seen.contains(item))
+        .collect();
+
+    cursor.pages += 1;
+    cursor.updated_at = Utc::now();
+    match last_timestamp {
+        // A full page within one millisecond: move past it rather than loop
+        Some(ts) if new_items.is_empty() && full => cursor.next_start = ts + 1,
+        Some(ts) if !new_items.is_empty() => {
+            cursor.items.extend(new_items);
+            cursor.next_start = ts;
+            cursor.complete = !full;
+        }
+        _ => cursor.complete = true,
+    }
+}
+
+fn time(item: &Value) -> Option<i64> {
+    item.get("time").and_then(|t| t.as_i64())
+}
@@ -0,0 +1,274 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use crate::services::positions::CostBasisEngine;
+use crate::services::timeline::{Timeline, TimelineEvent};
+
+/// Decimal places kept for ratios and yields
+const RATIO_SCALE: i64 = 8;
+
+const MILLIS_PER_YEAR: i64 = 365 * 24 * 60 * 60 * 1000;
+const MILLIS_PER_HOUR: i64 = 60 * 60 * 1000;
+
+/// A spot holding paired with a perp short on the same asset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasisPair {
+    /// Perp coin, e.g. `HYPE`
+    pub coin: String,
+    /// Spot pair, e.g. `HYPE/USDC`
+    pub spot_coin: String,
+    /// Whether the spot leg is long and the perp leg short now
+    pub active: bool,
+    pub spot_size: BigDecimal,
+    pub perp_size: BigDecimal,
+    /// Spot plus perp size; zero when perfectly hedged
+    pub net_delta: BigDecimal,
+    /// Perp short as a fraction of the spot holding
+    pub hedge_ratio: Option<BigDecimal>,
+    pub mark_price: Option<BigDecimal>,
+    pub net_delta_notional: Option<BigDecimal>,
+    pub spot_cost_basis: BigDecimal,
+    pub spot_realized_pnl: BigDecimal,
+    pub perp_realized_pnl: BigDecimal,
+    #[serde(with = "crate::output::cost")]
+    pub fees: BigDecimal,
+    /// Perp funding received while both legs were open
+    pub funding_income: BigDecimal,
+    /// Start of the current hedged period, if active
+    pub hedged_since: Option<DateTime<Utc>>,
+    /// Total time both legs were open
+    pub hedged_hours: BigDecimal,
+    /// Time-weighted spot cost basis while hedged
+    pub average_capital: Option<BigDecimal>,
+    /// Funding income over average capital, annualized over the hedged time
+    pub annualized_funding_yield: Option<BigDecimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasisReport {
+    pub wallet: String,
+    pub as_of: DateTime<Utc>,
+    pub pairs: Vec<BasisPair>,
+    pub total_funding_income: BigDecimal,
+}
+
+/// Running state of one pair while replaying the timeline
+#[derive(Default)]
+struct PairState {
+    hedged_since: Option<DateTime<Utc>>,
+    hedged_ms: i64,
+    /// Spot cost basis multiplied by milliseconds hedged
+    capital_ms: BigDecimal,
+    capital: BigDecimal,
+    funding_income: BigDecimal,
+    spot_realized_pnl: BigDecimal,
+    perp_realized_pnl: BigDecimal,
+    fees: BigDecimal,
+}
+
+impl PairState {
+    /// Accrues hedged time and capital up to `now`
+    fn advance(&mut self, last: DateTime<Utc>, now: DateTime<Utc>) {
+        if self.hedged_since.is_some() {
+            let elapsed = (now - last).num_milliseconds().max(0);
+            self.hedged_ms += elapsed;
+            self.capital_ms = &self.capital_ms + &self.capital * BigDecimal::from(elapsed);
+        }
+    }
+}
+
+/// Splits spot-versus-perp basis trades out of a wallet's timeline.
+///
+/// Spot pairs are matched to the perp of the same base asset; wrapped spot tokens such as
+/// `UBTC` match the `BTC` perp. Funding counts toward a pair only while the spot leg is long
+/// and the perp leg short.
+pub struct BasisTracker;
+
+impl BasisTracker {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn track(
+        &self,
+        wallet: &str,
+        timeline: &Timeline,
+        mids: &Value,
+        as_of: DateTime<Utc>,
+    ) -> BasisReport {
+        let pairs = pair_coins(timeline);
+
+        let mut engine = CostBasisEngine::new();
+        let mut states: BTreeMap<&str, PairState> = pairs
+            .keys()
+            .map(|perp| (perp.as_str(), PairState::default()))
+            .collect();
+        let mut last = timeline.from_timestamp.unwrap_or(as_of);
+
+        for event in &timeline.events {
+            let now = event.timestamp();
+            for state in states.values_mut() {
+                state.advance(last, now);
+            }
+            last = now;
+
+            match event {
+                TimelineEvent::Fill {
+                    coin,
+                    fee,
+                    realized_pnl,
+                    ..
+                } => {
+                    engine.apply(event);
+
+                    let Some((perp, is_spot)) = pairs
+                        .iter()
+                        .find(|(perp, spot)| *perp == coin || *spot == coin)
+                        .map(|(perp, spot)| (perp.as_str(), spot == coin))
+                    else {
+                        continue;
+                    };
+                    let state = states.get_mut(perp).expect("state for every pair");
+                    let realized = realized_pnl.clone().unwrap_or_default();
+
+                    state.fees = &state.fees + fee;
+                    if is_spot {
+                        state.spot_realized_pnl = &state.spot_realized_pnl + realized;
+                    } else {
+                        state.perp_realized_pnl = &state.perp_realized_pnl + realized;
+                    }
+
+                    let (spot_size, perp_size, capital) = legs(&engine, perp, &pairs[perp]);
+                    let hedged = spot_size > BigDecimal::zero() && perp_size < BigDecimal::zero();
+                    state.hedged_since = match (hedged, state.hedged_since) {
+                        (true, Some(since)) => Some(since),
+                        (true, None) => Some(now),
+                        (false, _) => None,
+                    };
+                    state.capital = capital;
+                }
+                TimelineEvent::Funding { coin, amount, .. } => {
+                    if let Some(state) = states.get_mut(coin.as_str())
+                        && state.hedged_since.is_some()
+                    {
+                        state.funding_income = &state.funding_income + amount;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for state in states.values_mut() {
+            state.advance(last, as_of);
+        }
+
+        let pairs: Vec<BasisPair> = states
+            .into_iter()
+            .map(|(perp, state)| {
+                let spot_coin = pairs[perp].clone();
+                let (spot_size, perp_size, spot_cost_basis) = legs(&engine, perp, &spot_coin);
+                let net_delta = &spot_size + &perp_size;
+
+                let mark_price = mids
+                    .get(perp)
+                    .and_then(|mid| mid.as_str())
+                    .and_then(|mid| BigDecimal::from_str(mid).ok());
+                let hedge_ratio =
+                    (!spot_size.is_zero()).then(|| (-&perp_size / &spot_size).round(RATIO_SCALE));
+                let average_capital = (state.hedged_ms > 0).then(|| {
+                    (&state.capital_ms / BigDecimal::from(state.hedged_ms)).round(RATIO_SCALE)
+                });
+                let annualized_funding_yield = (!state.capital_ms.is_zero()).then(|| {
+                    (&state.funding_income * BigDecimal::from(MILLIS_PER_YEAR) / &state.capital_ms)
+                        .round(RATIO_SCALE)
+                });
+
+                BasisPair {
+                    coin: perp.to_string(),
+                    spot_coin,
+                    active: state.hedged_since.is_some(),
+                    net_delta_notional: mark_price.as_ref().map(|price| &net_delta * price),
+                    spot_size,
+                    perp_size,
+                    net_delta,
+                    hedge_ratio,
+                    mark_price,
+                    spot_cost_basis,
+                    spot_realized_pnl: state.spot_realized_pnl,
+                    perp_realized_pnl: state.perp_realized_pnl,
+                    fees: state.fees,
+                    funding_income: state.funding_income,
+                    hedged_since: state.hedged_since,
+                    hedged_hours: (BigDecimal::from(state.hedged_ms)
+                        / BigDecimal::from(MILLIS_PER_HOUR))
+                    .round(2),
+                    average_capital,
+                    annualized_funding_yield,
+                }
+            })
+            .collect();
+
+        let total_funding_income = pairs.iter().map(|pair| &pair.funding_income).sum();
+
+        BasisReport {
+            wallet: wallet.to_string(),
+            as_of,
+            pairs,
+            total_funding_income,
+        }
+    }
+}
+
+impl Default for BasisTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maps each traded perp to the spot pair on the same base asset
+fn pair_coins(timeline: &Timeline) -> BTreeMap<String, String> {
+    let coins: BTreeSet<&str> = timeline
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            TimelineEvent::Fill { coin, .. } => Some(coin.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let perps: BTreeSet<&str> = coins
+        .iter()
+        .copied()
+        .filter(|coin| !coin.contains('/') && !coin.contains(':') && !coin.starts_with('@'))
+        .collect();
+
+    coins
+        .iter()
+        .filter_map(|spot| {
+            let base = spot.split_once('/')?.0;
+            let perp = if perps.contains(base) {
+                base
+            } else {
+                base.strip_prefix('U').filter(|b| perps.contains(b))?
+            };
+            Some((perp.to_string(), spot.to_string()))
+        })
+        .collect()
+}
+
+/// Spot size, perp size and spot cost basis of a pair
+fn legs(engine: &CostBasisEngine, perp: &str, spot: &str) -> (BigDecimal, BigDecimal, BigDecimal) {
+    let spot_position = engine.position(spot);
+    (
+        spot_position
+            .as_ref()
+            .map(|p| p.size.clone())
+            .unwrap_or_default(),
+        engine.position(perp).map(|p| p.size).unwrap_or_default(),
+        spot_position.map(|p| p.cost_basis).unwrap_or_default(),
+    )
+}
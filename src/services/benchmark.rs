@@ -0,0 +1,341 @@
+use bigdecimal::{BigDecimal, One, Zero};
+use chrono::{Datelike, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::error::{AppError, AppResult};
+use crate::services::market_data::Candle;
+use crate::services::pnl_calculator::DailyPnl;
+use crate::services::timeline::{Timeline, TimelineEvent};
+
+/// Decimal places kept for returns and ratios
+const RATIO_SCALE: i64 = 8;
+
+/// Daily statistics are annualized over calendar days, as crypto trades every day
+const DAYS_PER_YEAR: u32 = 365;
+
+/// How often the basket is reset to its target weights
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rebalance {
+    /// Weights drift with prices after the first day
+    None,
+    Daily,
+    /// On Mondays
+    Weekly,
+    /// On the first day of each month
+    #[default]
+    Monthly,
+}
+
+impl Rebalance {
+    fn is_due(&self, date: NaiveDate) -> bool {
+        match self {
+            Rebalance::None => false,
+            Rebalance::Daily => true,
+            Rebalance::Weekly => date.weekday() == chrono::Weekday::Mon,
+            Rebalance::Monthly => date.day() == 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkWeight {
+    pub coin: String,
+    pub weight: BigDecimal,
+}
+
+/// A static-weight basket of coins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkBasket {
+    pub weights: Vec<BenchmarkWeight>,
+    pub rebalance: Rebalance,
+}
+
+impl BenchmarkBasket {
+    /// Parses weights written as `BTC:0.6,ETH:0.4`, which must be positive and sum to 1
+    pub fn parse(weights: &str, rebalance: Rebalance) -> AppResult<Self> {
+        let weights = weights
+            .split(',')
+            .filter(|part| !part.trim().is_empty())
+            .map(|part| {
+                let (coin, weight) = part.split_once(':').ok_or_else(|| {
+                    AppError::ValidationError(format!(
+                        "Invalid weight {}; expected <coin>:<weight>",
+                        part
+                    ))
+                })?;
+                let weight = BigDecimal::from_str(weight.trim())
+                    .ok()
+                    .filter(|weight| *weight > BigDecimal::zero())
+                    .ok_or_else(|| {
+                        AppError::ValidationError(format!("Invalid weight for {}", coin))
+                    })?;
+                Ok(BenchmarkWeight {
+                    coin: coin.trim().to_string(),
+                    weight,
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+
+        if weights.is_empty() {
+            return Err(AppError::ValidationError(
+                "weights must name at least one coin".to_string(),
+            ));
+        }
+        let total = weights
+            .iter()
+            .fold(BigDecimal::zero(), |acc, w| acc + &w.weight);
+        if (total - BigDecimal::one()).abs() > BigDecimal::new(1.into(), 4) {
+            return Err(AppError::ValidationError(
+                "weights must sum to 1".to_string(),
+            ));
+        }
+
+        Ok(Self { weights, rebalance })
+    }
+}
+
+/// Wallet and benchmark performance over one UTC day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkDay {
+    pub date: String,
+    /// None when the wallet had no capital at the start of the day
+    pub wallet_return: Option<BigDecimal>,
+    pub benchmark_return: BigDecimal,
+    pub wallet_cumulative_return: BigDecimal,
+    pub benchmark_cumulative_return: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkComparison {
+    pub wallet: String,
+    pub basket: BenchmarkBasket,
+    pub start_date: String,
+    pub end_date: String,
+    pub wallet_total_return: BigDecimal,
+    pub benchmark_total_return: BigDecimal,
+    /// Annualized standard deviation of daily active returns
+    pub tracking_error: Option<BigDecimal>,
+    /// Annualized mean active return over tracking error
+    pub information_ratio: Option<BigDecimal>,
+    pub days: Vec<BenchmarkDay>,
+}
+
+/// Compares a wallet's equity curve with a static-weight benchmark basket.
+///
+/// The wallet's start-of-day equity is reconstructed backwards from its current account
+/// value, removing each later day's PnL and net deposits, so transfers do not count as
+/// returns. Daily PnL is realized, as in `/pnl/daily`.
+pub struct BenchmarkCalculator;
+
+impl BenchmarkCalculator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Compares returns for every day in `days`.
+    ///
+    /// `candles` holds each basket coin's candles over the range, sorted by time.
+    pub fn compare(
+        &self,
+        timeline: &Timeline,
+        daily_pnl: &[DailyPnl],
+        current_equity: &BigDecimal,
+        basket: BenchmarkBasket,
+        candles: &BTreeMap<String, Vec<Candle>>,
+        days: RangeInclusive<NaiveDate>,
+    ) -> AppResult<BenchmarkComparison> {
+        let (start, end) = days.into_inner();
+        let dates: Vec<NaiveDate> = start.iter_days().take_while(|d| *d <= end).collect();
+        let wallet_returns = wallet_returns(timeline, daily_pnl, current_equity, &dates);
+        let benchmark_returns = benchmark_returns(&basket, candles, &dates)?;
+
+        let one = BigDecimal::one();
+        let mut wallet_growth = BigDecimal::one();
+        let mut benchmark_growth = BigDecimal::one();
+        let mut active_returns = Vec::new();
+        let mut days = Vec::with_capacity(dates.len());
+
+        for ((date, wallet_return), benchmark_return) in
+            dates.iter().zip(wallet_returns).zip(benchmark_returns)
+        {
+            if let Some(wallet_return) = &wallet_return {
+                wallet_growth = (&wallet_growth * (&one + wallet_return)).round(RATIO_SCALE);
+                active_returns.push(wallet_return - &benchmark_return);
+            }
+            benchmark_growth = (&benchmark_growth * (&one + &benchmark_return)).round(RATIO_SCALE);
+
+            days.push(BenchmarkDay {
+                date: date.format("%Y-%m-%d").to_string(),
+                wallet_return,
+                benchmark_return,
+                wallet_cumulative_return: &wallet_growth - &one,
+                benchmark_cumulative_return: &benchmark_growth - &one,
+            });
+        }
+
+        let tracking_error = std_dev(&active_returns)
+            .zip(BigDecimal::from(DAYS_PER_YEAR).sqrt())
+            .map(|(sd, annualizer)| (sd * annualizer).round(RATIO_SCALE));
+        let information_ratio = match (mean(&active_returns), &tracking_error) {
+            (Some(mean), Some(te)) if !te.is_zero() => {
+                Some((mean * BigDecimal::from(DAYS_PER_YEAR) / te).round(RATIO_SCALE))
+            }
+            _ => None,
+        };
+
+        Ok(BenchmarkComparison {
+            wallet: timeline.wallet.clone(),
+            basket,
+            start_date: start.format("%Y-%m-%d").to_string(),
+            end_date: end.format("%Y-%m-%d").to_string(),
+            wallet_total_return: wallet_growth - &one,
+            benchmark_total_return: benchmark_growth - &one,
+            tracking_error,
+            information_ratio,
+            days,
+        })
+    }
+}
+
+impl Default for BenchmarkCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Daily PnL over start-of-day equity, walking back from today's equity
+fn wallet_returns(
+    timeline: &Timeline,
+    daily_pnl: &[DailyPnl],
+    current_equity: &BigDecimal,
+    dates: &[NaiveDate],
+) -> Vec<Option<BigDecimal>> {
+    let pnl: BTreeMap<&str, &BigDecimal> = daily_pnl
+        .iter()
+        .map(|day| (day.date.as_str(), &day.pnl))
+        .collect();
+
+    let mut net_flows: BTreeMap<String, BigDecimal> = BTreeMap::new();
+    for event in &timeline.events {
+        let (timestamp, amount) = match event {
+            TimelineEvent::Deposit {
+                timestamp, amount, ..
+            } => (timestamp, amount.clone()),
+            TimelineEvent::Withdrawal {
+                timestamp, amount, ..
+            } => (timestamp, -amount.clone()),
+            _ => continue,
+        };
+        let flow = net_flows
+            .entry(timestamp.format("%Y-%m-%d").to_string())
+            .or_default();
+        *flow = &*flow + amount;
+    }
+
+    let mut end_of_day = current_equity.clone();
+    let mut returns: Vec<Option<BigDecimal>> = dates
+        .iter()
+        .rev()
+        .map(|date| {
+            let key = date.format("%Y-%m-%d").to_string();
+            let day_pnl = pnl
+                .get(key.as_str())
+                .map_or_else(BigDecimal::zero, |p| (*p).clone());
+            let flow = net_flows.get(&key).cloned().unwrap_or_default();
+
+            let start_of_day = &end_of_day - &day_pnl - &flow;
+            end_of_day = start_of_day.clone();
+
+            (start_of_day > BigDecimal::zero()).then(|| (day_pnl / start_of_day).round(RATIO_SCALE))
+        })
+        .collect();
+    returns.reverse();
+    returns
+}
+
+/// Daily returns of the basket, rebalanced to its weights on schedule
+fn benchmark_returns(
+    basket: &BenchmarkBasket,
+    candles: &BTreeMap<String, Vec<Candle>>,
+    dates: &[NaiveDate],
+) -> AppResult<Vec<BigDecimal>> {
+    let now = Utc::now();
+    let closes: Vec<Vec<BigDecimal>> = basket
+        .weights
+        .iter()
+        .map(|w| {
+            let coin_candles = candles.get(&w.coin).map(Vec::as_slice).unwrap_or_default();
+            dates
+                .iter()
+                .map(|date| {
+                    let end_of_day = Utc
+                        .from_utc_datetime(&date.and_hms_opt(23, 59, 59).unwrap_or_default())
+                        .min(now);
+                    // The last close at or before the end of the day, bridging candle gaps
+                    coin_candles
+                        .iter()
+                        .rev()
+                        .find(|c| c.open_time <= end_of_day)
+                        .map(|c| c.close.clone())
+                        .filter(|close| *close > BigDecimal::zero())
+                        .ok_or_else(|| {
+                            AppError::ValidationError(format!("No {} price on {}", w.coin, date))
+                        })
+                })
+                .collect()
+        })
+        .collect::<AppResult<_>>()?;
+
+    let mut units: Vec<BigDecimal> = Vec::new();
+    let mut value = BigDecimal::one();
+    let mut returns = Vec::with_capacity(dates.len());
+
+    for (i, date) in dates.iter().enumerate() {
+        if units.is_empty() {
+            returns.push(BigDecimal::zero());
+        } else {
+            let today: BigDecimal = units
+                .iter()
+                .zip(&closes)
+                .fold(BigDecimal::zero(), |acc, (u, c)| acc + u * &c[i]);
+            returns.push(((&today - &value) / &value).round(RATIO_SCALE));
+            value = today;
+        }
+
+        if units.is_empty() || basket.rebalance.is_due(*date) {
+            units = basket
+                .weights
+                .iter()
+                .zip(&closes)
+                .map(|(w, c)| &value * &w.weight / &c[i])
+                .collect();
+        }
+    }
+
+    Ok(returns)
+}
+
+fn mean(values: &[BigDecimal]) -> Option<BigDecimal> {
+    if values.is_empty() {
+        return None;
+    }
+    let total = values.iter().fold(BigDecimal::zero(), |acc, v| acc + v);
+    Some(total / BigDecimal::from(values.len() as u64))
+}
+
+/// Sample standard deviation; needs at least two values
+fn std_dev(values: &[BigDecimal]) -> Option<BigDecimal> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = mean(values)?;
+    let squares = values.iter().fold(BigDecimal::zero(), |acc, v| {
+        let diff = v - &mean;
+        acc + &diff * &diff
+    });
+    (squares / BigDecimal::from(values.len() as u64 - 1)).sqrt()
+}
@@ -0,0 +1,179 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::services::positions::CostBasisEngine;
+use crate::services::timeline::{Timeline, TimelineEvent};
+
+/// Decimal places kept for averages and ratios
+const RATIO_SCALE: i64 = 8;
+
+/// How much of a wallet's capital was at work, and what it earned relative to it.
+///
+/// Equity is net deposits plus realized PnL, funding, fees and liquidation losses, so it
+/// excludes unrealized PnL. Deployed capital is the margin behind open positions: their
+/// notional at entry prices divided by each coin's current leverage, or 1x for coins without
+/// an open position to read it from. Averages are weighted by time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapitalEfficiency {
+    pub wallet: String,
+    pub period_start: Option<DateTime<Utc>>,
+    pub period_end: DateTime<Utc>,
+    pub net_pnl: BigDecimal,
+    pub average_equity: Option<BigDecimal>,
+    pub average_deployed_capital: Option<BigDecimal>,
+    /// Mean share of equity committed as margin, over time with positive equity
+    pub average_margin_utilization: Option<BigDecimal>,
+    /// Hours without any open position
+    pub idle_hours: BigDecimal,
+    /// Share of the period without any open position
+    pub idle_fraction: Option<BigDecimal>,
+    /// Net PnL over average equity
+    pub return_on_equity: Option<BigDecimal>,
+    /// Net PnL over average deployed capital
+    pub return_on_deployed_capital: Option<BigDecimal>,
+}
+
+/// Time-weighted sums over the intervals between events
+#[derive(Default)]
+struct Accumulator {
+    seconds: i64,
+    idle_seconds: i64,
+    equity: BigDecimal,
+    deployed: BigDecimal,
+    utilization: BigDecimal,
+    utilization_seconds: i64,
+}
+
+pub struct CapitalCalculator;
+
+impl CapitalCalculator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Replays the timeline, including ledger updates, up to `as_of`
+    pub fn calculate(
+        &self,
+        timeline: &Timeline,
+        leverage: &HashMap<String, BigDecimal>,
+        as_of: DateTime<Utc>,
+    ) -> CapitalEfficiency {
+        let mut engine = CostBasisEngine::new();
+        let mut equity = BigDecimal::zero();
+        let mut net_pnl = BigDecimal::zero();
+        let mut totals = Accumulator::default();
+
+        let period_start = timeline.events.first().map(|e| e.timestamp());
+        let mut events = timeline.events.iter().peekable();
+        while let Some(event) = events.next() {
+            engine.apply(event);
+            let pnl = match event {
+                TimelineEvent::Fill {
+                    realized_pnl, fee, ..
+                } => realized_pnl.clone().unwrap_or_default() - fee,
+                TimelineEvent::Funding { amount, .. } => amount.clone(),
+                TimelineEvent::Liquidation { loss, .. } => -loss.clone(),
+                TimelineEvent::Deposit { amount, .. } => {
+                    equity = &equity + amount;
+                    BigDecimal::zero()
+                }
+                TimelineEvent::Withdrawal { amount, .. } => {
+                    equity = &equity - amount;
+                    BigDecimal::zero()
+                }
+            };
+            equity = &equity + &pnl;
+            net_pnl = &net_pnl + &pnl;
+
+            let until = events
+                .peek()
+                .map(|next| next.timestamp())
+                .unwrap_or(as_of)
+                .max(event.timestamp());
+            let seconds = (until - event.timestamp()).num_seconds();
+            if seconds <= 0 {
+                continue;
+            }
+
+            let positions = engine.snapshot();
+            let deployed = positions
+                .iter()
+                .filter(|position| !position.size.is_zero())
+                .fold(BigDecimal::zero(), |acc, position| {
+                    let leverage = leverage
+                        .get(&position.coin)
+                        .filter(|leverage| **leverage > BigDecimal::zero())
+                        .cloned()
+                        .unwrap_or_else(|| BigDecimal::from(1));
+                    acc + &position.cost_basis / leverage
+                });
+            let weight = BigDecimal::from(seconds);
+
+            totals.seconds += seconds;
+            if deployed.is_zero() {
+                totals.idle_seconds += seconds;
+            }
+            totals.equity += &equity * &weight;
+            totals.deployed += &deployed * &weight;
+            if equity > BigDecimal::zero() {
+                totals.utilization += &deployed / &equity * &weight;
+                totals.utilization_seconds += seconds;
+            }
+        }
+
+        let average = |sum: &BigDecimal, seconds: i64| {
+            (seconds > 0).then(|| (sum / BigDecimal::from(seconds)).round(RATIO_SCALE))
+        };
+        let average_equity = average(&totals.equity, totals.seconds);
+        let average_deployed_capital = average(&totals.deployed, totals.seconds);
+        let ratio = |denominator: &Option<BigDecimal>| {
+            denominator
+                .as_ref()
+                .filter(|d| **d > BigDecimal::zero())
+                .map(|d| (&net_pnl / d).round(RATIO_SCALE))
+        };
+
+        CapitalEfficiency {
+            wallet: timeline.wallet.clone(),
+            period_start,
+            period_end: as_of,
+            return_on_equity: ratio(&average_equity),
+            return_on_deployed_capital: ratio(&average_deployed_capital),
+            net_pnl,
+            average_equity,
+            average_deployed_capital,
+            average_margin_utilization: average(&totals.utilization, totals.utilization_seconds),
+            idle_hours: (BigDecimal::from(totals.idle_seconds) / BigDecimal::from(3600))
+                .round(RATIO_SCALE),
+            idle_fraction: (totals.seconds > 0).then(|| {
+                (BigDecimal::from(totals.idle_seconds) / BigDecimal::from(totals.seconds))
+                    .round(RATIO_SCALE)
+            }),
+        }
+    }
+
+    /// Current leverage per coin from a clearinghouse state response
+    pub fn leverage_from_state(&self, user_state: &Value) -> HashMap<String, BigDecimal> {
+        user_state
+            .get("assetPositions")
+            .and_then(|positions| positions.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let position = entry.get("position")?;
+                let coin = position.get("coin")?.as_str()?;
+                let leverage = position.get("leverage")?.get("value")?.as_u64()?;
+                Some((coin.to_string(), BigDecimal::from(leverage)))
+            })
+            .collect()
+    }
+}
+
+impl Default for CapitalCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
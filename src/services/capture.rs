@@ -0,0 +1,293 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{rejection::QueryRejection, Query, Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::handlers::admin::AdminAuth;
+use crate::AppState;
+
+/// Placeholder for values dropped once a capture reaches its size limit
+const OMITTED: &str = "omitted: capture size limit reached";
+
+tokio::task_local! {
+    /// The capture collecting upstream calls and inputs for the current request, if any
+    static ACTIVE_CAPTURE: Arc<Mutex<CaptureRecorder>>;
+}
+
+/// Debug modes selectable with `?debug=`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DebugMode {
+    /// Store upstream payloads and computation inputs for later replay
+    Capture,
+    /// Report where the time behind the response went
+    Timing,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DebugOptions {
+    pub debug: Option<DebugMode>,
+}
+
+/// One upstream data source call made while serving a captured request
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedUpstream {
+    pub source: String,
+    pub call: String,
+    pub args: Value,
+    pub response: Option<Value>,
+    pub error: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// A named intermediate value the response was computed from
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedInput {
+    pub name: String,
+    pub value: Value,
+}
+
+/// Everything the server saw while serving a request made with `?debug=capture`
+#[derive(Debug, Clone, Serialize)]
+pub struct Capture {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub method: String,
+    pub uri: String,
+    pub status: u16,
+    pub upstream: Vec<CapturedUpstream>,
+    pub inputs: Vec<CapturedInput>,
+    /// The response body, as JSON when it parses and as text otherwise
+    pub response: Value,
+    /// Whether values were omitted to keep the capture within its size limit
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureSummary {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub method: String,
+    pub uri: String,
+    pub status: u16,
+    pub upstream_calls: usize,
+}
+
+struct CaptureRecorder {
+    upstream: Vec<CapturedUpstream>,
+    inputs: Vec<CapturedInput>,
+    /// Serialized bytes still allowed before values are omitted
+    remaining_bytes: usize,
+    truncated: bool,
+}
+
+impl CaptureRecorder {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            upstream: Vec::new(),
+            inputs: Vec::new(),
+            remaining_bytes: max_bytes,
+            truncated: false,
+        }
+    }
+
+    /// Keeps `value` if it fits in the remaining budget, or a placeholder otherwise
+    fn admit(&mut self, value: Value) -> Value {
+        let size = serde_json::to_vec(&value).map_or(usize::MAX, |bytes| bytes.len());
+        if size <= self.remaining_bytes {
+            self.remaining_bytes -= size;
+            value
+        } else {
+            self.truncated = true;
+            Value::String(OMITTED.to_string())
+        }
+    }
+}
+
+/// Records an upstream call if the current request is being captured
+pub fn record_upstream(source: &str, call: &str, args: Value, result: Result<Value, String>) {
+    let _ = ACTIVE_CAPTURE.try_with(|capture| {
+        let mut recorder = capture.lock().expect("capture lock poisoned");
+        let (response, error) = match result {
+            Ok(response) => (Some(recorder.admit(response)), None),
+            Err(error) => (None, Some(error)),
+        };
+        recorder.upstream.push(CapturedUpstream {
+            source: source.to_string(),
+            call: call.to_string(),
+            args,
+            response,
+            error,
+            at: Utc::now(),
+        });
+    });
+}
+
+/// Records a computation input if the current request is being captured.
+///
+/// The value is only built when a capture is active.
+pub fn record_input(name: &str, value: impl FnOnce() -> Value) {
+    let _ = ACTIVE_CAPTURE.try_with(|capture| {
+        let mut recorder = capture.lock().expect("capture lock poisoned");
+        let value = recorder.admit(value());
+        recorder.inputs.push(CapturedInput {
+            name: name.to_string(),
+            value,
+        });
+    });
+}
+
+/// Whether the current request is being captured
+pub fn is_capturing() -> bool {
+    ACTIVE_CAPTURE.try_with(|_| ()).is_ok()
+}
+
+/// Recent captures, oldest evicted first once `max_entries` is reached.
+///
+/// Each capture keeps at most `max_bytes` of serialized payloads; values past that are
+/// replaced with a placeholder.
+pub struct CaptureStore {
+    max_entries: usize,
+    max_bytes: usize,
+    captures: RwLock<VecDeque<Capture>>,
+}
+
+impl CaptureStore {
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            max_entries: max_entries.max(1),
+            max_bytes,
+            captures: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    fn insert(&self, capture: Capture) {
+        let mut captures = self.captures.write().expect("capture store lock poisoned");
+        while captures.len() >= self.max_entries {
+            captures.pop_front();
+        }
+        captures.push_back(capture);
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<Capture> {
+        self.captures
+            .read()
+            .expect("capture store lock poisoned")
+            .iter()
+            .find(|capture| capture.id == id)
+            .cloned()
+    }
+
+    /// Drops captures whose request or recorded data mentions `text`, ignoring case.
+    ///
+    /// Returns how many were removed.
+    pub fn remove_mentioning(&self, text: &str) -> usize {
+        let text = text.to_lowercase();
+        let mut captures = self.captures.write().expect("capture store lock poisoned");
+        let before = captures.len();
+        captures.retain(|capture| {
+            !serde_json::to_string(capture)
+                .unwrap_or_default()
+                .to_lowercase()
+                .contains(&text)
+        });
+        before - captures.len()
+    }
+
+    /// Lists captures, most recent first
+    pub fn list(&self) -> Vec<CaptureSummary> {
+        self.captures
+            .read()
+            .expect("capture store lock poisoned")
+            .iter()
+            .rev()
+            .map(|capture| CaptureSummary {
+                id: capture.id,
+                created_at: capture.created_at,
+                method: capture.method.clone(),
+                uri: capture.uri.clone(),
+                status: capture.status,
+                upstream_calls: capture.upstream.len(),
+            })
+            .collect()
+    }
+}
+
+/// Middleware capturing requests made with `?debug=capture`.
+///
+/// Captures hold whole upstream payloads, so they require admin credentials. The capture ID
+/// is returned in `X-Capture-Id`.
+pub async fn capture_requests(
+    State(state): State<AppState>,
+    admin: Result<AdminAuth, AppError>,
+    options: Result<Query<DebugOptions>, QueryRejection>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let options = match options {
+        Ok(Query(options)) => options,
+        Err(e) => return AppError::ValidationError(e.body_text()).into_response(),
+    };
+
+    if options.debug != Some(DebugMode::Capture) {
+        return next.run(request).await;
+    }
+    if let Err(e) = admin {
+        return e.into_response();
+    }
+    let store = &state.capture_store;
+
+    let id = Uuid::new_v4();
+    let created_at = Utc::now();
+    let method = request.method().to_string();
+    let uri = request.uri().to_string();
+
+    let recorder = Arc::new(Mutex::new(CaptureRecorder::new(store.max_bytes)));
+    let response = ACTIVE_CAPTURE
+        .scope(recorder.clone(), next.run(request))
+        .await;
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return AppError::InternalError(e.to_string()).into_response(),
+    };
+
+    let mut recorder = std::mem::replace(
+        &mut *recorder.lock().expect("capture lock poisoned"),
+        CaptureRecorder::new(0),
+    );
+    let response = recorder.admit(
+        serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned())),
+    );
+    store.insert(Capture {
+        id,
+        created_at,
+        method,
+        uri,
+        status: parts.status.as_u16(),
+        upstream: recorder.upstream,
+        inputs: recorder.inputs,
+        response,
+        truncated: recorder.truncated,
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&id.to_string()) {
+        parts
+            .headers
+            .insert(HeaderName::from_static("x-capture-id"), value);
+    }
+
+    Response::from_parts(parts, Body::from(bytes))
+}
@@ -0,0 +1,170 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::services::market_data::FundingRate;
+
+/// Decimal places kept for projected amounts and rates
+const PROJECTION_SCALE: i64 = 8;
+
+const HOURS_PER_YEAR: u32 = 24 * 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionSide {
+    Long,
+    Short,
+}
+
+/// A position held for a fixed time, to project funding for
+#[derive(Debug, Clone)]
+pub struct CarryPosition {
+    pub coin: String,
+    pub side: PositionSide,
+    pub size: BigDecimal,
+    pub price: BigDecimal,
+    pub duration_hours: u32,
+}
+
+/// Projected funding for one hourly rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarryScenario {
+    pub hourly_rate: BigDecimal,
+    /// Funding received over the holding period; negative is a cost
+    pub funding: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarryProjection {
+    pub coin: String,
+    pub side: PositionSide,
+    pub size: BigDecimal,
+    pub price: BigDecimal,
+    pub notional: BigDecimal,
+    pub duration_hours: u32,
+    pub history_start: Option<DateTime<Utc>>,
+    pub history_end: Option<DateTime<Utc>>,
+    pub samples: usize,
+    /// Typical spacing between settlements in the history
+    pub funding_interval_hours: Option<u32>,
+    /// Mean rate over the history, annualized; positive means longs pay shorts
+    pub annualized_rate: Option<BigDecimal>,
+    /// At the mean historical rate
+    pub expected: Option<CarryScenario>,
+    /// At the 10th and 90th percentile hourly rates, whichever is worse for the position
+    pub adverse: Option<CarryScenario>,
+    /// At the 10th and 90th percentile hourly rates, whichever is better for the position
+    pub favorable: Option<CarryScenario>,
+}
+
+/// Projects funding income or cost for a hypothetical position from recent funding rates.
+///
+/// Rates are normalized to hourly using the typical settlement spacing in the history, so
+/// venues settling hourly and every eight hours can be compared directly.
+pub struct CarrySimulator;
+
+impl CarrySimulator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn project(&self, position: &CarryPosition, rates: &[FundingRate]) -> CarryProjection {
+        let notional = &position.size * &position.price;
+        let interval_hours = settlement_interval_hours(rates);
+
+        let mut hourly_rates: Vec<BigDecimal> = match interval_hours {
+            Some(hours) => rates
+                .iter()
+                .map(|r| &r.rate / BigDecimal::from(hours))
+                .collect(),
+            None => Vec::new(),
+        };
+        hourly_rates.sort();
+
+        let scenario = |hourly_rate: BigDecimal| {
+            // Longs pay positive rates and receive negative ones
+            let paid = &notional * &hourly_rate * BigDecimal::from(position.duration_hours);
+            let funding = match position.side {
+                PositionSide::Long => -paid,
+                PositionSide::Short => paid,
+            };
+            CarryScenario {
+                hourly_rate: hourly_rate.round(PROJECTION_SCALE),
+                funding: funding.round(PROJECTION_SCALE),
+            }
+        };
+
+        let mean = (!hourly_rates.is_empty()).then(|| {
+            hourly_rates.iter().sum::<BigDecimal>() / BigDecimal::from(hourly_rates.len() as u64)
+        });
+        let percentile = |p: usize| {
+            (!hourly_rates.is_empty())
+                .then(|| hourly_rates[(hourly_rates.len() - 1) * p / 100].clone())
+        };
+
+        let (adverse, favorable) = match (percentile(10), percentile(90)) {
+            (Some(low), Some(high)) => {
+                let (low, high) = (scenario(low), scenario(high));
+                if low.funding <= high.funding {
+                    (Some(low), Some(high))
+                } else {
+                    (Some(high), Some(low))
+                }
+            }
+            _ => (None, None),
+        };
+
+        CarryProjection {
+            coin: position.coin.clone(),
+            side: position.side,
+            size: position.size.clone(),
+            price: position.price.clone(),
+            notional: notional.clone(),
+            duration_hours: position.duration_hours,
+            history_start: rates.first().map(|r| r.time),
+            history_end: rates.last().map(|r| r.time),
+            samples: rates.len(),
+            funding_interval_hours: interval_hours,
+            annualized_rate: mean
+                .as_ref()
+                .map(|m| (m * BigDecimal::from(HOURS_PER_YEAR)).round(PROJECTION_SCALE)),
+            expected: mean.map(scenario),
+            adverse,
+            favorable,
+        }
+    }
+
+    /// Mean rate over a funding history, normalized to hourly and annualized
+    pub fn annualized_rate(&self, rates: &[FundingRate]) -> Option<BigDecimal> {
+        let hours = BigDecimal::from(settlement_interval_hours(rates)?);
+        let total: BigDecimal = rates.iter().map(|r| &r.rate / &hours).sum();
+        Some(
+            (total * BigDecimal::from(HOURS_PER_YEAR) / BigDecimal::from(rates.len() as u64))
+                .round(PROJECTION_SCALE),
+        )
+    }
+}
+
+impl Default for CarrySimulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Median spacing between consecutive settlements, in whole hours (at least one)
+fn settlement_interval_hours(rates: &[FundingRate]) -> Option<u32> {
+    let mut gaps: Vec<i64> = rates
+        .windows(2)
+        .map(|pair| (pair[1].time - pair[0].time).num_minutes())
+        .filter(|minutes| *minutes > 0)
+        .collect();
+
+    if gaps.is_empty() {
+        // A single settlement gives no spacing; assume hourly
+        return (!rates.is_empty()).then_some(1);
+    }
+
+    gaps.sort_unstable();
+    let median_minutes = gaps[gaps.len() / 2];
+    Some(((median_minutes + 30) / 60).max(1) as u32)
+}
@@ -0,0 +1,294 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::services::assets::AssetRegistry;
+use crate::services::ingestion::IngestionService;
+use crate::services::market_data::Candle;
+use crate::services::pnl_calculator::CalculationMetadata;
+use crate::services::timeline::{Timeline, TimelineEvent, DEFAULT_COLLATERAL};
+
+/// Currency converted amounts are reported in; spot markets are quoted against it
+pub const REPORTING_CURRENCY: &str = DEFAULT_COLLATERAL;
+
+/// Decimal places kept for converted amounts
+const AMOUNT_SCALE: i64 = 8;
+
+/// PnL components and transfers denominated in one currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencyPnl {
+    pub currency: String,
+    pub realized_pnl: BigDecimal,
+    pub funding_pnl: BigDecimal,
+    #[serde(with = "crate::output::cost")]
+    pub fees: BigDecimal,
+    /// Realized plus funding minus fees
+    pub net_pnl: BigDecimal,
+    pub deposits: BigDecimal,
+    pub withdrawals: BigDecimal,
+}
+
+impl CurrencyPnl {
+    pub fn new(currency: &str) -> Self {
+        Self {
+            currency: currency.to_string(),
+            realized_pnl: BigDecimal::from(0),
+            funding_pnl: BigDecimal::from(0),
+            fees: BigDecimal::from(0),
+            net_pnl: BigDecimal::from(0),
+            deposits: BigDecimal::from(0),
+            withdrawals: BigDecimal::from(0),
+        }
+    }
+
+    pub fn add(&mut self, component: Component, amount: &BigDecimal) {
+        match component {
+            Component::RealizedPnl => {
+                self.realized_pnl = &self.realized_pnl + amount;
+                self.net_pnl = &self.net_pnl + amount;
+            }
+            Component::Funding => {
+                self.funding_pnl = &self.funding_pnl + amount;
+                self.net_pnl = &self.net_pnl + amount;
+            }
+            Component::Fee => {
+                self.fees = &self.fees + amount;
+                self.net_pnl = &self.net_pnl - amount;
+            }
+            Component::Deposit => self.deposits = &self.deposits + amount,
+            Component::Withdrawal => self.withdrawals = &self.withdrawals + amount,
+        }
+    }
+
+    fn rounded(mut self) -> Self {
+        for value in [
+            &mut self.realized_pnl,
+            &mut self.funding_pnl,
+            &mut self.fees,
+            &mut self.net_pnl,
+            &mut self.deposits,
+            &mut self.withdrawals,
+        ] {
+            *value = value.round(AMOUNT_SCALE);
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    RealizedPnl,
+    Funding,
+    Fee,
+    Deposit,
+    Withdrawal,
+}
+
+/// Splits an event into amounts by currency and PnL component
+pub fn components(event: &TimelineEvent) -> Vec<(&str, Component, &BigDecimal)> {
+    match event {
+        TimelineEvent::Fill {
+            coin,
+            fee,
+            fee_token,
+            realized_pnl,
+            ..
+        } => {
+            let mut parts = vec![(fee_token.as_str(), Component::Fee, fee)];
+            if let Some(pnl) = realized_pnl {
+                parts.push((
+                    settlement_currency(coin, fee_token),
+                    Component::RealizedPnl,
+                    pnl,
+                ));
+            }
+            parts
+        }
+        TimelineEvent::Funding { amount, token, .. } => {
+            vec![(token.as_str(), Component::Funding, amount)]
+        }
+        TimelineEvent::Deposit { amount, token, .. } => {
+            vec![(token.as_str(), Component::Deposit, amount)]
+        }
+        TimelineEvent::Withdrawal { amount, token, .. } => {
+            vec![(token.as_str(), Component::Withdrawal, amount)]
+        }
+        TimelineEvent::Liquidation { .. } => Vec::new(),
+    }
+}
+
+/// Spot PnL is realized in the quote token; perps settle in the collateral fees are paid in
+fn settlement_currency<'a>(coin: &'a str, fee_token: &'a str) -> &'a str {
+    coin.split_once('/')
+        .map(|(_, quote)| quote)
+        .unwrap_or(fee_token)
+}
+
+/// How amounts in a currency were converted to the reporting currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateSource {
+    /// Already the reporting currency
+    Reporting,
+    /// Configured to convert one to one, e.g. other dollar stablecoins
+    Par,
+    /// Spot candle close against the reporting currency at each event's time
+    Market,
+    /// No rate found; amounts are left out of the converted totals
+    Unpriced,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencyBreakdown {
+    pub currency: String,
+    pub rate_source: RateSource,
+    pub native: CurrencyPnl,
+    /// Native amounts converted at event-time rates
+    pub converted: CurrencyPnl,
+    /// Events with no rate at their time, left out of `converted`
+    pub unpriced_events: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollateralReport {
+    pub wallet: String,
+    pub reporting_currency: String,
+    pub currencies: Vec<CurrencyBreakdown>,
+    /// All currencies converted to the reporting currency
+    pub total: CurrencyPnl,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calculation: Option<CalculationMetadata>,
+}
+
+/// Converts multi-collateral PnL components to the reporting currency at event-time rates
+pub struct CollateralService {
+    ingestion_service: Arc<IngestionService>,
+    asset_registry: Arc<AssetRegistry>,
+    /// Tokens converted one to one
+    par_tokens: BTreeSet<String>,
+}
+
+impl CollateralService {
+    pub fn new(
+        ingestion_service: Arc<IngestionService>,
+        asset_registry: Arc<AssetRegistry>,
+        par_tokens: BTreeSet<String>,
+    ) -> Self {
+        Self {
+            ingestion_service,
+            asset_registry,
+            par_tokens,
+        }
+    }
+
+    pub async fn report(&self, wallet: &str, timeline: &Timeline) -> CollateralReport {
+        let currencies: BTreeSet<&str> = timeline
+            .events
+            .iter()
+            .flat_map(components)
+            .map(|(currency, _, _)| currency)
+            .collect();
+
+        let mut sources: HashMap<&str, RateSource> = HashMap::new();
+        let mut candles: HashMap<&str, Vec<Candle>> = HashMap::new();
+        for currency in currencies {
+            let source = if currency == REPORTING_CURRENCY {
+                RateSource::Reporting
+            } else if self.par_tokens.contains(currency) {
+                RateSource::Par
+            } else {
+                match self.fetch_rates(currency, timeline).await {
+                    Some(currency_candles) => {
+                        candles.insert(currency, currency_candles);
+                        RateSource::Market
+                    }
+                    None => RateSource::Unpriced,
+                }
+            };
+            sources.insert(currency, source);
+        }
+
+        let mut breakdowns: BTreeMap<&str, CurrencyBreakdown> = BTreeMap::new();
+        let mut total = CurrencyPnl::new(REPORTING_CURRENCY);
+
+        for event in &timeline.events {
+            for (currency, component, amount) in components(event) {
+                let source = sources
+                    .get(currency)
+                    .copied()
+                    .unwrap_or(RateSource::Unpriced);
+                let breakdown = breakdowns
+                    .entry(currency)
+                    .or_insert_with(|| CurrencyBreakdown {
+                        currency: currency.to_string(),
+                        rate_source: source,
+                        native: CurrencyPnl::new(currency),
+                        converted: CurrencyPnl::new(REPORTING_CURRENCY),
+                        unpriced_events: 0,
+                    });
+                breakdown.native.add(component, amount);
+
+                let rate = match source {
+                    RateSource::Reporting | RateSource::Par => Some(BigDecimal::from(1)),
+                    RateSource::Market => candles
+                        .get(currency)
+                        .and_then(|c| rate_at(c, event.timestamp())),
+                    RateSource::Unpriced => None,
+                };
+                match rate {
+                    Some(rate) => {
+                        let converted = amount * rate;
+                        breakdown.converted.add(component, &converted);
+                        total.add(component, &converted);
+                    }
+                    None => breakdown.unpriced_events += 1,
+                }
+            }
+        }
+
+        CollateralReport {
+            wallet: wallet.to_string(),
+            reporting_currency: REPORTING_CURRENCY.to_string(),
+            currencies: breakdowns
+                .into_values()
+                .map(|mut breakdown| {
+                    breakdown.converted = breakdown.converted.rounded();
+                    breakdown
+                })
+                .collect(),
+            total: total.rounded(),
+            calculation: None,
+        }
+    }
+
+    /// Fetches spot candles for `currency` against the reporting currency over the timeline
+    async fn fetch_rates(&self, currency: &str, timeline: &Timeline) -> Option<Vec<Candle>> {
+        let pair = format!("{}/{}", currency, REPORTING_CURRENCY);
+        let Some(coin) = self.asset_registry.spot_coin(&pair) else {
+            tracing::warn!("No {} spot market to price {} collateral", pair, currency);
+            return None;
+        };
+        let (start, end) = (timeline.from_timestamp?, timeline.to_timestamp?);
+
+        match self
+            .ingestion_service
+            .fetch_candles(&coin, start.timestamp_millis(), end.timestamp_millis())
+            .await
+        {
+            Ok(candles) if !candles.is_empty() => Some(candles),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("Failed to fetch {} rates: {}", pair, e);
+                None
+            }
+        }
+    }
+}
+
+/// Close of the latest candle opened at or before `timestamp`, assuming candles sorted by time
+fn rate_at(candles: &[Candle], timestamp: DateTime<Utc>) -> Option<BigDecimal> {
+    let index = candles.partition_point(|c| c.open_time <= timestamp);
+    index.checked_sub(1).map(|i| candles[i].close.clone())
+}
@@ -0,0 +1,263 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+use crate::storage::StoredHistory;
+
+/// Field set on a stored event that upstream has since restated
+const SUPERSEDED_FIELD: &str = "superseded";
+
+/// Fill fields whose change means the exchange restated the fill
+const FILL_FIELDS: [&str; 6] = ["coin", "side", "px", "sz", "fee", "closedPnl"];
+
+/// Funding fields whose change means the exchange restated the payment
+const FUNDING_FIELDS: [&str; 4] = ["coin", "usdc", "szi", "fundingRate"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestatementKind {
+    /// Upstream now reports different values for the event
+    Corrected,
+    /// Upstream no longer reports the event, e.g. a busted trade
+    Removed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventCategory {
+    Fill,
+    Funding,
+}
+
+/// Marker stored on the original version of a restated event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Supersession {
+    kind: RestatementKind,
+    detected_at: DateTime<Utc>,
+}
+
+/// One restated event, with the original and corrected upstream payloads.
+///
+/// Reports covering `event_time` that were produced before `detected_at` used the original.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Restatement {
+    pub kind: RestatementKind,
+    pub category: EventCategory,
+    /// Venue plus trade ID for fills, or venue, coin and time for funding
+    pub key: String,
+    pub event_time: Option<DateTime<Utc>>,
+    pub detected_at: DateTime<Utc>,
+    /// Fields whose values differ between the original and the correction
+    pub changed_fields: Vec<String>,
+    pub original: Value,
+    pub corrected: Option<Value>,
+}
+
+/// Merges a fresh upstream fetch into the previously stored events.
+///
+/// Events whose values changed, or that upstream stopped reporting within the time range the
+/// fetch covers, are kept in their original form marked as superseded, next to the current
+/// version if there is one. Previously superseded events, and events of venues the fetch
+/// returned nothing for, are carried over unchanged.
+pub fn merge_restated(
+    category: EventCategory,
+    previous: &[Value],
+    current: Vec<Value>,
+    detected_at: DateTime<Utc>,
+) -> Vec<Value> {
+    let current_keys: HashMap<String, &Value> = current
+        .iter()
+        .filter_map(|event| event_key(category, event).map(|key| (key, event)))
+        .collect();
+    // Upstream history may be truncated, so only events inside the range fetched from their
+    // venue can be removed. A venue that returned nothing, e.g. because it failed, keeps its
+    // events as they were.
+    let mut covered_from: HashMap<&str, i64> = HashMap::new();
+    for event in &current {
+        if let Some(time) = event_millis(event) {
+            covered_from
+                .entry(event_venue(event))
+                .and_modify(|from| *from = (*from).min(time))
+                .or_insert(time);
+        }
+    }
+
+    let mut superseded = Vec::new();
+    let mut carried = Vec::new();
+    for original in previous {
+        if is_superseded(original) {
+            superseded.push(original.clone());
+            continue;
+        }
+        let Some(key) = event_key(category, original) else {
+            continue;
+        };
+
+        let kind = match current_keys.get(&key) {
+            Some(corrected) if !changed_fields(category, original, corrected).is_empty() => {
+                RestatementKind::Corrected
+            }
+            Some(_) => continue,
+            None if !covered_from.contains_key(event_venue(original)) => {
+                carried.push(original.clone());
+                continue;
+            }
+            None if covered_from
+                .get(event_venue(original))
+                .is_some_and(|from| event_millis(original) >= Some(*from)) =>
+            {
+                RestatementKind::Removed
+            }
+            None => continue,
+        };
+
+        tracing::warn!("Upstream restated {:?} {}: {:?}", category, key, kind);
+        let mut original = original.clone();
+        if let Some(fields) = original.as_object_mut() {
+            fields.insert(
+                SUPERSEDED_FIELD.to_string(),
+                json!(Supersession { kind, detected_at }),
+            );
+        }
+        superseded.push(original);
+    }
+
+    let mut merged = current;
+    merged.extend(carried);
+    merged.extend(superseded);
+    merged.sort_by_key(event_millis);
+    merged
+}
+
+/// Adds back previously stored ledger updates of venues missing from a fresh fetch, so a venue
+/// that failed to answer does not lose its deposits and withdrawals
+pub fn keep_missing_venues(previous: &[Value], mut current: Vec<Value>) -> Vec<Value> {
+    let fetched: HashSet<&str> = current.iter().map(event_venue).collect();
+    let missing: Vec<Value> = previous
+        .iter()
+        .filter(|update| !fetched.contains(event_venue(update)))
+        .cloned()
+        .collect();
+    if missing.is_empty() {
+        return current;
+    }
+
+    current.extend(missing);
+    current.sort_by_key(event_millis);
+    current
+}
+
+/// Whether a stored event has been replaced by a restatement
+pub fn is_superseded(event: &Value) -> bool {
+    event.get(SUPERSEDED_FIELD).is_some()
+}
+
+/// Returns the history with superseded events dropped, as reports should see it
+pub fn active_history(mut history: StoredHistory) -> StoredHistory {
+    history.fills.retain(|event| !is_superseded(event));
+    history.funding.retain(|event| !is_superseded(event));
+    history
+}
+
+/// Lists restatements recorded in a stored history, most recently detected first
+pub fn restatements(history: &StoredHistory) -> Vec<Restatement> {
+    let mut restatements: Vec<Restatement> = [
+        (EventCategory::Fill, &history.fills),
+        (EventCategory::Funding, &history.funding),
+    ]
+    .into_iter()
+    .flat_map(|(category, events)| {
+        let current: HashMap<String, &Value> = events
+            .iter()
+            .filter(|event| !is_superseded(event))
+            .filter_map(|event| event_key(category, event).map(|key| (key, event)))
+            .collect();
+
+        events
+            .iter()
+            .filter_map(|event| {
+                let supersession: Supersession =
+                    serde_json::from_value(event.get(SUPERSEDED_FIELD)?.clone()).ok()?;
+                let key = event_key(category, event)?;
+                let corrected = current
+                    .get(&key)
+                    .filter(|_| supersession.kind == RestatementKind::Corrected)
+                    .map(|corrected| (*corrected).clone());
+
+                let mut original = event.clone();
+                if let Some(fields) = original.as_object_mut() {
+                    fields.remove(SUPERSEDED_FIELD);
+                }
+
+                Some(Restatement {
+                    kind: supersession.kind,
+                    category,
+                    event_time: event_millis(event).and_then(DateTime::from_timestamp_millis),
+                    detected_at: supersession.detected_at,
+                    changed_fields: corrected
+                        .as_ref()
+                        .map(|corrected| changed_fields(category, &original, corrected))
+                        .unwrap_or_default(),
+                    key,
+                    original,
+                    corrected,
+                })
+            })
+            .collect::<Vec<_>>()
+    })
+    .collect();
+
+    restatements.sort_by_key(|r| std::cmp::Reverse(r.detected_at));
+    restatements
+}
+
+/// Identifies an event across fetches, matching how the timeline derives event IDs
+fn event_venue(event: &Value) -> &str {
+    event
+        .get("venue")
+        .and_then(|v| v.as_str())
+        .unwrap_or("hyperliquid")
+}
+
+fn event_key(category: EventCategory, event: &Value) -> Option<String> {
+    let venue = event_venue(event);
+    let coin = event.get("coin").and_then(|c| c.as_str())?;
+    let time = event_millis(event)?;
+
+    let key = match category {
+        EventCategory::Fill => match event.get("tid") {
+            Some(Value::Number(tid)) => tid.to_string(),
+            Some(Value::String(tid)) => tid.clone(),
+            _ => format!(
+                "{}:{}:{}",
+                event
+                    .get("hash")
+                    .and_then(|h| h.as_str())
+                    .unwrap_or_default(),
+                coin,
+                time
+            ),
+        },
+        EventCategory::Funding => format!("{}:{}", coin, time),
+    };
+
+    Some(format!("{}:{}", venue, key))
+}
+
+fn event_millis(event: &Value) -> Option<i64> {
+    event.get("time").and_then(|t| t.as_i64())
+}
+
+fn changed_fields(category: EventCategory, original: &Value, corrected: &Value) -> Vec<String> {
+    let fields: &[&str] = match category {
+        EventCategory::Fill => &FILL_FIELDS,
+        EventCategory::Funding => &FUNDING_FIELDS,
+    };
+
+    fields
+        .iter()
+        .filter(|field| original.get(**field) != corrected.get(**field))
+        .map(|field| field.to_string())
+        .collect()
+}
@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::services::alerts::AlertService;
+use crate::services::capture::CaptureStore;
+use crate::services::ingestion::IngestionService;
+use crate::services::sharing::ShareService;
+
+/// What was removed when a wallet's data was deleted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletionReceipt {
+    pub id: Uuid,
+    pub wallet: String,
+    pub deleted_at: DateTime<Utc>,
+    /// Stored events by category, including superseded versions
+    pub fills: usize,
+    pub funding: usize,
+    pub ledger_updates: usize,
+    pub alert_rules: usize,
+    pub fired_alerts: usize,
+    pub dead_letters: usize,
+    /// Shared report snapshots, whose links stop working
+    pub shared_reports: usize,
+    /// Debug captures whose requests or recorded data mentioned the wallet
+    pub captures: usize,
+}
+
+/// Removes everything stored about a wallet on request
+pub struct DeletionService {
+    ingestion_service: Arc<IngestionService>,
+    alert_service: Arc<AlertService>,
+    capture_store: Arc<CaptureStore>,
+    share_service: Arc<ShareService>,
+}
+
+impl DeletionService {
+    pub fn new(
+        ingestion_service: Arc<IngestionService>,
+        alert_service: Arc<AlertService>,
+        capture_store: Arc<CaptureStore>,
+        share_service: Arc<ShareService>,
+    ) -> Self {
+        Self {
+            ingestion_service,
+            alert_service,
+            capture_store,
+            share_service,
+        }
+    }
+
+    /// Deletes a wallet's synced history, alerts, shared reports and captures.
+    ///
+    /// Alert rules go first so the evaluator stops syncing the wallet again. The deletion is
+    /// recorded, so configured hot wallets are not warmed and running backfills stop without
+    /// storing until a request syncs the wallet again; heat scores derive from the stored
+    /// history and go with it.
+    pub async fn forget_wallet(&self, wallet: &str) -> AppResult<DeletionReceipt> {
+        let (alert_rules, fired_alerts, dead_letters) =
+            self.alert_service.forget_wallet(wallet).await?;
+        let history = self.ingestion_service.forget_wallet(wallet).await?;
+        let shared_reports = self.share_service.forget_wallet(wallet).await?;
+        let captures = self.capture_store.remove_mentioning(wallet);
+
+        let receipt = DeletionReceipt {
+            id: Uuid::new_v4(),
+            wallet: wallet.to_lowercase(),
+            deleted_at: Utc::now(),
+            fills: history.as_ref().map_or(0, |h| h.fills.len()),
+            funding: history.as_ref().map_or(0, |h| h.funding.len()),
+            ledger_updates: history.as_ref().map_or(0, |h| h.ledger.len()),
+            alert_rules,
+            fired_alerts,
+            dead_letters,
+            shared_reports,
+            captures,
+        };
+        tracing::info!("Deleted stored data for wallet {} ({})", wallet, receipt.id);
+
+        Ok(receipt)
+    }
+}
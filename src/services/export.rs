@@ -0,0 +1,162 @@
+use bigdecimal::BigDecimal;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::output::SignConvention;
+use crate::services::trades::RoundTrip;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Languages whose accounting software expects a decimal comma
+const DECIMAL_COMMA_LANGUAGES: &[&str] = &[
+    "bg", "cs", "da", "de", "el", "es", "fi", "fr", "hr", "hu", "id", "it", "lt", "lv", "nb", "nl",
+    "no", "pl", "pt", "ro", "ru", "sk", "sl", "sv", "tr", "uk",
+];
+
+/// Locale-dependent formatting applied to exported files
+#[derive(Debug, Clone)]
+pub struct ExportFormat {
+    pub decimal_separator: char,
+    /// Field delimiter; locales with a decimal comma use `;`
+    pub delimiter: u8,
+    pub date_format: String,
+}
+
+impl ExportFormat {
+    /// Builds a format from a BCP 47 locale (e.g. `de-DE`) and a date format.
+    ///
+    /// The date format is a preset (`iso`, `us`, `eu`) or a strftime pattern.
+    pub fn new(locale: Option<&str>, date_format: Option<&str>) -> AppResult<Self> {
+        let language = locale
+            .map(|l| {
+                l.split(['-', '_'])
+                    .next()
+                    .unwrap_or_default()
+                    .to_lowercase()
+            })
+            .unwrap_or_default();
+        let decimal_comma = DECIMAL_COMMA_LANGUAGES.contains(&language.as_str());
+
+        let date_format = match date_format {
+            None => DEFAULT_DATE_FORMAT.to_string(),
+            Some("iso") => "%Y-%m-%dT%H:%M:%SZ".to_string(),
+            Some("us") => "%m/%d/%Y %H:%M:%S".to_string(),
+            Some("eu") => "%d.%m.%Y %H:%M:%S".to_string(),
+            Some(pattern) => {
+                if StrftimeItems::new(pattern).any(|item| matches!(item, Item::Error)) {
+                    return Err(AppError::ValidationError(format!(
+                        "Invalid date format: {}",
+                        pattern
+                    )));
+                }
+                pattern.to_string()
+            }
+        };
+
+        Ok(Self {
+            decimal_separator: if decimal_comma { ',' } else { '.' },
+            delimiter: if decimal_comma { b';' } else { b',' },
+            date_format,
+        })
+    }
+
+    fn decimal(&self, value: &BigDecimal) -> String {
+        let s = value.to_plain_string();
+        if self.decimal_separator == '.' {
+            s
+        } else {
+            s.replace('.', &self.decimal_separator.to_string())
+        }
+    }
+
+    fn optional_decimal(&self, value: Option<&BigDecimal>) -> String {
+        value.map(|v| self.decimal(v)).unwrap_or_default()
+    }
+
+    fn time(&self, time: Option<DateTime<Utc>>) -> String {
+        time.map(|t| t.format(&self.date_format).to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// One trade-journal row, column names follow common journal import templates
+#[derive(Debug, Serialize)]
+struct JournalRow {
+    #[serde(rename = "Symbol")]
+    symbol: String,
+    #[serde(rename = "Side")]
+    side: String,
+    #[serde(rename = "Entry Time")]
+    entry_time: String,
+    #[serde(rename = "Entry Price")]
+    entry_price: String,
+    #[serde(rename = "Exit Time")]
+    exit_time: String,
+    #[serde(rename = "Exit Price")]
+    exit_price: String,
+    #[serde(rename = "Size")]
+    size: String,
+    #[serde(rename = "PnL")]
+    pnl: String,
+    #[serde(rename = "Fees")]
+    fees: String,
+    #[serde(rename = "Funding")]
+    funding: String,
+    #[serde(rename = "Net PnL")]
+    net_pnl: String,
+    #[serde(rename = "Tags")]
+    tags: String,
+    #[serde(rename = "Notes")]
+    notes: String,
+}
+
+pub struct ExportService;
+
+impl ExportService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Renders round trips as a trade-journal CSV (Edgewonk/Tradervue style).
+    ///
+    /// Times are UTC. Tags and notes are left empty for the journal tool to fill in.
+    pub fn journal_csv(&self, trips: &[RoundTrip], format: &ExportFormat) -> AppResult<String> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(format.delimiter)
+            .from_writer(Vec::new());
+
+        for trip in trips {
+            writer
+                .serialize(JournalRow {
+                    symbol: trip.coin.clone(),
+                    side: trip.direction.clone(),
+                    entry_time: format.time(trip.entry_time),
+                    entry_price: format.optional_decimal(trip.entry_price.as_ref()),
+                    exit_time: format.time(trip.exit_time),
+                    exit_price: format.optional_decimal(trip.exit_price.as_ref()),
+                    size: format.decimal(&trip.size),
+                    pnl: format.decimal(&trip.realized_pnl),
+                    fees: format.decimal(&SignConvention::current().cost(&trip.fees)),
+                    funding: format.decimal(&trip.funding),
+                    net_pnl: format.decimal(&trip.net_pnl),
+                    tags: String::new(),
+                    notes: String::new(),
+                })
+                .map_err(|e| AppError::InternalError(format!("CSV export failed: {}", e)))?;
+        }
+
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| AppError::InternalError(format!("CSV export failed: {}", e)))?;
+
+        String::from_utf8(bytes).map_err(|e| AppError::InternalError(e.to_string()))
+    }
+}
+
+impl Default for ExportService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
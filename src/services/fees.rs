@@ -0,0 +1,324 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::str::FromStr;
+
+use crate::error::{AppError, AppResult};
+use crate::services::timeline::{Timeline, TimelineEvent};
+
+/// Tiers are set by traded volume over this many trailing days
+const VOLUME_WINDOW_DAYS: i64 = 14;
+
+/// Decimal places kept for simulated fees
+const FEE_SCALE: i64 = 8;
+
+/// Hyperliquid's perp base schedule as (14-day volume, taker rate, maker rate)
+const BUILTIN_TIERS: [(&str, &str, &str); 7] = [
+    ("0", "0.00045", "0.00015"),
+    ("5000000", "0.0004", "0.00012"),
+    ("25000000", "0.00035", "0.00008"),
+    ("100000000", "0.0003", "0.00004"),
+    ("500000000", "0.00028", "0"),
+    ("2000000000", "0.00026", "0"),
+    ("7000000000", "0.00024", "0"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeTier {
+    /// Trailing 14-day volume at which the tier starts
+    pub min_volume: BigDecimal,
+    /// Fraction of notional charged when adding liquidity; negative is a rebate
+    pub maker_rate: BigDecimal,
+    /// Fraction of notional charged when taking liquidity
+    pub taker_rate: BigDecimal,
+}
+
+/// Fee tiers in force from `effective_from` until the next version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub effective_from: DateTime<Utc>,
+    /// Ordered by `min_volume`
+    pub tiers: Vec<FeeTier>,
+}
+
+impl FeeSchedule {
+    /// Index of the highest tier whose volume threshold is reached
+    fn tier_for(&self, volume: &BigDecimal) -> usize {
+        self.tiers
+            .iter()
+            .rposition(|tier| tier.min_volume <= *volume)
+            .unwrap_or(0)
+    }
+}
+
+/// Versioned fee schedules, looked up by fill time
+#[derive(Debug, Clone)]
+pub struct FeeScheduleTable {
+    /// Ordered by `effective_from`
+    schedules: Vec<FeeSchedule>,
+}
+
+impl FeeScheduleTable {
+    pub fn new(mut schedules: Vec<FeeSchedule>) -> AppResult<Self> {
+        if schedules.is_empty() || schedules.iter().any(|s| s.tiers.is_empty()) {
+            return Err(AppError::ValidationError(
+                "Fee schedule table needs at least one version, each with tiers".to_string(),
+            ));
+        }
+
+        schedules.sort_by_key(|s| s.effective_from);
+        for schedule in &mut schedules {
+            schedule
+                .tiers
+                .sort_by(|a, b| a.min_volume.cmp(&b.min_volume));
+        }
+
+        Ok(Self { schedules })
+    }
+
+    /// Loads versions from a JSON array of schedules
+    pub fn load(path: &str) -> AppResult<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            AppError::InternalError(format!("Failed to read fee schedule file {}: {}", path, e))
+        })?;
+        Self::new(serde_json::from_str(&contents)?)
+    }
+
+    /// Today's Hyperliquid perp schedule, applied to all of history
+    pub fn builtin() -> Self {
+        let decimal = |value: &str| BigDecimal::from_str(value).expect("valid builtin rate");
+
+        Self {
+            schedules: vec![FeeSchedule {
+                effective_from: DateTime::UNIX_EPOCH,
+                tiers: BUILTIN_TIERS
+                    .iter()
+                    .map(|(min_volume, taker_rate, maker_rate)| FeeTier {
+                        min_volume: decimal(min_volume),
+                        maker_rate: decimal(maker_rate),
+                        taker_rate: decimal(taker_rate),
+                    })
+                    .collect(),
+            }],
+        }
+    }
+
+    pub fn schedules(&self) -> &[FeeSchedule] {
+        &self.schedules
+    }
+
+    /// The version in force at `timestamp`, or the earliest for older fills
+    pub fn schedule_at(&self, timestamp: DateTime<Utc>) -> &FeeSchedule {
+        self.schedules
+            .iter()
+            .rev()
+            .find(|s| s.effective_from <= timestamp)
+            .unwrap_or(&self.schedules[0])
+    }
+}
+
+/// Fills simulated under one schedule version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleUsage {
+    pub effective_from: DateTime<Utc>,
+    pub fill_count: usize,
+    pub volume: BigDecimal,
+    pub actual_fees: BigDecimal,
+    pub scheduled_fees: BigDecimal,
+}
+
+/// Fills simulated at one tier index
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TierUsage {
+    pub fill_count: usize,
+    pub volume: BigDecimal,
+    pub scheduled_fees: BigDecimal,
+}
+
+/// The tier the wallet's recent volume reaches under the current schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TierProjection {
+    pub tier: usize,
+    pub rolling_volume: BigDecimal,
+    pub maker_rate: BigDecimal,
+    pub taker_rate: BigDecimal,
+    /// Additional volume needed to reach the next tier, if there is one
+    pub volume_to_next_tier: Option<BigDecimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeSimulation {
+    pub wallet: String,
+    /// Tier every fill was simulated at, when pinned; otherwise each fill's volume tier
+    pub pinned_tier: Option<usize>,
+    pub fill_count: usize,
+    pub volume: BigDecimal,
+    pub actual_fees: BigDecimal,
+    pub scheduled_fees: BigDecimal,
+    /// Actual minus scheduled fees
+    pub difference: BigDecimal,
+    pub by_schedule: Vec<ScheduleUsage>,
+    pub by_tier: BTreeMap<usize, TierUsage>,
+    pub current_tier: TierProjection,
+}
+
+/// Recomputes perp fees with the schedule in force at each fill.
+///
+/// Only Hyperliquid perp fills are simulated; spot and other venues have their own schedules.
+/// A fill's tier comes from the wallet's volume over the preceding 14 days, so the timeline
+/// should start 14 days before `from` for tiers to be accurate.
+pub struct FeeSimulator {
+    table: FeeScheduleTable,
+}
+
+impl FeeSimulator {
+    pub fn new(table: FeeScheduleTable) -> Self {
+        Self { table }
+    }
+
+    pub fn table(&self) -> &FeeScheduleTable {
+        &self.table
+    }
+
+    pub fn simulate(
+        &self,
+        wallet: &str,
+        timeline: &Timeline,
+        from: Option<DateTime<Utc>>,
+        pinned_tier: Option<usize>,
+        now: DateTime<Utc>,
+    ) -> FeeSimulation {
+        let window = Duration::days(VOLUME_WINDOW_DAYS);
+        let zero = BigDecimal::from(0);
+
+        let mut recent: VecDeque<(DateTime<Utc>, BigDecimal)> = VecDeque::new();
+        let mut rolling_volume = zero.clone();
+        let mut volume = zero.clone();
+        let mut actual_fees = zero.clone();
+        let mut scheduled_fees = zero.clone();
+        let mut fill_count = 0;
+        let mut by_schedule: BTreeMap<DateTime<Utc>, ScheduleUsage> = BTreeMap::new();
+        let mut by_tier: BTreeMap<usize, TierUsage> = BTreeMap::new();
+
+        for event in &timeline.events {
+            let TimelineEvent::Fill {
+                timestamp,
+                coin,
+                size,
+                price,
+                fee,
+                crossed,
+                ..
+            } = event
+            else {
+                continue;
+            };
+            if !is_perp(coin) {
+                continue;
+            }
+
+            while recent
+                .front()
+                .is_some_and(|(t, _)| *t <= *timestamp - window)
+            {
+                let (_, expired) = recent.pop_front().expect("non-empty window");
+                rolling_volume = &rolling_volume - expired;
+            }
+            let notional = size * price;
+
+            if from.is_none_or(|from| *timestamp >= from) {
+                let schedule = self.table.schedule_at(*timestamp);
+                let tier = pinned_tier
+                    .unwrap_or_else(|| schedule.tier_for(&rolling_volume))
+                    .min(schedule.tiers.len() - 1);
+                let rates = &schedule.tiers[tier];
+                let rate = if *crossed {
+                    &rates.taker_rate
+                } else {
+                    &rates.maker_rate
+                };
+                let scheduled = &notional * rate;
+
+                fill_count += 1;
+                volume = &volume + &notional;
+                actual_fees = &actual_fees + fee;
+                scheduled_fees = &scheduled_fees + &scheduled;
+
+                let usage = by_schedule
+                    .entry(schedule.effective_from)
+                    .or_insert_with(|| ScheduleUsage {
+                        effective_from: schedule.effective_from,
+                        fill_count: 0,
+                        volume: zero.clone(),
+                        actual_fees: zero.clone(),
+                        scheduled_fees: zero.clone(),
+                    });
+                usage.fill_count += 1;
+                usage.volume = &usage.volume + &notional;
+                usage.actual_fees = &usage.actual_fees + fee;
+                usage.scheduled_fees = &usage.scheduled_fees + &scheduled;
+
+                let tier_usage = by_tier.entry(tier).or_default();
+                tier_usage.fill_count += 1;
+                tier_usage.volume = &tier_usage.volume + &notional;
+                tier_usage.scheduled_fees = &tier_usage.scheduled_fees + &scheduled;
+            }
+
+            rolling_volume = &rolling_volume + &notional;
+            recent.push_back((*timestamp, notional));
+        }
+
+        let current_volume: BigDecimal = recent
+            .iter()
+            .filter(|(t, _)| *t > now - window)
+            .map(|(_, notional)| notional)
+            .sum();
+
+        FeeSimulation {
+            wallet: wallet.to_string(),
+            pinned_tier,
+            fill_count,
+            volume,
+            difference: (&actual_fees - &scheduled_fees).round(FEE_SCALE),
+            actual_fees,
+            scheduled_fees: scheduled_fees.round(FEE_SCALE),
+            by_schedule: by_schedule
+                .into_values()
+                .map(|mut usage| {
+                    usage.scheduled_fees = usage.scheduled_fees.round(FEE_SCALE);
+                    usage
+                })
+                .collect(),
+            by_tier: by_tier
+                .into_iter()
+                .map(|(tier, mut usage)| {
+                    usage.scheduled_fees = usage.scheduled_fees.round(FEE_SCALE);
+                    (tier, usage)
+                })
+                .collect(),
+            current_tier: self.project_tier(current_volume, now),
+        }
+    }
+
+    fn project_tier(&self, rolling_volume: BigDecimal, now: DateTime<Utc>) -> TierProjection {
+        let schedule = self.table.schedule_at(now);
+        let tier = schedule.tier_for(&rolling_volume);
+
+        TierProjection {
+            tier,
+            maker_rate: schedule.tiers[tier].maker_rate.clone(),
+            taker_rate: schedule.tiers[tier].taker_rate.clone(),
+            volume_to_next_tier: schedule
+                .tiers
+                .get(tier + 1)
+                .map(|next| &next.min_volume - &rolling_volume),
+            rolling_volume,
+        }
+    }
+}
+
+/// Hyperliquid perps are named without a spot quote or venue prefix
+fn is_perp(coin: &str) -> bool {
+    !coin.contains('/') && !coin.contains(':') && !coin.starts_with('@')
+}
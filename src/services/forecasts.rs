@@ -0,0 +1,244 @@
+use bigdecimal::{BigDecimal, Signed, Zero};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::error::AppResult;
+use crate::services::funding_scanner::{FundingOpportunity, HYPERLIQUID_VENUE};
+use crate::services::ingestion::IngestionService;
+use crate::storage::Storage;
+
+/// Decimal places kept for error statistics
+const ERROR_SCALE: i64 = 10;
+
+/// How far a realized funding entry may be from the predicted settlement time and match it
+const MATCH_TOLERANCE_MINUTES: i64 = 5;
+
+/// The last Hyperliquid funding rate predicted for one settlement, and the rate it settled at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingPrediction {
+    pub coin: String,
+    pub funding_time: DateTime<Utc>,
+    pub predicted_at: DateTime<Utc>,
+    pub predicted_rate: BigDecimal,
+    /// Set once the settlement has happened and its rate was found in funding history
+    pub realized_rate: Option<BigDecimal>,
+}
+
+/// How closely predictions matched realized rates, per settlement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForecastAccuracy {
+    /// Settlements with both a prediction and a realized rate
+    pub settlements: usize,
+    /// Mean of realized minus predicted; positive means predictions ran low
+    pub mean_error: Option<BigDecimal>,
+    pub mean_absolute_error: Option<BigDecimal>,
+    pub root_mean_squared_error: Option<BigDecimal>,
+    /// Share of settlements where the prediction had the realized sign (who pays whom)
+    pub direction_accuracy: Option<BigDecimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoinForecastAccuracy {
+    pub coin: String,
+    #[serde(flatten)]
+    pub accuracy: ForecastAccuracy,
+}
+
+/// Accuracy of the predicted funding shown by the scanner, against what settled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForecastAccuracyReport {
+    pub first_settlement: Option<DateTime<Utc>>,
+    pub last_settlement: Option<DateTime<Utc>>,
+    /// Past settlements whose realized rate could not be found yet
+    pub unresolved: usize,
+    pub overall: ForecastAccuracy,
+    pub coins: Vec<CoinForecastAccuracy>,
+}
+
+/// Records funding predictions as they are polled and scores them once they settle
+pub struct ForecastTracker {
+    ingestion_service: Arc<IngestionService>,
+    storage: Arc<dyn Storage>,
+}
+
+impl ForecastTracker {
+    pub fn new(ingestion_service: Arc<IngestionService>, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            ingestion_service,
+            storage,
+        }
+    }
+
+    /// Stores each coin's predicted Hyperliquid rate for its next settlement, replacing any
+    /// earlier prediction for the same settlement
+    pub async fn record(
+        &self,
+        opportunities: &[FundingOpportunity],
+        predicted_at: DateTime<Utc>,
+    ) -> AppResult<()> {
+        let predictions: Vec<FundingPrediction> = opportunities
+            .iter()
+            .filter_map(|opportunity| {
+                let funding = opportunity.venues.get(HYPERLIQUID_VENUE)?;
+                Some(FundingPrediction {
+                    coin: opportunity.coin.clone(),
+                    funding_time: funding.next_funding_time?,
+                    predicted_at,
+                    predicted_rate: funding.rate.clone(),
+                    realized_rate: None,
+                })
+            })
+            .collect();
+
+        if predictions.is_empty() {
+            return Ok(());
+        }
+        self.storage.save_funding_predictions(predictions).await
+    }
+
+    /// Looks up realized rates for settled predictions, then scores every resolved one
+    pub async fn report(
+        &self,
+        coin: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> AppResult<ForecastAccuracyReport> {
+        let mut predictions: Vec<FundingPrediction> = self
+            .storage
+            .list_funding_predictions()
+            .await?
+            .into_iter()
+            .filter(|p| p.funding_time <= now)
+            .filter(|p| coin.is_none_or(|coin| p.coin == coin))
+            .collect();
+
+        self.resolve(&mut predictions).await?;
+
+        let unresolved = predictions
+            .iter()
+            .filter(|p| p.realized_rate.is_none())
+            .count();
+        let resolved: Vec<&FundingPrediction> = predictions
+            .iter()
+            .filter(|p| p.realized_rate.is_some())
+            .collect();
+
+        let mut by_coin: BTreeMap<&str, Vec<&FundingPrediction>> = BTreeMap::new();
+        for prediction in &resolved {
+            by_coin
+                .entry(prediction.coin.as_str())
+                .or_default()
+                .push(prediction);
+        }
+
+        Ok(ForecastAccuracyReport {
+            first_settlement: resolved.iter().map(|p| p.funding_time).min(),
+            last_settlement: resolved.iter().map(|p| p.funding_time).max(),
+            unresolved,
+            overall: accuracy(&resolved),
+            coins: by_coin
+                .into_iter()
+                .map(|(coin, predictions)| CoinForecastAccuracy {
+                    coin: coin.to_string(),
+                    accuracy: accuracy(&predictions),
+                })
+                .collect(),
+        })
+    }
+
+    /// Fills in realized rates from funding history, storing those found
+    async fn resolve(&self, predictions: &mut [FundingPrediction]) -> AppResult<()> {
+        let tolerance = Duration::minutes(MATCH_TOLERANCE_MINUTES);
+        let mut windows: BTreeMap<String, (DateTime<Utc>, DateTime<Utc>)> = BTreeMap::new();
+        for prediction in predictions.iter().filter(|p| p.realized_rate.is_none()) {
+            let window = windows
+                .entry(prediction.coin.clone())
+                .or_insert((prediction.funding_time, prediction.funding_time));
+            window.0 = window.0.min(prediction.funding_time);
+            window.1 = window.1.max(prediction.funding_time);
+        }
+
+        let mut resolved = Vec::new();
+        for (coin, (start, end)) in windows {
+            let rates = match self
+                .ingestion_service
+                .fetch_funding_rates(
+                    &coin,
+                    (start - tolerance).timestamp_millis(),
+                    (end + tolerance).timestamp_millis(),
+                )
+                .await
+            {
+                Ok(rates) => rates,
+                Err(e) => {
+                    tracing::warn!("Failed to fetch funding history for {}: {}", coin, e);
+                    continue;
+                }
+            };
+
+            for prediction in predictions
+                .iter_mut()
+                .filter(|p| p.coin == coin && p.realized_rate.is_none())
+            {
+                prediction.realized_rate = rates
+                    .iter()
+                    .filter(|rate| (rate.time - prediction.funding_time).abs() <= tolerance)
+                    .min_by_key(|rate| (rate.time - prediction.funding_time).abs())
+                    .map(|rate| rate.rate.clone());
+                if prediction.realized_rate.is_some() {
+                    resolved.push(prediction.clone());
+                }
+            }
+        }
+
+        if resolved.is_empty() {
+            return Ok(());
+        }
+        self.storage.save_funding_predictions(resolved).await
+    }
+}
+
+fn accuracy(predictions: &[&FundingPrediction]) -> ForecastAccuracy {
+    let errors: Vec<(BigDecimal, bool)> = predictions
+        .iter()
+        .filter_map(|p| {
+            let realized = p.realized_rate.as_ref()?;
+            let same_sign = realized.signum() == p.predicted_rate.signum();
+            Some((realized - &p.predicted_rate, same_sign))
+        })
+        .collect();
+
+    if errors.is_empty() {
+        return ForecastAccuracy {
+            settlements: 0,
+            mean_error: None,
+            mean_absolute_error: None,
+            root_mean_squared_error: None,
+            direction_accuracy: None,
+        };
+    }
+
+    let count = BigDecimal::from(errors.len() as u64);
+    let mean = |values: BigDecimal| (values / &count).round(ERROR_SCALE);
+    let sum = errors
+        .iter()
+        .fold(BigDecimal::zero(), |acc, (error, _)| acc + error);
+    let absolute = errors
+        .iter()
+        .fold(BigDecimal::zero(), |acc, (error, _)| acc + error.abs());
+    let squared = errors
+        .iter()
+        .fold(BigDecimal::zero(), |acc, (error, _)| acc + error * error);
+    let matching = errors.iter().filter(|(_, same_sign)| *same_sign).count();
+
+    ForecastAccuracy {
+        settlements: errors.len(),
+        mean_error: Some(mean(sum)),
+        mean_absolute_error: Some(mean(absolute)),
+        root_mean_squared_error: (squared / &count)
+            .sqrt()
+            .map(|rmse| rmse.round(ERROR_SCALE)),
+        direction_accuracy: Some(mean(BigDecimal::from(matching as u64))),
+    }
+}
@@ -0,0 +1,259 @@
+use bigdecimal::{BigDecimal, Signed, ToPrimitive, Zero};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::services::market_data::FundingRate;
+use crate::services::timeline::{Timeline, TimelineEvent};
+
+/// Decimal places kept for rates and bin edges
+const RATE_SCALE: i64 = 10;
+
+/// One bin of rates, counting settlements the wallet held and market settlements alike
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateBin {
+    pub lower: BigDecimal,
+    pub upper: BigDecimal,
+    pub experienced: usize,
+    pub market: usize,
+}
+
+/// Funding rates a wallet settled at in one coin, against the market on the same days.
+///
+/// Rates are per settlement and signed so that positive means the wallet's side paid:
+/// the venue rate for long positions and its negation for shorts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoinFundingComparison {
+    pub coin: String,
+    /// Funding payments with a known position size
+    pub settlements: usize,
+    /// Days with at least one such payment
+    pub days: usize,
+    /// Mean rate at the wallet's settlements, weighted by position size
+    pub experienced_rate: Option<BigDecimal>,
+    /// Mean market rate of each settlement's day, with the same weights and sides
+    pub market_rate: Option<BigDecimal>,
+    /// Experienced minus market rate; positive means the wallet held through pricier
+    /// funding windows than the days it held on averaged
+    pub excess_rate: Option<BigDecimal>,
+    /// Experienced rates against every market settlement on the days held, signed by the
+    /// wallet's net side on the day
+    pub histogram: Vec<RateBin>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingRateComparison {
+    pub wallet: String,
+    pub coins: Vec<CoinFundingComparison>,
+}
+
+/// A funding payment reduced to what the comparison needs
+struct Settlement {
+    date: NaiveDate,
+    /// Venue rate signed so positive means the wallet paid
+    paid_rate: BigDecimal,
+    weight: BigDecimal,
+    /// +1 for long, -1 for short
+    side: BigDecimal,
+}
+
+/// Compares the funding rates a wallet experienced with market funding over the same days
+pub struct FundingComparator;
+
+impl FundingComparator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Per coin, the epoch-millisecond range of market funding history the comparison needs
+    pub fn market_windows(&self, timeline: &Timeline) -> BTreeMap<String, (i64, i64)> {
+        let mut windows: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+        for (coin, settlement) in settlements(timeline) {
+            let start = settlement
+                .date
+                .and_hms_opt(0, 0, 0)
+                .expect("midnight")
+                .and_utc();
+            let end = start + Duration::days(1);
+            let window = windows
+                .entry(coin)
+                .or_insert((start.timestamp_millis(), end.timestamp_millis()));
+            window.0 = window.0.min(start.timestamp_millis());
+            window.1 = window.1.max(end.timestamp_millis());
+        }
+        windows
+    }
+
+    /// Compares each coin's settlements with `market` funding history for that coin
+    pub fn compare(
+        &self,
+        timeline: &Timeline,
+        market: &HashMap<String, Vec<FundingRate>>,
+        bins: usize,
+    ) -> FundingRateComparison {
+        let mut by_coin: BTreeMap<String, Vec<Settlement>> = BTreeMap::new();
+        for (coin, settlement) in settlements(timeline) {
+            by_coin.entry(coin).or_default().push(settlement);
+        }
+
+        let coins = by_coin
+            .into_iter()
+            .map(|(coin, settlements)| {
+                let rates = market.get(&coin).map(Vec::as_slice).unwrap_or_default();
+                compare_coin(coin, &settlements, rates, bins)
+            })
+            .collect();
+
+        FundingRateComparison {
+            wallet: timeline.wallet.clone(),
+            coins,
+        }
+    }
+}
+
+impl Default for FundingComparator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn settlements(timeline: &Timeline) -> impl Iterator<Item = (String, Settlement)> + '_ {
+    timeline.events.iter().filter_map(|event| {
+        let TimelineEvent::Funding {
+            timestamp,
+            coin,
+            funding_rate,
+            position_size: Some(size),
+            ..
+        } = event
+        else {
+            return None;
+        };
+        if size.is_zero() {
+            return None;
+        }
+
+        let side = BigDecimal::from(if size.is_positive() { 1 } else { -1 });
+        Some((
+            coin.clone(),
+            Settlement {
+                date: timestamp.date_naive(),
+                paid_rate: funding_rate * &side,
+                weight: size.abs(),
+                side,
+            },
+        ))
+    })
+}
+
+fn compare_coin(
+    coin: String,
+    settlements: &[Settlement],
+    market: &[FundingRate],
+    bins: usize,
+) -> CoinFundingComparison {
+    let mut market_by_day: BTreeMap<NaiveDate, Vec<&BigDecimal>> = BTreeMap::new();
+    for rate in market {
+        market_by_day
+            .entry(rate.time.date_naive())
+            .or_default()
+            .push(&rate.rate);
+    }
+    let day_means: BTreeMap<NaiveDate, BigDecimal> = market_by_day
+        .iter()
+        .map(|(date, rates)| {
+            let sum = rates.iter().fold(BigDecimal::zero(), |acc, r| acc + *r);
+            (*date, sum / BigDecimal::from(rates.len() as u64))
+        })
+        .collect();
+
+    let mut experienced = (BigDecimal::zero(), BigDecimal::zero());
+    let mut versus_market = (BigDecimal::zero(), BigDecimal::zero());
+    let mut net_side: BTreeMap<NaiveDate, BigDecimal> = BTreeMap::new();
+    for settlement in settlements {
+        experienced.0 += &settlement.paid_rate * &settlement.weight;
+        experienced.1 += &settlement.weight;
+        if let Some(mean) = day_means.get(&settlement.date) {
+            versus_market.0 += mean * &settlement.side * &settlement.weight;
+            versus_market.1 += &settlement.weight;
+        }
+        *net_side.entry(settlement.date).or_default() += &settlement.side * &settlement.weight;
+    }
+
+    let weighted_mean = |(sum, weight): (BigDecimal, BigDecimal)| {
+        (!weight.is_zero()).then(|| (sum / weight).round(RATE_SCALE))
+    };
+    let experienced_rate = weighted_mean(experienced);
+    let market_rate = weighted_mean(versus_market);
+
+    let held_market: Vec<BigDecimal> = net_side
+        .iter()
+        .filter(|(_, side)| !side.is_zero())
+        .flat_map(|(date, side)| {
+            let sign = BigDecimal::from(if side.is_positive() { 1 } else { -1 });
+            market_by_day
+                .get(date)
+                .into_iter()
+                .flatten()
+                .map(move |rate| *rate * &sign)
+        })
+        .collect();
+    let experienced_rates: Vec<BigDecimal> =
+        settlements.iter().map(|s| s.paid_rate.clone()).collect();
+
+    CoinFundingComparison {
+        coin,
+        settlements: settlements.len(),
+        days: net_side.len(),
+        excess_rate: match (&experienced_rate, &market_rate) {
+            (Some(experienced), Some(market)) => Some(experienced - market),
+            _ => None,
+        },
+        experienced_rate,
+        market_rate,
+        histogram: shared_histogram(&experienced_rates, &held_market, bins),
+    }
+}
+
+/// Equal-width bins spanning both series, so their counts can be overlaid
+fn shared_histogram(
+    experienced: &[BigDecimal],
+    market: &[BigDecimal],
+    bins: usize,
+) -> Vec<RateBin> {
+    let (Some(min), Some(max)) = (
+        experienced.iter().chain(market).min(),
+        experienced.iter().chain(market).max(),
+    ) else {
+        return Vec::new();
+    };
+
+    let bins = if min == max { 1 } else { bins.max(1) };
+    let width = (max - min) / BigDecimal::from(bins as u64);
+    let index = |value: &BigDecimal| {
+        if width.is_zero() {
+            return 0;
+        }
+        ((value - min) / &width)
+            .with_scale(0)
+            .to_usize()
+            .unwrap_or(0)
+            .min(bins - 1)
+    };
+
+    let mut histogram: Vec<RateBin> = (0..bins)
+        .map(|i| RateBin {
+            lower: (min + &width * BigDecimal::from(i as u64)).round(RATE_SCALE),
+            upper: (min + &width * BigDecimal::from(i as u64 + 1)).round(RATE_SCALE),
+            experienced: 0,
+            market: 0,
+        })
+        .collect();
+    for value in experienced {
+        histogram[index(value)].experienced += 1;
+    }
+    for value in market {
+        histogram[index(value)].market += 1;
+    }
+    histogram
+}
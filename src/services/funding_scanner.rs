@@ -0,0 +1,234 @@
+use bigdecimal::{BigDecimal, Signed, Zero};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use crate::services::carry::PositionSide;
+
+/// Decimal places kept for rates and yields
+const YIELD_SCALE: i64 = 8;
+
+const HOURS_PER_YEAR: u32 = 24 * 365;
+
+/// Venue name Hyperliquid uses for its own perps in predicted fundings
+pub const HYPERLIQUID_VENUE: &str = "HlPerp";
+
+/// How scanner results are ranked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScannerSort {
+    /// Hyperliquid funding against a spot hedge
+    #[default]
+    Carry,
+    /// Largest funding spread between two venues
+    Spread,
+}
+
+/// A venue's next predicted funding for a coin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueFunding {
+    pub rate: BigDecimal,
+    pub interval_hours: u32,
+    /// Positive means longs pay shorts
+    pub annualized_rate: BigDecimal,
+    pub next_funding_time: Option<DateTime<Utc>>,
+}
+
+/// Long the perp on the cheaper venue and short it on the dearer one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingSpread {
+    pub long_venue: String,
+    pub short_venue: String,
+    pub annualized_yield: BigDecimal,
+}
+
+/// The wallet's current perp position in a coin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletExposure {
+    pub side: PositionSide,
+    pub size: BigDecimal,
+    pub notional: BigDecimal,
+    /// Whether the position receives the predicted Hyperliquid funding
+    pub receives_funding: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingOpportunity {
+    pub coin: String,
+    /// Annualized yield of holding spot against the Hyperliquid perp on the receiving side
+    pub carry_yield: Option<BigDecimal>,
+    /// Perp side that receives funding in the carry trade
+    pub carry_side: Option<PositionSide>,
+    pub best_spread: Option<FundingSpread>,
+    pub venues: BTreeMap<String, VenueFunding>,
+    /// Mean realized Hyperliquid funding over the trailing window, annualized
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trailing_annualized_rate: Option<BigDecimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposure: Option<WalletExposure>,
+}
+
+impl FundingOpportunity {
+    fn score(&self, sort: ScannerSort) -> BigDecimal {
+        match sort {
+            ScannerSort::Carry => self.carry_yield.clone(),
+            ScannerSort::Spread => self
+                .best_spread
+                .as_ref()
+                .map(|s| s.annualized_yield.clone()),
+        }
+        .unwrap_or_default()
+    }
+}
+
+/// Ranks coins by the yield of delta-neutral funding trades, from predicted funding rates
+pub struct FundingScanner;
+
+impl FundingScanner {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Ranks every coin with a predicted rate, best first.
+    ///
+    /// `user_state`, when given, marks coins the wallet already holds a perp position in.
+    pub fn scan(
+        &self,
+        predicted: &Value,
+        user_state: Option<&Value>,
+        sort: ScannerSort,
+    ) -> Vec<FundingOpportunity> {
+        let exposures = user_state.map(exposures_from_state).unwrap_or_default();
+
+        let mut opportunities: Vec<FundingOpportunity> = predicted
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let coin = entry.get(0)?.as_str()?;
+                let venues: BTreeMap<String, VenueFunding> = entry
+                    .get(1)?
+                    .as_array()?
+                    .iter()
+                    .filter_map(|venue| {
+                        let name = venue.get(0)?.as_str()?;
+                        Some((name.to_string(), parse_venue_funding(venue.get(1)?)?))
+                    })
+                    .collect();
+                (!venues.is_empty()).then(|| opportunity(coin, venues, &exposures))
+            })
+            .collect();
+
+        opportunities.sort_by_key(|o| std::cmp::Reverse(o.score(sort)));
+        opportunities
+    }
+}
+
+impl Default for FundingScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn opportunity(
+    coin: &str,
+    venues: BTreeMap<String, VenueFunding>,
+    exposures: &HashMap<String, (BigDecimal, BigDecimal)>,
+) -> FundingOpportunity {
+    let hyperliquid = venues.get(HYPERLIQUID_VENUE);
+    let carry_side = hyperliquid
+        .filter(|funding| !funding.annualized_rate.is_zero())
+        .map(|funding| {
+            if funding.annualized_rate.is_positive() {
+                PositionSide::Short
+            } else {
+                PositionSide::Long
+            }
+        });
+
+    let cheapest = venues
+        .iter()
+        .min_by(|a, b| a.1.annualized_rate.cmp(&b.1.annualized_rate));
+    let dearest = venues
+        .iter()
+        .max_by(|a, b| a.1.annualized_rate.cmp(&b.1.annualized_rate));
+    let best_spread = match (cheapest, dearest) {
+        (Some((long, low)), Some((short, high))) if long != short => Some(FundingSpread {
+            long_venue: long.clone(),
+            short_venue: short.clone(),
+            annualized_yield: (&high.annualized_rate - &low.annualized_rate).round(YIELD_SCALE),
+        }),
+        _ => None,
+    };
+
+    let exposure = exposures.get(coin).map(|(size, notional)| {
+        let side = if size.is_positive() {
+            PositionSide::Long
+        } else {
+            PositionSide::Short
+        };
+        WalletExposure {
+            receives_funding: carry_side == Some(side),
+            side,
+            size: size.clone(),
+            notional: notional.clone(),
+        }
+    });
+
+    FundingOpportunity {
+        coin: coin.to_string(),
+        carry_yield: hyperliquid.map(|funding| funding.annualized_rate.abs()),
+        carry_side,
+        best_spread,
+        venues,
+        trailing_annualized_rate: None,
+        exposure,
+    }
+}
+
+fn parse_venue_funding(value: &Value) -> Option<VenueFunding> {
+    let rate = value
+        .get("fundingRate")
+        .and_then(|r| r.as_str())
+        .and_then(|r| BigDecimal::from_str(r).ok())?;
+    let interval_hours = value
+        .get("fundingIntervalHours")
+        .and_then(|h| h.as_u64())
+        .filter(|h| *h > 0)
+        .map_or(1, |h| h as u32);
+
+    Some(VenueFunding {
+        annualized_rate: (&rate * BigDecimal::from(HOURS_PER_YEAR / interval_hours))
+            .round(YIELD_SCALE),
+        rate,
+        interval_hours,
+        next_funding_time: value
+            .get("nextFundingTime")
+            .and_then(|t| t.as_i64())
+            .and_then(DateTime::from_timestamp_millis),
+    })
+}
+
+/// Signed size and notional of each open perp position, by coin
+fn exposures_from_state(user_state: &Value) -> HashMap<String, (BigDecimal, BigDecimal)> {
+    user_state
+        .get("assetPositions")
+        .and_then(|positions| positions.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|p| {
+            let position = p.get("position")?;
+            let decimal = |key: &str| {
+                position
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .and_then(|v| BigDecimal::from_str(v).ok())
+            };
+            let size = decimal("szi").filter(|size| !size.is_zero())?;
+            let coin = position.get("coin")?.as_str()?.to_string();
+            Some((coin, (size, decimal("positionValue").unwrap_or_default())))
+        })
+        .collect()
+}
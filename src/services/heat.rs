@@ -0,0 +1,151 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::error::AppResult;
+use crate::services::corrections;
+use crate::storage::{Storage, StoredHistory};
+
+/// Daily volume, in USD, worth one point of heat score, the same as one trade per day
+const VOLUME_PER_POINT: i64 = 10_000;
+
+/// Decimal places kept for rates and scores
+const SCORE_SCALE: i64 = 4;
+
+/// How actively a stored wallet trades, from fills in the trailing window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletHeat {
+    pub wallet: String,
+    pub synced_at: DateTime<Utc>,
+    pub last_trade_at: Option<DateTime<Utc>>,
+    pub trades_per_day: BigDecimal,
+    pub volume_per_day: BigDecimal,
+    /// Trades per day plus daily volume in units of 10,000 USD
+    pub score: BigDecimal,
+    /// No trades within the dormancy threshold; background syncs run rarely
+    pub dormant: bool,
+}
+
+/// Heat of every stored wallet, hottest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatReport {
+    pub window_days: i64,
+    pub dormant_after_days: i64,
+    pub dormant_sync_secs: i64,
+    pub active: usize,
+    pub dormant: usize,
+    pub wallets: Vec<WalletHeat>,
+}
+
+/// Scores wallet activity from stored history and decides when dormant wallets are resynced
+pub struct HeatTracker {
+    storage: Arc<dyn Storage>,
+    window: Duration,
+    dormant_after: Duration,
+    dormant_sync_interval: Duration,
+}
+
+impl HeatTracker {
+    pub fn new(
+        storage: Arc<dyn Storage>,
+        window_days: i64,
+        dormant_after_days: i64,
+        dormant_sync_secs: i64,
+    ) -> Self {
+        Self {
+            storage,
+            window: Duration::days(window_days.max(1)),
+            dormant_after: Duration::days(dormant_after_days.max(0)),
+            dormant_sync_interval: Duration::seconds(dormant_sync_secs.max(0)),
+        }
+    }
+
+    /// Scores every wallet with stored history
+    pub async fn report(&self, now: DateTime<Utc>) -> AppResult<HeatReport> {
+        let mut wallets = Vec::new();
+        for wallet in self.storage.list_history_wallets().await? {
+            if let Some(stored) = self.storage.load_history(&wallet).await? {
+                wallets.push(self.score(&wallet, corrections::active_history(stored), now));
+            }
+        }
+        wallets.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.wallet.cmp(&b.wallet)));
+
+        let dormant = wallets.iter().filter(|heat| heat.dormant).count();
+        Ok(HeatReport {
+            window_days: self.window.num_days(),
+            dormant_after_days: self.dormant_after.num_days(),
+            dormant_sync_secs: self.dormant_sync_interval.num_seconds(),
+            active: wallets.len() - dormant,
+            dormant,
+            wallets,
+        })
+    }
+
+    /// Whether a background sync of the wallet should run now.
+    ///
+    /// Wallets without stored history and active wallets are always due; dormant wallets
+    /// only once their last sync is older than the dormant sync interval.
+    pub async fn sync_due(&self, wallet: &str, now: DateTime<Utc>) -> AppResult<bool> {
+        let key = wallet.to_lowercase();
+        let Some(stored) = self.storage.load_history(&key).await? else {
+            return Ok(true);
+        };
+
+        let heat = self.score(&key, corrections::active_history(stored), now);
+        Ok(!heat.dormant || now - heat.synced_at >= self.dormant_sync_interval)
+    }
+
+    fn score(&self, wallet: &str, history: StoredHistory, now: DateTime<Utc>) -> WalletHeat {
+        let window_start = now - self.window;
+        let mut last_trade_at: Option<DateTime<Utc>> = None;
+        let mut trades: u64 = 0;
+        let mut volume = BigDecimal::zero();
+
+        for fill in &history.fills {
+            let Some(time) = fill_time(fill) else {
+                continue;
+            };
+            last_trade_at = last_trade_at.max(Some(time));
+            if time < window_start || time > now {
+                continue;
+            }
+
+            trades += 1;
+            if let (Some(size), Some(price)) = (decimal(fill, "sz"), decimal(fill, "px")) {
+                volume += size * price;
+            }
+        }
+
+        let days = BigDecimal::from(self.window.num_days());
+        let trades_per_day = (BigDecimal::from(trades) / &days).round(SCORE_SCALE);
+        let volume_per_day = (volume / &days).round(SCORE_SCALE);
+        let score = (&trades_per_day + &volume_per_day / BigDecimal::from(VOLUME_PER_POINT))
+            .round(SCORE_SCALE);
+
+        WalletHeat {
+            wallet: wallet.to_string(),
+            synced_at: history.synced_at,
+            dormant: last_trade_at.is_none_or(|last| now - last > self.dormant_after),
+            last_trade_at,
+            trades_per_day,
+            volume_per_day,
+            score,
+        }
+    }
+}
+
+fn fill_time(fill: &Value) -> Option<DateTime<Utc>> {
+    fill.get("time")
+        .and_then(|t| t.as_i64())
+        .and_then(DateTime::from_timestamp_millis)
+}
+
+fn decimal(value: &Value, key: &str) -> Option<BigDecimal> {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .and_then(|v| BigDecimal::from_str(v).ok())
+}
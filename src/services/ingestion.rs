@@ -1,16 +1,90 @@
-use serde_json::Value;
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 
 use crate::datasource::DataSource;
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
+use crate::services::anomalies::AnomalyDetector;
+use crate::services::capture;
+use crate::services::corrections::{self, EventCategory, Restatement};
+use crate::services::heat::HeatTracker;
+use crate::services::market_data::{Candle, CandleInterval, FundingRate};
+use crate::storage::{Storage, StoredHistory, StoredRawPayloads};
+
+/// Longest a caller waits for another sync of the same wallet before giving up
+const WALLET_LOCK_WAIT_SECS: u64 = 600;
+
+/// Version of the logic deriving stored events from upstream payloads, such as anomaly
+/// flags and restatement tracking.
+///
+/// Bump it when that logic, or timeline parsing that stored aggregates depend on, changes:
+/// wallets stored with an older version are then re-derived from their raw payloads.
+pub const NORMALIZATION_VERSION: u32 = 1;
+
+/// How fresh the data behind a response must be
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Freshness {
+    /// Fetch from upstream before responding
+    #[default]
+    Blocking,
+    /// Serve stored data immediately and refresh it in the background
+    StaleOk,
+}
+
+/// Fills and funding for a wallet, with where they came from
+#[derive(Debug, Clone)]
+pub struct WalletHistory {
+    pub fills: Vec<Value>,
+    pub funding: Vec<Value>,
+    /// Deposits, withdrawals and other non-trading balance changes
+    pub ledger: Vec<Value>,
+    pub synced_at: DateTime<Utc>,
+    /// True when served from storage instead of a fresh upstream fetch
+    pub stale: bool,
+    /// `NORMALIZATION_VERSION` the history was derived with
+    pub normalization_version: u32,
+}
+
+/// Candles fetched for anomaly checks of one coin
+struct CachedCandles {
+    interval: CandleInterval,
+    start_time: i64,
+    candles: Vec<Candle>,
+}
 
 pub struct IngestionService {
     datasource: Arc<dyn DataSource>,
+    storage: Arc<dyn Storage>,
+    anomaly_detector: AnomalyDetector,
+    /// Candles of earlier anomaly checks by coin, so later syncs only fetch newer ones
+    anomaly_candles: Mutex<HashMap<String, CachedCandles>>,
+    refreshing: Mutex<HashSet<String>>,
+    /// Serializes syncs and other writes of a wallet's history within this process
+    wallet_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// Whether full syncs also keep the upstream responses for reprocessing
+    store_raw_payloads: bool,
 }
 
 impl IngestionService {
-    pub fn new(datasource: Arc<dyn DataSource>) -> Self {
-        Self { datasource }
+    pub fn new(
+        datasource: Arc<dyn DataSource>,
+        storage: Arc<dyn Storage>,
+        anomaly_detector: AnomalyDetector,
+        store_raw_payloads: bool,
+    ) -> Self {
+        Self {
+            datasource,
+            storage,
+            anomaly_detector,
+            anomaly_candles: Mutex::new(HashMap::new()),
+            refreshing: Mutex::new(HashSet::new()),
+            wallet_locks: Mutex::new(HashMap::new()),
+            store_raw_payloads,
+        }
     }
 
     /// Fetches all fills for a wallet, handling the 500 item pagination limit
@@ -29,6 +103,554 @@ impl IngestionService {
         Ok(funding)
     }
 
+    /// Fetches deposits, withdrawals and other ledger updates for a wallet
+    pub async fn fetch_all_ledger_updates(
+        &self,
+        wallet: &str,
+        since: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        tracing::info!("Fetching ledger updates for wallet: {}", wallet);
+        let updates = self.datasource.get_ledger_updates(wallet, since).await?;
+        tracing::info!("Fetched {} ledger updates", updates.len());
+        Ok(updates)
+    }
+
+    /// Fetches fills and funding, serving stored data when the caller accepts staleness.
+    ///
+    /// With `Freshness::StaleOk` and a previously synced wallet, stored data is returned at
+    /// once and a background refresh is started. Otherwise data is fetched from upstream, and
+    /// full-history fetches are written back to storage.
+    pub async fn fetch_history(
+        self: &Arc<Self>,
+        wallet: &str,
+        since: Option<i64>,
+        freshness: Freshness,
+    ) -> AppResult<WalletHistory> {
+        let history = self.load_history(wallet, since, freshness).await?;
+
+        capture::record_input("wallet_history", || {
+            json!({
+                "wallet": wallet,
+                "since": since,
+                "fills": history.fills,
+                "funding": history.funding,
+                "ledger": history.ledger,
+                "synced_at": history.synced_at,
+                "stale": history.stale,
+            })
+        });
+
+        Ok(history)
+    }
+
+    /// Fetches history without recording it as a capture input
+    async fn load_history(
+        self: &Arc<Self>,
+        wallet: &str,
+        since: Option<i64>,
+        freshness: Freshness,
+    ) -> AppResult<WalletHistory> {
+        if freshness == Freshness::StaleOk
+            && let Some(stored) = self.storage.load_history(&storage_key(wallet)).await?
+        {
+            self.spawn_refresh(wallet);
+            let stored = corrections::active_history(stored);
+
+            return Ok(WalletHistory {
+                fills: filter_since(stored.fills, since),
+                funding: filter_since(stored.funding, since),
+                ledger: filter_since(stored.ledger, since),
+                synced_at: stored.synced_at,
+                stale: true,
+                normalization_version: stored.normalization_version,
+            });
+        }
+
+        if since.is_none() {
+            let stored = self.sync_wallet(wallet).await?;
+            return Ok(WalletHistory {
+                fills: stored.fills,
+                funding: stored.funding,
+                ledger: stored.ledger,
+                synced_at: stored.synced_at,
+                stale: false,
+                normalization_version: stored.normalization_version,
+            });
+        }
+
+        let mut fills = self.fetch_all_fills(wallet, since).await?;
+        let mut funding = self.fetch_all_funding(wallet, since).await?;
+        self.flag_anomalies(&mut fills, &mut funding).await;
+        let ledger = self.fetch_all_ledger_updates(wallet, since).await?;
+
+        Ok(WalletHistory {
+            fills,
+            funding,
+            ledger,
+            synced_at: Utc::now(),
+            stale: false,
+            normalization_version: NORMALIZATION_VERSION,
+        })
+    }
+
+    /// Fetches a wallet's full history from upstream and stores it.
+    ///
+    /// Events restated since the previous sync stay in storage marked as superseded; the
+    /// returned history holds only current versions. A wallet is synced one caller at a time:
+    /// a caller that waited on another's sync gets its result instead of syncing again.
+    ///
+    /// A wallet deleted on request is tracked again once a request syncs it.
+    pub async fn sync_wallet(&self, wallet: &str) -> AppResult<StoredHistory> {
+        let requested_at = Utc::now();
+
+        self.with_wallet_lock(wallet, || async {
+            let stored = self.sync_locked(wallet, requested_at).await?;
+            self.storage
+                .clear_wallet_deletion(&storage_key(wallet))
+                .await?;
+            Ok(stored)
+        })
+        .await
+    }
+
+    /// Syncs a wallet in the background, unless it was deleted on request and no request has
+    /// synced it since
+    pub async fn warm_wallet(&self, wallet: &str) -> AppResult<Option<StoredHistory>> {
+        let requested_at = Utc::now();
+
+        self.with_wallet_lock(wallet, || async {
+            let key = storage_key(wallet);
+            if self.storage.load_wallet_deletion(&key).await?.is_some() {
+                return Ok(None);
+            }
+            self.sync_locked(wallet, requested_at).await.map(Some)
+        })
+        .await
+    }
+
+    /// Syncs a wallet whose lock the caller holds
+    async fn sync_locked(
+        &self,
+        wallet: &str,
+        requested_at: DateTime<Utc>,
+    ) -> AppResult<StoredHistory> {
+        if let Some(stored) = self.storage.load_history(&storage_key(wallet)).await?
+            && stored.synced_at >= requested_at
+        {
+            tracing::debug!("Wallet {} was synced while waiting", wallet);
+            return Ok(corrections::active_history(stored));
+        }
+
+        let fills = self.fetch_all_fills(wallet, None).await?;
+        let funding = self.fetch_all_funding(wallet, None).await?;
+        let ledger = self.fetch_all_ledger_updates(wallet, None).await?;
+
+        self.store_synced(wallet, fills, funding, ledger).await
+    }
+
+    /// Removes a wallet's stored history, returning it if there was one, and records the
+    /// deletion so background syncs leave the wallet alone.
+    ///
+    /// Holds the wallet's lock so a sync in progress cannot store it again.
+    pub async fn forget_wallet(&self, wallet: &str) -> AppResult<Option<StoredHistory>> {
+        let key = storage_key(wallet);
+        self.with_wallet_lock(wallet, || async {
+            let history = self.storage.delete_history(&key).await?;
+            self.storage.save_wallet_deletion(&key, Utc::now()).await?;
+            Ok(history)
+        })
+        .await
+    }
+
+    /// Whether the wallet was deleted on request after `since`
+    pub async fn deleted_since(&self, wallet: &str, since: DateTime<Utc>) -> AppResult<bool> {
+        Ok(self
+            .storage
+            .load_wallet_deletion(&storage_key(wallet))
+            .await?
+            .is_some_and(|deleted_at| deleted_at >= since))
+    }
+
+    /// Runs `f` while holding the wallet's lock, waiting up to `WALLET_LOCK_WAIT_SECS` for it.
+    ///
+    /// The lock only orders callers within this process; replicas sharing storage are not
+    /// coordinated.
+    async fn with_wallet_lock<T, F, Fut>(&self, wallet: &str, f: F) -> AppResult<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let key = storage_key(wallet);
+        let lock = self
+            .wallet_locks
+            .lock()
+            .expect("wallet locks poisoned")
+            .entry(key.clone())
+            .or_default()
+            .clone();
+
+        let result = match tokio::time::timeout(
+            std::time::Duration::from_secs(WALLET_LOCK_WAIT_SECS),
+            lock.lock(),
+        )
+        .await
+        {
+            Ok(_guard) => f().await,
+            Err(_) => Err(AppError::ExternalApiError(format!(
+                "Timed out waiting for another sync of wallet {}",
+                wallet
+            ))),
+        };
+
+        // Drop the entry once no other caller holds or waits on it
+        let mut locks = self.wallet_locks.lock().expect("wallet locks poisoned");
+        if Arc::strong_count(&lock) == 2 {
+            locks.remove(&key);
+        }
+        result
+    }
+
+    /// Stores a wallet's full history fetched page by page since `started_at`, as a sync
+    /// would have, unless the wallet was deleted on request in the meantime.
+    ///
+    /// Holds the wallet's lock like `sync_wallet`, so the two never interleave.
+    pub async fn store_backfill(
+        &self,
+        wallet: &str,
+        started_at: DateTime<Utc>,
+        fills: Vec<Value>,
+        funding: Vec<Value>,
+        ledger: Vec<Value>,
+    ) -> AppResult<StoredHistory> {
+        self.with_wallet_lock(wallet, || async {
+            if self.deleted_since(wallet, started_at).await? {
+                return Err(deleted_during_backfill(wallet));
+            }
+            let stored = self.store_synced(wallet, fills, funding, ledger).await?;
+            self.storage
+                .clear_wallet_deletion(&storage_key(wallet))
+                .await?;
+            Ok(stored)
+        })
+        .await
+    }
+
+    /// Flags and stores a full upstream history, keeping restated events as superseded
+    async fn store_synced(
+        &self,
+        wallet: &str,
+        mut fills: Vec<Value>,
+        mut funding: Vec<Value>,
+        mut ledger: Vec<Value>,
+    ) -> AppResult<StoredHistory> {
+        let synced_at = Utc::now();
+        if self.store_raw_payloads {
+            let payloads = StoredRawPayloads::new(&fills, &funding, &ledger, synced_at)?;
+            tracing::debug!(
+                "Storing {} bytes of raw payloads for wallet {}",
+                payloads.size(),
+                wallet
+            );
+            self.storage
+                .save_raw_payloads(&storage_key(wallet), payloads)
+                .await?;
+        }
+
+        self.flag_anomalies(&mut fills, &mut funding).await;
+
+        if let Some(previous) = self.storage.load_history(&storage_key(wallet)).await? {
+            fills =
+                corrections::merge_restated(EventCategory::Fill, &previous.fills, fills, synced_at);
+            funding = corrections::merge_restated(
+                EventCategory::Funding,
+                &previous.funding,
+                funding,
+                synced_at,
+            );
+            ledger = corrections::keep_missing_venues(&previous.ledger, ledger);
+        }
+
+        let stored = StoredHistory {
+            fills,
+            funding,
+            ledger,
+            synced_at,
+            normalization_version: NORMALIZATION_VERSION,
+        };
+        self.storage
+            .save_history(&storage_key(wallet), stored.clone())
+            .await?;
+
+        Ok(corrections::active_history(stored))
+    }
+
+    /// Re-derives a wallet's stored events from the raw payloads of its last sync with the
+    /// current normalization logic, and drops aggregates materialized from the old events.
+    ///
+    /// Returns false when no raw payloads match the stored history, in which case the wallet
+    /// is brought up to date by its next sync instead.
+    pub async fn reprocess_wallet(&self, wallet: &str) -> AppResult<bool> {
+        self.with_wallet_lock(wallet, || self.reprocess_wallet_locked(wallet))
+            .await
+    }
+
+    async fn reprocess_wallet_locked(&self, wallet: &str) -> AppResult<bool> {
+        let key = storage_key(wallet);
+        let (Some(stored), Some(raw)) = (
+            self.storage.load_history(&key).await?,
+            self.storage.load_raw_payloads(&key).await?,
+        ) else {
+            return Ok(false);
+        };
+        if stored.normalization_version >= NORMALIZATION_VERSION {
+            return Ok(true);
+        }
+        if raw.fetched_at != stored.synced_at {
+            return Ok(false);
+        }
+
+        let mut fills = raw.fills()?;
+        let mut funding = raw.funding()?;
+        self.flag_anomalies(&mut fills, &mut funding).await;
+
+        // Superseded versions only exist in the stored history, so they are carried over
+        let reprocessed = StoredHistory {
+            fills: corrections::merge_restated(
+                EventCategory::Fill,
+                &stored.fills,
+                fills,
+                stored.synced_at,
+            ),
+            funding: corrections::merge_restated(
+                EventCategory::Funding,
+                &stored.funding,
+                funding,
+                stored.synced_at,
+            ),
+            ledger: raw.ledger()?,
+            synced_at: stored.synced_at,
+            normalization_version: NORMALIZATION_VERSION,
+        };
+        self.storage.save_history(&key, reprocessed).await?;
+        self.storage.delete_aggregates(&key).await?;
+
+        Ok(true)
+    }
+
+    /// Lists stored wallets whose events predate the current normalization logic
+    pub async fn outdated_wallets(&self) -> AppResult<Vec<String>> {
+        let mut outdated = Vec::new();
+        for wallet in self.storage.list_history_wallets().await? {
+            if let Some(stored) = self.storage.load_history(&wallet).await?
+                && stored.normalization_version < NORMALIZATION_VERSION
+            {
+                outdated.push(wallet);
+            }
+        }
+        outdated.sort();
+        Ok(outdated)
+    }
+
+    /// Loads a wallet's stored history without syncing it, with superseded events dropped
+    pub async fn stored_history(&self, wallet: &str) -> AppResult<Option<StoredHistory>> {
+        Ok(self
+            .storage
+            .load_history(&storage_key(wallet))
+            .await?
+            .map(corrections::active_history))
+    }
+
+    /// Lists restatements detected across syncs of a wallet, most recent first
+    pub async fn restatements(&self, wallet: &str) -> AppResult<Vec<Restatement>> {
+        Ok(self
+            .storage
+            .load_history(&storage_key(wallet))
+            .await?
+            .map(|stored| corrections::restatements(&stored))
+            .unwrap_or_default())
+    }
+
+    /// Tags unusual fills and funding payments with `flags`.
+    ///
+    /// Prices are checked against candles covering each coin's fills, reusing candles from
+    /// earlier checks; coins whose candles cannot be fetched skip the price check rather than
+    /// failing ingestion.
+    async fn flag_anomalies(&self, fills: &mut [Value], funding: &mut [Value]) {
+        let mut ranges: HashMap<String, (i64, i64)> = HashMap::new();
+        for fill in fills.iter() {
+            if let (Some(coin), Some(time)) = (
+                fill.get("coin").and_then(|c| c.as_str()),
+                fill.get("time").and_then(|t| t.as_i64()),
+            ) {
+                let range = ranges.entry(coin.to_string()).or_insert((time, time));
+                range.0 = range.0.min(time);
+                range.1 = range.1.max(time);
+            }
+        }
+
+        let mut candles = HashMap::new();
+        for (coin, (start, end)) in ranges {
+            match self.anomaly_candles(&coin, start, end).await {
+                Ok(coin_candles) => {
+                    candles.insert(coin, coin_candles);
+                }
+                Err(e) => tracing::warn!("Skipping price anomaly check for {}: {}", coin, e),
+            }
+        }
+
+        self.anomaly_detector.flag_fills(fills, &candles);
+        self.anomaly_detector.flag_funding(funding);
+    }
+
+    /// Candles of a coin over a range, fetching only what earlier checks did not cover.
+    ///
+    /// The cache is extended from its last candle, which may have been open when fetched, as
+    /// long as the range still fits the cached interval; otherwise it is fetched afresh.
+    async fn anomaly_candles(
+        &self,
+        coin: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Candle>> {
+        let cached = self
+            .anomaly_candles
+            .lock()
+            .expect("anomaly candles lock poisoned")
+            .remove(coin);
+
+        let (interval, start_time, mut candles) = match cached {
+            Some(cached)
+                if cached.start_time <= start_time
+                    && CandleInterval::covering(end_time - cached.start_time)
+                        == cached.interval =>
+            {
+                (cached.interval, cached.start_time, cached.candles)
+            }
+            _ => (
+                CandleInterval::covering(end_time - start_time),
+                start_time,
+                Vec::new(),
+            ),
+        };
+
+        let fetch_from = match candles.pop() {
+            Some(last) if last.close_time.timestamp_millis() >= end_time => {
+                candles.push(last);
+                None
+            }
+            Some(last) => Some(last.open_time.timestamp_millis()),
+            None => Some(start_time),
+        };
+        if let Some(fetch_from) = fetch_from {
+            candles.extend(
+                self.fetch_candles_at(coin, interval, fetch_from, end_time)
+                    .await?,
+            );
+        }
+
+        self.anomaly_candles
+            .lock()
+            .expect("anomaly candles lock poisoned")
+            .insert(
+                coin.to_string(),
+                CachedCandles {
+                    interval,
+                    start_time,
+                    candles: candles.clone(),
+                },
+            );
+        Ok(candles)
+    }
+
+    /// Syncs `wallets` now and then every `interval`, so requests for them can be served
+    /// from storage right after startup. Dormant wallets are only synced when `heat` says
+    /// they are due, and wallets deleted on request only once a request has synced them.
+    pub fn spawn_warmer(
+        self: &Arc<Self>,
+        wallets: Vec<String>,
+        interval: std::time::Duration,
+        heat: Arc<HeatTracker>,
+    ) {
+        if wallets.is_empty() {
+            return;
+        }
+        let service = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                for wallet in &wallets {
+                    match heat.sync_due(wallet, Utc::now()).await {
+                        Ok(false) => {
+                            tracing::debug!("Skipping dormant wallet {}", wallet);
+                            continue;
+                        }
+                        Ok(true) => {}
+                        Err(e) => tracing::warn!("Failed to score wallet {}: {}", wallet, e),
+                    }
+                    match service.warm_wallet(wallet).await {
+                        Ok(Some(_)) => tracing::debug!("Warmed wallet {}", wallet),
+                        Ok(None) => tracing::debug!("Skipping deleted wallet {}", wallet),
+                        Err(e) => tracing::warn!("Failed to warm wallet {}: {}", wallet, e),
+                    }
+                }
+            }
+        });
+    }
+
+    /// Starts a background sync unless one is already running for the wallet
+    fn spawn_refresh(self: &Arc<Self>, wallet: &str) {
+        let key = storage_key(wallet);
+        if !self
+            .refreshing
+            .lock()
+            .expect("refresh set lock poisoned")
+            .insert(key.clone())
+        {
+            return;
+        }
+
+        let service = Arc::clone(self);
+        let wallet = wallet.to_string();
+
+        tokio::spawn(async move {
+            if let Err(e) = service.sync_wallet(&wallet).await {
+                tracing::warn!("Background refresh failed for wallet {}: {}", wallet, e);
+            }
+            service
+                .refreshing
+                .lock()
+                .expect("refresh set lock poisoned")
+                .remove(&key);
+        });
+    }
+
+    /// Additional venues merged into the primary source's history, which cannot be paged
+    pub fn venue_sources(&self) -> Vec<(String, Arc<dyn DataSource>)> {
+        self.datasource.venue_sources()
+    }
+
+    /// Fetches one page of fills without syncing the wallet: the earliest from `start_time`,
+    /// or the most recent when omitted
+    pub async fn fetch_fill_page(
+        &self,
+        wallet: &str,
+        start_time: Option<i64>,
+    ) -> AppResult<Vec<Value>> {
+        self.datasource.get_fill_page(wallet, start_time).await
+    }
+
+    /// Fetches one page of funding payments, the earliest from `start_time`
+    pub async fn fetch_funding_page(&self, wallet: &str, start_time: i64) -> AppResult<Vec<Value>> {
+        self.datasource.get_funding_page(wallet, start_time).await
+    }
+
+    /// Fetches one page of ledger updates, the earliest from `start_time`
+    pub async fn fetch_ledger_page(&self, wallet: &str, start_time: i64) -> AppResult<Vec<Value>> {
+        self.datasource.get_ledger_page(wallet, start_time).await
+    }
+
     /// Fetches current user state (positions, balances)
     pub async fn fetch_user_state(&self, wallet: &str) -> AppResult<Value> {
         self.datasource.get_user_state(wallet).await
@@ -38,4 +660,79 @@ impl IngestionService {
     pub async fn fetch_all_mids(&self) -> AppResult<Value> {
         self.datasource.get_all_mids().await
     }
+
+    /// Fetches the next predicted funding rate per coin and venue
+    pub async fn fetch_predicted_fundings(&self) -> AppResult<Value> {
+        self.datasource.get_predicted_fundings().await
+    }
+
+    /// Fetches candles for a coin, choosing the finest interval that covers the range
+    pub async fn fetch_candles(
+        &self,
+        coin: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Candle>> {
+        let interval = CandleInterval::covering(end_time - start_time);
+        self.fetch_candles_at(coin, interval, start_time, end_time)
+            .await
+    }
+
+    /// Fetches candles for a coin at a given interval
+    pub async fn fetch_candles_at(
+        &self,
+        coin: &str,
+        interval: CandleInterval,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<Candle>> {
+        let candles = self
+            .datasource
+            .get_candles(coin, interval.as_str(), start_time, end_time)
+            .await?;
+        Ok(candles.iter().filter_map(Candle::from_value).collect())
+    }
+
+    /// Fetches settled funding rates for a coin, oldest first
+    pub async fn fetch_funding_rates(
+        &self,
+        coin: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> AppResult<Vec<FundingRate>> {
+        let rates = self
+            .datasource
+            .get_funding_rates(coin, start_time, end_time)
+            .await?;
+        let mut rates: Vec<FundingRate> =
+            rates.iter().filter_map(FundingRate::from_value).collect();
+        rates.sort_by_key(|rate| rate.time);
+        Ok(rates)
+    }
+}
+
+/// Wallet addresses are case-insensitive
+fn storage_key(wallet: &str) -> String {
+    wallet.to_lowercase()
+}
+
+pub fn deleted_during_backfill(wallet: &str) -> AppError {
+    AppError::ValidationError(format!(
+        "Wallet {} was deleted while the backfill ran; start a new one",
+        wallet
+    ))
+}
+
+fn filter_since(items: Vec<Value>, since: Option<i64>) -> Vec<Value> {
+    match since {
+        Some(since) => items
+            .into_iter()
+            .filter(|item| {
+                item.get("time")
+                    .and_then(|t| t.as_i64())
+                    .is_some_and(|t| t >= since)
+            })
+            .collect(),
+        None => items,
+    }
 }
@@ -0,0 +1,218 @@
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+
+use crate::services::pnl_calculator::{DailyPnl, PnlSummary};
+use crate::services::timeline::Timeline;
+
+/// One breach of an invariant, with where it happened and the values that disagree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Violation {
+    /// What the values refer to, e.g. a date, a coin or an event ID
+    pub context: String,
+    pub expected: Option<BigDecimal>,
+    pub actual: Option<BigDecimal>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvariantCheck {
+    pub name: String,
+    pub description: String,
+    pub passed: bool,
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub wallet: String,
+    pub events_checked: usize,
+    pub passed: bool,
+    pub checks: Vec<InvariantCheck>,
+}
+
+/// Verifies that the PnL views computed from one timeline agree with each other
+pub struct InvariantChecker;
+
+impl InvariantChecker {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn check(
+        &self,
+        wallet: &str,
+        timeline: &Timeline,
+        summary: &PnlSummary,
+        daily: &[DailyPnl],
+    ) -> SelfTestReport {
+        let checks = vec![
+            self.timeline_ordered(timeline),
+            self.daily_matches_summary(summary, daily),
+            self.assets_match_aggregate(summary),
+            self.cumulative_consistent(daily),
+        ];
+
+        SelfTestReport {
+            wallet: wallet.to_string(),
+            events_checked: timeline.events.len(),
+            passed: checks.iter().all(|check| check.passed),
+            checks,
+        }
+    }
+
+    fn timeline_ordered(&self, timeline: &Timeline) -> InvariantCheck {
+        let violations = timeline
+            .events
+            .windows(2)
+            .filter(|pair| pair[0].timestamp() > pair[1].timestamp())
+            .map(|pair| Violation {
+                context: pair[1].id().to_string(),
+                expected: None,
+                actual: None,
+                detail: format!(
+                    "{} at {} follows {} at {}",
+                    pair[1].id(),
+                    pair[1].timestamp(),
+                    pair[0].id(),
+                    pair[0].timestamp()
+                ),
+            })
+            .collect();
+
+        invariant(
+            "timeline_ordered",
+            "Timeline events are in time order",
+            violations,
+        )
+    }
+
+    /// Daily PnL has no unrealized component, so it is compared with net PnL less unrealized
+    fn daily_matches_summary(&self, summary: &PnlSummary, daily: &[DailyPnl]) -> InvariantCheck {
+        let daily_total: BigDecimal = daily.iter().map(|day| &day.pnl).sum();
+        let realized_net = &summary.net_pnl - &summary.unrealized_pnl;
+
+        let mut violations = Vec::new();
+        if daily_total != realized_net {
+            violations.push(Violation {
+                context: format!("{} to {}", summary.period_start, summary.period_end),
+                expected: Some(realized_net.clone()),
+                actual: Some(daily_total.clone()),
+                detail: format!(
+                    "Daily PnL sums to {} but summary net PnL excluding unrealized is {}",
+                    daily_total, realized_net
+                ),
+            });
+        }
+
+        invariant(
+            "daily_sum_equals_net_pnl",
+            "Sum of daily PnL equals summary net PnL excluding unrealized PnL",
+            violations,
+        )
+    }
+
+    fn assets_match_aggregate(&self, summary: &PnlSummary) -> InvariantCheck {
+        let assets = summary.by_asset.values();
+        let totals = [
+            (
+                "realized_pnl",
+                assets.clone().map(|a| &a.realized_pnl).sum::<BigDecimal>(),
+                summary.realized_pnl.clone(),
+            ),
+            (
+                "funding_pnl",
+                assets.clone().map(|a| &a.funding_pnl).sum::<BigDecimal>(),
+                summary.funding_pnl.clone(),
+            ),
+            (
+                "fees",
+                assets.clone().map(|a| &a.fees).sum::<BigDecimal>(),
+                summary.trading_fees.clone(),
+            ),
+            (
+                "net_pnl",
+                assets.map(|a| &a.net_pnl).sum::<BigDecimal>(),
+                &summary.net_pnl - &summary.unrealized_pnl,
+            ),
+        ];
+
+        let violations = totals
+            .into_iter()
+            .filter(|(_, by_asset, aggregate)| by_asset != aggregate)
+            .map(|(field, by_asset, aggregate)| Violation {
+                context: field.to_string(),
+                detail: format!(
+                    "Per-asset {} sums to {} but the aggregate is {}",
+                    field, by_asset, aggregate
+                ),
+                expected: Some(aggregate),
+                actual: Some(by_asset),
+            })
+            .collect();
+
+        invariant(
+            "asset_totals_equal_aggregate",
+            "Per-asset realized, funding, fees and net PnL sum to the aggregate",
+            violations,
+        )
+    }
+
+    fn cumulative_consistent(&self, daily: &[DailyPnl]) -> InvariantCheck {
+        let mut violations = Vec::new();
+        let mut running = BigDecimal::from(0);
+        let mut previous_date: Option<&str> = None;
+
+        for day in daily {
+            if previous_date.is_some_and(|previous| previous >= day.date.as_str()) {
+                violations.push(Violation {
+                    context: day.date.clone(),
+                    expected: None,
+                    actual: None,
+                    detail: format!(
+                        "{} does not follow {}",
+                        day.date,
+                        previous_date.unwrap_or_default()
+                    ),
+                });
+            }
+
+            running = &running + &day.pnl;
+            if day.cumulative_pnl != running {
+                violations.push(Violation {
+                    context: day.date.clone(),
+                    expected: Some(running.clone()),
+                    actual: Some(day.cumulative_pnl.clone()),
+                    detail: format!(
+                        "Cumulative PnL on {} is {} but previous cumulative plus the day's PnL is {}",
+                        day.date, day.cumulative_pnl, running
+                    ),
+                });
+                // Continue from the reported value so one bad day is reported once
+                running = day.cumulative_pnl.clone();
+            }
+
+            previous_date = Some(&day.date);
+        }
+
+        invariant(
+            "cumulative_consistent",
+            "Daily dates increase and each cumulative PnL equals the previous one plus the day's PnL",
+            violations,
+        )
+    }
+}
+
+impl Default for InvariantChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invariant(name: &str, description: &str, violations: Vec<Violation>) -> InvariantCheck {
+    InvariantCheck {
+        name: name.to_string(),
+        description: description.to_string(),
+        passed: violations.is_empty(),
+        violations,
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Where a paginated step of a job will continue from, as last persisted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub step: String,
+    /// Start of the next page, epoch milliseconds
+    pub next_start: i64,
+    pub pages: usize,
+    pub items: usize,
+    pub complete: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A background job tracked in-process, with enough progress to resume it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    pub wallet: String,
+    pub status: JobStatus,
+    pub total_steps: usize,
+    pub completed_steps: Vec<String>,
+    /// Resume points of steps fetched page by page
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checkpoints: Vec<Checkpoint>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub struct JobRegistry {
+    jobs: RwLock<HashMap<Uuid, Job>>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self {
+            jobs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Registers a new queued job
+    pub fn create(&self, kind: &str, wallet: &str) -> Job {
+        let now = Utc::now();
+        let job = Job {
+            id: Uuid::new_v4(),
+            kind: kind.to_string(),
+            wallet: wallet.to_string(),
+            status: JobStatus::Queued,
+            total_steps: 0,
+            completed_steps: Vec::new(),
+            checkpoints: Vec::new(),
+            error: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.jobs
+            .write()
+            .expect("job registry lock poisoned")
+            .insert(job.id, job.clone());
+        job
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<Job> {
+        self.jobs
+            .read()
+            .expect("job registry lock poisoned")
+            .get(&id)
+            .cloned()
+    }
+
+    /// Lists jobs, most recent first
+    pub fn list(&self) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self
+            .jobs
+            .read()
+            .expect("job registry lock poisoned")
+            .values()
+            .cloned()
+            .collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+        jobs
+    }
+
+    /// Applies a change to a job and bumps its update time
+    pub fn update(&self, id: Uuid, change: impl FnOnce(&mut Job)) {
+        if let Some(job) = self
+            .jobs
+            .write()
+            .expect("job registry lock poisoned")
+            .get_mut(&id)
+        {
+            change(job);
+            job.updated_at = Utc::now();
+        }
+    }
+}
+
+impl Default for JobRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,142 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Hyperliquid returns at most this many candles per request
+const MAX_CANDLES_PER_REQUEST: i64 = 5000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candle {
+    pub open_time: DateTime<Utc>,
+    pub close_time: DateTime<Utc>,
+    pub open: BigDecimal,
+    pub high: BigDecimal,
+    pub low: BigDecimal,
+    pub close: BigDecimal,
+}
+
+impl Candle {
+    /// Parses a Hyperliquid candle (`t`, `T`, `o`, `h`, `l`, `c`)
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let time = |key: &str| {
+            value
+                .get(key)
+                .and_then(|t| t.as_i64())
+                .and_then(DateTime::from_timestamp_millis)
+        };
+        let price = |key: &str| {
+            value
+                .get(key)
+                .and_then(|p| p.as_str())
+                .and_then(|p| BigDecimal::from_str(p).ok())
+        };
+
+        Some(Self {
+            open_time: time("t")?,
+            close_time: time("T")?,
+            open: price("o")?,
+            high: price("h")?,
+            low: price("l")?,
+            close: price("c")?,
+        })
+    }
+}
+
+/// A funding rate settled at `time`, per funding interval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingRate {
+    pub time: DateTime<Utc>,
+    pub rate: BigDecimal,
+}
+
+impl FundingRate {
+    /// Parses a Hyperliquid funding history entry (`time`, `fundingRate`)
+    pub fn from_value(value: &Value) -> Option<Self> {
+        Some(Self {
+            time: value
+                .get("time")
+                .and_then(|t| t.as_i64())
+                .and_then(DateTime::from_timestamp_millis)?,
+            rate: value
+                .get("fundingRate")
+                .and_then(|r| r.as_str())
+                .and_then(|r| BigDecimal::from_str(r).ok())?,
+        })
+    }
+}
+
+/// Returns the candle containing `timestamp`, assuming candles sorted by time
+pub fn candle_at(candles: &[Candle], timestamp: DateTime<Utc>) -> Option<&Candle> {
+    let index = candles.partition_point(|c| c.open_time <= timestamp);
+    index
+        .checked_sub(1)
+        .map(|i| &candles[i])
+        .filter(|c| c.close_time >= timestamp)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandleInterval {
+    OneMinute,
+    FiveMinutes,
+    FifteenMinutes,
+    OneHour,
+    FourHours,
+    OneDay,
+}
+
+impl CandleInterval {
+    const ALL: [CandleInterval; 6] = [
+        CandleInterval::OneMinute,
+        CandleInterval::FiveMinutes,
+        CandleInterval::FifteenMinutes,
+        CandleInterval::OneHour,
+        CandleInterval::FourHours,
+        CandleInterval::OneDay,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandleInterval::OneMinute => "1m",
+            CandleInterval::FiveMinutes => "5m",
+            CandleInterval::FifteenMinutes => "15m",
+            CandleInterval::OneHour => "1h",
+            CandleInterval::FourHours => "4h",
+            CandleInterval::OneDay => "1d",
+        }
+    }
+
+    pub fn millis(&self) -> i64 {
+        let minutes = match self {
+            CandleInterval::OneMinute => 1,
+            CandleInterval::FiveMinutes => 5,
+            CandleInterval::FifteenMinutes => 15,
+            CandleInterval::OneHour => 60,
+            CandleInterval::FourHours => 240,
+            CandleInterval::OneDay => 1440,
+        };
+        minutes * 60 * 1000
+    }
+
+    /// Finest interval whose candles over `span_ms` fit in a single request
+    pub fn covering(span_ms: i64) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|interval| span_ms / interval.millis() < MAX_CANDLES_PER_REQUEST)
+            .unwrap_or(CandleInterval::OneDay)
+    }
+
+    /// Coarsest interval no longer than `span_ms`, or None if even one minute is longer
+    pub fn within(span_ms: i64) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .rev()
+            .find(|interval| interval.millis() <= span_ms)
+    }
+
+    /// Longest span one request returns candles for at this interval
+    pub fn request_span(&self) -> i64 {
+        self.millis() * MAX_CANDLES_PER_REQUEST
+    }
+}
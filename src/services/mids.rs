@@ -0,0 +1,133 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use crate::error::AppResult;
+use crate::services::assets::AssetRegistry;
+use crate::services::ingestion::IngestionService;
+use crate::services::normalized::NormalizedFill;
+
+/// Decimal places kept for slippage
+const BPS_SCALE: i64 = 4;
+
+/// Mid prices of every coin at one poll, keyed by normalized coin name
+struct MidSnapshot {
+    captured_at: DateTime<Utc>,
+    mids: HashMap<String, BigDecimal>,
+}
+
+/// Keeps recent mid-price snapshots so fresh fills can be compared with the mid at fill time
+/// before candles covering them are available.
+///
+/// Only fills within the retention window, and no further than two poll intervals after a
+/// snapshot, are enriched.
+pub struct MidsPoller {
+    ingestion_service: Arc<IngestionService>,
+    asset_registry: Arc<AssetRegistry>,
+    interval: Duration,
+    retention: Duration,
+    snapshots: RwLock<VecDeque<MidSnapshot>>,
+}
+
+impl MidsPoller {
+    pub fn new(
+        ingestion_service: Arc<IngestionService>,
+        asset_registry: Arc<AssetRegistry>,
+        interval: std::time::Duration,
+        retention: std::time::Duration,
+    ) -> Self {
+        Self {
+            ingestion_service,
+            asset_registry,
+            interval: Duration::from_std(interval).unwrap_or_else(|_| Duration::seconds(5)),
+            retention: Duration::from_std(retention).unwrap_or_else(|_| Duration::minutes(15)),
+            snapshots: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    /// Polls mids on the configured interval, forever
+    pub fn spawn_poller(self: &Arc<Self>) {
+        let Ok(interval) = self.interval.to_std() else {
+            return;
+        };
+        if interval.is_zero() {
+            return;
+        }
+        let poller = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = poller.poll().await {
+                    tracing::warn!("Mid price poll failed: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn poll(&self) -> AppResult<()> {
+        let response = self.ingestion_service.fetch_all_mids().await?;
+        let captured_at = Utc::now();
+        let mids = parse_mids(&response)
+            .into_iter()
+            .map(|(coin, mid)| (self.asset_registry.normalize_coin(&coin, captured_at), mid))
+            .collect();
+
+        let mut snapshots = self.snapshots.write().expect("mids lock poisoned");
+        snapshots.push_back(MidSnapshot { captured_at, mids });
+        while snapshots
+            .front()
+            .is_some_and(|oldest| captured_at - oldest.captured_at > self.retention)
+        {
+            snapshots.pop_front();
+        }
+        Ok(())
+    }
+
+    /// The mid prevailing at `at`: the latest snapshot at or before it, if recent enough
+    pub fn mid_at(&self, coin: &str, at: DateTime<Utc>) -> Option<BigDecimal> {
+        let snapshots = self.snapshots.read().expect("mids lock poisoned");
+        let snapshot = snapshots
+            .iter()
+            .rev()
+            .find(|snapshot| snapshot.captured_at <= at)?;
+        if at - snapshot.captured_at > self.interval * 2 {
+            return None;
+        }
+        snapshot.mids.get(coin).cloned()
+    }
+
+    /// Sets the mid at fill time and the slippage against it, where a snapshot covers the fill
+    pub fn enrich(&self, fill: &mut NormalizedFill) {
+        let Some(mid) = self.mid_at(&fill.coin, fill.timestamp) else {
+            return;
+        };
+        if mid.is_zero() {
+            return;
+        }
+
+        let difference = if fill.side == "B" {
+            &fill.price - &mid
+        } else {
+            &mid - &fill.price
+        };
+        fill.slippage_bps = Some((difference * BigDecimal::from(10_000) / &mid).round(BPS_SCALE));
+        fill.mid_price = Some(mid);
+    }
+}
+
+fn parse_mids(response: &Value) -> Vec<(String, BigDecimal)> {
+    response
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(coin, mid)| {
+            let mid = BigDecimal::from_str(mid.as_str()?).ok()?;
+            Some((coin.clone(), mid))
+        })
+        .collect()
+}
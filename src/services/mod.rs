@@ -1,3 +1,45 @@
+pub mod activity;
+pub mod aggregates;
+pub mod alerts;
+pub mod anomalies;
+pub mod archive;
+pub mod assets;
+pub mod attestation;
+pub mod backfill;
+pub mod basis;
+pub mod benchmark;
+pub mod capital;
+pub mod capture;
+pub mod carry;
+pub mod collateral;
+pub mod corrections;
+pub mod deletion;
+pub mod export;
+pub mod fees;
+pub mod forecasts;
+pub mod funding_comparison;
+pub mod funding_scanner;
+pub mod heat;
 pub mod ingestion;
+pub mod invariants;
+pub mod jobs;
+pub mod market_data;
+pub mod mids;
+pub mod normalized;
 pub mod pnl_calculator;
+pub mod portfolio;
+pub mod positions;
+pub mod reconciliation;
+pub mod reports;
+pub mod reprocess;
+pub mod scenarios;
+pub mod sequence;
+pub mod sharing;
+pub mod slo;
+pub mod sources;
+pub mod statements;
+pub mod stats;
 pub mod timeline;
+pub mod timing;
+pub mod trades;
+pub mod volume;
@@ -0,0 +1,152 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::services::anomalies::AnomalyFlag;
+use crate::services::timeline::{CoinUnits, TimelineEvent};
+
+/// Version of the normalized fill and funding schemas; bumped on breaking changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A fill in the venue-independent model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizedFill {
+    pub id: String,
+    pub venue: String,
+    pub timestamp: DateTime<Utc>,
+    pub coin: String,
+    /// `B` for buys, `A` for sells
+    pub side: String,
+    /// Size and price as quoted by the venue, per contract for multi-coin contracts
+    pub size: BigDecimal,
+    pub price: BigDecimal,
+    /// Size and price in whole coins, where the venue quotes the coin in larger contracts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coin_units: Option<CoinUnits>,
+    #[serde(with = "crate::output::cost")]
+    pub fee: BigDecimal,
+    pub fee_token: String,
+    pub realized_pnl: Option<BigDecimal>,
+    pub start_position: Option<BigDecimal>,
+    /// True when the fill took liquidity (taker)
+    pub crossed: bool,
+    pub order_id: Option<u64>,
+    pub tx_hash: Option<String>,
+    pub flags: Vec<AnomalyFlag>,
+    /// Mid price when the fill happened, from live snapshots; only on recent fills
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mid_price: Option<BigDecimal>,
+    /// Execution price against `mid_price` in bps; positive is worse for the trader
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slippage_bps: Option<BigDecimal>,
+}
+
+/// A funding payment in the venue-independent model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizedFunding {
+    pub id: String,
+    pub venue: String,
+    pub timestamp: DateTime<Utc>,
+    pub coin: String,
+    /// Positive when the wallet received funding
+    pub amount: BigDecimal,
+    pub token: String,
+    pub funding_rate: BigDecimal,
+    pub position_size: Option<BigDecimal>,
+    pub flags: Vec<AnomalyFlag>,
+}
+
+/// A page of normalized events, tagged with the schema they follow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizedEvents<T> {
+    pub schema_version: u32,
+    pub events: Vec<T>,
+}
+
+impl<T> NormalizedEvents<T> {
+    pub fn new(events: Vec<T>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            events,
+        }
+    }
+}
+
+impl NormalizedFill {
+    /// Converts a timeline fill; other events yield None
+    pub fn from_event(event: TimelineEvent) -> Option<Self> {
+        match event {
+            TimelineEvent::Fill {
+                id,
+                timestamp,
+                coin,
+                side,
+                size,
+                price,
+                coin_units,
+                fee,
+                fee_token,
+                realized_pnl,
+                start_position,
+                crossed,
+                order_id,
+                tx_hash,
+                flags,
+            } => Some(Self {
+                venue: venue(&id),
+                id,
+                timestamp,
+                coin,
+                side,
+                size,
+                price,
+                coin_units: coin_units.map(|units| *units),
+                fee,
+                fee_token,
+                realized_pnl,
+                start_position,
+                crossed,
+                order_id,
+                tx_hash,
+                flags,
+                mid_price: None,
+                slippage_bps: None,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl NormalizedFunding {
+    /// Converts a timeline funding payment; other events yield None
+    pub fn from_event(event: TimelineEvent) -> Option<Self> {
+        match event {
+            TimelineEvent::Funding {
+                id,
+                timestamp,
+                coin,
+                amount,
+                token,
+                funding_rate,
+                position_size,
+                flags,
+            } => Some(Self {
+                venue: venue(&id),
+                id,
+                timestamp,
+                coin,
+                amount,
+                token,
+                funding_rate,
+                position_size,
+                flags,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Venue named by the first segment of an event ID
+fn venue(id: &str) -> String {
+    id.split(':').next().unwrap_or_default().to_string()
+}
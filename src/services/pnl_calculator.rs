@@ -1,10 +1,74 @@
 use bigdecimal::BigDecimal;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
 
-use crate::services::timeline::{Timeline, TimelineEvent};
+use crate::services::collateral::{self, CurrencyPnl};
+use crate::services::timeline::{Granularity, Timeline, TimelineEvent};
+
+/// Funding settled this soon after 00:00 UTC is treated as straddling the day boundary
+const FUNDING_BOUNDARY_TOLERANCE_SECS: i64 = 60;
+
+/// Which day funding settled at the day boundary is attributed to.
+///
+/// Hourly funding settled at 00:00 UTC accrued over the last hour of the previous day.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingAttribution {
+    /// The day that just ended
+    Preceding,
+    /// The day the payment settled on
+    #[default]
+    Following,
+    /// Half to each day
+    Split,
+}
+
+impl FundingAttribution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FundingAttribution::Preceding => "preceding",
+            FundingAttribution::Following => "following",
+            FundingAttribution::Split => "split",
+        }
+    }
+
+    /// Splits a funding payment into (date, amount) parts
+    pub fn attribute(
+        &self,
+        timestamp: DateTime<Utc>,
+        amount: &BigDecimal,
+    ) -> Vec<(NaiveDate, BigDecimal)> {
+        let date = timestamp.date_naive();
+        let since_midnight = timestamp - date.and_hms_opt(0, 0, 0).expect("midnight").and_utc();
+        let at_boundary = since_midnight < Duration::seconds(FUNDING_BOUNDARY_TOLERANCE_SECS);
+
+        match (self, date.pred_opt()) {
+            (FundingAttribution::Preceding, Some(previous)) if at_boundary => {
+                vec![(previous, amount.clone())]
+            }
+            (FundingAttribution::Split, Some(previous)) if at_boundary => {
+                let half = amount / BigDecimal::from(2);
+                vec![(previous, half.clone()), (date, amount - half)]
+            }
+            _ => vec![(date, amount.clone())],
+        }
+    }
+}
+
+impl FromStr for FundingAttribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "preceding" => Ok(FundingAttribution::Preceding),
+            "following" => Ok(FundingAttribution::Following),
+            "split" => Ok(FundingAttribution::Split),
+            other => Err(format!("Unknown funding attribution: {}", other)),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PnlSummary {
@@ -15,9 +79,14 @@ pub struct PnlSummary {
     pub unrealized_pnl: BigDecimal,
     pub total_pnl: BigDecimal,
     pub funding_pnl: BigDecimal,
+    #[serde(with = "crate::output::cost")]
     pub trading_fees: BigDecimal,
     pub net_pnl: BigDecimal,
     pub by_asset: HashMap<String, AssetPnl>,
+    /// Components in the currency each was paid in, before any conversion
+    pub by_currency: BTreeMap<String, CurrencyPnl>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calculation: Option<CalculationMetadata>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +94,7 @@ pub struct AssetPnl {
     pub coin: String,
     pub realized_pnl: BigDecimal,
     pub funding_pnl: BigDecimal,
+    #[serde(with = "crate::output::cost")]
     pub fees: BigDecimal,
     pub net_pnl: BigDecimal,
     pub trade_count: u32,
@@ -37,11 +107,111 @@ pub struct DailyPnl {
     pub cumulative_pnl: BigDecimal,
 }
 
-pub struct PnlCalculator;
+/// Parallel per-period series of what drove one coin's PnL.
+///
+/// Price PnL is realized trading PnL net of liquidation losses; net PnL is price PnL plus
+/// funding minus fees. Periods without fills, funding or liquidations are omitted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PnlDecomposition {
+    pub wallet: String,
+    pub coin: String,
+    pub granularity: Granularity,
+    pub funding_attribution: FundingAttribution,
+    pub periods: Vec<String>,
+    pub price_pnl: Vec<BigDecimal>,
+    pub funding_pnl: Vec<BigDecimal>,
+    #[serde(with = "crate::output::costs")]
+    pub fees: Vec<BigDecimal>,
+    pub net_pnl: Vec<BigDecimal>,
+    pub cumulative_net_pnl: Vec<BigDecimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calculation: Option<CalculationMetadata>,
+}
+
+/// How realized PnL was arrived at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PnlMethod {
+    /// Closed PnL as reported by the exchange on each fill
+    ExchangeReported,
+    /// Replayed from fills, closing the oldest lots first
+    Fifo,
+}
+
+/// Prices PnL was valued at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    /// Execution prices only; open positions are not valued
+    FillPrices,
+    /// Execution prices, with open positions at the exchange's mark price
+    MarkPrice,
+}
+
+/// What a PnL figure was calculated from, for reconciling it with other tools
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculationMetadata {
+    pub method: PnlMethod,
+    pub price_source: PriceSource,
+    /// Start of the requested period, when one was given
+    pub requested_from: Option<DateTime<Utc>>,
+    /// First and last events the calculation saw
+    pub covered_from: Option<DateTime<Utc>>,
+    pub covered_to: Option<DateTime<Utc>>,
+    pub events_considered: usize,
+    /// Raw history items that could not be parsed and were left out
+    pub events_skipped: usize,
+    /// `NORMALIZATION_VERSION` the events were derived with
+    pub normalization_version: u32,
+    /// Served from stored daily aggregates instead of a timeline built for the request
+    #[serde(default)]
+    pub from_aggregates: bool,
+}
+
+impl CalculationMetadata {
+    pub fn new(
+        method: PnlMethod,
+        price_source: PriceSource,
+        timeline: &Timeline,
+        since: Option<i64>,
+        normalization_version: u32,
+    ) -> Self {
+        Self {
+            method,
+            price_source,
+            requested_from: since.and_then(DateTime::from_timestamp_millis),
+            covered_from: timeline.from_timestamp,
+            covered_to: timeline.to_timestamp,
+            events_considered: timeline.events.len(),
+            events_skipped: timeline.skipped_events,
+            normalization_version,
+            from_aggregates: false,
+        }
+    }
+}
+
+/// One period of a decomposition
+#[derive(Default)]
+struct DecompositionPeriod {
+    price_pnl: BigDecimal,
+    funding_pnl: BigDecimal,
+    fees: BigDecimal,
+}
+
+pub struct PnlCalculator {
+    funding_attribution: FundingAttribution,
+}
 
 impl PnlCalculator {
-    pub fn new() -> Self {
-        Self
+    pub fn new(funding_attribution: FundingAttribution) -> Self {
+        Self {
+            funding_attribution,
+        }
+    }
+
+    /// The configured rule for funding settled at the day boundary
+    pub fn funding_attribution(&self) -> FundingAttribution {
+        self.funding_attribution
     }
 
     /// Calculates PnL summary from timeline events
@@ -55,8 +225,16 @@ impl PnlCalculator {
         let mut funding_pnl = BigDecimal::from(0);
         let mut trading_fees = BigDecimal::from(0);
         let mut by_asset: HashMap<String, AssetPnl> = HashMap::new();
+        let mut by_currency: BTreeMap<String, CurrencyPnl> = BTreeMap::new();
 
         for event in &timeline.events {
+            for (currency, component, amount) in collateral::components(event) {
+                by_currency
+                    .entry(currency.to_string())
+                    .or_insert_with(|| CurrencyPnl::new(currency))
+                    .add(component, amount);
+            }
+
             match event {
                 TimelineEvent::Fill {
                     coin,
@@ -124,54 +302,132 @@ impl PnlCalculator {
             trading_fees,
             net_pnl,
             by_asset,
+            by_currency,
+            calculation: None,
         }
     }
 
-    /// Calculates daily PnL breakdown
-    pub fn calculate_daily(&self, timeline: &Timeline) -> Vec<DailyPnl> {
-        let mut daily_map: HashMap<String, BigDecimal> = HashMap::new();
+    /// Calculates daily PnL breakdown, attributing boundary funding by `funding_attribution`
+    pub fn calculate_daily(
+        &self,
+        timeline: &Timeline,
+        funding_attribution: FundingAttribution,
+    ) -> Vec<DailyPnl> {
+        let mut daily_map: BTreeMap<NaiveDate, BigDecimal> = BTreeMap::new();
 
         for event in &timeline.events {
-            let date = event.timestamp().format("%Y-%m-%d").to_string();
+            for (date, pnl) in self.daily_parts(event, funding_attribution) {
+                let entry = daily_map.entry(date).or_insert_with(|| BigDecimal::from(0));
+                *entry = &*entry + &pnl;
+            }
+        }
+
+        cumulative_daily(daily_map)
+    }
+
+    /// Splits an event's PnL into the days it is attributed to
+    pub fn daily_parts(
+        &self,
+        event: &TimelineEvent,
+        funding_attribution: FundingAttribution,
+    ) -> Vec<(NaiveDate, BigDecimal)> {
+        let date = event.timestamp().date_naive();
 
-            let pnl = match event {
+        match event {
+            TimelineEvent::Fill {
+                realized_pnl,
+                fee,
+                ..
+            } => {
+                let rpnl = realized_pnl.clone().unwrap_or_default();
+                vec![(date, &rpnl - fee)]
+            }
+            TimelineEvent::Funding {
+                timestamp, amount, ..
+            } => funding_attribution.attribute(*timestamp, amount),
+            TimelineEvent::Liquidation { loss, .. } => vec![(date, -loss.clone())],
+            _ => vec![(date, BigDecimal::from(0))],
+        }
+    }
+
+    /// Splits one coin's PnL per period into price, funding and fee components, attributing
+    /// boundary funding by `funding_attribution`
+    pub fn decompose(
+        &self,
+        timeline: &Timeline,
+        coin: &str,
+        granularity: Granularity,
+        funding_attribution: FundingAttribution,
+    ) -> PnlDecomposition {
+        let mut periods: BTreeMap<String, DecompositionPeriod> = BTreeMap::new();
+
+        for event in &timeline.events {
+            match event {
                 TimelineEvent::Fill {
-                    realized_pnl,
+                    timestamp,
+                    coin: fill_coin,
                     fee,
+                    realized_pnl,
                     ..
-                } => {
-                    let rpnl = realized_pnl.clone().unwrap_or_default();
-                    &rpnl - fee
+                } if fill_coin == coin => {
+                    let period = periods.entry(granularity.bucket(*timestamp)).or_default();
+                    if let Some(pnl) = realized_pnl {
+                        period.price_pnl = &period.price_pnl + pnl;
+                    }
+                    period.fees = &period.fees + fee;
                 }
-                TimelineEvent::Funding { amount, .. } => amount.clone(),
-                TimelineEvent::Liquidation { loss, .. } => -loss.clone(),
-                _ => BigDecimal::from(0),
-            };
-
-            let entry = daily_map.entry(date).or_insert_with(|| BigDecimal::from(0));
-            *entry = &*entry + &pnl;
+                TimelineEvent::Funding {
+                    timestamp,
+                    coin: funding_coin,
+                    amount,
+                    ..
+                } if funding_coin == coin => {
+                    for (date, part) in funding_attribution.attribute(*timestamp, amount) {
+                        let start = date.and_hms_opt(0, 0, 0).expect("midnight").and_utc();
+                        let period = periods.entry(granularity.bucket(start)).or_default();
+                        period.funding_pnl = &period.funding_pnl + part;
+                    }
+                }
+                TimelineEvent::Liquidation {
+                    timestamp,
+                    coin: liquidated_coin,
+                    loss,
+                    ..
+                } if liquidated_coin == coin => {
+                    let period = periods.entry(granularity.bucket(*timestamp)).or_default();
+                    period.price_pnl = &period.price_pnl - loss;
+                }
+                _ => {}
+            }
         }
 
-        let mut daily_pnl: Vec<DailyPnl> = daily_map
-            .into_iter()
-            .map(|(date, pnl)| DailyPnl {
-                date,
-                pnl,
-                cumulative_pnl: BigDecimal::from(0),
-            })
-            .collect();
-
-        // Sort by date
-        daily_pnl.sort_by(|a, b| a.date.cmp(&b.date));
-
-        // Calculate cumulative PnL
+        let mut decomposition = PnlDecomposition {
+            wallet: timeline.wallet.clone(),
+            coin: coin.to_string(),
+            granularity,
+            funding_attribution,
+            periods: Vec::new(),
+            price_pnl: Vec::new(),
+            funding_pnl: Vec::new(),
+            fees: Vec::new(),
+            net_pnl: Vec::new(),
+            cumulative_net_pnl: Vec::new(),
+            calculation: None,
+        };
         let mut cumulative = BigDecimal::from(0);
-        for day in &mut daily_pnl {
-            cumulative = &cumulative + &day.pnl;
-            day.cumulative_pnl = cumulative.clone();
+        for (label, period) in periods {
+            let net = &period.price_pnl + &period.funding_pnl - &period.fees;
+            cumulative = &cumulative + &net;
+
+            decomposition.periods.push(label);
+            decomposition.price_pnl.push(period.price_pnl);
+            decomposition.funding_pnl.push(period.funding_pnl);
+            decomposition.fees.push(period.fees);
+            decomposition.net_pnl.push(net);
+            decomposition.cumulative_net_pnl.push(cumulative.clone());
         }
 
-        daily_pnl
+        decomposition
     }
 
     /// Calculates unrealized PnL from current positions
@@ -196,6 +452,21 @@ impl PnlCalculator {
 
 impl Default for PnlCalculator {
     fn default() -> Self {
-        Self::new()
+        Self::new(FundingAttribution::default())
     }
 }
+
+/// Orders per-day PnL by date and adds the running total
+pub fn cumulative_daily(days: BTreeMap<NaiveDate, BigDecimal>) -> Vec<DailyPnl> {
+    let mut cumulative = BigDecimal::from(0);
+    days.into_iter()
+        .map(|(date, pnl)| {
+            cumulative = &cumulative + &pnl;
+            DailyPnl {
+                date: date.format("%Y-%m-%d").to_string(),
+                pnl,
+                cumulative_pnl: cumulative.clone(),
+            }
+        })
+        .collect()
+}
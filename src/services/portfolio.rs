@@ -0,0 +1,269 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::services::timeline::{Timeline, TimelineEvent};
+
+/// Decimal places kept for ratios
+const RATIO_SCALE: i64 = 8;
+
+/// Largest delay between a withdrawal and the deposit it funds in another wallet
+const TRANSFER_MATCH_WINDOW_MINUTES: i64 = 60;
+
+/// Withdrawals arrive net of the bridge fee (1 USDC)
+const WITHDRAWAL_FEE_TOLERANCE: i64 = 1;
+
+/// How a withdrawal was paired with its deposit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferMatch {
+    /// Both ledger entries name the other wallet
+    Exact,
+    /// Bridge transfers paired by amount and timing
+    Inferred,
+}
+
+/// A withdrawal from one grouped wallet matched to a deposit into another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InternalTransfer {
+    pub from_wallet: String,
+    pub to_wallet: String,
+    pub withdrawal_id: String,
+    pub deposit_id: String,
+    pub withdrawn_at: DateTime<Utc>,
+    pub deposited_at: DateTime<Utc>,
+    pub amount: BigDecimal,
+    /// Amount credited to the receiving wallet, net of the bridge fee
+    pub received: BigDecimal,
+    pub token: String,
+    pub match_kind: TransferMatch,
+}
+
+/// Capital flows and PnL of one grouped wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioWallet {
+    pub wallet: String,
+    /// Deposits from outside the portfolio
+    pub external_deposits: BigDecimal,
+    /// Withdrawals leaving the portfolio
+    pub external_withdrawals: BigDecimal,
+    pub internal_transfers_in: BigDecimal,
+    pub internal_transfers_out: BigDecimal,
+    pub net_pnl: BigDecimal,
+}
+
+/// Deposits, withdrawals and returns of several owned wallets taken together.
+///
+/// Transfers between grouped wallets are neither deposits nor withdrawals of the portfolio;
+/// only their bridge fees, lost to the portfolio, count against net PnL. Net PnL is realized
+/// PnL plus funding, less fees and liquidation losses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioReport {
+    pub wallets: Vec<PortfolioWallet>,
+    pub external_deposits: BigDecimal,
+    pub external_withdrawals: BigDecimal,
+    pub net_deposits: BigDecimal,
+    pub internal_transfers: Vec<InternalTransfer>,
+    /// Bridge fees paid on internal transfers
+    pub internal_transfer_fees: BigDecimal,
+    pub net_pnl: BigDecimal,
+    /// Net PnL over external deposits
+    pub return_on_deposits: Option<BigDecimal>,
+}
+
+/// A deposit or withdrawal awaiting a match
+struct Transfer<'a> {
+    wallet: &'a str,
+    id: &'a str,
+    timestamp: DateTime<Utc>,
+    amount: &'a BigDecimal,
+    token: &'a str,
+    /// Other wallet named by a transfer within Hyperliquid; none for bridge transfers
+    counterparty: Option<&'a str>,
+}
+
+pub struct PortfolioService;
+
+impl PortfolioService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Matches transfers between the wallets' timelines, which must include ledger updates
+    pub fn calculate(&self, timelines: &[Timeline]) -> PortfolioReport {
+        let internal_transfers = self.detect_internal_transfers(timelines);
+
+        let mut wallets = Vec::with_capacity(timelines.len());
+        for timeline in timelines {
+            let mut wallet = PortfolioWallet {
+                wallet: timeline.wallet.clone(),
+                external_deposits: BigDecimal::zero(),
+                external_withdrawals: BigDecimal::zero(),
+                internal_transfers_in: BigDecimal::zero(),
+                internal_transfers_out: BigDecimal::zero(),
+                net_pnl: BigDecimal::zero(),
+            };
+
+            for event in &timeline.events {
+                match event {
+                    TimelineEvent::Fill {
+                        realized_pnl, fee, ..
+                    } => {
+                        wallet.net_pnl =
+                            &wallet.net_pnl + realized_pnl.clone().unwrap_or_default() - fee;
+                    }
+                    TimelineEvent::Funding { amount, .. } => {
+                        wallet.net_pnl = &wallet.net_pnl + amount;
+                    }
+                    TimelineEvent::Liquidation { loss, .. } => {
+                        wallet.net_pnl = &wallet.net_pnl - loss;
+                    }
+                    TimelineEvent::Deposit { id, amount, .. } => {
+                        if internal_transfers.iter().any(|t| t.deposit_id == *id) {
+                            wallet.internal_transfers_in = &wallet.internal_transfers_in + amount;
+                        } else {
+                            wallet.external_deposits = &wallet.external_deposits + amount;
+                        }
+                    }
+                    TimelineEvent::Withdrawal { id, amount, .. } => {
+                        if internal_transfers.iter().any(|t| t.withdrawal_id == *id) {
+                            wallet.internal_transfers_out = &wallet.internal_transfers_out + amount;
+                        } else {
+                            wallet.external_withdrawals = &wallet.external_withdrawals + amount;
+                        }
+                    }
+                }
+            }
+            wallets.push(wallet);
+        }
+
+        let sum = |field: fn(&PortfolioWallet) -> &BigDecimal| {
+            wallets
+                .iter()
+                .fold(BigDecimal::zero(), |acc, wallet| acc + field(wallet))
+        };
+        let external_deposits = sum(|w| &w.external_deposits);
+        let external_withdrawals = sum(|w| &w.external_withdrawals);
+        let internal_transfer_fees = internal_transfers
+            .iter()
+            .fold(BigDecimal::zero(), |acc, t| acc + (&t.amount - &t.received));
+        let net_pnl = sum(|w| &w.net_pnl) - &internal_transfer_fees;
+
+        PortfolioReport {
+            net_deposits: &external_deposits - &external_withdrawals,
+            return_on_deposits: (external_deposits > BigDecimal::zero())
+                .then(|| (&net_pnl / &external_deposits).round(RATIO_SCALE)),
+            wallets,
+            external_deposits,
+            external_withdrawals,
+            internal_transfers,
+            internal_transfer_fees,
+            net_pnl,
+        }
+    }
+
+    /// Pairs withdrawals from grouped wallets with deposits into other grouped wallets.
+    ///
+    /// Transfers within Hyperliquid name both wallets and pair exactly. Bridge withdrawals are
+    /// inferred to fund the earliest unmatched bridge deposit of the same token into another
+    /// grouped wallet, within the match window and short of it by at most the bridge fee.
+    pub fn detect_internal_transfers(&self, timelines: &[Timeline]) -> Vec<InternalTransfer> {
+        let mut withdrawals = Vec::new();
+        let mut deposits = Vec::new();
+        for timeline in timelines {
+            for event in &timeline.events {
+                match event {
+                    TimelineEvent::Withdrawal {
+                        id,
+                        timestamp,
+                        amount,
+                        token,
+                        destination,
+                    } => withdrawals.push(Transfer {
+                        wallet: &timeline.wallet,
+                        id,
+                        timestamp: *timestamp,
+                        amount,
+                        token,
+                        counterparty: destination.as_deref(),
+                    }),
+                    TimelineEvent::Deposit {
+                        id,
+                        timestamp,
+                        amount,
+                        token,
+                        source,
+                    } => deposits.push(Transfer {
+                        wallet: &timeline.wallet,
+                        id,
+                        timestamp: *timestamp,
+                        amount,
+                        token,
+                        counterparty: source.as_deref(),
+                    }),
+                    _ => {}
+                }
+            }
+        }
+        withdrawals.sort_by_key(|t| t.timestamp);
+        deposits.sort_by_key(|t| t.timestamp);
+
+        let window = Duration::minutes(TRANSFER_MATCH_WINDOW_MINUTES);
+        let tolerance = BigDecimal::from(WITHDRAWAL_FEE_TOLERANCE);
+        let mut matched = vec![false; deposits.len()];
+        let mut transfers = Vec::new();
+
+        for withdrawal in &withdrawals {
+            let is_match = |deposit: &Transfer| match withdrawal.counterparty {
+                Some(destination) => deposit.counterparty.is_some_and(|source| {
+                    source.eq_ignore_ascii_case(withdrawal.wallet)
+                        && deposit.wallet.eq_ignore_ascii_case(destination)
+                        && deposit.timestamp == withdrawal.timestamp
+                        && deposit.amount == withdrawal.amount
+                }),
+                None => {
+                    deposit.counterparty.is_none()
+                        && !deposit.wallet.eq_ignore_ascii_case(withdrawal.wallet)
+                        && deposit.timestamp >= withdrawal.timestamp
+                        && deposit.timestamp - withdrawal.timestamp <= window
+                        && deposit.amount <= withdrawal.amount
+                        && withdrawal.amount - deposit.amount <= tolerance
+                }
+            };
+            let candidate = (0..deposits.len()).find(|&index| {
+                !matched[index]
+                    && deposits[index].token == withdrawal.token
+                    && is_match(&deposits[index])
+            });
+            let Some(index) = candidate else {
+                continue;
+            };
+            matched[index] = true;
+
+            let deposit = &deposits[index];
+            transfers.push(InternalTransfer {
+                from_wallet: withdrawal.wallet.to_string(),
+                to_wallet: deposit.wallet.to_string(),
+                withdrawal_id: withdrawal.id.to_string(),
+                deposit_id: deposit.id.to_string(),
+                withdrawn_at: withdrawal.timestamp,
+                deposited_at: deposit.timestamp,
+                amount: withdrawal.amount.clone(),
+                received: deposit.amount.clone(),
+                token: withdrawal.token.to_string(),
+                match_kind: match withdrawal.counterparty {
+                    Some(_) => TransferMatch::Exact,
+                    None => TransferMatch::Inferred,
+                },
+            });
+        }
+
+        transfers
+    }
+}
+
+impl Default for PortfolioService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,398 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::str::FromStr;
+
+use crate::services::pnl_calculator::CalculationMetadata;
+use crate::services::timeline::{event_id, signed_size, TimelineEvent, DEFAULT_COLLATERAL};
+
+/// Decimal places kept for derived prices
+const PRICE_SCALE: i64 = 8;
+
+/// A block of a position acquired by a single fill
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lot {
+    pub opened_at: DateTime<Utc>,
+    pub size: BigDecimal,
+    pub price: BigDecimal,
+}
+
+/// An open lot, with what closing it at the current mid price would realize
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenLot {
+    pub opened_at: DateTime<Utc>,
+    pub size: BigDecimal,
+    pub price: BigDecimal,
+    pub cost_basis: BigDecimal,
+    /// Whole days held as of the report
+    pub held_days: i64,
+    pub unrealized_pnl: Option<BigDecimal>,
+}
+
+/// The lots making up one open position, oldest first, the order FIFO closes them in.
+///
+/// When `basis_incomplete` is set, the oldest lot holds the size carried from before
+/// history began, priced at the first fill seen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionLots {
+    pub coin: String,
+    pub direction: String,
+    pub size: BigDecimal,
+    pub average_entry_price: Option<BigDecimal>,
+    pub cost_basis: BigDecimal,
+    pub mark_price: Option<BigDecimal>,
+    pub unrealized_pnl: Option<BigDecimal>,
+    pub basis_incomplete: bool,
+    pub lots: Vec<OpenLot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenLotsReport {
+    pub wallet: String,
+    pub as_of: DateTime<Utc>,
+    pub positions: Vec<PositionLots>,
+}
+
+/// How a fill divided between reducing the open position and opening a new one.
+///
+/// Only a fill that flips the position has both portions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillSplit {
+    pub closing_size: BigDecimal,
+    pub opening_size: BigDecimal,
+    /// FIFO PnL realized by the closing portion
+    pub realized_pnl: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionSnapshot {
+    pub coin: String,
+    pub size: BigDecimal,
+    pub average_entry_price: Option<BigDecimal>,
+    pub cost_basis: BigDecimal,
+    pub realized_pnl: BigDecimal,
+    /// Set when history started mid-position and part of the basis was estimated
+    pub basis_incomplete: bool,
+}
+
+/// Reconstructed account state at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateAt {
+    pub wallet: String,
+    pub as_of: DateTime<Utc>,
+    pub events_replayed: usize,
+    pub positions: Vec<PositionSnapshot>,
+    pub realized_pnl: BigDecimal,
+    pub funding_pnl: BigDecimal,
+    #[serde(with = "crate::output::cost")]
+    pub trading_fees: BigDecimal,
+    pub net_pnl: BigDecimal,
+}
+
+/// A fill that has not happened, used for what-if previews
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HypotheticalFill {
+    pub coin: String,
+    /// "B" for buy, "A" for sell, as on Hyperliquid
+    pub side: String,
+    pub size: BigDecimal,
+    pub price: BigDecimal,
+    #[serde(default)]
+    pub fee: BigDecimal,
+    /// Epoch milliseconds; defaults to now so the fill lands after real history. Times before
+    /// the coin's last real fill are rejected, as replaying that fill would reseed over it.
+    pub time: Option<i64>,
+}
+
+impl HypotheticalFill {
+    pub fn to_event(&self, index: usize) -> TimelineEvent {
+        let timestamp = self
+            .time
+            .and_then(DateTime::from_timestamp_millis)
+            .unwrap_or_else(Utc::now);
+
+        TimelineEvent::Fill {
+            id: event_id("preview", "fill", &index.to_string()),
+            timestamp,
+            coin: self.coin.clone(),
+            side: self.side.clone(),
+            size: self.size.clone(),
+            price: self.price.clone(),
+            coin_units: None,
+            fee: self.fee.clone(),
+            fee_token: DEFAULT_COLLATERAL.to_string(),
+            realized_pnl: None,
+            start_position: None,
+            crossed: false,
+            order_id: None,
+            tx_hash: None,
+            flags: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PnlPreview {
+    pub wallet: String,
+    pub hypothetical_fills: usize,
+    pub changes: Vec<PositionChange>,
+    pub realized_pnl_change: BigDecimal,
+    #[serde(with = "crate::output::cost")]
+    pub fees: BigDecimal,
+    pub net_pnl_change: BigDecimal,
+    pub calculation: CalculationMetadata,
+}
+
+/// Effect of hypothetical fills on one coin's position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionChange {
+    pub coin: String,
+    pub size_before: BigDecimal,
+    pub size_after: BigDecimal,
+    pub cost_basis_before: BigDecimal,
+    pub cost_basis_after: BigDecimal,
+    pub realized_pnl_change: BigDecimal,
+}
+
+/// Open lots and FIFO-realized PnL for one coin
+#[derive(Debug, Clone, Default)]
+struct PositionBook {
+    /// Signed position size (long positive, short negative)
+    size: BigDecimal,
+    lots: VecDeque<Lot>,
+    realized_pnl: BigDecimal,
+    basis_incomplete: bool,
+}
+
+impl PositionBook {
+    fn cost_basis(&self) -> BigDecimal {
+        self.lots
+            .iter()
+            .fold(BigDecimal::zero(), |acc, lot| acc + &lot.size * &lot.price)
+    }
+
+    fn snapshot(&self, coin: &str) -> PositionSnapshot {
+        let cost_basis = self.cost_basis();
+        let average_entry_price =
+            (!self.size.is_zero()).then(|| (&cost_basis / self.size.abs()).round(PRICE_SCALE));
+
+        PositionSnapshot {
+            coin: coin.to_string(),
+            size: self.size.clone(),
+            average_entry_price,
+            cost_basis,
+            realized_pnl: self.realized_pnl.clone(),
+            basis_incomplete: self.basis_incomplete,
+        }
+    }
+}
+
+/// Replays fills into per-coin FIFO lots
+#[derive(Debug, Clone, Default)]
+pub struct CostBasisEngine {
+    books: BTreeMap<String, PositionBook>,
+}
+
+impl CostBasisEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds an engine from a sequence of events in timeline order
+    pub fn replay<'a>(events: impl IntoIterator<Item = &'a TimelineEvent>) -> Self {
+        let mut engine = Self::new();
+        for event in events {
+            engine.apply(event);
+        }
+        engine
+    }
+
+    /// Applies a timeline event; only fills change positions, and return how they split
+    pub fn apply(&mut self, event: &TimelineEvent) -> Option<FillSplit> {
+        let TimelineEvent::Fill {
+            timestamp,
+            coin,
+            side,
+            size,
+            price,
+            start_position,
+            ..
+        } = event
+        else {
+            return None;
+        };
+
+        let book = self.books.entry(coin.clone()).or_default();
+
+        // History began mid-position: seed it at this fill's price
+        if let Some(start) = start_position
+            && *start != book.size
+        {
+            book.lots.clear();
+            if !start.is_zero() {
+                book.lots.push_back(Lot {
+                    opened_at: *timestamp,
+                    size: start.abs(),
+                    price: price.clone(),
+                });
+            }
+            book.size = start.clone();
+            book.basis_incomplete = true;
+        }
+
+        let delta = signed_size(side, size);
+        let is_long = book.size > BigDecimal::zero();
+        let increasing = book.size.is_zero() || is_long == (delta > BigDecimal::zero());
+
+        if increasing {
+            book.lots.push_back(Lot {
+                opened_at: *timestamp,
+                size: size.clone(),
+                price: price.clone(),
+            });
+            book.size = &book.size + &delta;
+            return Some(FillSplit {
+                closing_size: BigDecimal::zero(),
+                opening_size: size.clone(),
+                realized_pnl: BigDecimal::zero(),
+            });
+        }
+
+        let mut remaining = size.clone();
+        let mut realized_pnl = BigDecimal::zero();
+        while !remaining.is_zero() {
+            let Some(lot) = book.lots.front_mut() else {
+                break;
+            };
+
+            let consumed = remaining.clone().min(lot.size.clone());
+            let pnl = if is_long {
+                &consumed * (price - &lot.price)
+            } else {
+                &consumed * (&lot.price - price)
+            };
+            realized_pnl = &realized_pnl + pnl;
+
+            lot.size = &lot.size - &consumed;
+            remaining = &remaining - &consumed;
+
+            if lot.size.is_zero() {
+                book.lots.pop_front();
+            }
+        }
+
+        book.realized_pnl = &book.realized_pnl + &realized_pnl;
+
+        // Whatever is left after closing every lot opens the opposite side, at the fill price
+        if !remaining.is_zero() {
+            book.lots.push_back(Lot {
+                opened_at: *timestamp,
+                size: remaining.clone(),
+                price: price.clone(),
+            });
+        }
+
+        book.size = &book.size + &delta;
+        Some(FillSplit {
+            closing_size: size - &remaining,
+            opening_size: remaining,
+            realized_pnl,
+        })
+    }
+
+    /// Returns the current state of every coin that has been traded
+    pub fn snapshot(&self) -> Vec<PositionSnapshot> {
+        self.books
+            .iter()
+            .map(|(coin, book)| book.snapshot(coin))
+            .collect()
+    }
+
+    /// Open lots of every open position, valued at `mids` where they have the coin
+    pub fn open_lots(&self, mids: &Value, as_of: DateTime<Utc>) -> Vec<PositionLots> {
+        self.books
+            .iter()
+            .filter(|(_, book)| !book.size.is_zero())
+            .map(|(coin, book)| {
+                let snapshot = book.snapshot(coin);
+                let is_long = book.size > BigDecimal::zero();
+                let mark_price = mids
+                    .get(coin)
+                    .and_then(|mid| mid.as_str())
+                    .and_then(|mid| BigDecimal::from_str(mid).ok());
+                let pnl = |size: &BigDecimal, price: &BigDecimal| {
+                    mark_price.as_ref().map(|mark| {
+                        if is_long {
+                            size * (mark - price)
+                        } else {
+                            size * (price - mark)
+                        }
+                    })
+                };
+
+                let lots: Vec<OpenLot> = book
+                    .lots
+                    .iter()
+                    .map(|lot| OpenLot {
+                        opened_at: lot.opened_at,
+                        size: lot.size.clone(),
+                        price: lot.price.clone(),
+                        cost_basis: &lot.size * &lot.price,
+                        held_days: (as_of - lot.opened_at).num_days().max(0),
+                        unrealized_pnl: pnl(&lot.size, &lot.price),
+                    })
+                    .collect();
+                let unrealized_pnl = lots
+                    .iter()
+                    .map(|lot| lot.unrealized_pnl.clone())
+                    .sum::<Option<BigDecimal>>();
+
+                PositionLots {
+                    coin: coin.clone(),
+                    direction: if is_long { "long" } else { "short" }.to_string(),
+                    size: snapshot.size,
+                    average_entry_price: snapshot.average_entry_price,
+                    cost_basis: snapshot.cost_basis,
+                    mark_price,
+                    unrealized_pnl,
+                    basis_incomplete: snapshot.basis_incomplete,
+                    lots,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the current state of one coin, if it has been traded
+    pub fn position(&self, coin: &str) -> Option<PositionSnapshot> {
+        self.books.get(coin).map(|book| book.snapshot(coin))
+    }
+}
+
+/// Compares two engine states coin by coin, listing only coins that changed
+pub fn diff_positions(before: &CostBasisEngine, after: &CostBasisEngine) -> Vec<PositionChange> {
+    let empty = PositionBook::default();
+
+    after
+        .books
+        .iter()
+        .filter_map(|(coin, after_book)| {
+            let before_book = before.books.get(coin).unwrap_or(&empty);
+            let realized_pnl_change = &after_book.realized_pnl - &before_book.realized_pnl;
+
+            if after_book.size == before_book.size && realized_pnl_change.is_zero() {
+                return None;
+            }
+
+            Some(PositionChange {
+                coin: coin.clone(),
+                size_before: before_book.size.clone(),
+                size_after: after_book.size.clone(),
+                cost_basis_before: before_book.cost_basis(),
+                cost_basis_after: after_book.cost_basis(),
+                realized_pnl_change,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,356 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::datasource::evm::{OnchainTransfer, TransferDirection};
+use crate::services::timeline::{signed_size, Timeline, TimelineEvent};
+
+/// Hyperliquid settles funding every hour
+const FUNDING_INTERVAL_MS: i64 = 60 * 60 * 1000;
+
+/// Largest delay between a bridge transfer and its exchange ledger update
+const TRANSFER_MATCH_WINDOW_MS: i64 = 60 * 60 * 1000;
+
+/// Withdrawals arrive on-chain net of the bridge fee (1 USDC)
+const WITHDRAWAL_FEE_TOLERANCE: i64 = 1;
+
+/// Only Hyperliquid ledger updates pass through the bridge
+const BRIDGE_EVENT_PREFIX: &str = "hyperliquid:";
+
+/// Most recent bridge transfers cross-checked on-chain per report, bounding the RPC calls
+const MAX_CHECKED_BRIDGE_TRANSFERS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapKind {
+    /// A funding payment arrived while fills say the position was flat
+    FundingWithoutPosition,
+    /// Funding reports a position on the other side with no fill in between
+    PositionFlipWithoutFill,
+    /// Hourly funding payments are missing while a position was open
+    MissingFunding,
+    /// The exchange reports a deposit with no matching bridge transfer
+    DepositNotOnChain,
+    /// The exchange reports a withdrawal with no matching bridge transfer
+    WithdrawalNotOnChain,
+    /// A bridge transfer has no matching exchange ledger update
+    UnrecordedOnChainTransfer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gap {
+    pub kind: GapKind,
+    pub coin: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GapReport {
+    pub wallet: String,
+    pub events_checked: usize,
+    /// Whether deposits and withdrawals were cross-checked against the bridge contract
+    pub onchain_checked: bool,
+    /// Time of the oldest ledger transfer cross-checked; older ones were not checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub onchain_checked_from: Option<DateTime<Utc>>,
+    pub gaps: Vec<Gap>,
+}
+
+/// Where to look on-chain for a wallet's most recent bridge transfers
+#[derive(Debug, Clone)]
+pub struct BridgeScan {
+    /// Block time ranges (epoch ms), oldest first
+    pub windows: Vec<(i64, i64)>,
+    /// Time of the oldest ledger transfer the windows cover
+    pub checked_from: DateTime<Utc>,
+}
+
+/// Per-coin state carried between events while scanning for gaps
+#[derive(Default)]
+struct CoinState {
+    position: BigDecimal,
+    opened_at: Option<DateTime<Utc>>,
+    last_funding: Option<DateTime<Utc>>,
+    last_funding_size: Option<BigDecimal>,
+    filled_since_funding: bool,
+    seen_fill: bool,
+}
+
+pub struct ReconciliationService;
+
+impl ReconciliationService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Scans a timeline for inconsistencies between fills and funding payments
+    pub fn detect_gaps(&self, timeline: &Timeline) -> GapReport {
+        let mut coins: HashMap<String, CoinState> = HashMap::new();
+        let mut gaps = Vec::new();
+
+        for event in &timeline.events {
+            match event {
+                TimelineEvent::Fill {
+                    timestamp,
+                    coin,
+                    side,
+                    size,
+                    start_position,
+                    ..
+                } => {
+                    let state = coins.entry(coin.clone()).or_default();
+                    if let Some(start) = start_position {
+                        state.position = start.clone();
+                    }
+
+                    let was_flat = state.position.is_zero();
+                    state.position = &state.position + signed_size(side, size);
+                    state.filled_since_funding = true;
+                    state.seen_fill = true;
+
+                    if state.position.is_zero() {
+                        state.opened_at = None;
+                        state.last_funding = None;
+                    } else if was_flat {
+                        state.opened_at = Some(*timestamp);
+                    }
+                }
+                TimelineEvent::Funding {
+                    timestamp,
+                    coin,
+                    amount,
+                    position_size,
+                    ..
+                } => {
+                    let state = coins.entry(coin.clone()).or_default();
+
+                    // Funding before the first fill in range: trust the reported size
+                    if !state.seen_fill
+                        && let Some(size) = position_size
+                    {
+                        state.position = size.clone();
+                    }
+
+                    if state.position.is_zero() {
+                        gaps.push(Gap {
+                            kind: GapKind::FundingWithoutPosition,
+                            coin: coin.clone(),
+                            from: *timestamp,
+                            to: *timestamp,
+                            detail: format!(
+                                "Funding of {} with no open position from fills",
+                                amount
+                            ),
+                        });
+                    }
+
+                    if let (Some(previous), Some(current)) =
+                        (&state.last_funding_size, position_size)
+                        && !state.filled_since_funding
+                        && (*previous > BigDecimal::zero()) != (*current > BigDecimal::zero())
+                    {
+                        gaps.push(Gap {
+                            kind: GapKind::PositionFlipWithoutFill,
+                            coin: coin.clone(),
+                            from: state.last_funding.unwrap_or(*timestamp),
+                            to: *timestamp,
+                            detail: format!(
+                                "Position went from {} to {} without a fill",
+                                previous, current
+                            ),
+                        });
+                    }
+
+                    let missing = match (state.last_funding, state.opened_at) {
+                        (Some(last), _) => funding_hour(*timestamp) - funding_hour(last) - 1,
+                        (None, Some(opened)) => {
+                            funding_hour(*timestamp)
+                                - opened.timestamp_millis().div_euclid(FUNDING_INTERVAL_MS)
+                                - 1
+                        }
+                        (None, None) => 0,
+                    };
+
+                    if missing > 0 {
+                        gaps.push(Gap {
+                            kind: GapKind::MissingFunding,
+                            coin: coin.clone(),
+                            from: state.last_funding.or(state.opened_at).unwrap_or(*timestamp),
+                            to: *timestamp,
+                            detail: format!(
+                                "{} hourly funding payment(s) missing on an open position",
+                                missing
+                            ),
+                        });
+                    }
+
+                    state.last_funding = Some(*timestamp);
+                    state.last_funding_size = position_size.clone();
+                    state.filled_since_funding = false;
+                }
+                _ => {}
+            }
+        }
+
+        gaps.sort_by_key(|gap| gap.from);
+
+        GapReport {
+            wallet: timeline.wallet.clone(),
+            events_checked: timeline.events.len(),
+            onchain_checked: false,
+            onchain_checked_from: None,
+            gaps,
+        }
+    }
+
+    /// Plans the on-chain scan for the most recent bridge transfers, or None without any.
+    ///
+    /// Each window spans the match window around one transfer, with overlapping windows
+    /// merged, so the scan covers a few blocks per transfer rather than the whole history.
+    pub fn bridge_scan(&self, timeline: &Timeline) -> Option<BridgeScan> {
+        let transfers: Vec<&TimelineEvent> = bridge_transfers(timeline).collect();
+        let recent = &transfers[transfers.len().saturating_sub(MAX_CHECKED_BRIDGE_TRANSFERS)..];
+        let checked_from = recent.first()?.timestamp();
+
+        let mut windows: Vec<(i64, i64)> = Vec::new();
+        for time in recent.iter().map(|e| e.timestamp().timestamp_millis()) {
+            let (start, end) = (
+                time - TRANSFER_MATCH_WINDOW_MS,
+                time + TRANSFER_MATCH_WINDOW_MS,
+            );
+            match windows.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => windows.push((start, end)),
+            }
+        }
+
+        Some(BridgeScan {
+            windows,
+            checked_from,
+        })
+    }
+
+    /// Matches exchange-reported deposits and withdrawals against bridge transfers.
+    ///
+    /// Transfers match when they go the same direction within an hour of each other and the
+    /// amounts agree, allowing for the bridge fee on withdrawals. Ledger transfers before
+    /// `checked_from` were not scanned for and are skipped.
+    pub fn check_transfers(
+        &self,
+        timeline: &Timeline,
+        onchain: &[OnchainTransfer],
+        checked_from: DateTime<Utc>,
+    ) -> Vec<Gap> {
+        let mut matched = vec![false; onchain.len()];
+        let mut gaps = Vec::new();
+        let tolerance = BigDecimal::from(WITHDRAWAL_FEE_TOLERANCE);
+
+        let bridge_events =
+            bridge_transfers(timeline).filter(|event| event.timestamp() >= checked_from);
+
+        for event in bridge_events {
+            let (direction, timestamp, amount, token, kind) = match event {
+                TimelineEvent::Deposit {
+                    timestamp,
+                    amount,
+                    token,
+                    ..
+                } => (
+                    TransferDirection::Deposit,
+                    timestamp,
+                    amount,
+                    token,
+                    GapKind::DepositNotOnChain,
+                ),
+                TimelineEvent::Withdrawal {
+                    timestamp,
+                    amount,
+                    token,
+                    ..
+                } => (
+                    TransferDirection::Withdrawal,
+                    timestamp,
+                    amount,
+                    token,
+                    GapKind::WithdrawalNotOnChain,
+                ),
+                _ => continue,
+            };
+
+            let found = onchain.iter().enumerate().position(|(i, transfer)| {
+                let difference = (amount - &transfer.amount).abs();
+                !matched[i]
+                    && transfer.direction == direction
+                    && (transfer.timestamp - *timestamp).num_milliseconds().abs()
+                        <= TRANSFER_MATCH_WINDOW_MS
+                    && match direction {
+                        TransferDirection::Deposit => difference.is_zero(),
+                        TransferDirection::Withdrawal => difference <= tolerance,
+                    }
+            });
+
+            match found {
+                Some(i) => matched[i] = true,
+                None => gaps.push(Gap {
+                    kind,
+                    coin: token.clone(),
+                    from: *timestamp,
+                    to: *timestamp,
+                    detail: format!(
+                        "{} of {} has no matching bridge transfer",
+                        event.id(),
+                        amount
+                    ),
+                }),
+            }
+        }
+
+        for (transfer, _) in onchain
+            .iter()
+            .zip(&matched)
+            .filter(|(_, matched)| !**matched)
+        {
+            gaps.push(Gap {
+                kind: GapKind::UnrecordedOnChainTransfer,
+                coin: "USDC".to_string(),
+                from: transfer.timestamp,
+                to: transfer.timestamp,
+                detail: format!(
+                    "Bridge {:?} of {} in {} has no ledger update",
+                    transfer.direction, transfer.amount, transfer.tx_hash
+                ),
+            });
+        }
+
+        gaps
+    }
+}
+
+impl Default for ReconciliationService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Deposits and withdrawals that passed through the bridge
+fn bridge_transfers(timeline: &Timeline) -> impl Iterator<Item = &TimelineEvent> {
+    timeline.events.iter().filter(|event| {
+        event.id().starts_with(BRIDGE_EVENT_PREFIX)
+            && matches!(
+                event,
+                TimelineEvent::Deposit { source: None, .. }
+                    | TimelineEvent::Withdrawal {
+                        destination: None,
+                        ..
+                    }
+            )
+    })
+}
+
+/// Index of the funding hour a payment belongs to; payments land a few ms after the hour
+fn funding_hour(timestamp: DateTime<Utc>) -> i64 {
+    (timestamp.timestamp_millis() + FUNDING_INTERVAL_MS / 2).div_euclid(FUNDING_INTERVAL_MS)
+}
@@ -0,0 +1,84 @@
+use serde::Serialize;
+use tera::{Context, Tera};
+
+use crate::error::{AppError, AppResult};
+
+/// Template rendering a statement from `/reports/statement`
+pub const STATEMENT_TEMPLATE: &str = "statement.html";
+
+/// Template rendering the message body of a fired alert
+pub const ALERT_TEMPLATE: &str = "alert.txt";
+
+/// Templates used when a deployment provides none under the same name
+const BUILTIN_TEMPLATES: [(&str, &str); 2] = [
+    (
+        STATEMENT_TEMPLATE,
+        include_str!("../templates/statement.html"),
+    ),
+    (ALERT_TEMPLATE, include_str!("../templates/alert.txt")),
+];
+
+/// Renders reports and notification bodies from Tera templates.
+///
+/// Templates in the configured directory are named by their path relative to it and take
+/// precedence over the built-in ones, so deployments can rebrand or trim outputs without
+/// code changes.
+pub struct ReportRenderer {
+    tera: Tera,
+}
+
+impl ReportRenderer {
+    pub fn new(template_dir: Option<&str>) -> AppResult<Self> {
+        let mut tera = match template_dir {
+            Some(dir) => {
+                Tera::new(&format!("{}/**/*", dir.trim_end_matches('/'))).map_err(|e| {
+                    AppError::InternalError(format!("Failed to load templates from {}: {}", dir, e))
+                })?
+            }
+            None => Tera::default(),
+        };
+
+        let mut builtin = Tera::default();
+        builtin
+            .add_raw_templates(BUILTIN_TEMPLATES)
+            .map_err(|e| AppError::InternalError(format!("Invalid built-in template: {}", e)))?;
+        tera.extend(&builtin)
+            .map_err(|e| AppError::InternalError(format!("Failed to merge templates: {}", e)))?;
+
+        Ok(Self { tera })
+    }
+
+    /// Names of all available templates, sorted
+    pub fn templates(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.tera.get_template_names().map(str::to_string).collect();
+        names.sort();
+        names
+    }
+
+    /// Renders a template with each top-level field of `context` as a variable
+    pub fn render(&self, template: &str, context: &impl Serialize) -> AppResult<String> {
+        if !self.tera.get_template_names().any(|name| name == template) {
+            return Err(AppError::NotFound(format!(
+                "Template {} not found",
+                template
+            )));
+        }
+
+        let context = Context::from_serialize(context)
+            .map_err(|e| AppError::InternalError(format!("Invalid template context: {}", e)))?;
+        self.tera
+            .render(template, &context)
+            .map_err(|e| AppError::InternalError(format!("Failed to render {}: {}", template, e)))
+    }
+}
+
+/// Content type for a rendered template, from its file extension
+pub fn content_type(template: &str) -> &'static str {
+    match template.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("html") | Some("htm") => "text/html; charset=utf-8",
+        Some("csv") => "text/csv; charset=utf-8",
+        Some("json") => "application/json",
+        Some("md") => "text/markdown; charset=utf-8",
+        _ => "text/plain; charset=utf-8",
+    }
+}
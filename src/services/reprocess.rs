@@ -0,0 +1,103 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::ingestion::IngestionService;
+use crate::services::jobs::{Job, JobRegistry, JobStatus};
+
+pub const REPROCESS_JOB_KIND: &str = "reprocess";
+
+/// Wallet recorded on reprocess jobs, which cover every stored wallet
+const ALL_WALLETS: &str = "*";
+
+/// Re-derives stored wallets from their raw payloads after the normalization version changes.
+///
+/// Each run is tracked as a job whose steps are the wallets it has brought up to date.
+pub struct ReprocessService {
+    ingestion_service: Arc<IngestionService>,
+    job_registry: Arc<JobRegistry>,
+}
+
+impl ReprocessService {
+    pub fn new(ingestion_service: Arc<IngestionService>, job_registry: Arc<JobRegistry>) -> Self {
+        Self {
+            ingestion_service,
+            job_registry,
+        }
+    }
+
+    /// Queues a reprocess run over all outdated wallets
+    pub fn start(self: &Arc<Self>) -> AppResult<Job> {
+        let active = self.job_registry.list().into_iter().find(|job| {
+            job.kind == REPROCESS_JOB_KIND
+                && matches!(job.status, JobStatus::Queued | JobStatus::Running)
+        });
+        if let Some(job) = active {
+            return Err(AppError::ValidationError(format!(
+                "Reprocess job {} is already {:?}",
+                job.id, job.status
+            )));
+        }
+
+        let job = self.job_registry.create(REPROCESS_JOB_KIND, ALL_WALLETS);
+        self.spawn(job.id);
+        Ok(job)
+    }
+
+    /// Starts a run if any stored wallet predates the current normalization version
+    pub async fn start_if_outdated(self: &Arc<Self>) -> AppResult<Option<Job>> {
+        let outdated = self.ingestion_service.outdated_wallets().await?;
+        if outdated.is_empty() {
+            return Ok(None);
+        }
+
+        tracing::info!("{} stored wallets need reprocessing", outdated.len());
+        self.start().map(Some)
+    }
+
+    fn spawn(self: &Arc<Self>, id: Uuid) {
+        let service = Arc::clone(self);
+
+        tokio::spawn(async move {
+            service
+                .job_registry
+                .update(id, |job| job.status = JobStatus::Running);
+
+            match service.reprocess(id).await {
+                Ok(()) => {
+                    tracing::info!("Reprocess job {} completed", id);
+                    service
+                        .job_registry
+                        .update(id, |job| job.status = JobStatus::Completed);
+                }
+                Err(e) => {
+                    tracing::error!("Reprocess job {} failed: {}", id, e);
+                    service.job_registry.update(id, |job| {
+                        job.status = JobStatus::Failed;
+                        job.error = Some(e.to_string());
+                    });
+                }
+            }
+        });
+    }
+
+    async fn reprocess(&self, id: Uuid) -> AppResult<()> {
+        let wallets = self.ingestion_service.outdated_wallets().await?;
+        self.job_registry
+            .update(id, |job| job.total_steps = wallets.len());
+
+        for wallet in wallets {
+            if !self.ingestion_service.reprocess_wallet(&wallet).await? {
+                tracing::info!(
+                    "No raw payloads to reprocess wallet {}; it updates on next sync",
+                    wallet
+                );
+            }
+
+            self.job_registry
+                .update(id, |job| job.completed_steps.push(wallet));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use bigdecimal::{BigDecimal, One, Signed, Zero};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+
+use crate::error::{AppError, AppResult};
+
+/// Decimal places kept for prices, amounts and ratios
+const SCENARIO_SCALE: i64 = 8;
+
+/// Coins not counted as alts
+const MAJOR_COINS: [&str; 2] = ["BTC", "ETH"];
+
+/// Scenarios applied when neither the request nor the deployment names any
+pub const DEFAULT_SCENARIOS: &str = "all:-10;alts:-30;BTC:+5";
+
+/// Which positions a price move applies to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShockTarget {
+    All,
+    /// Every coin except BTC and ETH
+    Alts,
+    Coin(String),
+}
+
+impl ShockTarget {
+    fn matches(&self, coin: &str) -> bool {
+        match self {
+            ShockTarget::All => true,
+            ShockTarget::Alts => !MAJOR_COINS.contains(&coin),
+            ShockTarget::Coin(target) => target == coin,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceMove {
+    pub target: ShockTarget,
+    /// Percent change in price, e.g. `-10`
+    pub percent: BigDecimal,
+}
+
+/// A set of price moves applied together; later moves override earlier ones for a coin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub moves: Vec<PriceMove>,
+}
+
+impl Scenario {
+    /// Parses moves written as `all:-10,BTC:+5`
+    pub fn parse(spec: &str) -> AppResult<Self> {
+        let wiped_out = BigDecimal::from(-100);
+        let moves = spec
+            .split(',')
+            .filter(|part| !part.trim().is_empty())
+            .map(|part| {
+                let (target, percent) = part.trim().split_once(':').ok_or_else(|| {
+                    AppError::ValidationError(format!(
+                        "Invalid price move {}; expected <all|alts|coin>:<percent>",
+                        part
+                    ))
+                })?;
+                let percent = BigDecimal::from_str(percent.trim_start_matches('+'))
+                    .ok()
+                    .filter(|percent| *percent > wiped_out)
+                    .ok_or_else(|| {
+                        AppError::ValidationError(format!(
+                            "Invalid percent in {}; must be above -100",
+                            part
+                        ))
+                    })?;
+                let target = match target {
+                    "all" => ShockTarget::All,
+                    "alts" => ShockTarget::Alts,
+                    coin => ShockTarget::Coin(coin.to_string()),
+                };
+                Ok(PriceMove { target, percent })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+
+        if moves.is_empty() {
+            return Err(AppError::ValidationError(
+                "A scenario needs at least one price move".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            name: spec.trim().to_string(),
+            moves,
+        })
+    }
+
+    /// Parses `;`-separated scenarios
+    pub fn parse_list(specs: &str) -> AppResult<Vec<Self>> {
+        specs
+            .split(';')
+            .filter(|spec| !spec.trim().is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+
+    /// Percent change for a coin, if any move applies to it
+    fn percent_for(&self, coin: &str) -> Option<&BigDecimal> {
+        self.moves
+            .iter()
+            .rev()
+            .find(|m| m.target.matches(coin))
+            .map(|m| &m.percent)
+    }
+}
+
+/// A perp position as reported in the account state
+#[derive(Debug, Clone)]
+struct OpenPosition {
+    coin: String,
+    size: BigDecimal,
+    mark_price: BigDecimal,
+    liquidation_price: Option<BigDecimal>,
+    margin_used: BigDecimal,
+}
+
+impl OpenPosition {
+    fn from_value(value: &Value) -> Option<Self> {
+        let position = value.get("position")?;
+        let decimal = |key: &str| {
+            position
+                .get(key)
+                .and_then(|v| v.as_str())
+                .and_then(|v| BigDecimal::from_str(v).ok())
+        };
+
+        let size = decimal("szi").filter(|size| !size.is_zero())?;
+        let mark_price = Some((decimal("positionValue")? / size.abs()).round(SCENARIO_SCALE))
+            .filter(|price| *price > BigDecimal::zero())?;
+
+        Some(Self {
+            coin: position.get("coin")?.as_str()?.to_string(),
+            mark_price,
+            liquidation_price: decimal("liquidationPx"),
+            margin_used: decimal("marginUsed").unwrap_or_default(),
+            size,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionShock {
+    pub coin: String,
+    pub size: BigDecimal,
+    pub mark_price: BigDecimal,
+    pub shocked_price: BigDecimal,
+    pub pnl: BigDecimal,
+    pub liquidation_price: Option<BigDecimal>,
+    /// Whether the shocked price crosses the position's liquidation price
+    pub liquidated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioResult {
+    pub scenario: Scenario,
+    pub pnl: BigDecimal,
+    pub account_value: BigDecimal,
+    pub margin_used: BigDecimal,
+    /// Margin used over account value; None when the account would be wiped out
+    pub margin_usage: Option<BigDecimal>,
+    pub maintenance_margin: BigDecimal,
+    /// Whether account value would fall below cross maintenance margin
+    pub below_maintenance: bool,
+    pub positions: Vec<PositionShock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioReport {
+    pub wallet: String,
+    pub account_value: BigDecimal,
+    pub margin_used: BigDecimal,
+    pub maintenance_margin: BigDecimal,
+    pub scenarios: Vec<ScenarioResult>,
+}
+
+/// Applies price shocks to a wallet's current perp positions.
+///
+/// Margin requirements scale with each position's notional at the shocked price. Liquidation
+/// uses the price the exchange reports for each position, which for cross margin assumes
+/// other positions stay put, alongside an account-level check against maintenance margin.
+pub struct ScenarioAnalyzer {
+    defaults: Vec<Scenario>,
+}
+
+impl ScenarioAnalyzer {
+    pub fn new(defaults: Vec<Scenario>) -> Self {
+        Self { defaults }
+    }
+
+    /// Scenarios applied when a request names none
+    pub fn defaults(&self) -> &[Scenario] {
+        &self.defaults
+    }
+
+    pub fn analyze(
+        &self,
+        wallet: &str,
+        user_state: &Value,
+        scenarios: &[Scenario],
+    ) -> ScenarioReport {
+        let summary = |key: &str| {
+            user_state
+                .get("marginSummary")
+                .and_then(|s| s.get(key))
+                .and_then(|v| v.as_str())
+                .and_then(|v| BigDecimal::from_str(v).ok())
+                .unwrap_or_default()
+        };
+        let account_value = summary("accountValue");
+        let margin_used = summary("totalMarginUsed");
+        let maintenance_margin = user_state
+            .get("crossMaintenanceMarginUsed")
+            .and_then(|v| v.as_str())
+            .and_then(|v| BigDecimal::from_str(v).ok())
+            .unwrap_or_default();
+
+        let positions: Vec<OpenPosition> = user_state
+            .get("assetPositions")
+            .and_then(|positions| positions.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(OpenPosition::from_value)
+            .collect();
+
+        let scenarios = scenarios
+            .iter()
+            .map(|scenario| {
+                apply(
+                    scenario,
+                    &positions,
+                    &account_value,
+                    &margin_used,
+                    &maintenance_margin,
+                )
+            })
+            .collect();
+
+        ScenarioReport {
+            wallet: wallet.to_string(),
+            account_value,
+            margin_used,
+            maintenance_margin,
+            scenarios,
+        }
+    }
+}
+
+fn apply(
+    scenario: &Scenario,
+    positions: &[OpenPosition],
+    account_value: &BigDecimal,
+    margin_used: &BigDecimal,
+    maintenance_margin: &BigDecimal,
+) -> ScenarioResult {
+    let hundred = BigDecimal::from(100);
+    let mut pnl = BigDecimal::zero();
+    let mut shocked_margin = BigDecimal::zero();
+    let mut notional_before = BigDecimal::zero();
+    let mut notional_after = BigDecimal::zero();
+
+    let shocks: Vec<PositionShock> = positions
+        .iter()
+        .map(|p| {
+            let factor = BigDecimal::one()
+                + scenario
+                    .percent_for(&p.coin)
+                    .map_or_else(BigDecimal::zero, |percent| percent / &hundred);
+            let price = (&p.mark_price * factor).round(SCENARIO_SCALE);
+
+            let position_pnl = (&p.size * (&price - &p.mark_price)).round(SCENARIO_SCALE);
+            pnl = &pnl + &position_pnl;
+            shocked_margin = &shocked_margin + &p.margin_used * &price / &p.mark_price;
+            notional_before = &notional_before + (&p.size * &p.mark_price).abs();
+            notional_after = &notional_after + (&p.size * &price).abs();
+
+            let liquidated = p.liquidation_price.as_ref().is_some_and(|liq| {
+                if p.size.is_positive() {
+                    price <= *liq
+                } else {
+                    price >= *liq
+                }
+            });
+
+            PositionShock {
+                coin: p.coin.clone(),
+                size: p.size.clone(),
+                mark_price: p.mark_price.clone(),
+                shocked_price: price,
+                pnl: position_pnl,
+                liquidation_price: p.liquidation_price.clone(),
+                liquidated,
+            }
+        })
+        .collect();
+
+    // Maintenance margin scales with total notional at the shocked prices
+    let maintenance_after = if notional_before.is_zero() {
+        maintenance_margin.clone()
+    } else {
+        (maintenance_margin * &notional_after / &notional_before).round(SCENARIO_SCALE)
+    };
+
+    // Positions without a reported margin keep the account-level figure
+    let margin_after = if positions.iter().all(|p| p.margin_used.is_zero()) {
+        margin_used.clone()
+    } else {
+        shocked_margin.round(SCENARIO_SCALE)
+    };
+
+    let account_after = account_value + &pnl;
+    let margin_usage = (account_after > BigDecimal::zero())
+        .then(|| (&margin_after / &account_after).round(SCENARIO_SCALE));
+
+    ScenarioResult {
+        scenario: scenario.clone(),
+        pnl,
+        below_maintenance: account_after < maintenance_after,
+        account_value: account_after,
+        margin_used: margin_after,
+        margin_usage,
+        maintenance_margin: maintenance_after,
+        positions: shocks,
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::error::AppResult;
+use crate::services::timeline::TimelineEvent;
+use crate::storage::Storage;
+
+/// Numbers a wallet's timeline events in the order the service first served them.
+///
+/// Timestamps cannot order refreshes: fills share milliseconds, and late venue events or
+/// backfilled history arrive with timestamps before events already served. Sequence numbers
+/// only grow, so a client holding a cursor is sent exactly the events numbered from it on.
+pub struct EventSequencer {
+    storage: Arc<dyn Storage>,
+    /// Serializes numbering, so concurrent refreshes cannot hand out a number twice
+    lock: Mutex<()>,
+}
+
+impl EventSequencer {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Returns each event's sequence number, numbering unseen events after every earlier
+    /// one, and the number the next unseen event will get
+    pub async fn sequence(
+        &self,
+        wallet: &str,
+        events: &[TimelineEvent],
+    ) -> AppResult<(Vec<u64>, u64)> {
+        let wallet = wallet.to_lowercase();
+        let _guard = self.lock.lock().await;
+
+        let mut ids = self.storage.load_event_sequence(&wallet).await?;
+        let mut numbers: HashMap<String, u64> = ids
+            .iter()
+            .enumerate()
+            .map(|(number, id)| (id.clone(), number as u64))
+            .collect();
+        let known = ids.len();
+
+        let sequence = events
+            .iter()
+            .map(|event| {
+                *numbers.entry(event.id().to_string()).or_insert_with(|| {
+                    ids.push(event.id().to_string());
+                    (ids.len() - 1) as u64
+                })
+            })
+            .collect();
+
+        let next = ids.len() as u64;
+        if ids.len() > known {
+            self.storage.save_event_sequence(&wallet, ids).await?;
+        }
+        Ok((sequence, next))
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+use crate::datasource::cex::hmac_sha256;
+use crate::error::{AppError, AppResult};
+use crate::storage::Storage;
+
+/// Reports that can be shared through a link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SharedReportKind {
+    PnlSummary,
+    Statement,
+}
+
+/// A report frozen when its link was created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedReport {
+    pub id: Uuid,
+    pub report: SharedReportKind,
+    pub wallet: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub data: Value,
+}
+
+/// A signed link to a shared report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLink {
+    pub id: Uuid,
+    pub report: SharedReportKind,
+    pub wallet: String,
+    /// Path serving the snapshot, token included
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Issues expiring, signed links to read-only report snapshots.
+///
+/// Tokens are an HMAC of the snapshot ID and expiry, so a link cannot be extended or
+/// pointed at another snapshot. Without a configured secret, links stop working on restart.
+pub struct ShareService {
+    storage: Arc<dyn Storage>,
+    secret: Vec<u8>,
+}
+
+impl ShareService {
+    pub fn new(storage: Arc<dyn Storage>, secret: &str) -> Self {
+        Self {
+            storage,
+            secret: secret.as_bytes().to_vec(),
+        }
+    }
+
+    /// Stores a snapshot and returns a link to it valid for `ttl`
+    pub async fn create(
+        &self,
+        report: SharedReportKind,
+        wallet: &str,
+        data: Value,
+        ttl: Duration,
+    ) -> AppResult<ShareLink> {
+        let created_at = Utc::now();
+        let shared = SharedReport {
+            id: Uuid::new_v4(),
+            report,
+            wallet: wallet.to_string(),
+            created_at,
+            expires_at: created_at + ttl,
+            data,
+        };
+
+        let link = ShareLink {
+            id: shared.id,
+            report,
+            wallet: shared.wallet.clone(),
+            url: format!(
+                "/share/{}?token={}",
+                shared.id,
+                self.token(shared.id, shared.expires_at)
+            ),
+            expires_at: shared.expires_at,
+        };
+        self.storage.save_shared_report(shared).await?;
+
+        Ok(link)
+    }
+
+    /// Returns a snapshot if `token` was issued for it and it has not expired
+    pub async fn open(&self, id: Uuid, token: &str) -> AppResult<SharedReport> {
+        let not_found = || AppError::NotFound(format!("Shared report {} not found", id));
+
+        let shared = self
+            .storage
+            .load_shared_report(id)
+            .await?
+            .ok_or_else(not_found)?;
+        let expected = self.token(shared.id, shared.expires_at);
+        if !bool::from(token.as_bytes().ct_eq(expected.as_bytes())) {
+            return Err(AppError::Unauthorized("Invalid share token".to_string()));
+        }
+        if shared.expires_at <= Utc::now() {
+            return Err(not_found());
+        }
+
+        Ok(shared)
+    }
+
+    /// Deletes a wallet's shared reports, revoking their links
+    pub async fn forget_wallet(&self, wallet: &str) -> AppResult<usize> {
+        self.storage.delete_shared_reports(wallet).await
+    }
+
+    fn token(&self, id: Uuid, expires_at: DateTime<Utc>) -> String {
+        let message = format!("{}.{}", id, expires_at.timestamp_millis());
+        hex::encode(hmac_sha256(&self.secret, message.as_bytes()))
+    }
+}
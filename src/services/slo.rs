@@ -0,0 +1,211 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+/// Rolling windows reported for every endpoint and upstream, shortest first
+const WINDOWS: [(&str, i64); 3] = [("5m", 5 * 60), ("1h", 60 * 60), ("24h", 24 * 60 * 60)];
+
+/// Targets the rolling windows are measured against
+#[derive(Debug, Clone, Serialize)]
+pub struct SloTargets {
+    /// Fraction of calls that must succeed, e.g. `0.99`
+    pub success_rate: f64,
+    pub p95_latency_ms: u64,
+}
+
+impl Default for SloTargets {
+    fn default() -> Self {
+        Self {
+            success_rate: 0.99,
+            p95_latency_ms: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: DateTime<Utc>,
+    latency_ms: u64,
+    success: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WindowStats {
+    pub window: String,
+    pub calls: usize,
+    pub errors: usize,
+    /// None when there were no calls in the window
+    pub success_rate: Option<f64>,
+    pub p95_latency_ms: Option<u64>,
+    /// Share of the window's error budget still unspent; negative once overspent
+    pub error_budget_remaining: Option<f64>,
+    pub meets_slo: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SloEntry {
+    pub name: String,
+    pub windows: Vec<WindowStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SloReport {
+    pub generated_at: DateTime<Utc>,
+    pub targets: SloTargets,
+    /// API routes, keyed by method and route template
+    pub endpoints: Vec<SloEntry>,
+    /// Calls to external data sources, keyed by venue
+    pub upstreams: Vec<SloEntry>,
+}
+
+/// In-process success rate and latency tracking for API endpoints and upstream calls.
+///
+/// Samples are kept for the longest window and pruned as new ones arrive, so the report is
+/// available without an external metrics stack.
+pub struct SloTracker {
+    targets: SloTargets,
+    endpoints: RwLock<HashMap<String, VecDeque<Sample>>>,
+    upstreams: RwLock<HashMap<String, VecDeque<Sample>>>,
+}
+
+impl SloTracker {
+    pub fn new(targets: SloTargets) -> Self {
+        Self {
+            targets,
+            endpoints: RwLock::new(HashMap::new()),
+            upstreams: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn record_endpoint(&self, name: &str, latency_ms: u64, success: bool) {
+        record(&self.endpoints, name, latency_ms, success);
+    }
+
+    pub fn record_upstream(&self, name: &str, latency_ms: u64, success: bool) {
+        record(&self.upstreams, name, latency_ms, success);
+    }
+
+    pub fn report(&self) -> SloReport {
+        let now = Utc::now();
+
+        SloReport {
+            generated_at: now,
+            targets: self.targets.clone(),
+            endpoints: self.entries(&self.endpoints, now),
+            upstreams: self.entries(&self.upstreams, now),
+        }
+    }
+
+    /// Rolling windows for one upstream, if it has been called
+    pub fn upstream(&self, name: &str) -> Option<SloEntry> {
+        self.entries(&self.upstreams, Utc::now())
+            .into_iter()
+            .find(|entry| entry.name == name)
+    }
+
+    fn entries(
+        &self,
+        series: &RwLock<HashMap<String, VecDeque<Sample>>>,
+        now: DateTime<Utc>,
+    ) -> Vec<SloEntry> {
+        let series = series.read().expect("slo tracker lock poisoned");
+
+        let mut entries: Vec<SloEntry> = series
+            .iter()
+            .map(|(name, samples)| SloEntry {
+                name: name.clone(),
+                windows: WINDOWS
+                    .iter()
+                    .map(|(label, seconds)| {
+                        let since = now - Duration::seconds(*seconds);
+                        let window: Vec<&Sample> =
+                            samples.iter().filter(|s| s.at >= since).collect();
+                        self.window_stats(label, &window)
+                    })
+                    .collect(),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries
+    }
+
+    fn window_stats(&self, label: &str, samples: &[&Sample]) -> WindowStats {
+        let calls = samples.len();
+        let errors = samples.iter().filter(|s| !s.success).count();
+
+        let mut latencies: Vec<u64> = samples.iter().map(|s| s.latency_ms).collect();
+        latencies.sort_unstable();
+        let p95_latency_ms = (!latencies.is_empty())
+            .then(|| latencies[(latencies.len() * 95).div_ceil(100).saturating_sub(1)]);
+
+        let success_rate = (calls > 0).then(|| (calls - errors) as f64 / calls as f64);
+        let allowed_errors = (1.0 - self.targets.success_rate) * calls as f64;
+        let error_budget_remaining =
+            (calls > 0 && allowed_errors > 0.0).then(|| 1.0 - errors as f64 / allowed_errors);
+
+        let meets_slo = success_rate.is_none_or(|rate| rate >= self.targets.success_rate)
+            && p95_latency_ms.is_none_or(|p95| p95 <= self.targets.p95_latency_ms);
+
+        WindowStats {
+            window: label.to_string(),
+            calls,
+            errors,
+            success_rate,
+            p95_latency_ms,
+            error_budget_remaining,
+            meets_slo,
+        }
+    }
+}
+
+fn record(
+    series: &RwLock<HashMap<String, VecDeque<Sample>>>,
+    name: &str,
+    latency_ms: u64,
+    success: bool,
+) {
+    let now = Utc::now();
+    let retention = now - Duration::seconds(WINDOWS[WINDOWS.len() - 1].1);
+
+    let mut series = series.write().expect("slo tracker lock poisoned");
+    let samples = series.entry(name.to_string()).or_default();
+    while samples.front().is_some_and(|s| s.at < retention) {
+        samples.pop_front();
+    }
+    samples.push_back(Sample {
+        at: now,
+        latency_ms,
+        success,
+    });
+}
+
+/// Middleware recording each routed request; server errors count against the SLO
+pub async fn track_requests(
+    State(tracker): State<Arc<SloTracker>>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let name = format!(
+        "{} {}",
+        request.method(),
+        matched_path
+            .as_ref()
+            .map(|path| path.as_str())
+            .unwrap_or_else(|| request.uri().path())
+    );
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    tracker.record_endpoint(&name, latency_ms, !response.status().is_server_error());
+    response
+}
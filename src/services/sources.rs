@@ -0,0 +1,102 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use crate::datasource::{Capability, DataSource};
+use crate::services::slo::{SloTracker, WindowStats};
+
+/// Success rate below which a source is reported down rather than degraded
+const DOWN_SUCCESS_RATE: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceHealth {
+    Healthy,
+    /// Recent calls miss the success rate or latency target
+    Degraded,
+    /// Most recent calls fail
+    Down,
+    /// No recent calls to judge by
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceStatus {
+    pub name: String,
+    /// Whether unprefixed coins and account state come from this source
+    pub primary: bool,
+    /// Prefix of this source's coins, e.g. `gmx:`
+    pub coin_prefix: Option<String>,
+    pub capabilities: BTreeSet<Capability>,
+    pub health: SourceHealth,
+    /// Shortest SLO window with calls, which the health is judged on
+    pub recent: Option<WindowStats>,
+}
+
+/// Configured upstream sources, for capability checks and the `/meta/sources` listing
+pub struct SourceRegistry {
+    /// The first source is the primary one
+    sources: Vec<(String, Arc<dyn DataSource>)>,
+    slo_tracker: Arc<SloTracker>,
+}
+
+impl SourceRegistry {
+    pub fn new(slo_tracker: Arc<SloTracker>) -> Self {
+        Self {
+            sources: Vec::new(),
+            slo_tracker,
+        }
+    }
+
+    /// Adds a source under the name it is metered as; the first one added is the primary
+    pub fn register(&mut self, name: &str, source: Arc<dyn DataSource>) {
+        self.sources.push((name.to_string(), source));
+    }
+
+    pub fn statuses(&self) -> Vec<SourceStatus> {
+        self.sources
+            .iter()
+            .enumerate()
+            .map(|(index, (name, source))| {
+                let recent = self
+                    .slo_tracker
+                    .upstream(name)
+                    .and_then(|entry| entry.windows.into_iter().find(|window| window.calls > 0));
+
+                SourceStatus {
+                    name: name.clone(),
+                    primary: index == 0,
+                    coin_prefix: (index > 0).then(|| format!("{}:", name)),
+                    capabilities: source.capabilities(),
+                    health: health(recent.as_ref()),
+                    recent,
+                }
+            })
+            .collect()
+    }
+
+    /// Whether the source serving a coin, chosen by its venue prefix, supports a method
+    pub fn supports(&self, coin: &str, capability: Capability) -> bool {
+        let source = coin
+            .split_once(':')
+            .and_then(|(venue, _)| self.sources.iter().skip(1).find(|(name, _)| name == venue))
+            .or_else(|| self.sources.first());
+
+        source.is_some_and(|(_, source)| source.capabilities().contains(&capability))
+    }
+}
+
+fn health(recent: Option<&WindowStats>) -> SourceHealth {
+    match recent {
+        None => SourceHealth::Unknown,
+        Some(window)
+            if window
+                .success_rate
+                .is_some_and(|rate| rate < DOWN_SUCCESS_RATE) =>
+        {
+            SourceHealth::Down
+        }
+        Some(window) if !window.meets_slo => SourceHealth::Degraded,
+        Some(_) => SourceHealth::Healthy,
+    }
+}
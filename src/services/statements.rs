@@ -0,0 +1,240 @@
+use bigdecimal::{BigDecimal, Zero};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::services::pnl_calculator::{CalculationMetadata, FundingAttribution};
+use crate::services::timeline::{Timeline, TimelineEvent};
+
+/// One line of a waterfall, with the running total after it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaterfallStep {
+    pub label: String,
+    pub amount: BigDecimal,
+    pub running_total: BigDecimal,
+}
+
+/// Realized PnL broken down from gross trading PnL to net PnL.
+///
+/// Fees are costs; negative fees (maker rebates) are reported as rebates instead.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Waterfall {
+    pub gross_trading_pnl: BigDecimal,
+    #[serde(with = "crate::output::cost")]
+    pub fees: BigDecimal,
+    pub funding: BigDecimal,
+    pub rebates: BigDecimal,
+    #[serde(with = "crate::output::cost")]
+    pub liquidation_losses: BigDecimal,
+    pub net_pnl: BigDecimal,
+}
+
+/// A PnL-relevant amount from one event
+enum Entry {
+    Fill {
+        realized_pnl: Option<BigDecimal>,
+        fee: BigDecimal,
+    },
+    Funding(BigDecimal),
+    Liquidation(BigDecimal),
+}
+
+impl Waterfall {
+    fn add(&mut self, entry: &Entry) {
+        match entry {
+            Entry::Fill { realized_pnl, fee } => {
+                if let Some(pnl) = realized_pnl {
+                    self.gross_trading_pnl = &self.gross_trading_pnl + pnl;
+                    self.net_pnl = &self.net_pnl + pnl;
+                }
+                if fee < &BigDecimal::zero() {
+                    self.rebates = &self.rebates - fee;
+                } else {
+                    self.fees = &self.fees + fee;
+                }
+                self.net_pnl = &self.net_pnl - fee;
+            }
+            Entry::Funding(amount) => {
+                self.funding = &self.funding + amount;
+                self.net_pnl = &self.net_pnl + amount;
+            }
+            Entry::Liquidation(loss) => {
+                self.liquidation_losses = &self.liquidation_losses + loss;
+                self.net_pnl = &self.net_pnl - loss;
+            }
+        }
+    }
+
+    /// The waterfall as signed steps, ending at net PnL
+    pub fn steps(&self) -> Vec<WaterfallStep> {
+        let lines = [
+            ("gross_trading_pnl", self.gross_trading_pnl.clone()),
+            ("fees", -self.fees.clone()),
+            ("funding", self.funding.clone()),
+            ("rebates", self.rebates.clone()),
+            ("liquidation_losses", -self.liquidation_losses.clone()),
+        ];
+
+        let mut running_total = BigDecimal::zero();
+        let mut steps: Vec<WaterfallStep> = lines
+            .into_iter()
+            .map(|(label, amount)| {
+                running_total = &running_total + &amount;
+                WaterfallStep {
+                    label: label.to_string(),
+                    amount,
+                    running_total: running_total.clone(),
+                }
+            })
+            .collect();
+        steps.push(WaterfallStep {
+            label: "net_pnl".to_string(),
+            amount: self.net_pnl.clone(),
+            running_total: self.net_pnl.clone(),
+        });
+        steps
+    }
+}
+
+/// A waterfall with its steps spelled out for display
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaterfallView {
+    #[serde(flatten)]
+    pub totals: Waterfall,
+    pub steps: Vec<WaterfallStep>,
+}
+
+impl From<Waterfall> for WaterfallView {
+    fn from(totals: Waterfall) -> Self {
+        Self {
+            steps: totals.steps(),
+            totals,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementMonth {
+    /// Calendar month in UTC, e.g. `2024-03`
+    pub month: String,
+    pub waterfall: WaterfallView,
+    pub by_coin: BTreeMap<String, WaterfallView>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Statement {
+    pub wallet: String,
+    pub funding_attribution: FundingAttribution,
+    pub waterfall: WaterfallView,
+    pub by_coin: BTreeMap<String, WaterfallView>,
+    pub months: Vec<StatementMonth>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calculation: Option<CalculationMetadata>,
+}
+
+#[derive(Default)]
+struct MonthTotals {
+    waterfall: Waterfall,
+    by_coin: BTreeMap<String, Waterfall>,
+}
+
+pub struct StatementCalculator;
+
+impl StatementCalculator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Builds monthly and per-coin PnL waterfalls.
+    ///
+    /// Funding settled at a month boundary follows the same attribution rule as daily PnL, so
+    /// monthly net PnL equals the sum of the daily figures.
+    pub fn calculate(
+        &self,
+        timeline: &Timeline,
+        funding_attribution: FundingAttribution,
+    ) -> Statement {
+        let mut total = MonthTotals::default();
+        let mut months: BTreeMap<String, MonthTotals> = BTreeMap::new();
+
+        for event in &timeline.events {
+            let month = event.timestamp().format("%Y-%m").to_string();
+
+            let parts: Vec<(String, &str, Entry)> = match event {
+                TimelineEvent::Fill {
+                    coin,
+                    fee,
+                    realized_pnl,
+                    ..
+                } => vec![(
+                    month,
+                    coin.as_str(),
+                    Entry::Fill {
+                        realized_pnl: realized_pnl.clone(),
+                        fee: fee.clone(),
+                    },
+                )],
+                TimelineEvent::Funding {
+                    timestamp,
+                    coin,
+                    amount,
+                    ..
+                } => funding_attribution
+                    .attribute(*timestamp, amount)
+                    .into_iter()
+                    .map(|(date, amount)| {
+                        (
+                            date.format("%Y-%m").to_string(),
+                            coin.as_str(),
+                            Entry::Funding(amount),
+                        )
+                    })
+                    .collect(),
+                TimelineEvent::Liquidation { coin, loss, .. } => {
+                    vec![(month, coin.as_str(), Entry::Liquidation(loss.clone()))]
+                }
+                _ => Vec::new(),
+            };
+
+            for (month, coin, entry) in parts {
+                let month_totals = months.entry(month).or_default();
+                for waterfall in [
+                    &mut total.waterfall,
+                    total.by_coin.entry(coin.to_string()).or_default(),
+                    &mut month_totals.waterfall,
+                    month_totals.by_coin.entry(coin.to_string()).or_default(),
+                ] {
+                    waterfall.add(&entry);
+                }
+            }
+        }
+
+        Statement {
+            wallet: timeline.wallet.clone(),
+            funding_attribution,
+            waterfall: total.waterfall.into(),
+            by_coin: views(total.by_coin),
+            months: months
+                .into_iter()
+                .map(|(month, totals)| StatementMonth {
+                    month,
+                    waterfall: totals.waterfall.into(),
+                    by_coin: views(totals.by_coin),
+                })
+                .collect(),
+            calculation: None,
+        }
+    }
+}
+
+impl Default for StatementCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn views(by_coin: BTreeMap<String, Waterfall>) -> BTreeMap<String, WaterfallView> {
+    by_coin
+        .into_iter()
+        .map(|(coin, waterfall)| (coin, waterfall.into()))
+        .collect()
+}
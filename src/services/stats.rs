@@ -0,0 +1,920 @@
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+
+use crate::services::market_data::{candle_at, Candle};
+use crate::services::timeline::{signed_size, Timeline, TimelineEvent};
+use crate::services::trades::RoundTrip;
+
+/// Decimal places kept for ratios derived by division
+const RATIO_SCALE: i64 = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizingStats {
+    pub wallet: String,
+    pub equity: BigDecimal,
+    pub max_equity_fraction: BigDecimal,
+    pub trade_count: u32,
+    pub win_rate: Option<BigDecimal>,
+    pub average_win: Option<BigDecimal>,
+    pub average_loss: Option<BigDecimal>,
+    pub payoff_ratio: Option<BigDecimal>,
+    pub edge_per_trade: Option<BigDecimal>,
+    pub kelly_fraction: Option<BigDecimal>,
+    pub position_size_distribution: Option<SizeDistribution>,
+    pub oversized_trades: Vec<OversizedTrade>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeDistribution {
+    pub count: usize,
+    pub mean: BigDecimal,
+    pub median: BigDecimal,
+    pub p90: BigDecimal,
+    pub max: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OversizedTrade {
+    pub timestamp: DateTime<Utc>,
+    pub coin: String,
+    pub side: String,
+    pub position_notional: BigDecimal,
+    pub equity_fraction: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketMakingStats {
+    pub wallet: String,
+    pub coin: String,
+    pub buy_volume: BigDecimal,
+    pub sell_volume: BigDecimal,
+    /// (buy - sell) / (buy + sell) notional; 0 is perfectly balanced
+    pub volume_imbalance: Option<BigDecimal>,
+    pub maker_fill_share: Option<BigDecimal>,
+    /// Time-weighted signed position between the first and last fill
+    pub average_inventory: BigDecimal,
+    pub average_absolute_inventory: BigDecimal,
+    pub round_turns: u32,
+    pub matched_size: BigDecimal,
+    /// Sell price minus buy price per unit of matched size
+    pub spread_capture_per_unit: Option<BigDecimal>,
+    pub spread_capture_bps: Option<BigDecimal>,
+    pub adverse_selection_horizon_minutes: i64,
+    /// Average mid move against the fill over the horizon; positive means adverse. None when
+    /// no fill has a candle at the horizon no longer than the horizon itself.
+    pub adverse_selection_bps: Option<BigDecimal>,
+    /// Fills the adverse selection average covers
+    pub adverse_selection_fills: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramBin {
+    pub lower: BigDecimal,
+    pub upper: BigDecimal,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Histogram {
+    pub count: usize,
+    pub min: Option<BigDecimal>,
+    pub max: Option<BigDecimal>,
+    pub bins: Vec<HistogramBin>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Distributions {
+    pub wallet: String,
+    /// Net PnL per round-trip trade
+    pub trade_pnl: Histogram,
+    /// Notional (size x price) per fill
+    pub fill_notional: Histogram,
+    /// Slippage per fill versus the first fill of its order, in bps
+    pub slippage_bps: Histogram,
+}
+
+/// How far price moved against and in favor of a round trip while it was open.
+///
+/// Excursions are fractions of the entry price; the USD amounts apply them to the trip size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Excursion {
+    /// Worst price reached against the position
+    pub adverse_price: BigDecimal,
+    /// Best price reached in favor of the position
+    pub favorable_price: BigDecimal,
+    /// Maximum adverse excursion
+    pub mae: BigDecimal,
+    /// Maximum favorable excursion
+    pub mfe: BigDecimal,
+    pub mae_usd: BigDecimal,
+    pub mfe_usd: BigDecimal,
+    /// Share of the favorable excursion kept at exit; negative when exiting at a loss
+    pub mfe_captured: Option<BigDecimal>,
+    /// Number of candles wholly within the trip the excursion was measured over
+    pub candles: usize,
+}
+
+/// Excursion distributions for one group of trades
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcursionGroup {
+    pub count: usize,
+    pub mae: Option<SizeDistribution>,
+    pub mfe: Option<SizeDistribution>,
+    pub mfe_captured: Option<SizeDistribution>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcursionStats {
+    pub wallet: String,
+    /// Closed trades with an excursion measured
+    pub trade_count: usize,
+    /// How far winners went against the position before recovering; guides stop placement
+    pub winners: ExcursionGroup,
+    /// How far losers went in favor before reversing; guides target placement
+    pub losers: ExcursionGroup,
+}
+
+/// Execution quality of orders whose total notional falls in `[lower, upper)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionBucket {
+    pub lower: BigDecimal,
+    /// None for the open-ended top bucket
+    pub upper: Option<BigDecimal>,
+    pub order_count: usize,
+    pub fill_count: usize,
+    pub notional: BigDecimal,
+    /// Per-order price walk from the first fill to the average fill price
+    pub average_slippage_bps: Option<BigDecimal>,
+    pub p90_slippage_bps: Option<BigDecimal>,
+    /// Fees over notional
+    pub fee_rate_bps: Option<BigDecimal>,
+    /// Time from each order's first fill to its last
+    pub average_delay_ms: Option<BigDecimal>,
+    pub p90_delay_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionQuality {
+    pub wallet: String,
+    pub order_count: usize,
+    pub fill_count: usize,
+    pub buckets: Vec<ExecutionBucket>,
+}
+
+/// Execution of one order across all of its fills
+#[derive(Debug, Clone)]
+pub struct OrderExecution {
+    pub coin: String,
+    pub fill_count: usize,
+    /// Total notional of the order's fills
+    pub notional: BigDecimal,
+    pub fee: BigDecimal,
+    /// Walk from the first fill's price to the average fill price in bps; positive is worse
+    /// for the trader
+    pub slippage_bps: BigDecimal,
+    /// Time from the first fill to the last
+    pub duration_ms: i64,
+}
+
+/// Execution details of one fill relative to its order
+#[derive(Debug, Clone)]
+pub struct FillExecution {
+    pub timestamp: DateTime<Utc>,
+    pub coin: String,
+    pub notional: BigDecimal,
+    pub fee: BigDecimal,
+    /// Price walk from the order's first fill in bps; positive is worse for the trader
+    pub slippage_bps: BigDecimal,
+    /// Time since the order's first fill
+    pub delay_ms: i64,
+}
+
+/// Fills of one order accumulated while scanning the timeline
+struct OrderFills<'a> {
+    coin: &'a str,
+    is_buy: bool,
+    first_time: DateTime<Utc>,
+    first_price: &'a BigDecimal,
+    last_time: DateTime<Utc>,
+    fills: usize,
+    size: BigDecimal,
+    notional: BigDecimal,
+    fee: BigDecimal,
+}
+
+pub struct StatsCalculator;
+
+impl StatsCalculator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Calculates edge, payoff ratio, Kelly fraction and position sizing relative to equity.
+    ///
+    /// A trade is any fill that realized PnL; its result is net of the fill's fee.
+    /// Position sizes are measured after each fill against the current account value.
+    pub fn calculate_sizing(
+        &self,
+        wallet: &str,
+        timeline: &Timeline,
+        equity: BigDecimal,
+        max_equity_fraction: BigDecimal,
+    ) -> SizingStats {
+        let zero = BigDecimal::from(0);
+        let mut wins: Vec<BigDecimal> = Vec::new();
+        let mut losses: Vec<BigDecimal> = Vec::new();
+        let mut fractions: Vec<BigDecimal> = Vec::new();
+        let mut oversized_trades = Vec::new();
+        let mut positions: HashMap<String, BigDecimal> = HashMap::new();
+
+        for event in &timeline.events {
+            let TimelineEvent::Fill {
+                timestamp,
+                coin,
+                side,
+                size,
+                price,
+                fee,
+                realized_pnl,
+                start_position,
+                ..
+            } = event
+            else {
+                continue;
+            };
+
+            if let Some(pnl) = realized_pnl.as_ref().filter(|p| **p != zero) {
+                let net = pnl - fee;
+                if net > zero {
+                    wins.push(net);
+                } else {
+                    losses.push(-net);
+                }
+            }
+
+            let position = positions
+                .entry(coin.clone())
+                .or_insert_with(|| zero.clone());
+            if let Some(start) = start_position {
+                *position = start.clone();
+            }
+            *position = &*position + signed_size(side, size);
+
+            if equity <= zero {
+                continue;
+            }
+
+            let position_notional = (&*position * price).abs();
+            let equity_fraction = (&position_notional / &equity).round(RATIO_SCALE);
+
+            if equity_fraction > max_equity_fraction {
+                oversized_trades.push(OversizedTrade {
+                    timestamp: *timestamp,
+                    coin: coin.clone(),
+                    side: side.clone(),
+                    position_notional,
+                    equity_fraction: equity_fraction.clone(),
+                });
+            }
+
+            fractions.push(equity_fraction);
+        }
+
+        let trade_count = (wins.len() + losses.len()) as u32;
+        let average_win = mean(&wins);
+        let average_loss = mean(&losses);
+
+        let win_rate = (trade_count > 0).then(|| {
+            (BigDecimal::from(wins.len() as u64) / BigDecimal::from(trade_count)).round(RATIO_SCALE)
+        });
+
+        let payoff_ratio = match (&average_win, &average_loss) {
+            (Some(win), Some(loss)) if *loss > zero => Some((win / loss).round(RATIO_SCALE)),
+            _ => None,
+        };
+
+        let edge_per_trade = win_rate.as_ref().map(|w| {
+            let loss_rate = BigDecimal::from(1) - w;
+            let win_part = average_win.as_ref().map(|a| w * a).unwrap_or_default();
+            let loss_part = average_loss
+                .as_ref()
+                .map(|a| &loss_rate * a)
+                .unwrap_or_default();
+            (win_part - loss_part).round(RATIO_SCALE)
+        });
+
+        // Kelly: f* = W - (1 - W) / R
+        let kelly_fraction = match (&win_rate, &payoff_ratio) {
+            (Some(w), Some(r)) if *r > zero => {
+                Some((w - (BigDecimal::from(1) - w) / r).round(RATIO_SCALE))
+            }
+            _ => None,
+        };
+
+        SizingStats {
+            wallet: wallet.to_string(),
+            equity,
+            max_equity_fraction,
+            trade_count,
+            win_rate,
+            average_win,
+            average_loss,
+            payoff_ratio,
+            edge_per_trade,
+            kelly_fraction,
+            position_size_distribution: distribution(fractions),
+            oversized_trades,
+        }
+    }
+
+    /// Calculates passive-trading metrics for one coin.
+    ///
+    /// Round turns pair buys and sells FIFO regardless of which came first. Adverse selection
+    /// compares each fill price with the candle close `horizon_minutes` later.
+    pub fn calculate_market_making(
+        &self,
+        wallet: &str,
+        coin: &str,
+        timeline: &Timeline,
+        candles: &[Candle],
+        horizon_minutes: i64,
+    ) -> MarketMakingStats {
+        let zero = BigDecimal::from(0);
+        let bps = BigDecimal::from(10_000);
+        let mut buy_volume = zero.clone();
+        let mut sell_volume = zero.clone();
+        let mut maker_fills = 0u64;
+        let mut fill_count = 0u64;
+
+        // Unmatched (size, price) per side, consumed FIFO by the opposite side
+        let mut open_buys: VecDeque<(BigDecimal, BigDecimal)> = VecDeque::new();
+        let mut open_sells: VecDeque<(BigDecimal, BigDecimal)> = VecDeque::new();
+        let mut round_turns = 0u32;
+        let mut matched_size = zero.clone();
+        let mut captured = zero.clone();
+        let mut matched_notional = zero.clone();
+
+        let mut position = zero.clone();
+        let mut last_change: Option<DateTime<Utc>> = None;
+        let mut inventory_area = zero.clone();
+        let mut absolute_area = zero.clone();
+        let mut first_fill: Option<DateTime<Utc>> = None;
+
+        let mut adverse_total = zero.clone();
+        let mut adverse_count = 0u64;
+        let horizon = chrono::Duration::minutes(horizon_minutes);
+
+        for event in &timeline.events {
+            let TimelineEvent::Fill {
+                timestamp,
+                coin: fill_coin,
+                side,
+                size,
+                price,
+                start_position,
+                crossed,
+                ..
+            } = event
+            else {
+                continue;
+            };
+            if fill_coin != coin {
+                continue;
+            }
+
+            fill_count += 1;
+            if !crossed {
+                maker_fills += 1;
+            }
+
+            let is_buy = side == "B";
+            let notional = size * price;
+            if is_buy {
+                buy_volume = &buy_volume + &notional;
+            } else {
+                sell_volume = &sell_volume + &notional;
+            }
+
+            // Time-weighted inventory
+            if let Some(last) = last_change {
+                let elapsed = BigDecimal::from((*timestamp - last).num_milliseconds());
+                inventory_area = &inventory_area + &position * &elapsed;
+                absolute_area = &absolute_area + position.abs() * &elapsed;
+            }
+            first_fill.get_or_insert(*timestamp);
+            last_change = Some(*timestamp);
+            if let Some(start) = start_position {
+                position = start.clone();
+            }
+            position = &position + signed_size(side, size);
+
+            // Match against the opposite side
+            let (opposite, same) = if is_buy {
+                (&mut open_sells, &mut open_buys)
+            } else {
+                (&mut open_buys, &mut open_sells)
+            };
+            let mut remaining = size.clone();
+            while remaining > zero {
+                let Some((open_size, open_price)) = opposite.front_mut() else {
+                    break;
+                };
+                let matched = remaining.clone().min(open_size.clone());
+                let (buy_price, sell_price) = if is_buy {
+                    (price.clone(), open_price.clone())
+                } else {
+                    (open_price.clone(), price.clone())
+                };
+
+                captured = &captured + &matched * (&sell_price - &buy_price);
+                matched_notional = &matched_notional + &matched * (&sell_price + &buy_price) / 2;
+                matched_size = &matched_size + &matched;
+                round_turns += 1;
+
+                *open_size = &*open_size - &matched;
+                remaining = &remaining - &matched;
+                if *open_size == zero {
+                    opposite.pop_front();
+                }
+            }
+            if remaining > zero {
+                same.push_back((remaining, price.clone()));
+            }
+
+            // Mid move after the fill, signed so that positive is adverse. Candles longer than
+            // the horizon would measure a close far from it, so those fills are left out.
+            if let Some(later) = candle_at(candles, *timestamp + horizon)
+                && later.close_time - later.open_time < horizon
+                && *price > zero
+            {
+                let drift = (&later.close - price) / price * &bps;
+                adverse_total = &adverse_total + if is_buy { -drift } else { drift };
+                adverse_count += 1;
+            }
+        }
+
+        let span = match (first_fill, last_change) {
+            (Some(first), Some(last)) => BigDecimal::from((last - first).num_milliseconds()),
+            _ => zero.clone(),
+        };
+        let (average_inventory, average_absolute_inventory) = if span > zero {
+            (
+                (&inventory_area / &span).round(RATIO_SCALE),
+                (&absolute_area / &span).round(RATIO_SCALE),
+            )
+        } else {
+            (position.clone(), position.abs())
+        };
+
+        let total_volume = &buy_volume + &sell_volume;
+        let volume_imbalance = (total_volume > zero)
+            .then(|| ((&buy_volume - &sell_volume) / &total_volume).round(RATIO_SCALE));
+        let maker_fill_share = (fill_count > 0).then(|| {
+            (BigDecimal::from(maker_fills) / BigDecimal::from(fill_count)).round(RATIO_SCALE)
+        });
+
+        MarketMakingStats {
+            wallet: wallet.to_string(),
+            coin: coin.to_string(),
+            buy_volume,
+            sell_volume,
+            volume_imbalance,
+            maker_fill_share,
+            average_inventory,
+            average_absolute_inventory,
+            round_turns,
+            spread_capture_per_unit: (matched_size > zero)
+                .then(|| (&captured / &matched_size).round(RATIO_SCALE)),
+            spread_capture_bps: (matched_notional > zero)
+                .then(|| (&captured / &matched_notional * &bps).round(RATIO_SCALE)),
+            matched_size,
+            adverse_selection_horizon_minutes: horizon_minutes,
+            adverse_selection_bps: (adverse_count > 0)
+                .then(|| (adverse_total / BigDecimal::from(adverse_count)).round(RATIO_SCALE)),
+            adverse_selection_fills: adverse_count,
+        }
+    }
+
+    /// Measures the maximum adverse and favorable excursion of a round trip.
+    ///
+    /// Uses the entry and exit prices and the highs and lows of candles within the time the
+    /// trip was open, through `as_of` for trips still open. Trips without a known entry price
+    /// or without candles overlapping them have no excursion.
+    pub fn calculate_excursion(
+        &self,
+        trip: &RoundTrip,
+        candles: &[Candle],
+        as_of: DateTime<Utc>,
+    ) -> Option<Excursion> {
+        let entry_price = trip.entry_price.as_ref().filter(|p| !p.is_zero())?;
+        let entry_time = trip.entry_time?;
+        let exit_time = trip.exit_time.unwrap_or(as_of);
+
+        // Without market data over the trip there is nothing to measure against
+        if !candles
+            .iter()
+            .any(|c| c.close_time >= entry_time && c.open_time <= exit_time)
+        {
+            return None;
+        }
+
+        // Only candles wholly within the trip count; the fill prices bound the partial
+        // candles at either end, whose extremes may lie before entry or after exit
+        let open: Vec<&Candle> = candles
+            .iter()
+            .filter(|c| c.open_time >= entry_time && c.close_time <= exit_time)
+            .collect();
+        let prices = || {
+            open.iter()
+                .flat_map(|c| [&c.high, &c.low])
+                .chain([entry_price])
+                .chain(trip.exit_price.as_ref())
+        };
+        let high = prices().max()?;
+        let low = prices().min()?;
+
+        let is_long = trip.direction == "long";
+        let (adverse_price, favorable_price) = if is_long { (low, high) } else { (high, low) };
+
+        // Signed move from entry as a fraction of it, positive in the trade's favor
+        let favor = |price: &BigDecimal| {
+            let change = (price - entry_price) / entry_price;
+            if is_long {
+                change
+            } else {
+                -change
+            }
+        };
+        let zero = BigDecimal::from(0);
+        let mae = (-favor(adverse_price)).max(zero.clone());
+        let mfe = favor(favorable_price).max(zero);
+        let entry_notional = &trip.size * entry_price;
+
+        let mfe_captured = trip
+            .exit_price
+            .as_ref()
+            .filter(|_| !mfe.is_zero())
+            .map(|exit| (favor(exit) / &mfe).round(RATIO_SCALE));
+
+        Some(Excursion {
+            adverse_price: adverse_price.clone(),
+            favorable_price: favorable_price.clone(),
+            mae_usd: (&mae * &entry_notional).round(RATIO_SCALE),
+            mfe_usd: (&mfe * &entry_notional).round(RATIO_SCALE),
+            mae: mae.round(RATIO_SCALE),
+            mfe: mfe.round(RATIO_SCALE),
+            mfe_captured,
+            candles: open.len(),
+        })
+    }
+
+    /// Aggregates excursions of closed trades, split into winners and losers by net PnL
+    pub fn calculate_excursion_stats(&self, wallet: &str, trips: &[RoundTrip]) -> ExcursionStats {
+        let zero = BigDecimal::from(0);
+        let closed: Vec<(&RoundTrip, &Excursion)> = trips
+            .iter()
+            .filter(|t| t.exit_time.is_some())
+            .filter_map(|t| t.excursion.as_ref().map(|e| (t, e)))
+            .collect();
+
+        let group = |winners: bool| {
+            let excursions: Vec<&Excursion> = closed
+                .iter()
+                .filter(|(t, _)| (t.net_pnl > zero) == winners)
+                .map(|(_, e)| *e)
+                .collect();
+            ExcursionGroup {
+                count: excursions.len(),
+                mae: distribution(excursions.iter().map(|e| e.mae.clone()).collect()),
+                mfe: distribution(excursions.iter().map(|e| e.mfe.clone()).collect()),
+                mfe_captured: distribution(
+                    excursions
+                        .iter()
+                        .filter_map(|e| e.mfe_captured.clone())
+                        .collect(),
+                ),
+            }
+        };
+
+        ExcursionStats {
+            wallet: wallet.to_string(),
+            trade_count: closed.len(),
+            winners: group(true),
+            losers: group(false),
+        }
+    }
+
+    /// Bins trade PnL, fill notional and slippage into equal-width histograms
+    pub fn calculate_distributions(
+        &self,
+        wallet: &str,
+        timeline: &Timeline,
+        trips: &[RoundTrip],
+        bins: usize,
+    ) -> Distributions {
+        let executions = self.fill_executions(timeline);
+
+        Distributions {
+            wallet: wallet.to_string(),
+            trade_pnl: histogram(trips.iter().map(|t| t.net_pnl.clone()).collect(), bins),
+            fill_notional: histogram(
+                executions.iter().map(|e| e.notional.clone()).collect(),
+                bins,
+            ),
+            slippage_bps: histogram(
+                executions.iter().map(|e| e.slippage_bps.clone()).collect(),
+                bins,
+            ),
+        }
+    }
+
+    /// Buckets orders by total notional and reports slippage, fee rate and duration per bucket.
+    ///
+    /// `edges` are ascending bucket boundaries; orders below the first edge form the first
+    /// bucket and orders at or above the last edge the last one.
+    pub fn calculate_execution_quality(
+        &self,
+        wallet: &str,
+        timeline: &Timeline,
+        coin: Option<&str>,
+        edges: &[BigDecimal],
+    ) -> ExecutionQuality {
+        let bps = BigDecimal::from(10_000);
+        let orders: Vec<OrderExecution> = self
+            .order_executions(timeline)
+            .into_iter()
+            .filter(|o| coin.is_none_or(|coin| o.coin == coin))
+            .collect();
+
+        let mut buckets: Vec<Vec<&OrderExecution>> = vec![Vec::new(); edges.len() + 1];
+        for order in &orders {
+            let index = edges.partition_point(|edge| *edge <= order.notional);
+            buckets[index].push(order);
+        }
+
+        let buckets = buckets
+            .into_iter()
+            .enumerate()
+            .map(|(i, orders)| {
+                let notional: BigDecimal = orders.iter().map(|o| &o.notional).sum();
+                let fees: BigDecimal = orders.iter().map(|o| &o.fee).sum();
+
+                let mut slippage: Vec<BigDecimal> =
+                    orders.iter().map(|o| o.slippage_bps.clone()).collect();
+                slippage.sort();
+                let mut delays: Vec<i64> = orders.iter().map(|o| o.duration_ms).collect();
+                delays.sort_unstable();
+
+                ExecutionBucket {
+                    lower: i
+                        .checked_sub(1)
+                        .map(|j| edges[j].clone())
+                        .unwrap_or_default(),
+                    upper: edges.get(i).cloned(),
+                    order_count: orders.len(),
+                    fill_count: orders.iter().map(|o| o.fill_count).sum(),
+                    average_slippage_bps: mean(&slippage),
+                    p90_slippage_bps: slippage
+                        .get(slippage.len().saturating_sub(1) * 90 / 100)
+                        .cloned(),
+                    fee_rate_bps: (!notional.is_zero())
+                        .then(|| (&fees / &notional * &bps).round(RATIO_SCALE)),
+                    average_delay_ms: mean(
+                        &delays
+                            .iter()
+                            .map(|d| BigDecimal::from(*d))
+                            .collect::<Vec<_>>(),
+                    ),
+                    p90_delay_ms: delays
+                        .get(delays.len().saturating_sub(1) * 90 / 100)
+                        .copied(),
+                    notional,
+                }
+            })
+            .collect();
+
+        ExecutionQuality {
+            wallet: wallet.to_string(),
+            order_count: orders.len(),
+            fill_count: orders.iter().map(|o| o.fill_count).sum(),
+            buckets,
+        }
+    }
+
+    /// Groups fills by order ID, in order of each order's first fill.
+    ///
+    /// Fills without an order ID are treated as single-fill orders with no slippage.
+    pub fn order_executions(&self, timeline: &Timeline) -> Vec<OrderExecution> {
+        let bps = BigDecimal::from(10_000);
+        let mut orders: Vec<OrderFills> = Vec::new();
+        let mut by_oid: HashMap<u64, usize> = HashMap::new();
+
+        for event in &timeline.events {
+            let TimelineEvent::Fill {
+                timestamp,
+                coin,
+                side,
+                size,
+                price,
+                fee,
+                order_id,
+                ..
+            } = event
+            else {
+                continue;
+            };
+
+            let index = match order_id.and_then(|oid| by_oid.get(&oid)) {
+                Some(index) => *index,
+                None => {
+                    orders.push(OrderFills {
+                        coin,
+                        is_buy: side == "B",
+                        first_time: *timestamp,
+                        first_price: price,
+                        last_time: *timestamp,
+                        fills: 0,
+                        size: BigDecimal::zero(),
+                        notional: BigDecimal::zero(),
+                        fee: BigDecimal::zero(),
+                    });
+                    if let Some(oid) = order_id {
+                        by_oid.insert(*oid, orders.len() - 1);
+                    }
+                    orders.len() - 1
+                }
+            };
+
+            let order = &mut orders[index];
+            order.last_time = *timestamp;
+            order.fills += 1;
+            order.size += size;
+            order.notional += size * price;
+            order.fee += fee;
+        }
+
+        orders
+            .into_iter()
+            .map(|order| {
+                let slippage_bps = if !order.first_price.is_zero() && !order.size.is_zero() {
+                    let average = &order.notional / &order.size;
+                    let walk = (&average - order.first_price) / order.first_price * &bps;
+                    let signed = if order.is_buy { walk } else { -walk };
+                    signed.round(RATIO_SCALE)
+                } else {
+                    BigDecimal::from(0)
+                };
+
+                OrderExecution {
+                    coin: order.coin.to_string(),
+                    fill_count: order.fills,
+                    slippage_bps,
+                    duration_ms: (order.last_time - order.first_time).num_milliseconds(),
+                    notional: order.notional,
+                    fee: order.fee,
+                }
+            })
+            .collect()
+    }
+
+    /// Measures each fill against the first fill of the same order.
+    ///
+    /// Fills without an order ID are treated as single-fill orders with no slippage.
+    pub fn fill_executions(&self, timeline: &Timeline) -> Vec<FillExecution> {
+        let bps = BigDecimal::from(10_000);
+        let mut first_fills: HashMap<u64, (DateTime<Utc>, BigDecimal)> = HashMap::new();
+        let mut executions = Vec::new();
+
+        for event in &timeline.events {
+            let TimelineEvent::Fill {
+                timestamp,
+                coin,
+                side,
+                size,
+                price,
+                fee,
+                order_id,
+                ..
+            } = event
+            else {
+                continue;
+            };
+
+            let (first_time, first_price) = match order_id {
+                Some(oid) => first_fills
+                    .entry(*oid)
+                    .or_insert_with(|| (*timestamp, price.clone()))
+                    .clone(),
+                None => (*timestamp, price.clone()),
+            };
+
+            let slippage_bps = if !first_price.is_zero() {
+                let walk = (price - &first_price) / &first_price * &bps;
+                let signed = if side == "B" { walk } else { -walk };
+                signed.round(RATIO_SCALE)
+            } else {
+                BigDecimal::from(0)
+            };
+
+            executions.push(FillExecution {
+                timestamp: *timestamp,
+                coin: coin.clone(),
+                notional: size * price,
+                fee: fee.clone(),
+                slippage_bps,
+                delay_ms: (*timestamp - first_time).num_milliseconds(),
+            });
+        }
+
+        executions
+    }
+
+    /// Extracts the account value from a clearinghouse state response
+    pub fn equity_from_state(&self, user_state: &serde_json::Value) -> BigDecimal {
+        user_state
+            .get("marginSummary")
+            .and_then(|summary| summary.get("accountValue"))
+            .and_then(|value| value.as_str())
+            .and_then(|s| BigDecimal::from_str(s).ok())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for StatsCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn mean(values: &[BigDecimal]) -> Option<BigDecimal> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let total = values.iter().fold(BigDecimal::from(0), |acc, v| &acc + v);
+
+    Some((total / BigDecimal::from(values.len() as u64)).round(RATIO_SCALE))
+}
+
+fn distribution(mut values: Vec<BigDecimal>) -> Option<SizeDistribution> {
+    let mean = mean(&values)?;
+    values.sort();
+
+    let percentile = |p: usize| values[(values.len() - 1) * p / 100].clone();
+
+    Some(SizeDistribution {
+        count: values.len(),
+        mean,
+        median: percentile(50),
+        p90: percentile(90),
+        max: values[values.len() - 1].clone(),
+    })
+}
+
+/// Equal-width histogram between the smallest and largest value
+fn histogram(mut values: Vec<BigDecimal>, bins: usize) -> Histogram {
+    values.sort();
+    let (Some(min), Some(max)) = (values.first().cloned(), values.last().cloned()) else {
+        return Histogram {
+            count: 0,
+            min: None,
+            max: None,
+            bins: Vec::new(),
+        };
+    };
+
+    let bins = if min == max { 1 } else { bins.max(1) };
+    let width = (&max - &min) / BigDecimal::from(bins as u64);
+    let mut counts = vec![0usize; bins];
+
+    for value in &values {
+        let index = if !width.is_zero() {
+            ((value - &min) / &width)
+                .with_scale(0)
+                .to_usize()
+                .unwrap_or(0)
+                .min(bins - 1)
+        } else {
+            0
+        };
+        counts[index] += 1;
+    }
+
+    Histogram {
+        count: values.len(),
+        bins: counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| HistogramBin {
+                lower: (&min + &width * BigDecimal::from(i as u64)).round(RATIO_SCALE),
+                upper: (&min + &width * BigDecimal::from(i as u64 + 1)).round(RATIO_SCALE),
+                count,
+            })
+            .collect(),
+        min: Some(min),
+        max: Some(max),
+    }
+}
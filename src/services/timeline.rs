@@ -1,49 +1,118 @@
 use bigdecimal::BigDecimal;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
 
 use crate::error::AppResult;
+use crate::services::anomalies::AnomalyFlag;
+use crate::services::assets::{AssetRegistry, CoinMeta};
+use crate::services::timing::{self, Phase};
+
+const VENUE: &str = "hyperliquid";
+
+/// Collateral assumed for upstream events that do not name their token
+pub const DEFAULT_COLLATERAL: &str = "USDC";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "event_type", rename_all = "snake_case")]
 pub enum TimelineEvent {
     Fill {
+        id: String,
         timestamp: DateTime<Utc>,
         coin: String,
         side: String,
+        /// Size and price as quoted by the venue, per contract for multi-coin contracts
         size: BigDecimal,
         price: BigDecimal,
+        /// Size and price in whole coins, where the venue quotes the coin in larger contracts
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        coin_units: Option<Box<CoinUnits>>,
+        #[serde(with = "crate::output::cost")]
         fee: BigDecimal,
+        /// Token the fee was charged in
+        fee_token: String,
         realized_pnl: Option<BigDecimal>,
+        start_position: Option<BigDecimal>,
+        /// True when the fill took liquidity (taker)
+        crossed: bool,
+        order_id: Option<u64>,
         tx_hash: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        flags: Vec<AnomalyFlag>,
     },
     Funding {
+        id: String,
         timestamp: DateTime<Utc>,
         coin: String,
         amount: BigDecimal,
+        /// Token the payment settled in
+        token: String,
         funding_rate: BigDecimal,
+        position_size: Option<BigDecimal>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        flags: Vec<AnomalyFlag>,
     },
     Liquidation {
+        id: String,
         timestamp: DateTime<Utc>,
         coin: String,
         size: BigDecimal,
         price: BigDecimal,
+        #[serde(with = "crate::output::cost")]
         loss: BigDecimal,
     },
     Deposit {
+        id: String,
         timestamp: DateTime<Utc>,
         amount: BigDecimal,
         token: String,
+        /// Sending wallet of a transfer within Hyperliquid; bridge deposits have none
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
     },
     Withdrawal {
+        id: String,
         timestamp: DateTime<Utc>,
         amount: BigDecimal,
         token: String,
+        /// Receiving wallet of a transfer within Hyperliquid; bridge withdrawals have none
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        destination: Option<String>,
     },
 }
 
+/// A fill converted from contracts to coins; notional is the same in either unit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoinUnits {
+    /// Coins per contract
+    pub contract_size: u32,
+    pub size: BigDecimal,
+    pub price: BigDecimal,
+}
+
+impl CoinUnits {
+    /// Converts a size and price quoted per contract, keeping the precision `meta` allows
+    pub fn from_contracts(size: &BigDecimal, price: &BigDecimal, meta: &CoinMeta) -> Option<Self> {
+        if meta.contract_size <= 1 {
+            return None;
+        }
+        let contract_size = BigDecimal::from(meta.contract_size);
+        let digits = i64::from(meta.contract_size.ilog10());
+        let size_scale = (i64::from(meta.sz_decimals) - digits).max(0);
+        let price_scale = i64::from(meta.price_decimals) + digits;
+
+        Some(Self {
+            contract_size: meta.contract_size,
+            size: (size * &contract_size).with_scale(size_scale),
+            price: (price / &contract_size).with_scale(price_scale),
+        })
+    }
+}
+
 impl TimelineEvent {
     pub fn timestamp(&self) -> DateTime<Utc> {
         match self {
@@ -54,6 +123,40 @@ impl TimelineEvent {
             TimelineEvent::Withdrawal { timestamp, .. } => *timestamp,
         }
     }
+
+    /// Stable identifier derived from venue, upstream identifiers and event type
+    pub fn id(&self) -> &str {
+        match self {
+            TimelineEvent::Fill { id, .. } => id,
+            TimelineEvent::Funding { id, .. } => id,
+            TimelineEvent::Liquidation { id, .. } => id,
+            TimelineEvent::Deposit { id, .. } => id,
+            TimelineEvent::Withdrawal { id, .. } => id,
+        }
+    }
+
+    /// Anomaly flags set during ingestion
+    pub fn flags(&self) -> &[AnomalyFlag] {
+        match self {
+            TimelineEvent::Fill { flags, .. } => flags,
+            TimelineEvent::Funding { flags, .. } => flags,
+            _ => &[],
+        }
+    }
+}
+
+/// Builds a deterministic event ID such as `hyperliquid:fill:123456`
+pub fn event_id(venue: &str, event_type: &str, key: &str) -> String {
+    format!("{}:{}:{}", venue, event_type, key)
+}
+
+/// Returns the fill size signed by direction (buys positive, sells negative)
+pub fn signed_size(side: &str, size: &BigDecimal) -> BigDecimal {
+    if side == "B" {
+        size.clone()
+    } else {
+        -size.clone()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,13 +165,186 @@ pub struct Timeline {
     pub events: Vec<TimelineEvent>,
     pub from_timestamp: Option<DateTime<Utc>>,
     pub to_timestamp: Option<DateTime<Utc>>,
+    /// Raw fills and funding payments that could not be parsed and were left out
+    #[serde(skip)]
+    pub skipped_events: usize,
+}
+
+/// Consecutive funding payments for one coin within a UTC day, collapsed into one entry.
+///
+/// A fill or liquidation of the coin ends the run, so each rollup covers a single position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event_type", rename = "funding_rollup")]
+pub struct FundingRollup {
+    pub coin: String,
+    pub date: NaiveDate,
+    pub from_timestamp: DateTime<Utc>,
+    pub to_timestamp: DateTime<Utc>,
+    pub count: usize,
+    /// Total of the payments; positive when the wallet received funding
+    pub amount: BigDecimal,
+    pub token: String,
+    /// IDs of the collapsed payments, which ungrouped timelines and `/funding` list in full
+    pub event_ids: Vec<String>,
+}
+
+/// A timeline event, or a rollup standing in for several
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TimelineEntry {
+    Event(Box<TimelineEvent>),
+    FundingRollup(FundingRollup),
+}
+
+/// Collapses funding payments into daily rollups per coin, keeping other events as they are.
+///
+/// Each rollup takes the place of its first payment. Flagged payments are not collapsed,
+/// so their flags stay visible.
+pub fn group_funding(events: Vec<TimelineEvent>) -> Vec<TimelineEntry> {
+    let mut entries = Vec::with_capacity(events.len());
+    // Index of the rollup still collecting payments, by coin
+    let mut open: HashMap<String, usize> = HashMap::new();
+
+    for event in events {
+        match event {
+            TimelineEvent::Funding {
+                id,
+                timestamp,
+                coin,
+                amount,
+                token,
+                flags,
+                ..
+            } if flags.is_empty() => {
+                let date = timestamp.date_naive();
+                if let Some(&index) = open.get(&coin)
+                    && let TimelineEntry::FundingRollup(rollup) = &mut entries[index]
+                    && rollup.date == date
+                    && rollup.token == token
+                {
+                    rollup.to_timestamp = timestamp;
+                    rollup.count += 1;
+                    rollup.amount = &rollup.amount + amount;
+                    rollup.event_ids.push(id);
+                    continue;
+                }
+
+                open.insert(coin.clone(), entries.len());
+                entries.push(TimelineEntry::FundingRollup(FundingRollup {
+                    coin,
+                    date,
+                    from_timestamp: timestamp,
+                    to_timestamp: timestamp,
+                    count: 1,
+                    amount,
+                    token,
+                    event_ids: vec![id],
+                }));
+            }
+            event => {
+                // A position change ends the coin's run
+                match &event {
+                    TimelineEvent::Fill { coin, .. }
+                    | TimelineEvent::Liquidation { coin, .. } => {
+                        open.remove(coin);
+                    }
+                    _ => {}
+                }
+                entries.push(TimelineEntry::Event(Box::new(event)));
+            }
+        }
+    }
+
+    entries
+}
+
+/// Period length used when bucketing events over time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    #[default]
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Granularity {
+    /// Label of the period containing a timestamp (UTC), e.g. `2024-03-01`, `2024-W09`, `2024-03`
+    pub fn bucket(&self, timestamp: DateTime<Utc>) -> String {
+        let format = match self {
+            Granularity::Daily => "%Y-%m-%d",
+            Granularity::Weekly => "%G-W%V",
+            Granularity::Monthly => "%Y-%m",
+        };
+        timestamp.format(format).to_string()
+    }
+}
+
+/// Count, time range and totals over a selection of events
+#[derive(Debug, Clone, Default)]
+pub struct EventSummary {
+    pub count: usize,
+    pub from_timestamp: Option<DateTime<Utc>>,
+    pub to_timestamp: Option<DateTime<Utc>>,
+    pub total_fees: BigDecimal,
+    pub total_volume: BigDecimal,
+    pub total_funding: BigDecimal,
+}
+
+impl Timeline {
+    /// Summarizes all events in the timeline
+    pub fn summary(&self) -> EventSummary {
+        let mut summary = EventSummary {
+            count: self.events.len(),
+            from_timestamp: self.from_timestamp,
+            to_timestamp: self.to_timestamp,
+            ..Default::default()
+        };
+
+        for event in &self.events {
+            match event {
+                TimelineEvent::Fill {
+                    size, price, fee, ..
+                } => {
+                    summary.total_fees = &summary.total_fees + fee;
+                    summary.total_volume = &summary.total_volume + size * price;
+                }
+                TimelineEvent::Funding { amount, .. } => {
+                    summary.total_funding = &summary.total_funding + amount;
+                }
+                _ => {}
+            }
+        }
+
+        summary
+    }
+
+    /// Returns a copy containing only events at or before `as_of`
+    pub fn until(&self, as_of: DateTime<Utc>) -> Timeline {
+        let events: Vec<TimelineEvent> = self
+            .events
+            .iter()
+            .filter(|e| e.timestamp() <= as_of)
+            .cloned()
+            .collect();
+
+        Timeline {
+            wallet: self.wallet.clone(),
+            from_timestamp: events.first().map(|e| e.timestamp()),
+            to_timestamp: events.last().map(|e| e.timestamp()),
+            events,
+            skipped_events: self.skipped_events,
+        }
+    }
 }
 
-pub struct TimelineService;
+pub struct TimelineService {
+    asset_registry: Arc<AssetRegistry>,
+}
 
 impl TimelineService {
-    pub fn new() -> Self {
-        Self
+    pub fn new(asset_registry: Arc<AssetRegistry>) -> Self {
+        Self { asset_registry }
     }
 
     /// Reconstructs a timeline from fills and funding payments
@@ -78,42 +354,70 @@ impl TimelineService {
         fills: Vec<Value>,
         funding: Vec<Value>,
     ) -> AppResult<Timeline> {
+        let started = Instant::now();
         let mut events = Vec::new();
+        let mut skipped_events = 0;
 
         // Process fills
         for fill in fills {
-            if let Some(event) = self.parse_fill(&fill) {
-                events.push(event);
+            match self.parse_fill(&fill) {
+                Some(event) => events.push(event),
+                None => skipped_events += 1,
             }
         }
 
         // Process funding payments
         for payment in funding {
-            if let Some(event) = self.parse_funding(&payment) {
-                events.push(event);
+            match self.parse_funding(&payment) {
+                Some(event) => events.push(event),
+                None => skipped_events += 1,
             }
         }
 
-        // Sort by timestamp
-        events.sort_by(|a, b| a.timestamp().cmp(&b.timestamp()));
+        // Stable sort by timestamp: events sharing a millisecond keep upstream order, which is
+        // execution order for fills and the same on every poll. Comparing ID strings instead
+        // would put tid 1000 before tid 999 and reorder the fills of one order.
+        events.sort_by_key(|e| e.timestamp());
 
         let from_timestamp = events.first().map(|e| e.timestamp());
         let to_timestamp = events.last().map(|e| e.timestamp());
+        timing::record(Phase::Parse, started.elapsed());
 
         Ok(Timeline {
             wallet: wallet.to_string(),
             events,
             from_timestamp,
             to_timestamp,
+            skipped_events,
         })
     }
 
-    fn parse_fill(&self, fill: &Value) -> Option<TimelineEvent> {
+    /// Adds deposits and withdrawals from ledger updates to a timeline, keeping it sorted.
+    ///
+    /// Bridge transfers and transfers between Hyperliquid accounts are both included; the
+    /// latter record the other wallet and count as a deposit or withdrawal by direction.
+    pub fn add_ledger_updates(&self, timeline: &mut Timeline, updates: Vec<Value>) {
+        let started = Instant::now();
+        let parsed: Vec<_> = updates
+            .iter()
+            .filter_map(|update| self.parse_ledger_update(&timeline.wallet, update))
+            .collect();
+        timeline.events.extend(parsed);
+        timeline.events.sort_by_key(|e| e.timestamp());
+
+        timeline.from_timestamp = timeline.events.first().map(|e| e.timestamp());
+        timeline.to_timestamp = timeline.events.last().map(|e| e.timestamp());
+        timing::record(Phase::Parse, started.elapsed());
+    }
+
+    /// Parses a raw fill, as returned by the data source
+    pub fn parse_fill(&self, fill: &Value) -> Option<TimelineEvent> {
         let timestamp = fill.get("time")
             .and_then(|t| t.as_i64())
             .map(|ts| DateTime::from_timestamp_millis(ts).unwrap_or_default())?;
 
-        let coin = fill.get("coin").and_then(|c| c.as_str())?.to_string();
+        let coin = fill.get("coin").and_then(|c| c.as_str())?;
+        let coin = self.asset_registry.normalize_coin(coin, timestamp);
         let side = fill.get("side").and_then(|s| s.as_str())?.to_string();
 
         let size = fill.get("sz")
@@ -129,51 +433,193 @@ impl TimelineService {
             .and_then(|f| BigDecimal::from_str(f).ok())
             .unwrap_or_default();
 
+        let fee_token = fill.get("feeToken")
+            .and_then(|t| t.as_str())
+            .unwrap_or(DEFAULT_COLLATERAL)
+            .to_string();
+
         let realized_pnl = fill.get("closedPnl")
             .and_then(|p| p.as_str())
             .and_then(|p| BigDecimal::from_str(p).ok());
 
+        let start_position = fill.get("startPosition")
+            .and_then(|p| p.as_str())
+            .and_then(|p| BigDecimal::from_str(p).ok());
+
+        let crossed = fill.get("crossed").and_then(|c| c.as_bool()).unwrap_or(false);
+
+        let order_id = fill.get("oid").and_then(|o| o.as_u64());
+
         let tx_hash = fill.get("hash").and_then(|h| h.as_str()).map(String::from);
 
+        let flags = parse_flags(fill);
+
+        let venue = fill.get("venue").and_then(|v| v.as_str()).unwrap_or(VENUE);
+
+        // Trade IDs are unique per fill; fall back to hash + coin + time for older payloads
+        let tid = fill.get("tid").and_then(|t| match t {
+            Value::Number(n) => n.as_u64().map(|n| n.to_string()),
+            Value::String(s) => Some(s.clone()),
+            _ => None,
+        });
+        let key = match tid {
+            Some(tid) => tid,
+            None => format!(
+                "{}:{}:{}",
+                tx_hash.as_deref().unwrap_or_default(),
+                coin,
+                timestamp.timestamp_millis()
+            ),
+        };
+
+        // Only Hyperliquid perps are listed in the metadata that gives contract sizes
+        let coin_units = (venue == VENUE)
+            .then(|| self.asset_registry.coin_meta(&coin))
+            .flatten()
+            .and_then(|meta| CoinUnits::from_contracts(&size, &price, &meta))
+            .map(Box::new);
+
         Some(TimelineEvent::Fill {
+            id: event_id(venue, "fill", &key),
             timestamp,
             coin,
             side,
             size,
             price,
+            coin_units,
             fee,
+            fee_token,
             realized_pnl,
+            start_position,
+            crossed,
+            order_id,
             tx_hash,
+            flags,
         })
     }
 
-    fn parse_funding(&self, payment: &Value) -> Option<TimelineEvent> {
+    /// Parses a raw funding payment, as returned by the data source
+    pub fn parse_funding(&self, payment: &Value) -> Option<TimelineEvent> {
         let timestamp = payment.get("time")
             .and_then(|t| t.as_i64())
             .map(|ts| DateTime::from_timestamp_millis(ts).unwrap_or_default())?;
 
-        let coin = payment.get("coin").and_then(|c| c.as_str())?.to_string();
+        let coin = payment.get("coin").and_then(|c| c.as_str())?;
+        let coin = self.asset_registry.normalize_coin(coin, timestamp);
 
         let amount = payment.get("usdc")
             .and_then(|a| a.as_str())
             .and_then(|a| BigDecimal::from_str(a).ok())?;
 
+        let token = payment.get("token")
+            .and_then(|t| t.as_str())
+            .unwrap_or(DEFAULT_COLLATERAL)
+            .to_string();
+
         let funding_rate = payment.get("fundingRate")
             .and_then(|r| r.as_str())
             .and_then(|r| BigDecimal::from_str(r).ok())
             .unwrap_or_default();
 
+        let position_size = payment.get("szi")
+            .and_then(|s| s.as_str())
+            .and_then(|s| BigDecimal::from_str(s).ok());
+
+        let flags = parse_flags(payment);
+
+        let venue = payment.get("venue").and_then(|v| v.as_str()).unwrap_or(VENUE);
+
+        // A wallet receives at most one funding payment per coin per interval
+        let key = format!("{}:{}", coin, timestamp.timestamp_millis());
+
         Some(TimelineEvent::Funding {
+            id: event_id(venue, "funding", &key),
             timestamp,
             coin,
             amount,
+            token,
             funding_rate,
+            position_size,
+            flags,
         })
     }
-}
 
-impl Default for TimelineService {
-    fn default() -> Self {
-        Self::new()
+    fn parse_ledger_update(&self, wallet: &str, update: &Value) -> Option<TimelineEvent> {
+        let timestamp = update.get("time")
+            .and_then(|t| t.as_i64())
+            .map(|ts| DateTime::from_timestamp_millis(ts).unwrap_or_default())?;
+
+        let delta = update.get("delta")?;
+        let kind = delta.get("type").and_then(|t| t.as_str())?;
+
+        // Spot sends carry the amount in the sent token; the rest are USDC amounts
+        let amount_key = if kind == "send" { "amount" } else { "usdc" };
+        let amount = delta.get(amount_key)
+            .and_then(|a| a.as_str())
+            .and_then(|a| BigDecimal::from_str(a).ok())?;
+
+        let token = delta.get("token")
+            .and_then(|t| t.as_str())
+            .unwrap_or(DEFAULT_COLLATERAL)
+            .to_string();
+
+        let venue = update.get("venue").and_then(|v| v.as_str()).unwrap_or(VENUE);
+
+        // Ledger updates carry the L1 transaction hash; older payloads fall back to time
+        let key = update.get("hash")
+            .and_then(|h| h.as_str())
+            .map(String::from)
+            .unwrap_or_else(|| timestamp.timestamp_millis().to_string());
+
+        match kind {
+            "deposit" => Some(TimelineEvent::Deposit {
+                id: event_id(venue, "deposit", &key),
+                timestamp,
+                amount,
+                token,
+                source: None,
+            }),
+            "withdraw" => Some(TimelineEvent::Withdrawal {
+                id: event_id(venue, "withdrawal", &key),
+                timestamp,
+                amount,
+                token,
+                destination: None,
+            }),
+            "internalTransfer" | "send" => {
+                let sender = delta.get("user").and_then(|u| u.as_str())?;
+                let receiver = delta.get("destination").and_then(|d| d.as_str())?;
+                // Sends between a wallet's own spot and perp balances move no capital
+                if sender.eq_ignore_ascii_case(receiver) {
+                    None
+                } else if sender.eq_ignore_ascii_case(wallet) {
+                    Some(TimelineEvent::Withdrawal {
+                        id: event_id(venue, "withdrawal", &key),
+                        timestamp,
+                        amount,
+                        token,
+                        destination: Some(receiver.to_string()),
+                    })
+                } else if receiver.eq_ignore_ascii_case(wallet) {
+                    Some(TimelineEvent::Deposit {
+                        id: event_id(venue, "deposit", &key),
+                        timestamp,
+                        amount,
+                        token,
+                        source: Some(sender.to_string()),
+                    })
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
     }
 }
+
+fn parse_flags(value: &Value) -> Vec<AnomalyFlag> {
+    value
+        .get("flags")
+        .and_then(|f| serde_json::from_value(f.clone()).ok())
+        .unwrap_or_default()
+}
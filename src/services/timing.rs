@@ -0,0 +1,167 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{rejection::QueryRejection, Query, Request},
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::AppError;
+use crate::services::capture::{DebugMode, DebugOptions};
+
+tokio::task_local! {
+    /// Time spent per phase of the current request, when it asked for timing
+    static ACTIVE_TIMING: Arc<Mutex<TimingRecorder>>;
+}
+
+/// Parts of a request timed from inside the handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Calls to upstream data sources
+    Upstream,
+    /// Turning raw upstream events into a timeline
+    Parse,
+}
+
+#[derive(Debug, Default)]
+struct TimingRecorder {
+    upstream: Duration,
+    upstream_calls: usize,
+    parse: Duration,
+    handler: Duration,
+}
+
+/// Where the time behind a response went, in milliseconds.
+///
+/// Upstream time is summed over calls, so it can exceed wall time when calls overlap.
+/// Compute is the rest of the handler, including encoding its result; serialize covers the
+/// response middleware, such as output formatting.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingBreakdown {
+    pub upstream_ms: f64,
+    pub upstream_calls: usize,
+    pub parse_ms: f64,
+    pub compute_ms: f64,
+    pub serialize_ms: f64,
+    pub total_ms: f64,
+}
+
+impl TimingBreakdown {
+    fn header_value(&self) -> String {
+        format!(
+            "upstream={:.1}ms;calls={}, parse={:.1}ms, compute={:.1}ms, serialize={:.1}ms, total={:.1}ms",
+            self.upstream_ms,
+            self.upstream_calls,
+            self.parse_ms,
+            self.compute_ms,
+            self.serialize_ms,
+            self.total_ms
+        )
+    }
+}
+
+/// Adds time spent in a phase if the current request is being timed
+pub fn record(phase: Phase, elapsed: Duration) {
+    let _ = ACTIVE_TIMING.try_with(|timing| {
+        let mut timing = timing.lock().expect("timing lock poisoned");
+        match phase {
+            Phase::Upstream => {
+                timing.upstream += elapsed;
+                timing.upstream_calls += 1;
+            }
+            Phase::Parse => timing.parse += elapsed,
+        }
+    });
+}
+
+/// Route middleware measuring the handler itself, separately from outer middleware
+pub async fn time_handler(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let _ = ACTIVE_TIMING.try_with(|timing| {
+        timing.lock().expect("timing lock poisoned").handler += started.elapsed();
+    });
+    response
+}
+
+/// Middleware timing requests made with `?debug=timing`.
+///
+/// The breakdown is returned in `X-Timing` and, for JSON object responses, in a `_timing`
+/// field of the body.
+pub async fn time_requests(
+    options: Result<Query<DebugOptions>, QueryRejection>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let options = match options {
+        Ok(Query(options)) => options,
+        Err(e) => return AppError::ValidationError(e.body_text()).into_response(),
+    };
+
+    if options.debug != Some(DebugMode::Timing) {
+        return next.run(request).await;
+    }
+
+    let started = Instant::now();
+    let recorder = Arc::new(Mutex::new(TimingRecorder::default()));
+    let response = ACTIVE_TIMING
+        .scope(recorder.clone(), next.run(request))
+        .await;
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return AppError::InternalError(e.to_string()).into_response(),
+    };
+    let total = started.elapsed();
+
+    let recorder = std::mem::take(&mut *recorder.lock().expect("timing lock poisoned"));
+    let millis = |duration: Duration| (duration.as_secs_f64() * 1_000_000.0).round() / 1000.0;
+    let breakdown = TimingBreakdown {
+        upstream_ms: millis(recorder.upstream),
+        upstream_calls: recorder.upstream_calls,
+        parse_ms: millis(recorder.parse),
+        compute_ms: millis(
+            recorder
+                .handler
+                .saturating_sub(recorder.upstream + recorder.parse),
+        ),
+        serialize_ms: millis(total.saturating_sub(recorder.handler)),
+        total_ms: millis(total),
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&breakdown.header_value()) {
+        parts
+            .headers
+            .insert(HeaderName::from_static("x-timing"), value);
+    }
+
+    let is_json = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(Value::Object(mut map)) if is_json => {
+            map.insert(
+                "_timing".to_string(),
+                serde_json::to_value(&breakdown).unwrap_or_default(),
+            );
+            match serde_json::to_vec(&map) {
+                Ok(body) => {
+                    parts
+                        .headers
+                        .insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+                    Body::from(body)
+                }
+                Err(_) => Body::from(bytes),
+            }
+        }
+        _ => Body::from(bytes),
+    };
+
+    Response::from_parts(parts, body)
+}
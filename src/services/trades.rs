@@ -0,0 +1,247 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::services::positions::CostBasisEngine;
+use crate::services::stats::Excursion;
+use crate::services::timeline::{signed_size, Timeline, TimelineEvent};
+
+/// Decimal places kept for volume-weighted prices
+const PRICE_SCALE: i64 = 8;
+
+/// A fill that flipped the position, split between the trip it closed and the one it opened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlipFill {
+    pub time: DateTime<Utc>,
+    pub price: BigDecimal,
+    /// Size of the whole fill
+    pub fill_size: BigDecimal,
+    pub closing_size: BigDecimal,
+    pub opening_size: BigDecimal,
+    /// Fee split pro rata by size
+    #[serde(with = "crate::output::cost")]
+    pub closing_fee: BigDecimal,
+    #[serde(with = "crate::output::cost")]
+    pub opening_fee: BigDecimal,
+    /// PnL realized by the closing portion; the opening portion realizes none
+    pub realized_pnl: BigDecimal,
+}
+
+/// A position lifecycle from flat (or the start of history) back to flat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundTrip {
+    pub coin: String,
+    pub direction: String,
+    pub entry_time: Option<DateTime<Utc>>,
+    pub entry_price: Option<BigDecimal>,
+    pub exit_time: Option<DateTime<Utc>>,
+    pub exit_price: Option<BigDecimal>,
+    pub size: BigDecimal,
+    pub realized_pnl: BigDecimal,
+    #[serde(with = "crate::output::cost")]
+    pub fees: BigDecimal,
+    pub funding: BigDecimal,
+    pub net_pnl: BigDecimal,
+    /// The fill that opened this trip by flipping the previous one, when it was a flip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_flip: Option<FlipFill>,
+    /// The fill that closed this trip by flipping into the next one, when it was a flip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_flip: Option<FlipFill>,
+    /// Price excursions while open, when candles were looked up for the trip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excursion: Option<Excursion>,
+}
+
+/// Accumulator for a round trip that has not returned to flat yet
+struct OpenTrip {
+    coin: String,
+    is_long: bool,
+    entry_time: Option<DateTime<Utc>>,
+    entry_notional: BigDecimal,
+    entry_size: BigDecimal,
+    /// Size inherited from before the first fill in history, at an unknown price
+    carried_size: BigDecimal,
+    exit_notional: BigDecimal,
+    exit_size: BigDecimal,
+    realized_pnl: BigDecimal,
+    fees: BigDecimal,
+    funding: BigDecimal,
+    entry_flip: Option<FlipFill>,
+}
+
+impl OpenTrip {
+    fn new(coin: &str, is_long: bool, entry_time: Option<DateTime<Utc>>) -> Self {
+        Self {
+            coin: coin.to_string(),
+            is_long,
+            entry_time,
+            entry_notional: BigDecimal::zero(),
+            entry_size: BigDecimal::zero(),
+            carried_size: BigDecimal::zero(),
+            exit_notional: BigDecimal::zero(),
+            exit_size: BigDecimal::zero(),
+            realized_pnl: BigDecimal::zero(),
+            fees: BigDecimal::zero(),
+            funding: BigDecimal::zero(),
+            entry_flip: None,
+        }
+    }
+
+    fn finish(self, exit_time: Option<DateTime<Utc>>, exit_flip: Option<FlipFill>) -> RoundTrip {
+        let entry_price = (self.carried_size.is_zero() && !self.entry_size.is_zero())
+            .then(|| (&self.entry_notional / &self.entry_size).round(PRICE_SCALE));
+        let exit_price = (!self.exit_size.is_zero())
+            .then(|| (&self.exit_notional / &self.exit_size).round(PRICE_SCALE));
+        let net_pnl = &self.realized_pnl - &self.fees + &self.funding;
+
+        RoundTrip {
+            coin: self.coin,
+            direction: if self.is_long { "long" } else { "short" }.to_string(),
+            entry_time: self.entry_time,
+            entry_price,
+            exit_time,
+            exit_price,
+            size: &self.entry_size + &self.carried_size,
+            realized_pnl: self.realized_pnl,
+            fees: self.fees,
+            funding: self.funding,
+            net_pnl,
+            entry_flip: self.entry_flip,
+            exit_flip,
+            excursion: None,
+        }
+    }
+}
+
+pub struct TradeService;
+
+impl TradeService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Groups fills into round-trip trades per coin.
+    ///
+    /// A fill that flips the position closes the current trip and opens a new one with the
+    /// remainder; its fee is split pro rata between the two. Funding received while a trip is
+    /// open is attributed to it. Trips still open at the end of the timeline have no exit.
+    ///
+    /// Realized PnL is the venue's where it reports one, and FIFO cost-basis PnL otherwise.
+    pub fn build_round_trips(&self, timeline: &Timeline) -> Vec<RoundTrip> {
+        let mut engine = CostBasisEngine::new();
+        let mut positions: HashMap<String, BigDecimal> = HashMap::new();
+        let mut open_trips: HashMap<String, OpenTrip> = HashMap::new();
+        let mut trips = Vec::new();
+
+        for event in &timeline.events {
+            match event {
+                TimelineEvent::Fill {
+                    timestamp,
+                    coin,
+                    side,
+                    size,
+                    price,
+                    fee,
+                    realized_pnl,
+                    start_position,
+                    ..
+                } => {
+                    let split = engine.apply(event);
+                    let realized_pnl = realized_pnl
+                        .clone()
+                        .or_else(|| split.map(|split| split.realized_pnl))
+                        .unwrap_or_default();
+
+                    let position = positions.entry(coin.clone()).or_default();
+                    if let Some(start) = start_position {
+                        *position = start.clone();
+                    }
+
+                    // History began mid-position: carry it without an entry price
+                    if !position.is_zero() && !open_trips.contains_key(coin) {
+                        let mut trip = OpenTrip::new(coin, *position > BigDecimal::zero(), None);
+                        trip.carried_size = position.abs();
+                        open_trips.insert(coin.clone(), trip);
+                    }
+
+                    let delta = signed_size(side, size);
+                    let increasing = position.is_zero()
+                        || size.is_zero()
+                        || (*position > BigDecimal::zero()) == (delta > BigDecimal::zero());
+
+                    if increasing {
+                        let trip = open_trips.entry(coin.clone()).or_insert_with(|| {
+                            OpenTrip::new(coin, delta > BigDecimal::zero(), Some(*timestamp))
+                        });
+                        trip.entry_notional = &trip.entry_notional + size * price;
+                        trip.entry_size = &trip.entry_size + size;
+                        trip.fees = &trip.fees + fee;
+                        trip.realized_pnl = &trip.realized_pnl + &realized_pnl;
+                        *position = &*position + &delta;
+                        continue;
+                    }
+
+                    let closing_size = size.clone().min(position.abs());
+                    let opening_size = size - &closing_size;
+                    let closing_fee = (fee * &closing_size / size).round(PRICE_SCALE);
+                    let opening_fee = fee - &closing_fee;
+
+                    if let Some(trip) = open_trips.get_mut(coin) {
+                        trip.exit_notional = &trip.exit_notional + &closing_size * price;
+                        trip.exit_size = &trip.exit_size + &closing_size;
+                        trip.fees = &trip.fees + &closing_fee;
+                        trip.realized_pnl = &trip.realized_pnl + &realized_pnl;
+                    }
+
+                    *position = &*position + &delta;
+
+                    let flip = (!opening_size.is_zero()).then(|| FlipFill {
+                        time: *timestamp,
+                        price: price.clone(),
+                        fill_size: size.clone(),
+                        closing_size: closing_size.clone(),
+                        opening_size: opening_size.clone(),
+                        closing_fee: closing_fee.clone(),
+                        opening_fee: opening_fee.clone(),
+                        realized_pnl: realized_pnl.clone(),
+                    });
+
+                    if (position.is_zero() || flip.is_some())
+                        && let Some(trip) = open_trips.remove(coin)
+                    {
+                        trips.push(trip.finish(Some(*timestamp), flip.clone()));
+                    }
+
+                    if let Some(flip) = flip {
+                        let mut trip =
+                            OpenTrip::new(coin, delta > BigDecimal::zero(), Some(*timestamp));
+                        trip.entry_notional = &opening_size * price;
+                        trip.entry_size = opening_size;
+                        trip.fees = opening_fee;
+                        trip.entry_flip = Some(flip);
+                        open_trips.insert(coin.clone(), trip);
+                    }
+                }
+                TimelineEvent::Funding { coin, amount, .. } => {
+                    if let Some(trip) = open_trips.get_mut(coin) {
+                        trip.funding = &trip.funding + amount;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        trips.extend(open_trips.into_values().map(|trip| trip.finish(None, None)));
+        trips.sort_by_key(|trip| trip.entry_time);
+
+        trips
+    }
+}
+
+impl Default for TradeService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
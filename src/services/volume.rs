@@ -0,0 +1,101 @@
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::services::timeline::{Granularity, Timeline, TimelineEvent};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VolumeSplit {
+    pub maker_volume: BigDecimal,
+    pub taker_volume: BigDecimal,
+    pub total_volume: BigDecimal,
+    pub fill_count: u32,
+}
+
+impl VolumeSplit {
+    fn add(&mut self, notional: &BigDecimal, crossed: bool) {
+        if crossed {
+            self.taker_volume = &self.taker_volume + notional;
+        } else {
+            self.maker_volume = &self.maker_volume + notional;
+        }
+        self.total_volume = &self.total_volume + notional;
+        self.fill_count += 1;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumePeriod {
+    pub period: String,
+    #[serde(flatten)]
+    pub volume: VolumeSplit,
+    pub by_coin: BTreeMap<String, VolumeSplit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeReport {
+    pub wallet: String,
+    pub granularity: Granularity,
+    #[serde(flatten)]
+    pub volume: VolumeSplit,
+    pub periods: Vec<VolumePeriod>,
+}
+
+pub struct VolumeCalculator;
+
+impl VolumeCalculator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Sums traded notional (size x price) per period and coin, split by maker and taker
+    pub fn calculate(&self, timeline: &Timeline, granularity: Granularity) -> VolumeReport {
+        let mut total = VolumeSplit::default();
+        let mut periods: BTreeMap<String, VolumePeriod> = BTreeMap::new();
+
+        for event in &timeline.events {
+            let TimelineEvent::Fill {
+                timestamp,
+                coin,
+                size,
+                price,
+                crossed,
+                ..
+            } = event
+            else {
+                continue;
+            };
+
+            let notional = size * price;
+            let period_key = granularity.bucket(*timestamp);
+            let period = periods
+                .entry(period_key.clone())
+                .or_insert_with(|| VolumePeriod {
+                    period: period_key,
+                    volume: VolumeSplit::default(),
+                    by_coin: BTreeMap::new(),
+                });
+
+            period.volume.add(&notional, *crossed);
+            period
+                .by_coin
+                .entry(coin.clone())
+                .or_default()
+                .add(&notional, *crossed);
+            total.add(&notional, *crossed);
+        }
+
+        VolumeReport {
+            wallet: timeline.wallet.clone(),
+            granularity,
+            volume: total,
+            periods: periods.into_values().collect(),
+        }
+    }
+}
+
+impl Default for VolumeCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,32 @@
+pub mod s3;
+
+use async_trait::async_trait;
+
+use crate::error::AppResult;
+
+/// Trait for object stores that archive exported history via multipart uploads
+#[async_trait]
+pub trait ArchiveSink: Send + Sync {
+    /// Starts a multipart upload and returns its upload ID
+    async fn create_upload(&self, key: &str) -> AppResult<String>;
+
+    /// Uploads one part and returns its ETag
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        body: Vec<u8>,
+    ) -> AppResult<String>;
+
+    /// Completes a multipart upload from (part number, ETag) pairs
+    async fn complete_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: &[(u32, String)],
+    ) -> AppResult<()>;
+
+    /// Aborts a multipart upload, discarding uploaded parts
+    async fn abort_upload(&self, key: &str, upload_id: &str) -> AppResult<()>;
+}
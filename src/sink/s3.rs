@@ -0,0 +1,240 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, Response};
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, AppResult};
+use crate::sink::ArchiveSink;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// S3-compatible object store client using path-style URLs and SigV4 signing
+#[derive(Clone)]
+pub struct S3Sink {
+    client: Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl S3Sink {
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key_id: &str,
+        secret_access_key: &str,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+        }
+    }
+
+    /// Sends a signed request for an object key
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> AppResult<Response> {
+        let canonical_uri = format!(
+            "/{}/{}",
+            uri_encode(&self.bucket),
+            key.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+        );
+
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k), uri_encode(v)))
+            .collect();
+        query.sort();
+        let canonical_query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let url = format!("{}{}?{}", self.endpoint, canonical_uri, canonical_query);
+        let host = reqwest::Url::parse(&url)
+            .ok()
+            .and_then(|u| {
+                u.host_str().map(|h| match u.port() {
+                    Some(port) => format!("{}:{}", h, port),
+                    None => h.to_string(),
+                })
+            })
+            .ok_or_else(|| AppError::InternalError(format!("Invalid S3 endpoint: {}", url)))?;
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method,
+            canonical_uri,
+            canonical_query,
+            host,
+            payload_hash,
+            amz_date,
+            signed_headers,
+            payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac_sha256(
+                format!("AWS4{}", self.secret_access_key).as_bytes(),
+                date.as_bytes(),
+            ),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        );
+
+        let response = self
+            .client
+            .request(method, &url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalApiError(format!(
+                "S3 request failed ({}): {}",
+                status, error_text
+            )));
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl ArchiveSink for S3Sink {
+    async fn create_upload(&self, key: &str) -> AppResult<String> {
+        let response = self
+            .send(Method::POST, key, &[("uploads", "")], Vec::new())
+            .await?;
+        let body = response.text().await?;
+
+        xml_value(&body, "UploadId")
+            .ok_or_else(|| AppError::ExternalApiError("S3 did not return an upload ID".to_string()))
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        body: Vec<u8>,
+    ) -> AppResult<String> {
+        let part_number = part_number.to_string();
+        let response = self
+            .send(
+                Method::PUT,
+                key,
+                &[("partNumber", &part_number), ("uploadId", upload_id)],
+                body,
+            )
+            .await?;
+
+        response
+            .headers()
+            .get("etag")
+            .and_then(|e| e.to_str().ok())
+            .map(String::from)
+            .ok_or_else(|| AppError::ExternalApiError("S3 did not return an ETag".to_string()))
+    }
+
+    async fn complete_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: &[(u32, String)],
+    ) -> AppResult<()> {
+        let parts_xml: String = parts
+            .iter()
+            .map(|(number, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    number, etag
+                )
+            })
+            .collect();
+        let body = format!(
+            "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+            parts_xml
+        );
+
+        self.send(
+            Method::POST,
+            key,
+            &[("uploadId", upload_id)],
+            body.into_bytes(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn abort_upload(&self, key: &str, upload_id: &str) -> AppResult<()> {
+        self.send(Method::DELETE, key, &[("uploadId", upload_id)], Vec::new())
+            .await?;
+        Ok(())
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encodes everything except RFC 3986 unreserved characters, as SigV4 requires
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Extracts the text of the first `<tag>` element from an XML response
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&close)? + start;
+    Some(xml[start..end].to_string())
+}
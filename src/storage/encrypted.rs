@@ -0,0 +1,404 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::alerts::{AlertRule, DeadLetter, FiredAlert};
+use crate::services::assets::AssetMapping;
+use crate::services::forecasts::FundingPrediction;
+use crate::services::sharing::SharedReport;
+use crate::storage::{
+    Storage, StoredAggregates, StoredBackfillCursor, StoredHistory, StoredRawPayloads,
+};
+
+/// First bytes of a gzip stream, which unsealed raw payloads start with
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Length of the AES-GCM nonce prefixed to sealed raw payloads
+const NONCE_LEN: usize = 12;
+
+/// One category of a wallet's events, sealed as a whole
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SealedEvents {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Wraps a storage backend, encrypting each wallet's event payloads with its own key.
+///
+/// Keys are derived from the master key with HKDF-SHA256 and the wallet address, so a dump
+/// of the backend exposes no trading history and one wallet's key reveals nothing about
+/// another's. Raw upstream payloads, backfill progress and daily PnL aggregates are sealed the
+/// same way. Sync times, alerts, shared report snapshots (already public by intent), asset
+/// mappings and funding predictions are stored as-is.
+/// Histories written before encryption was enabled are read unchanged and sealed on their
+/// next sync.
+pub struct EncryptedStorage {
+    inner: Arc<dyn Storage>,
+    hkdf: Hkdf<Sha256>,
+}
+
+impl EncryptedStorage {
+    /// `master_key` is a base64-encoded 32-byte key.
+    pub fn new(inner: Arc<dyn Storage>, master_key: &str) -> AppResult<Self> {
+        let key = STANDARD
+            .decode(master_key.trim())
+            .ok()
+            .filter(|key| key.len() == 32)
+            .ok_or_else(|| {
+                AppError::InternalError(
+                    "Storage master key must be 32 base64-encoded bytes".to_string(),
+                )
+            })?;
+
+        Ok(Self {
+            inner,
+            hkdf: Hkdf::<Sha256>::new(None, &key),
+        })
+    }
+
+    fn cipher(&self, wallet: &str) -> Aes256Gcm {
+        let mut key = [0u8; 32];
+        self.hkdf
+            .expand(format!("history:{}", wallet).as_bytes(), &mut key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+    }
+
+    fn encrypt(
+        &self,
+        wallet: &str,
+        category: &str,
+        plaintext: &[u8],
+    ) -> AppResult<(Vec<u8>, Vec<u8>)> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher(wallet)
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: category.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                AppError::InternalError(format!("Failed to encrypt {} for {}", category, wallet))
+            })?;
+        Ok((nonce.to_vec(), ciphertext))
+    }
+
+    fn seal(&self, wallet: &str, category: &str, events: &[Value]) -> AppResult<Vec<Value>> {
+        let (nonce, ciphertext) = self.encrypt(wallet, category, &serde_json::to_vec(events)?)?;
+
+        let sealed = SealedEvents {
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+        Ok(vec![serde_json::to_value(sealed)?])
+    }
+
+    /// Seals compressed bytes as the nonce followed by the ciphertext
+    fn seal_bytes(&self, wallet: &str, category: &str, bytes: &[u8]) -> AppResult<Vec<u8>> {
+        let (mut sealed, ciphertext) = self.encrypt(wallet, category, bytes)?;
+        sealed.extend(ciphertext);
+        Ok(sealed)
+    }
+
+    /// Opens bytes from `seal_bytes`, passing through gzip data stored before encryption
+    fn open_bytes(&self, wallet: &str, category: &str, sealed: Vec<u8>) -> AppResult<Vec<u8>> {
+        let opened = (sealed.len() > NONCE_LEN)
+            .then(|| {
+                let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+                self.cipher(wallet)
+                    .decrypt(
+                        Nonce::from_slice(nonce),
+                        Payload {
+                            msg: ciphertext,
+                            aad: category.as_bytes(),
+                        },
+                    )
+                    .ok()
+            })
+            .flatten();
+
+        match opened {
+            Some(bytes) => Ok(bytes),
+            None if sealed.starts_with(&GZIP_MAGIC) => Ok(sealed),
+            None => Err(AppError::InternalError(format!(
+                "Failed to decrypt {} for {}",
+                category, wallet
+            ))),
+        }
+    }
+
+    fn open(&self, wallet: &str, category: &str, events: Vec<Value>) -> AppResult<Vec<Value>> {
+        let sealed = match events.as_slice() {
+            [value] => match SealedEvents::deserialize(value) {
+                Ok(sealed) => sealed,
+                Err(_) => return Ok(events),
+            },
+            _ => return Ok(events),
+        };
+
+        let invalid =
+            || AppError::InternalError(format!("Failed to decrypt {} for {}", category, wallet));
+        let nonce = STANDARD
+            .decode(&sealed.nonce)
+            .ok()
+            .filter(|nonce| nonce.len() == 12)
+            .ok_or_else(invalid)?;
+        let ciphertext = STANDARD.decode(&sealed.ciphertext).map_err(|_| invalid())?;
+        let plaintext = self
+            .cipher(wallet)
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: category.as_bytes(),
+                },
+            )
+            .map_err(|_| invalid())?;
+
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn open_history(&self, wallet: &str, history: StoredHistory) -> AppResult<StoredHistory> {
+        Ok(StoredHistory {
+            fills: self.open(wallet, "fills", history.fills)?,
+            funding: self.open(wallet, "funding", history.funding)?,
+            ledger: self.open(wallet, "ledger", history.ledger)?,
+            synced_at: history.synced_at,
+            normalization_version: history.normalization_version,
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for EncryptedStorage {
+    async fn load_history(&self, wallet: &str) -> AppResult<Option<StoredHistory>> {
+        self.inner
+            .load_history(wallet)
+            .await?
+            .map(|history| self.open_history(wallet, history))
+            .transpose()
+    }
+
+    async fn save_history(&self, wallet: &str, history: StoredHistory) -> AppResult<()> {
+        let sealed = StoredHistory {
+            fills: self.seal(wallet, "fills", &history.fills)?,
+            funding: self.seal(wallet, "funding", &history.funding)?,
+            ledger: self.seal(wallet, "ledger", &history.ledger)?,
+            synced_at: history.synced_at,
+            normalization_version: history.normalization_version,
+        };
+        self.inner.save_history(wallet, sealed).await
+    }
+
+    async fn list_history_wallets(&self) -> AppResult<Vec<String>> {
+        self.inner.list_history_wallets().await
+    }
+
+    async fn delete_history(&self, wallet: &str) -> AppResult<Option<StoredHistory>> {
+        self.inner
+            .delete_history(wallet)
+            .await?
+            .map(|history| self.open_history(wallet, history))
+            .transpose()
+    }
+
+    async fn load_raw_payloads(&self, wallet: &str) -> AppResult<Option<StoredRawPayloads>> {
+        self.inner
+            .load_raw_payloads(wallet)
+            .await?
+            .map(|payloads| {
+                Ok(StoredRawPayloads {
+                    fills: self.open_bytes(wallet, "raw_fills", payloads.fills)?,
+                    funding: self.open_bytes(wallet, "raw_funding", payloads.funding)?,
+                    ledger: self.open_bytes(wallet, "raw_ledger", payloads.ledger)?,
+                    fetched_at: payloads.fetched_at,
+                })
+            })
+            .transpose()
+    }
+
+    async fn save_raw_payloads(&self, wallet: &str, payloads: StoredRawPayloads) -> AppResult<()> {
+        let sealed = StoredRawPayloads {
+            fills: self.seal_bytes(wallet, "raw_fills", &payloads.fills)?,
+            funding: self.seal_bytes(wallet, "raw_funding", &payloads.funding)?,
+            ledger: self.seal_bytes(wallet, "raw_ledger", &payloads.ledger)?,
+            fetched_at: payloads.fetched_at,
+        };
+        self.inner.save_raw_payloads(wallet, sealed).await
+    }
+
+    async fn load_backfill_cursor(
+        &self,
+        wallet: &str,
+        data_type: &str,
+    ) -> AppResult<Option<StoredBackfillCursor>> {
+        let category = format!("backfill_{}", data_type);
+        self.inner
+            .load_backfill_cursor(wallet, data_type)
+            .await?
+            .map(|cursor| {
+                Ok(StoredBackfillCursor {
+                    items: self.open(wallet, &category, cursor.items)?,
+                    ..cursor
+                })
+            })
+            .transpose()
+    }
+
+    async fn save_backfill_cursor(
+        &self,
+        wallet: &str,
+        data_type: &str,
+        cursor: StoredBackfillCursor,
+    ) -> AppResult<()> {
+        let category = format!("backfill_{}", data_type);
+        let sealed = StoredBackfillCursor {
+            items: self.seal(wallet, &category, &cursor.items)?,
+            ..cursor
+        };
+        self.inner
+            .save_backfill_cursor(wallet, data_type, sealed)
+            .await
+    }
+
+    async fn delete_backfill_cursors(&self, wallet: &str) -> AppResult<usize> {
+        self.inner.delete_backfill_cursors(wallet).await
+    }
+
+    async fn load_event_sequence(&self, wallet: &str) -> AppResult<Vec<String>> {
+        self.inner.load_event_sequence(wallet).await
+    }
+
+    async fn save_event_sequence(&self, wallet: &str, ids: Vec<String>) -> AppResult<()> {
+        self.inner.save_event_sequence(wallet, ids).await
+    }
+
+    async fn load_wallet_deletion(&self, wallet: &str) -> AppResult<Option<DateTime<Utc>>> {
+        self.inner.load_wallet_deletion(wallet).await
+    }
+
+    async fn save_wallet_deletion(
+        &self,
+        wallet: &str,
+        deleted_at: DateTime<Utc>,
+    ) -> AppResult<()> {
+        self.inner.save_wallet_deletion(wallet, deleted_at).await
+    }
+
+    async fn clear_wallet_deletion(&self, wallet: &str) -> AppResult<()> {
+        self.inner.clear_wallet_deletion(wallet).await
+    }
+
+    async fn load_aggregates(&self, wallet: &str) -> AppResult<Option<StoredAggregates>> {
+        self.inner
+            .load_aggregates(wallet)
+            .await?
+            .map(|aggregates| {
+                Ok(StoredAggregates {
+                    days: self.open(wallet, "daily_pnl", aggregates.days)?,
+                    ..aggregates
+                })
+            })
+            .transpose()
+    }
+
+    async fn save_aggregates(&self, wallet: &str, aggregates: StoredAggregates) -> AppResult<()> {
+        let sealed = StoredAggregates {
+            days: self.seal(wallet, "daily_pnl", &aggregates.days)?,
+            ..aggregates
+        };
+        self.inner.save_aggregates(wallet, sealed).await
+    }
+
+    async fn delete_aggregates(&self, wallet: &str) -> AppResult<bool> {
+        self.inner.delete_aggregates(wallet).await
+    }
+
+    async fn list_alert_rules(&self) -> AppResult<Vec<AlertRule>> {
+        self.inner.list_alert_rules().await
+    }
+
+    async fn save_alert_rule(&self, rule: AlertRule) -> AppResult<()> {
+        self.inner.save_alert_rule(rule).await
+    }
+
+    async fn delete_alert_rule(&self, id: Uuid) -> AppResult<bool> {
+        self.inner.delete_alert_rule(id).await
+    }
+
+    async fn list_fired_alerts(&self) -> AppResult<Vec<FiredAlert>> {
+        self.inner.list_fired_alerts().await
+    }
+
+    async fn append_fired_alert(&self, alert: FiredAlert) -> AppResult<()> {
+        self.inner.append_fired_alert(alert).await
+    }
+
+    async fn update_fired_alert(&self, alert: FiredAlert) -> AppResult<()> {
+        self.inner.update_fired_alert(alert).await
+    }
+
+    async fn delete_fired_alerts(&self, wallet: &str) -> AppResult<usize> {
+        self.inner.delete_fired_alerts(wallet).await
+    }
+
+    async fn list_dead_letters(&self) -> AppResult<Vec<DeadLetter>> {
+        self.inner.list_dead_letters().await
+    }
+
+    async fn save_dead_letter(&self, letter: DeadLetter) -> AppResult<()> {
+        self.inner.save_dead_letter(letter).await
+    }
+
+    async fn delete_dead_letter(&self, id: Uuid) -> AppResult<bool> {
+        self.inner.delete_dead_letter(id).await
+    }
+
+    async fn delete_dead_letters(&self, wallet: &str) -> AppResult<usize> {
+        self.inner.delete_dead_letters(wallet).await
+    }
+
+    async fn save_shared_report(&self, report: SharedReport) -> AppResult<()> {
+        self.inner.save_shared_report(report).await
+    }
+
+    async fn load_shared_report(&self, id: Uuid) -> AppResult<Option<SharedReport>> {
+        self.inner.load_shared_report(id).await
+    }
+
+    async fn delete_shared_reports(&self, wallet: &str) -> AppResult<usize> {
+        self.inner.delete_shared_reports(wallet).await
+    }
+
+    async fn load_asset_mappings(&self) -> AppResult<Vec<AssetMapping>> {
+        self.inner.load_asset_mappings().await
+    }
+
+    async fn save_asset_mappings(&self, mappings: Vec<AssetMapping>) -> AppResult<()> {
+        self.inner.save_asset_mappings(mappings).await
+    }
+
+    async fn list_funding_predictions(&self) -> AppResult<Vec<FundingPrediction>> {
+        self.inner.list_funding_predictions().await
+    }
+
+    async fn save_funding_predictions(
+        &self,
+        predictions: Vec<FundingPrediction>,
+    ) -> AppResult<()> {
+        self.inner.save_funding_predictions(predictions).await
+    }
+}
@@ -0,0 +1,295 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::services::alerts::{AlertRule, DeadLetter, FiredAlert};
+use crate::services::assets::AssetMapping;
+use crate::services::forecasts::FundingPrediction;
+use crate::services::sharing::SharedReport;
+use crate::storage::{
+    Storage, StoredAggregates, StoredBackfillCursor, StoredHistory, StoredRawPayloads,
+};
+
+/// Process-local storage; contents are lost on restart
+pub struct MemoryStorage {
+    histories: RwLock<HashMap<String, StoredHistory>>,
+    raw_payloads: RwLock<HashMap<String, StoredRawPayloads>>,
+    /// Cursors by wallet and data type
+    backfill_cursors: RwLock<HashMap<(String, String), StoredBackfillCursor>>,
+    /// Timeline event IDs by wallet, in the order they were first served
+    event_sequences: RwLock<HashMap<String, Vec<String>>>,
+    /// When wallets were deleted on request, until they are synced again
+    wallet_deletions: RwLock<HashMap<String, DateTime<Utc>>>,
+    aggregates: RwLock<HashMap<String, StoredAggregates>>,
+    alert_rules: RwLock<HashMap<Uuid, AlertRule>>,
+    fired_alerts: RwLock<Vec<FiredAlert>>,
+    dead_letters: RwLock<HashMap<Uuid, DeadLetter>>,
+    shared_reports: RwLock<HashMap<Uuid, SharedReport>>,
+    asset_mappings: RwLock<Vec<AssetMapping>>,
+    funding_predictions: RwLock<HashMap<(String, DateTime<Utc>), FundingPrediction>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self {
+            histories: RwLock::new(HashMap::new()),
+            raw_payloads: RwLock::new(HashMap::new()),
+            backfill_cursors: RwLock::new(HashMap::new()),
+            event_sequences: RwLock::new(HashMap::new()),
+            wallet_deletions: RwLock::new(HashMap::new()),
+            aggregates: RwLock::new(HashMap::new()),
+            alert_rules: RwLock::new(HashMap::new()),
+            fired_alerts: RwLock::new(Vec::new()),
+            dead_letters: RwLock::new(HashMap::new()),
+            shared_reports: RwLock::new(HashMap::new()),
+            asset_mappings: RwLock::new(Vec::new()),
+            funding_predictions: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn load_history(&self, wallet: &str) -> AppResult<Option<StoredHistory>> {
+        Ok(self.histories.read().await.get(wallet).cloned())
+    }
+
+    async fn save_history(&self, wallet: &str, history: StoredHistory) -> AppResult<()> {
+        self.histories
+            .write()
+            .await
+            .insert(wallet.to_string(), history);
+        Ok(())
+    }
+
+    async fn list_history_wallets(&self) -> AppResult<Vec<String>> {
+        Ok(self.histories.read().await.keys().cloned().collect())
+    }
+
+    async fn delete_history(&self, wallet: &str) -> AppResult<Option<StoredHistory>> {
+        self.raw_payloads.write().await.remove(wallet);
+        self.delete_backfill_cursors(wallet).await?;
+        self.event_sequences.write().await.remove(wallet);
+        self.aggregates.write().await.remove(wallet);
+        Ok(self.histories.write().await.remove(wallet))
+    }
+
+    async fn load_raw_payloads(&self, wallet: &str) -> AppResult<Option<StoredRawPayloads>> {
+        Ok(self.raw_payloads.read().await.get(wallet).cloned())
+    }
+
+    async fn save_raw_payloads(&self, wallet: &str, payloads: StoredRawPayloads) -> AppResult<()> {
+        self.raw_payloads
+            .write()
+            .await
+            .insert(wallet.to_string(), payloads);
+        Ok(())
+    }
+
+    async fn load_backfill_cursor(
+        &self,
+        wallet: &str,
+        data_type: &str,
+    ) -> AppResult<Option<StoredBackfillCursor>> {
+        Ok(self
+            .backfill_cursors
+            .read()
+            .await
+            .get(&(wallet.to_string(), data_type.to_string()))
+            .cloned())
+    }
+
+    async fn save_backfill_cursor(
+        &self,
+        wallet: &str,
+        data_type: &str,
+        cursor: StoredBackfillCursor,
+    ) -> AppResult<()> {
+        self.backfill_cursors
+            .write()
+            .await
+            .insert((wallet.to_string(), data_type.to_string()), cursor);
+        Ok(())
+    }
+
+    async fn delete_backfill_cursors(&self, wallet: &str) -> AppResult<usize> {
+        let mut cursors = self.backfill_cursors.write().await;
+        let before = cursors.len();
+        cursors.retain(|(cursor_wallet, _), _| cursor_wallet != wallet);
+        Ok(before - cursors.len())
+    }
+
+    async fn load_event_sequence(&self, wallet: &str) -> AppResult<Vec<String>> {
+        Ok(self
+            .event_sequences
+            .read()
+            .await
+            .get(wallet)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn save_event_sequence(&self, wallet: &str, ids: Vec<String>) -> AppResult<()> {
+        self.event_sequences
+            .write()
+            .await
+            .insert(wallet.to_string(), ids);
+        Ok(())
+    }
+
+    async fn load_wallet_deletion(&self, wallet: &str) -> AppResult<Option<DateTime<Utc>>> {
+        Ok(self.wallet_deletions.read().await.get(wallet).copied())
+    }
+
+    async fn save_wallet_deletion(
+        &self,
+        wallet: &str,
+        deleted_at: DateTime<Utc>,
+    ) -> AppResult<()> {
+        self.wallet_deletions
+            .write()
+            .await
+            .insert(wallet.to_string(), deleted_at);
+        Ok(())
+    }
+
+    async fn clear_wallet_deletion(&self, wallet: &str) -> AppResult<()> {
+        self.wallet_deletions.write().await.remove(wallet);
+        Ok(())
+    }
+
+    async fn load_aggregates(&self, wallet: &str) -> AppResult<Option<StoredAggregates>> {
+        Ok(self.aggregates.read().await.get(wallet).cloned())
+    }
+
+    async fn save_aggregates(&self, wallet: &str, aggregates: StoredAggregates) -> AppResult<()> {
+        self.aggregates
+            .write()
+            .await
+            .insert(wallet.to_string(), aggregates);
+        Ok(())
+    }
+
+    async fn delete_aggregates(&self, wallet: &str) -> AppResult<bool> {
+        Ok(self.aggregates.write().await.remove(wallet).is_some())
+    }
+
+    async fn list_alert_rules(&self) -> AppResult<Vec<AlertRule>> {
+        Ok(self.alert_rules.read().await.values().cloned().collect())
+    }
+
+    async fn save_alert_rule(&self, rule: AlertRule) -> AppResult<()> {
+        self.alert_rules.write().await.insert(rule.id, rule);
+        Ok(())
+    }
+
+    async fn delete_alert_rule(&self, id: Uuid) -> AppResult<bool> {
+        Ok(self.alert_rules.write().await.remove(&id).is_some())
+    }
+
+    async fn list_fired_alerts(&self) -> AppResult<Vec<FiredAlert>> {
+        Ok(self.fired_alerts.read().await.clone())
+    }
+
+    async fn append_fired_alert(&self, alert: FiredAlert) -> AppResult<()> {
+        self.fired_alerts.write().await.push(alert);
+        Ok(())
+    }
+
+    async fn update_fired_alert(&self, alert: FiredAlert) -> AppResult<()> {
+        let mut alerts = self.fired_alerts.write().await;
+        if let Some(existing) = alerts.iter_mut().find(|existing| existing.id == alert.id) {
+            *existing = alert;
+        }
+        Ok(())
+    }
+
+    async fn delete_fired_alerts(&self, wallet: &str) -> AppResult<usize> {
+        let mut alerts = self.fired_alerts.write().await;
+        let before = alerts.len();
+        alerts.retain(|alert| !alert.wallet.eq_ignore_ascii_case(wallet));
+        Ok(before - alerts.len())
+    }
+
+    async fn list_dead_letters(&self) -> AppResult<Vec<DeadLetter>> {
+        Ok(self.dead_letters.read().await.values().cloned().collect())
+    }
+
+    async fn save_dead_letter(&self, letter: DeadLetter) -> AppResult<()> {
+        self.dead_letters.write().await.insert(letter.id, letter);
+        Ok(())
+    }
+
+    async fn delete_dead_letter(&self, id: Uuid) -> AppResult<bool> {
+        Ok(self.dead_letters.write().await.remove(&id).is_some())
+    }
+
+    async fn delete_dead_letters(&self, wallet: &str) -> AppResult<usize> {
+        let mut letters = self.dead_letters.write().await;
+        let before = letters.len();
+        letters.retain(|_, letter| !letter.alert.wallet.eq_ignore_ascii_case(wallet));
+        Ok(before - letters.len())
+    }
+
+    async fn save_shared_report(&self, report: SharedReport) -> AppResult<()> {
+        // Expired snapshots are dropped as new ones arrive
+        let now = Utc::now();
+        let mut reports = self.shared_reports.write().await;
+        reports.retain(|_, report| report.expires_at > now);
+        reports.insert(report.id, report);
+        Ok(())
+    }
+
+    async fn load_shared_report(&self, id: Uuid) -> AppResult<Option<SharedReport>> {
+        Ok(self.shared_reports.read().await.get(&id).cloned())
+    }
+
+    async fn delete_shared_reports(&self, wallet: &str) -> AppResult<usize> {
+        let mut reports = self.shared_reports.write().await;
+        let before = reports.len();
+        reports.retain(|_, report| !report.wallet.eq_ignore_ascii_case(wallet));
+        Ok(before - reports.len())
+    }
+
+    async fn load_asset_mappings(&self) -> AppResult<Vec<AssetMapping>> {
+        Ok(self.asset_mappings.read().await.clone())
+    }
+
+    async fn save_asset_mappings(&self, mappings: Vec<AssetMapping>) -> AppResult<()> {
+        *self.asset_mappings.write().await = mappings;
+        Ok(())
+    }
+
+    async fn list_funding_predictions(&self) -> AppResult<Vec<FundingPrediction>> {
+        Ok(self
+            .funding_predictions
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn save_funding_predictions(
+        &self,
+        predictions: Vec<FundingPrediction>,
+    ) -> AppResult<()> {
+        let mut stored = self.funding_predictions.write().await;
+        for prediction in predictions {
+            stored.insert(
+                (prediction.coin.clone(), prediction.funding_time),
+                prediction,
+            );
+        }
+        Ok(())
+    }
+}
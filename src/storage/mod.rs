@@ -0,0 +1,237 @@
+pub mod encrypted;
+pub mod memory;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::Value;
+use std::io::{Read, Write};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::alerts::{AlertRule, DeadLetter, FiredAlert};
+use crate::services::assets::AssetMapping;
+use crate::services::forecasts::FundingPrediction;
+use crate::services::pnl_calculator::FundingAttribution;
+use crate::services::sharing::SharedReport;
+
+/// Raw upstream history for a wallet as of its last successful sync
+#[derive(Debug, Clone)]
+pub struct StoredHistory {
+    pub fills: Vec<Value>,
+    pub funding: Vec<Value>,
+    pub ledger: Vec<Value>,
+    pub synced_at: DateTime<Utc>,
+    /// `NORMALIZATION_VERSION` of the ingestion logic the events were derived with
+    pub normalization_version: u32,
+}
+
+/// Daily PnL materialized from a wallet's stored history
+#[derive(Debug, Clone)]
+pub struct StoredAggregates {
+    pub funding_attribution: FundingAttribution,
+    /// Sync time of the history the aggregates reflect
+    pub synced_at: DateTime<Utc>,
+    /// One bucket per UTC date, kept as JSON like raw events so backends can seal them
+    pub days: Vec<Value>,
+}
+
+/// Upstream responses from a wallet's last full sync, as returned before any annotation.
+///
+/// Each category is a gzip-compressed JSON array, so timelines can be rebuilt with newer
+/// parsing logic without fetching the full history from upstream again.
+#[derive(Debug, Clone)]
+pub struct StoredRawPayloads {
+    pub fills: Vec<u8>,
+    pub funding: Vec<u8>,
+    pub ledger: Vec<u8>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Progress of a backfill of one data type for a wallet, saved after every page.
+///
+/// `items` holds everything fetched so far, kept as JSON like raw events so backends can seal
+/// them, so an interrupted backfill resumes from `next_start` without losing earlier pages.
+#[derive(Debug, Clone)]
+pub struct StoredBackfillCursor {
+    /// Start of the next page to fetch, epoch milliseconds
+    pub next_start: i64,
+    pub pages: usize,
+    /// Set once the last page has been fetched
+    pub complete: bool,
+    pub items: Vec<Value>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl StoredRawPayloads {
+    pub fn new(
+        fills: &[Value],
+        funding: &[Value],
+        ledger: &[Value],
+        fetched_at: DateTime<Utc>,
+    ) -> AppResult<Self> {
+        Ok(Self {
+            fills: compress(fills)?,
+            funding: compress(funding)?,
+            ledger: compress(ledger)?,
+            fetched_at,
+        })
+    }
+
+    pub fn fills(&self) -> AppResult<Vec<Value>> {
+        decompress(&self.fills)
+    }
+
+    pub fn funding(&self) -> AppResult<Vec<Value>> {
+        decompress(&self.funding)
+    }
+
+    pub fn ledger(&self) -> AppResult<Vec<Value>> {
+        decompress(&self.ledger)
+    }
+
+    /// Compressed size of all categories in bytes
+    pub fn size(&self) -> usize {
+        self.fills.len() + self.funding.len() + self.ledger.len()
+    }
+}
+
+fn compress(events: &[Value]) -> AppResult<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&serde_json::to_vec(events)?)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| AppError::InternalError(format!("Failed to compress raw payload: {}", e)))
+}
+
+fn decompress(bytes: &[u8]) -> AppResult<Vec<Value>> {
+    let mut json = Vec::new();
+    GzDecoder::new(bytes)
+        .read_to_end(&mut json)
+        .map_err(|e| AppError::InternalError(format!("Failed to decompress raw payload: {}", e)))?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// Trait for backends that persist synced wallet history
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Loads the stored history for a wallet, if it has been synced
+    async fn load_history(&self, wallet: &str) -> AppResult<Option<StoredHistory>>;
+
+    /// Replaces the stored history for a wallet
+    async fn save_history(&self, wallet: &str, history: StoredHistory) -> AppResult<()>;
+
+    /// Lists the wallets that have stored history
+    async fn list_history_wallets(&self) -> AppResult<Vec<String>>;
+
+    /// Removes the stored history for a wallet, along with its raw payloads, backfill cursors,
+    /// event sequence and aggregates materialized from it, returning the history if there was
+    /// one
+    async fn delete_history(&self, wallet: &str) -> AppResult<Option<StoredHistory>>;
+
+    /// Loads the raw upstream payloads of a wallet's last full sync
+    async fn load_raw_payloads(&self, wallet: &str) -> AppResult<Option<StoredRawPayloads>>;
+
+    /// Replaces the raw upstream payloads for a wallet
+    async fn save_raw_payloads(&self, wallet: &str, payloads: StoredRawPayloads) -> AppResult<()>;
+
+    /// Loads a wallet's backfill cursor for one data type
+    async fn load_backfill_cursor(
+        &self,
+        wallet: &str,
+        data_type: &str,
+    ) -> AppResult<Option<StoredBackfillCursor>>;
+
+    /// Replaces a wallet's backfill cursor for one data type
+    async fn save_backfill_cursor(
+        &self,
+        wallet: &str,
+        data_type: &str,
+        cursor: StoredBackfillCursor,
+    ) -> AppResult<()>;
+
+    /// Removes every backfill cursor of a wallet, returning how many there were
+    async fn delete_backfill_cursors(&self, wallet: &str) -> AppResult<usize>;
+
+    /// Loads the IDs of a wallet's timeline events, in the order they were first served
+    async fn load_event_sequence(&self, wallet: &str) -> AppResult<Vec<String>>;
+
+    /// Replaces a wallet's event sequence
+    async fn save_event_sequence(&self, wallet: &str, ids: Vec<String>) -> AppResult<()>;
+
+    /// Loads when a wallet's data was last deleted on request, unless it was synced again since
+    async fn load_wallet_deletion(&self, wallet: &str) -> AppResult<Option<DateTime<Utc>>>;
+
+    /// Records that a wallet's data was deleted on request
+    async fn save_wallet_deletion(&self, wallet: &str, deleted_at: DateTime<Utc>)
+        -> AppResult<()>;
+
+    /// Forgets a wallet's deletion once a request syncs it again
+    async fn clear_wallet_deletion(&self, wallet: &str) -> AppResult<()>;
+
+    /// Loads the materialized daily aggregates for a wallet
+    async fn load_aggregates(&self, wallet: &str) -> AppResult<Option<StoredAggregates>>;
+
+    /// Replaces the materialized daily aggregates for a wallet
+    async fn save_aggregates(&self, wallet: &str, aggregates: StoredAggregates) -> AppResult<()>;
+
+    /// Removes the materialized daily aggregates for a wallet, returning whether there were any
+    async fn delete_aggregates(&self, wallet: &str) -> AppResult<bool>;
+
+    /// Lists all alert rules
+    async fn list_alert_rules(&self) -> AppResult<Vec<AlertRule>>;
+
+    /// Inserts or replaces an alert rule by ID
+    async fn save_alert_rule(&self, rule: AlertRule) -> AppResult<()>;
+
+    /// Deletes an alert rule, returning whether it existed
+    async fn delete_alert_rule(&self, id: Uuid) -> AppResult<bool>;
+
+    /// Lists all fired alerts
+    async fn list_fired_alerts(&self) -> AppResult<Vec<FiredAlert>>;
+
+    /// Records a fired alert
+    async fn append_fired_alert(&self, alert: FiredAlert) -> AppResult<()>;
+
+    /// Replaces a fired alert by ID, once its delivery has finished; ignored if it was removed
+    async fn update_fired_alert(&self, alert: FiredAlert) -> AppResult<()>;
+
+    /// Deletes every fired alert for a wallet, returning how many there were
+    async fn delete_fired_alerts(&self, wallet: &str) -> AppResult<usize>;
+
+    /// Lists webhook deliveries awaiting replay
+    async fn list_dead_letters(&self) -> AppResult<Vec<DeadLetter>>;
+
+    /// Inserts or replaces a dead letter by ID
+    async fn save_dead_letter(&self, letter: DeadLetter) -> AppResult<()>;
+
+    /// Deletes a dead letter, returning whether it existed
+    async fn delete_dead_letter(&self, id: Uuid) -> AppResult<bool>;
+
+    /// Deletes every dead letter for a wallet, returning how many there were
+    async fn delete_dead_letters(&self, wallet: &str) -> AppResult<usize>;
+
+    /// Inserts or replaces a shared report snapshot by ID
+    async fn save_shared_report(&self, report: SharedReport) -> AppResult<()>;
+
+    /// Loads a shared report snapshot
+    async fn load_shared_report(&self, id: Uuid) -> AppResult<Option<SharedReport>>;
+
+    /// Deletes every shared report for a wallet, returning how many there were
+    async fn delete_shared_reports(&self, wallet: &str) -> AppResult<usize>;
+
+    /// Loads all asset mapping versions
+    async fn load_asset_mappings(&self) -> AppResult<Vec<AssetMapping>>;
+
+    /// Replaces all asset mapping versions
+    async fn save_asset_mappings(&self, mappings: Vec<AssetMapping>) -> AppResult<()>;
+
+    /// Lists all recorded funding predictions
+    async fn list_funding_predictions(&self) -> AppResult<Vec<FundingPrediction>>;
+
+    /// Inserts or replaces funding predictions by coin and settlement time
+    async fn save_funding_predictions(&self, predictions: Vec<FundingPrediction>)
+        -> AppResult<()>;
+}